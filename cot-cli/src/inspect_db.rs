@@ -0,0 +1,413 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use convert_case::{Case, Casing};
+use cot::db::introspection::{ColumnSchema, TableSchema};
+use cot::db::migrations::{DynMigration, MigrationDependency, MigrationEngine, Operation};
+use cot::db::Database;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use tracing::{info, warn};
+
+use crate::migration_generator::{
+    generator_for_path, MigrationAsSource, MigrationGenerator, MigrationGeneratorOptions,
+    SourceFile,
+};
+
+/// The name of the file the generated models are written to.
+const MODELS_FILE_NAME: &str = "models.rs";
+
+/// Generates models for the tables existing in the database at given URL,
+/// along with the initial migration creating them.
+///
+/// The migration is marked as applied in the database, as the tables already
+/// exist there. It only contains the generated models, and the existing
+/// migrations of the crate must be up to date with its models.
+pub fn inspect_db(
+    path: &Path,
+    database_url: &str,
+    options: MigrationGeneratorOptions,
+) -> anyhow::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .with_context(|| "unable to create async runtime")?;
+
+    runtime.block_on(inspect_db_async(path, database_url, options))
+}
+
+async fn inspect_db_async(
+    path: &Path,
+    database_url: &str,
+    options: MigrationGeneratorOptions,
+) -> anyhow::Result<()> {
+    let mut generator = generator_for_path(path, options)?;
+    let src_dir = generator.source_dir()?;
+    let Ok(models_dir) = resolve_path(&generator.output_dir())?
+        .strip_prefix(&src_dir)
+        .map(Path::to_path_buf)
+    else {
+        bail!(
+            "the output directory must be inside the source directory of the crate ({}), as the \
+            generated models are referred to from there",
+            src_dir.display()
+        );
+    };
+    let models_path = generator.output_dir().join(MODELS_FILE_NAME);
+    if models_path.exists() {
+        bail!(
+            "models file already exists: {}; move it away before inspecting the database",
+            models_path.display()
+        );
+    }
+
+    let database = Database::new(database_url)
+        .await
+        .with_context(|| "unable to connect to the database")?;
+    let tables = database
+        .introspect()
+        .await
+        .with_context(|| "unable to introspect the database")?;
+
+    // nothing is written until both the models and the migration are generated
+    let models = generate_models(&tables)?;
+    let models_file = SourceFile::parse(models_dir.join(MODELS_FILE_NAME), &models)?;
+    let migration = generator
+        .generate_models_migration_to_write(models_file)
+        .with_context(|| "unable to generate the migration for the inspected models")?;
+
+    let mut written_files = Vec::new();
+    let result = write_and_mark_applied(
+        &generator,
+        &database,
+        &models_path,
+        &models,
+        &migration,
+        &mut written_files,
+    )
+    .await;
+    if result.is_err() {
+        for file in written_files {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+    result
+}
+
+/// Writes the models and the migration files, and marks the migration as
+/// applied. The paths of the files are pushed to `written_files` before they
+/// are written, so that they can be removed if any step fails.
+async fn write_and_mark_applied(
+    generator: &MigrationGenerator,
+    database: &Database,
+    models_path: &Path,
+    models: &str,
+    migration: &MigrationAsSource,
+    written_files: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(generator.output_dir())
+        .with_context(|| "unable to create the output directory")?;
+    written_files.push(models_path.to_path_buf());
+    std::fs::write(models_path, models)
+        .with_context(|| format!("unable to write models file: {}", models_path.display()))?;
+    info!("Generated models: {}", models_path.display());
+
+    written_files.push(generator.migration_file_path(migration));
+    generator.write_migration(migration)?;
+
+    let applied_migration = AppliedMigration {
+        app_name: generator.app_name().to_owned(),
+        name: migration.name.clone(),
+    };
+    MigrationEngine::new([applied_migration])?
+        .run(database)
+        .await
+        .with_context(|| "unable to mark the migration as applied")?;
+    info!(
+        "Marked the migration as applied; add `mod models;` to the crate and register the \
+        migration in the app"
+    );
+
+    Ok(())
+}
+
+/// Returns the canonical form of given path, which might not exist yet.
+fn resolve_path(path: &Path) -> anyhow::Result<PathBuf> {
+    let path = std::path::absolute(path)
+        .with_context(|| format!("unable to resolve path: {}", path.display()))?;
+
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            bail!("unable to resolve path: {}", path.display());
+        };
+        missing.push(name);
+        existing = parent;
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .with_context(|| format!("unable to resolve path: {}", path.display()))?;
+    resolved.extend(missing.into_iter().rev());
+    Ok(resolved)
+}
+
+/// A migration that doesn't contain any operations, used to mark the initial
+/// migration as applied without touching the already existing tables.
+#[derive(Debug, Clone)]
+struct AppliedMigration {
+    app_name: String,
+    name: String,
+}
+
+impl DynMigration for AppliedMigration {
+    fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dependencies(&self) -> &[MigrationDependency] {
+        &[]
+    }
+
+    fn operations(&self) -> &[Operation] {
+        &[]
+    }
+}
+
+/// Generates the source code of the models for given tables.
+///
+/// Tables internal to Cot, as well as the tables that can't be represented
+/// as models (e.g. because they don't have a single-column primary key), are
+/// skipped with a warning.
+///
+/// # Errors
+///
+/// Returns an error if there are no tables that models could be generated
+/// for.
+pub fn generate_models(tables: &[TableSchema]) -> anyhow::Result<String> {
+    let tables: Vec<_> = tables
+        .iter()
+        .filter(|table| !table.name().starts_with("cot__"))
+        .filter(|table| {
+            let reason = unsupported_table_reason(table);
+            if let Some(reason) = &reason {
+                warn!("Skipping table `{}`: {reason}", table.name());
+            }
+            reason.is_none()
+        })
+        .collect();
+    if tables.is_empty() {
+        bail!("no tables found that models could be generated for");
+    }
+
+    let model_names: HashMap<&str, syn::Ident> = tables
+        .iter()
+        .map(|table| (table.name(), model_name(table.name())))
+        .collect();
+
+    let mut imports = BTreeSet::from(["model"]);
+    let models: Vec<_> = tables
+        .iter()
+        .map(|table| generate_model(table, &model_names, &mut imports))
+        .collect();
+
+    let imports = imports.into_iter().map(|import| format_ident!("{import}"));
+    let tokens = quote! {
+        use cot::db::{#(#imports),*};
+
+        #(#models)*
+    };
+    let file: syn::File = syn::parse2(tokens).expect("generated models must be valid Rust");
+
+    let version = env!("CARGO_PKG_VERSION");
+    let date_time = chrono::offset::Utc::now().format("%Y-%m-%d %H:%M:%S%:z");
+    let header =
+        format!("//! Generated by cot CLI {version} on {date_time} from an existing database");
+
+    Ok(format!("{header}\n\n{}", prettyplease::unparse(&file)))
+}
+
+/// Returns the reason why given table can't be represented as a model, or
+/// [`None`] if it can.
+fn unsupported_table_reason(table: &TableSchema) -> Option<String> {
+    if !is_valid_ident(&table.name().to_case(Case::Pascal)) {
+        return Some("the table name is not a valid Rust identifier".to_owned());
+    }
+
+    let primary_keys: Vec<_> = table
+        .columns()
+        .iter()
+        .filter(|column| column.is_primary_key())
+        .collect();
+    if primary_keys.len() != 1 {
+        return Some("the table doesn't have a single-column primary key".to_owned());
+    }
+    if primary_keys[0].name() != "id" && table.column("id").is_some() {
+        return Some("the `id` column is not the primary key".to_owned());
+    }
+
+    if let Some(column) = table
+        .columns()
+        .iter()
+        .find(|column| !is_valid_ident(column.name()))
+    {
+        return Some(format!(
+            "column `{}` is not a valid Rust identifier",
+            column.name()
+        ));
+    }
+
+    None
+}
+
+/// Returns whether given string is a valid, non-keyword Rust identifier.
+#[must_use]
+fn is_valid_ident(name: &str) -> bool {
+    syn::parse_str::<syn::Ident>(name).is_ok()
+}
+
+#[must_use]
+fn model_name(table_name: &str) -> syn::Ident {
+    format_ident!("{}", table_name.to_case(Case::Pascal))
+}
+
+#[must_use]
+fn generate_model(
+    table: &TableSchema,
+    model_names: &HashMap<&str, syn::Ident>,
+    imports: &mut BTreeSet<&'static str>,
+) -> TokenStream {
+    let name = &model_names[table.name()];
+    let table_name = table.name();
+    let fields = table
+        .columns()
+        .iter()
+        .map(|column| generate_field(table_name, column, model_names, imports));
+
+    quote! {
+        #[derive(Debug, Clone)]
+        #[model(table_name = #table_name)]
+        pub struct #name {
+            #(#fields,)*
+        }
+    }
+}
+
+#[must_use]
+fn generate_field(
+    table_name: &str,
+    column: &ColumnSchema,
+    model_names: &HashMap<&str, syn::Ident>,
+    imports: &mut BTreeSet<&'static str>,
+) -> TokenStream {
+    let name = format_ident!("{}", column.name());
+
+    let mut ty = if let Some(model_name) = column
+        .foreign_key_table()
+        .and_then(|foreign_table| model_names.get(foreign_table))
+    {
+        imports.insert("ForeignKey");
+        quote! { ForeignKey<#model_name> }
+    } else {
+        if let Some(foreign_table) = column.foreign_key_table() {
+            warn!(
+                "Column `{table_name}.{}` references table `{foreign_table}` that has no model; \
+                generating a plain field instead of a foreign key",
+                column.name()
+            );
+        }
+        rust_type(table_name, column, imports)
+    };
+
+    if column.is_primary_key() && is_integer_type(column.type_name()) {
+        imports.insert("Auto");
+        ty = quote! { Auto<#ty> };
+    }
+    if column.is_nullable() && !column.is_primary_key() {
+        ty = quote! { Option<#ty> };
+    }
+
+    let mut attrs = Vec::new();
+    if column.is_primary_key() && column.name() != "id" {
+        attrs.push(quote! { primary_key });
+    }
+    if column.is_unique() {
+        attrs.push(quote! { unique });
+    }
+    let attrs = if attrs.is_empty() {
+        quote! {}
+    } else {
+        quote! { #[model(#(#attrs),*)] }
+    };
+
+    quote! {
+        #attrs
+        #name: #ty
+    }
+}
+
+#[must_use]
+fn is_integer_type(type_name: &str) -> bool {
+    matches!(
+        type_name,
+        "smallint" | "int2" | "integer" | "int" | "int4" | "mediumint" | "bigint" | "int8"
+    )
+}
+
+/// Returns the Rust type for given column, based on the (normalized) name of
+/// its type.
+#[must_use]
+fn rust_type(
+    table_name: &str,
+    column: &ColumnSchema,
+    imports: &mut BTreeSet<&'static str>,
+) -> TokenStream {
+    match column.type_name() {
+        "boolean" | "bool" => quote! { bool },
+        "tinyint" => quote! { i8 },
+        "tinyint unsigned" => quote! { u8 },
+        "smallint" | "int2" => quote! { i16 },
+        "smallint unsigned" => quote! { u16 },
+        "integer" | "int" | "int4" | "mediumint" => quote! { i32 },
+        "int unsigned" | "mediumint unsigned" => quote! { u32 },
+        "bigint" | "int8" => quote! { i64 },
+        "bigint unsigned" => quote! { u64 },
+        "real" | "float" | "float4" => quote! { f32 },
+        "double" | "double precision" | "float8" => quote! { f64 },
+        "date" | "date_text" => quote! { chrono::NaiveDate },
+        "time" | "time without time zone" | "time_text" => quote! { chrono::NaiveTime },
+        "datetime" | "timestamp" | "timestamp without time zone" | "datetime_text" => {
+            quote! { chrono::NaiveDateTime }
+        }
+        "timestamp with time zone" | "timestamptz" | "timestamp_with_timezone_text" => {
+            quote! { chrono::DateTime<chrono::FixedOffset> }
+        }
+        "blob" | "bytea" | "binary" | "varbinary" | "mediumblob" | "longblob" => {
+            quote! { Vec<u8> }
+        }
+        "varchar" | "character varying" | "char" | "character" | "nvarchar" => {
+            if let Some(max_length) = column.max_length() {
+                imports.insert("LimitedString");
+                let max_length = proc_macro2::Literal::u32_unsuffixed(max_length);
+                quote! { LimitedString<#max_length> }
+            } else {
+                quote! { String }
+            }
+        }
+        "text" | "tinytext" | "mediumtext" | "longtext" => quote! { String },
+        type_name => {
+            warn!(
+                "Column `{table_name}.{}` has unsupported type `{type_name}`; generating a \
+                `String` field instead",
+                column.name()
+            );
+            quote! { String }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
+pub mod inspect_db;
 pub mod migration_generator;
 pub mod new_project;
 mod utils;
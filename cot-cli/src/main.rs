@@ -1,5 +1,6 @@
 #![allow(unreachable_pub)] // triggers false positives because we have both a binary and library
 
+mod inspect_db;
 mod migration_generator;
 mod new_project;
 mod utils;
@@ -11,7 +12,11 @@ use clap::{Parser, Subcommand};
 use clap_verbosity_flag::Verbosity;
 use tracing_subscriber::util::SubscriberInitExt;
 
-use crate::migration_generator::{make_migrations, MigrationGeneratorOptions};
+use crate::inspect_db::inspect_db;
+use crate::migration_generator::{
+    check_migrations, make_migrations, merge_migrations, squash_migrations,
+    MigrationGeneratorOptions,
+};
 use crate::new_project::{new_project, CotSource};
 
 #[derive(Debug, Parser)]
@@ -48,6 +53,52 @@ enum Commands {
         /// in the crate's src/ directory)
         #[arg(long)]
         output_dir: Option<PathBuf>,
+        /// Don't write any migrations; fail if the migrations are not up to
+        /// date with the models instead
+        #[arg(long, conflicts_with = "merge")]
+        check: bool,
+        /// Generate a migration merging the migrations created on divergent
+        /// branches instead of a migration for the model changes
+        #[arg(long)]
+        merge: bool,
+    },
+    /// Generate models and an initial migration from an existing database
+    #[command(name = "inspectdb")]
+    InspectDb {
+        /// Path to the crate directory to generate the models in (default:
+        /// current directory)
+        path: Option<PathBuf>,
+        /// URL of the database to inspect
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+        /// Name of the app to use in the migration (default: crate name)
+        #[arg(long)]
+        app_name: Option<String>,
+        /// Directory to write the models and the migration to (default: the
+        /// crate's src/ directory)
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Squash a range of migrations of a Cot project into a single migration
+    SquashMigrations {
+        /// Path to the crate directory to squash migrations in (default:
+        /// current directory)
+        path: Option<PathBuf>,
+        /// Name of the app to use in the migration (default: crate name)
+        #[arg(long)]
+        app_name: Option<String>,
+        /// The first migration to squash, either as a full name or a prefix
+        /// such as `m_0002` (default: the first migration)
+        #[arg(long)]
+        start: Option<String>,
+        /// The last migration to squash, either as a full name or a prefix
+        /// such as `m_0005`
+        #[arg(long)]
+        end: String,
+        /// Directory to write the migration to (default: migrations/ directory
+        /// in the crate's src/ directory)
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
     },
 }
 
@@ -90,13 +141,51 @@ fn main() -> anyhow::Result<()> {
             path,
             app_name,
             output_dir,
+            check,
+            merge,
+        } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let options = MigrationGeneratorOptions {
+                app_name,
+                output_dir,
+            };
+            if check {
+                check_migrations(&path, options)
+                    .with_context(|| "migrations are not up to date")?;
+            } else if merge {
+                merge_migrations(&path, options).with_context(|| "unable to merge migrations")?;
+            } else {
+                make_migrations(&path, options).with_context(|| "unable to create migrations")?;
+            }
+        }
+        Commands::InspectDb {
+            path,
+            database_url,
+            app_name,
+            output_dir,
+        } => {
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let options = MigrationGeneratorOptions {
+                app_name,
+                output_dir,
+            };
+            inspect_db(&path, &database_url, options)
+                .with_context(|| "unable to inspect the database")?;
+        }
+        Commands::SquashMigrations {
+            path,
+            app_name,
+            start,
+            end,
+            output_dir,
         } => {
             let path = path.unwrap_or_else(|| PathBuf::from("."));
             let options = MigrationGeneratorOptions {
                 app_name,
                 output_dir,
             };
-            make_migrations(&path, options).with_context(|| "unable to create migrations")?;
+            squash_migrations(&path, options, start.as_deref(), &end)
+                .with_context(|| "unable to squash migrations")?;
         }
     }
 
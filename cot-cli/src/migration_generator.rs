@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fmt::{Debug, Display};
+use std::fmt::{Debug, Display, Write as _};
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -21,6 +21,37 @@ use tracing::{debug, info, trace};
 use crate::utils::find_cargo_toml;
 
 pub fn make_migrations(path: &Path, options: MigrationGeneratorOptions) -> anyhow::Result<()> {
+    generator_for_path(path, options)?
+        .generate_and_write_migrations()
+        .with_context(|| "unable to generate migrations")?;
+    Ok(())
+}
+
+pub fn squash_migrations(
+    path: &Path,
+    options: MigrationGeneratorOptions,
+    start: Option<&str>,
+    end: &str,
+) -> anyhow::Result<()> {
+    generator_for_path(path, options)?
+        .generate_and_write_squashed_migration(start, end)
+        .with_context(|| "unable to squash migrations")
+}
+
+pub fn merge_migrations(path: &Path, options: MigrationGeneratorOptions) -> anyhow::Result<()> {
+    generator_for_path(path, options)?
+        .generate_and_write_merge_migration()
+        .with_context(|| "unable to merge migrations")
+}
+
+pub fn check_migrations(path: &Path, options: MigrationGeneratorOptions) -> anyhow::Result<()> {
+    generator_for_path(path, options)?.check_migrations_in_crate()
+}
+
+pub(crate) fn generator_for_path(
+    path: &Path,
+    options: MigrationGeneratorOptions,
+) -> anyhow::Result<MigrationGenerator> {
     match find_cargo_toml(
         &path
             .canonicalize()
@@ -34,16 +65,16 @@ pub fn make_migrations(path: &Path, options: MigrationGeneratorOptions) -> anyho
                 .with_context(|| "unable to find package in Cargo.toml")?
                 .name;
 
-            MigrationGenerator::new(cargo_toml_path, crate_name, options)
-                .generate_and_write_migrations()
-                .with_context(|| "unable to generate migrations")?;
+            Ok(MigrationGenerator::new(
+                cargo_toml_path,
+                crate_name,
+                options,
+            ))
         }
         None => {
             bail!("Cargo.toml not found in the specified directory or any parent directory.")
         }
     }
-
-    Ok(())
 }
 
 #[derive(Debug, Clone, Default)]
@@ -73,16 +104,83 @@ impl MigrationGenerator {
         }
     }
 
-    fn generate_and_write_migrations(&mut self) -> anyhow::Result<()> {
+    /// Generates migrations for the crate and writes them to the migrations
+    /// directory. Returns the name of the generated migration, or [`None`]
+    /// if the migrations are up to date.
+    pub(crate) fn generate_and_write_migrations(&mut self) -> anyhow::Result<Option<String>> {
+        let source_files = self.get_source_files()?;
+
+        let Some(migration) = self.generate_migrations_to_write(source_files)? else {
+            return Ok(None);
+        };
+        self.write_migration(&migration)?;
+
+        Ok(Some(migration.name))
+    }
+
+    fn generate_and_write_merge_migration(&mut self) -> anyhow::Result<()> {
         let source_files = self.get_source_files()?;
 
-        if let Some(migration) = self.generate_migrations_to_write(source_files)? {
-            self.write_migration(&migration)?;
+        let migration = self.generate_merge_migration_to_write(source_files)?;
+        self.write_migration(&migration)?;
+
+        Ok(())
+    }
+
+    fn check_migrations_in_crate(&mut self) -> anyhow::Result<()> {
+        let source_files = self.get_source_files()?;
+
+        self.check_migrations(source_files)?;
+        info!("Migrations are up to date");
+
+        Ok(())
+    }
+
+    /// Checks that the migrations are up to date with the models, i.e. that
+    /// generating migrations wouldn't produce any new operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new migration would be generated; the error
+    /// message lists the operations it would contain.
+    pub fn check_migrations(&mut self, source_files: Vec<SourceFile>) -> anyhow::Result<()> {
+        if let Some(migration) = self.generate_migrations(source_files)? {
+            let operations =
+                migration
+                    .operations
+                    .iter()
+                    .fold(String::new(), |mut output, operation| {
+                        let _ = write!(output, "\n  - {operation}");
+                        output
+                    });
+            bail!(
+                "models have changes that are not reflected in the migrations; \
+                `{}` would be generated with the following operations:{operations}",
+                migration.migration_name
+            );
         }
 
         Ok(())
     }
 
+    fn generate_and_write_squashed_migration(
+        &mut self,
+        start: Option<&str>,
+        end: &str,
+    ) -> anyhow::Result<()> {
+        let source_files = self.get_source_files()?;
+
+        let migration = self.generate_squashed_migration_to_write(source_files, start, end)?;
+        self.write_migration(&migration)?;
+        info!(
+            "Add `{}::Migration` to the list of migrations of the app; the replaced migrations \
+            can be removed once all the databases have been migrated",
+            migration.name
+        );
+
+        Ok(())
+    }
+
     /// Generate migrations as a ready-to-write source code.
     pub fn generate_migrations_to_write(
         &mut self,
@@ -105,6 +203,7 @@ impl MigrationGenerator {
     ) -> anyhow::Result<Option<GeneratedMigration>> {
         let AppState { models, migrations } = self.process_source_files(source_files)?;
         let migration_processor = MigrationProcessor::new(migrations)?;
+        migration_processor.ensure_single_leaf()?;
         let migration_models = migration_processor.latest_models();
 
         let (modified_models, operations) = self.generate_operations(&models, &migration_models);
@@ -120,15 +219,187 @@ impl MigrationGenerator {
         }
     }
 
-    fn get_source_files(&mut self) -> anyhow::Result<Vec<SourceFile>> {
-        let src_dir = self
-            .cargo_toml_path
+    /// Generates a migration creating only the models defined in
+    /// `models_file`, a file that is not written to the source directory
+    /// yet, as a ready-to-write source code.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the migrations of the crate are not up to date
+    /// with its models, or if a model in `models_file` uses the same table
+    /// as one of the existing models.
+    pub(crate) fn generate_models_migration_to_write(
+        &mut self,
+        models_file: SourceFile,
+    ) -> anyhow::Result<MigrationAsSource> {
+        let source_files = self.get_source_files()?;
+        self.check_migrations(source_files.clone())?;
+
+        let AppState { models, migrations } = self.process_source_files(source_files)?;
+        let new_models = self.process_source_files(vec![models_file])?.models;
+        if let Some(model) = new_models.iter().find(|new_model| {
+            models
+                .iter()
+                .any(|model| model.model.table_name == new_model.model.table_name)
+        }) {
+            bail!(
+                "table `{}` already has a model in the crate",
+                model.model.table_name
+            );
+        }
+
+        let (modified_models, operations) = self.generate_operations(&new_models, &Vec::new());
+        let migration_processor = MigrationProcessor::new(migrations)?;
+        let migration = GeneratedMigration::new(
+            migration_processor.next_migration_name()?,
+            modified_models,
+            migration_processor.base_dependencies(),
+            operations,
+        );
+        let migration_name = migration.migration_name.clone();
+        let content = self.generate_migration_file_content(migration);
+        Ok(MigrationAsSource::new(migration_name, content))
+    }
+
+    /// Generate a merge migration as a ready-to-write source code.
+    pub fn generate_merge_migration_to_write(
+        &mut self,
+        source_files: Vec<SourceFile>,
+    ) -> anyhow::Result<MigrationAsSource> {
+        let migration = self.generate_merge_migration(source_files)?;
+        let migration_name = migration.migration_name.clone();
+        let content = self.generate_migration_file_content(migration);
+        Ok(MigrationAsSource::new(migration_name, content))
+    }
+
+    /// Generate a migration that doesn't contain any operations, but depends
+    /// on all the leaf migrations, i.e. the migrations that no other migration
+    /// depends on.
+    ///
+    /// Multiple leaf migrations appear when migrations are created on
+    /// divergent branches; the merge migration makes the history linear
+    /// again, so that new migrations can be generated on top of it.
+    pub fn generate_merge_migration(
+        &mut self,
+        source_files: Vec<SourceFile>,
+    ) -> anyhow::Result<GeneratedMigration> {
+        let AppState { migrations, .. } = self.process_source_files(source_files)?;
+        let migration_processor = MigrationProcessor::new(migrations)?;
+
+        let leaves = migration_processor.leaf_migrations();
+        if leaves.len() < 2 {
+            bail!("there are no divergent migrations to merge");
+        }
+        let merged_models = migration_processor.merged_models(&leaves);
+        let dependencies = leaves
+            .into_iter()
+            .map(|migration| DynDependency::Migration {
+                app: migration.app_name.clone(),
+                migration: migration.name.clone(),
+            })
+            .collect();
+
+        let migration_name = migration_processor.merge_migration_name()?;
+        Ok(GeneratedMigration::new(
+            migration_name,
+            merged_models,
+            dependencies,
+            Vec::new(),
+        ))
+    }
+
+    /// Generate a squashed migration as a ready-to-write source code.
+    pub fn generate_squashed_migration_to_write(
+        &mut self,
+        source_files: Vec<SourceFile>,
+        start: Option<&str>,
+        end: &str,
+    ) -> anyhow::Result<MigrationAsSource> {
+        let migration = self.generate_squashed_migration(source_files, start, end)?;
+        let migration_name = migration.migration_name.clone();
+        let content = self.generate_migration_file_content(migration);
+        Ok(MigrationAsSource::new(migration_name, content))
+    }
+
+    /// Generate a single migration that is equivalent to all the existing
+    /// migrations from `start` (or the first migration, if `None`) to `end`,
+    /// inclusive.
+    ///
+    /// The migrations can be referred to either by their full name, or by a
+    /// prefix ending before an underscore (e.g. `m_0002`).
+    pub fn generate_squashed_migration(
+        &mut self,
+        source_files: Vec<SourceFile>,
+        start: Option<&str>,
+        end: &str,
+    ) -> anyhow::Result<GeneratedMigration> {
+        let AppState { models, migrations } = self.process_source_files(source_files)?;
+        let migration_processor = MigrationProcessor::new(migrations)?;
+
+        let start_index = match start {
+            Some(start) => migration_processor.find_migration(start)?,
+            None => 0,
+        };
+        let end_index = migration_processor.find_migration(end)?;
+        if start_index >= end_index {
+            bail!("at least two migrations are needed to squash them");
+        }
+
+        let models_before = migration_processor.latest_models_until(start_index);
+        let models_after = Self::with_app_model_types(
+            migration_processor.latest_models_until(end_index + 1),
+            &models,
+        );
+
+        let (modified_models, operations) = self.generate_operations(&models_after, &models_before);
+        if operations.is_empty() {
+            bail!("migrations to squash don't contain any operations");
+        }
+
+        let migration_name = migration_processor.squashed_migration_name(start_index, end_index)?;
+        let dependencies = migration_processor.dependencies_before(start_index);
+
+        let mut migration =
+            GeneratedMigration::new(migration_name, modified_models, dependencies, operations);
+        migration.replaces = migration_processor.migration_names(start_index, end_index);
+        Ok(migration)
+    }
+
+    /// Replaces the types of the migration models with the types of the
+    /// corresponding application models, so that the operations generated for
+    /// them can be compared with foreign key targets.
+    #[must_use]
+    fn with_app_model_types(
+        mut migration_models: Vec<ModelInSource>,
+        app_models: &[ModelInSource],
+    ) -> Vec<ModelInSource> {
+        let app_model_types: HashMap<_, _> = app_models
+            .iter()
+            .map(|model| (&model.model.table_name, &model.model.resolved_ty))
+            .collect();
+
+        for model in &mut migration_models {
+            if let Some(&ty) = app_model_types.get(&model.model.table_name) {
+                model.model.resolved_ty = ty.clone();
+            }
+        }
+
+        migration_models
+    }
+
+    /// Returns the canonical path of the directory the source files of the
+    /// crate are read from.
+    pub(crate) fn source_dir(&self) -> anyhow::Result<PathBuf> {
+        self.cargo_toml_path
             .parent()
             .with_context(|| "unable to find parent dir")?
-            .join("src");
-        let src_dir = src_dir
+            .join("src")
             .canonicalize()
-            .with_context(|| "unable to canonicalize src dir")?;
+            .with_context(|| "unable to canonicalize src dir")
+    }
+
+    fn get_source_files(&mut self) -> anyhow::Result<Vec<SourceFile>> {
+        let src_dir = self.source_dir()?;
 
         let source_file_paths = Self::find_source_files(&src_dir)?;
         let source_files = source_file_paths
@@ -192,6 +463,7 @@ impl MigrationGenerator {
         trace!("Processing file: {:?}", &path);
 
         let symbol_resolver = SymbolResolver::from_file(&file, &path);
+        let migration_impl = self.parse_migration_impl(&file);
 
         let mut migration_models = Vec::new();
         for item in file.items {
@@ -228,7 +500,11 @@ impl MigrationGenerator {
             }
         }
 
-        if !migration_models.is_empty() {
+        if !migration_models.is_empty() || migration_impl.is_some() {
+            let MigrationImpl {
+                dependencies,
+                replaces,
+            } = migration_impl.unwrap_or_default();
             let migration_name = path
                 .file_stem()
                 .with_context(|| format!("unable to get migration file name: {}", path.display()))?
@@ -238,12 +514,49 @@ impl MigrationGenerator {
                 app_name: self.crate_name.clone(),
                 name: migration_name,
                 models: migration_models,
+                dependencies,
+                replaces,
             });
         }
 
         Ok(())
     }
 
+    /// Reads the dependencies and the replaced migrations from the
+    /// implementation of the `Migration` trait in a migration file.
+    ///
+    /// Only the dependencies on the migrations of the same app are returned.
+    /// Returns [`None`] if the file doesn't contain the implementation.
+    fn parse_migration_impl(&self, file: &syn::File) -> Option<MigrationImpl> {
+        let app_name = self.app_name();
+
+        let item_impl = file.items.iter().find_map(|item| match item {
+            syn::Item::Impl(item_impl) if is_migration_impl(item_impl) => Some(item_impl),
+            _ => None,
+        })?;
+
+        let mut migration_impl = MigrationImpl::default();
+        for item in &item_impl.items {
+            let syn::ImplItem::Const(item_const) = item else {
+                continue;
+            };
+
+            if item_const.ident == "DEPENDENCIES" {
+                migration_impl.dependencies = array_elements(&item_const.expr)
+                    .filter_map(migration_dependency_from_expr)
+                    .filter(|(app, _)| app == app_name)
+                    .map(|(_, migration)| migration)
+                    .collect();
+            } else if item_const.ident == "REPLACES" {
+                migration_impl.replaces = array_elements(&item_const.expr)
+                    .filter_map(string_from_expr)
+                    .collect();
+            }
+        }
+
+        Some(migration_impl)
+    }
+
     fn args_from_attr(path: &Path, attr: &syn::Attribute) -> Result<ModelArgs, ParsingError> {
         match attr.meta {
             Meta::Path(_) => {
@@ -420,7 +733,18 @@ impl MigrationGenerator {
             .map(|dependency| dependency.repr())
             .collect();
 
-        let app_name = self.options.app_name.as_ref().unwrap_or(&self.crate_name);
+        let replaces = if migration.replaces.is_empty() {
+            quote! {}
+        } else {
+            let replaced = &migration.replaces;
+            quote! {
+                const REPLACES: &'static [&'static str] = &[
+                    #(#replaced,)*
+                ];
+            }
+        };
+
+        let app_name = self.app_name();
         let migration_name = &migration.migration_name;
         let migration_def = quote! {
             #[derive(Debug, Copy, Clone)]
@@ -435,6 +759,7 @@ impl MigrationGenerator {
                 const OPERATIONS: &'static [::cot::db::migrations::Operation] = &[
                     #(#operations,)*
                 ];
+                #replaces
             }
         };
 
@@ -450,14 +775,32 @@ impl MigrationGenerator {
         Self::generate_migration(migration_def, models_def)
     }
 
-    fn write_migration(&self, migration: &MigrationAsSource) -> anyhow::Result<()> {
-        let src_path = self
-            .options
+    /// Returns the name of the app the migrations are generated for.
+    #[must_use]
+    pub(crate) fn app_name(&self) -> &str {
+        self.options.app_name.as_ref().unwrap_or(&self.crate_name)
+    }
+
+    /// Returns the directory the generated source files are written to.
+    #[must_use]
+    pub(crate) fn output_dir(&self) -> PathBuf {
+        self.options
             .output_dir
             .clone()
-            .unwrap_or(self.cargo_toml_path.parent().unwrap().join("src"));
-        let migration_path = src_path.join("migrations");
-        let migration_file = migration_path.join(format!("{}.rs", migration.name));
+            .unwrap_or(self.cargo_toml_path.parent().unwrap().join("src"))
+    }
+
+    /// Returns the path of the file given migration is written to.
+    #[must_use]
+    pub(crate) fn migration_file_path(&self, migration: &MigrationAsSource) -> PathBuf {
+        self.output_dir()
+            .join("migrations")
+            .join(format!("{}.rs", migration.name))
+    }
+
+    pub(crate) fn write_migration(&self, migration: &MigrationAsSource) -> anyhow::Result<()> {
+        let migration_path = self.output_dir().join("migrations");
+        let migration_file = self.migration_file_path(migration);
 
         std::fs::create_dir_all(&migration_path).with_context(|| {
             format!(
@@ -499,7 +842,7 @@ impl MigrationGenerator {
     fn model_to_migration_model(model: &ModelInSource) -> TokenStream {
         let mut model_source = model.model_item.clone();
         model_source.vis = syn::Visibility::Inherited;
-        model_source.ident = format_ident!("_{}", model_source.ident);
+        model_source.ident = format_ident!("_{}", model.model.original_name);
         model_source.attrs.clear();
         model_source
             .attrs
@@ -577,8 +920,15 @@ impl MigrationProcessor {
     /// necessary operations.
     #[must_use]
     fn latest_models(&self) -> Vec<ModelInSource> {
+        self.latest_models_until(self.migrations.len())
+    }
+
+    /// Returns the latest versions of the migration models, taking into
+    /// account only the migrations before the given index.
+    #[must_use]
+    fn latest_models_until(&self, index: usize) -> Vec<ModelInSource> {
         let mut migration_models: HashMap<String, &ModelInSource> = HashMap::new();
-        for migration in &self.migrations {
+        for migration in &self.migrations[..index] {
             for model in &migration.models {
                 migration_models.insert(model.model.table_name.clone(), model);
             }
@@ -593,15 +943,7 @@ impl MigrationProcessor {
         }
 
         let last_migration = self.migrations.last().unwrap();
-        let last_migration_number = last_migration
-            .name
-            .split('_')
-            .nth(1)
-            .with_context(|| format!("migration number not found: {}", last_migration.name))?
-            .parse::<u32>()
-            .with_context(|| {
-                format!("unable to parse migration number: {}", last_migration.name)
-            })?;
+        let (_, last_migration_number) = migration_number_range(&last_migration.name)?;
 
         let migration_number = last_migration_number + 1;
         let now = chrono::Utc::now();
@@ -610,21 +952,249 @@ impl MigrationProcessor {
         Ok(format!("m_{migration_number:04}_auto_{date_time}"))
     }
 
+    /// Returns the migrations that no other migration depends on.
+    ///
+    /// Migrations replaced by a squashed migration are never leaves; the
+    /// dependencies on them are treated as dependencies on the squashed
+    /// migration instead.
+    #[must_use]
+    fn leaf_migrations(&self) -> Vec<&Migration> {
+        let replaced_by: HashMap<&str, &str> = self
+            .migrations
+            .iter()
+            .flat_map(|migration| {
+                migration
+                    .replaces
+                    .iter()
+                    .map(|replaced| (replaced.as_str(), migration.name.as_str()))
+            })
+            .collect();
+        let depended_on: HashSet<&str> = self
+            .migrations
+            .iter()
+            .flat_map(|migration| &migration.dependencies)
+            .map(|dependency| {
+                replaced_by
+                    .get(dependency.as_str())
+                    .copied()
+                    .unwrap_or(dependency)
+            })
+            .collect();
+
+        self.migrations
+            .iter()
+            .filter(|migration| {
+                !replaced_by.contains_key(migration.name.as_str())
+                    && !depended_on.contains(migration.name.as_str())
+            })
+            .collect()
+    }
+
+    /// Returns an error if there is more than one leaf migration, as new
+    /// migrations can't be generated until the divergent migrations are
+    /// merged.
+    fn ensure_single_leaf(&self) -> anyhow::Result<()> {
+        let leaves = self.leaf_migrations();
+        if leaves.len() > 1 {
+            let names: Vec<_> = leaves
+                .iter()
+                .map(|migration| migration.name.as_str())
+                .collect();
+            bail!(
+                "multiple leaf migrations found: {}; run `cot make-migrations --merge` to merge \
+                them",
+                names.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of the migration that merges the divergent migrations.
+    fn merge_migration_name(&self) -> anyhow::Result<String> {
+        let mut last_number = 0;
+        for migration in &self.migrations {
+            let (_, number) = migration_number_range(&migration.name)?;
+            last_number = last_number.max(number);
+        }
+
+        Ok(format!("m_{:04}_merge", last_number + 1))
+    }
+
+    /// Returns the versions of the migration models after merging the given
+    /// divergent migrations, for the models changed by any of them.
+    ///
+    /// A model changed on a single branch is taken as is; the changes made to
+    /// a model on several branches are applied, field by field, to the
+    /// version of the model in the migrations shared by all the branches.
+    #[must_use]
+    fn merged_models(&self, leaves: &[&Migration]) -> Vec<ModelInSource> {
+        let histories: Vec<_> = leaves
+            .iter()
+            .map(|leaf| self.ancestors(&leaf.name))
+            .collect();
+        let common: HashSet<usize> = histories[0]
+            .iter()
+            .filter(|index| histories[1..].iter().all(|history| history.contains(index)))
+            .copied()
+            .collect();
+        let base_models = self.latest_models_in(&common);
+
+        let mut merged_models: Vec<ModelInSource> = Vec::new();
+        for history in &histories {
+            for (table_name, model) in self.latest_models_in(history) {
+                let base_model = base_models.get(&table_name).copied();
+                if base_model == Some(model) {
+                    continue;
+                }
+
+                match merged_models
+                    .iter_mut()
+                    .find(|merged| merged.model.table_name == table_name)
+                {
+                    Some(merged) => merged.merge_changes(base_model, model),
+                    None => merged_models.push(model.clone()),
+                }
+            }
+        }
+
+        merged_models.sort_by(|a, b| a.model.table_name.cmp(&b.model.table_name));
+        merged_models
+    }
+
+    /// Returns the indices of the migration with given name and of all the
+    /// migrations it (transitively) depends on.
+    #[must_use]
+    fn ancestors(&self, name: &str) -> HashSet<usize> {
+        let index_of = |name: &str| {
+            self.migrations
+                .iter()
+                .position(|migration| migration.name == name)
+                .or_else(|| {
+                    self.migrations
+                        .iter()
+                        .position(|migration| migration.replaces.iter().any(|r| r == name))
+                })
+        };
+
+        let mut ancestors = HashSet::new();
+        let mut to_visit: Vec<_> = index_of(name).into_iter().collect();
+        while let Some(index) = to_visit.pop() {
+            if ancestors.insert(index) {
+                to_visit.extend(
+                    self.migrations[index]
+                        .dependencies
+                        .iter()
+                        .filter_map(|dependency| index_of(dependency)),
+                );
+            }
+        }
+
+        ancestors
+    }
+
+    /// Returns the latest versions of the migration models, taking into
+    /// account only the migrations with given indices, by table name.
+    #[must_use]
+    fn latest_models_in(&self, indices: &HashSet<usize>) -> HashMap<String, &ModelInSource> {
+        let mut migration_models = HashMap::new();
+        for (index, migration) in self.migrations.iter().enumerate() {
+            if indices.contains(&index) {
+                for model in &migration.models {
+                    migration_models.insert(model.model.table_name.clone(), model);
+                }
+            }
+        }
+
+        migration_models
+    }
+
+    /// Returns the name of the migration that replaces the migrations between
+    /// given indices (inclusive).
+    fn squashed_migration_name(&self, start: usize, end: usize) -> anyhow::Result<String> {
+        let (first_number, _) = migration_number_range(&self.migrations[start].name)?;
+        let (_, last_number) = migration_number_range(&self.migrations[end].name)?;
+
+        Ok(format!("m_{first_number:04}_squashed_{last_number:04}"))
+    }
+
+    /// Returns the names of the migrations between given indices (inclusive).
+    #[must_use]
+    fn migration_names(&self, start: usize, end: usize) -> Vec<String> {
+        self.migrations[start..=end]
+            .iter()
+            .map(|migration| migration.name.clone())
+            .collect()
+    }
+
+    /// Returns the index of the migration with given name, or with a name
+    /// starting with given prefix followed by an underscore.
+    fn find_migration(&self, name: &str) -> anyhow::Result<usize> {
+        let prefix = format!("{name}_");
+        let matching: Vec<_> = self
+            .migrations
+            .iter()
+            .enumerate()
+            .filter(|(_, migration)| migration.name == name || migration.name.starts_with(&prefix))
+            .collect();
+
+        match matching.as_slice() {
+            [] => bail!("migration not found: {name}"),
+            [(index, _)] => Ok(*index),
+            _ => {
+                let names: Vec<_> = matching
+                    .iter()
+                    .map(|(_, migration)| migration.name.as_str())
+                    .collect();
+                bail!(
+                    "migration name is ambiguous: {name} (matches: {})",
+                    names.join(", ")
+                )
+            }
+        }
+    }
+
     /// Returns the list of dependencies for the next migration, based on the
     /// already existing and processed migrations.
     fn base_dependencies(&self) -> Vec<DynDependency> {
-        if self.migrations.is_empty() {
+        self.dependencies_before(self.migrations.len())
+    }
+
+    /// Returns the list of dependencies for a migration that is placed
+    /// before the migration with given index.
+    fn dependencies_before(&self, index: usize) -> Vec<DynDependency> {
+        if index == 0 {
             return Vec::new();
         }
 
-        let last_migration = self.migrations.last().unwrap();
+        let previous_migration = &self.migrations[index - 1];
         vec![DynDependency::Migration {
-            app: last_migration.app_name.clone(),
-            migration: last_migration.name.clone(),
+            app: previous_migration.app_name.clone(),
+            migration: previous_migration.name.clone(),
         }]
     }
 }
 
+/// Returns the numbers of the first and the last migration covered by the
+/// migration with given name. These are equal unless the migration is a
+/// squashed migration (e.g. `m_0001_squashed_0005`).
+fn migration_number_range(name: &str) -> anyhow::Result<(u32, u32)> {
+    let parse_number = |number: Option<&str>| {
+        number
+            .with_context(|| format!("migration number not found: {name}"))?
+            .parse::<u32>()
+            .with_context(|| format!("unable to parse migration number: {name}"))
+    };
+
+    let first_number = parse_number(name.split('_').nth(1))?;
+    let last_number = match name.split_once("_squashed_") {
+        Some((_, rest)) => parse_number(rest.split('_').next())?,
+        None => first_number,
+    };
+
+    Ok((first_number, last_number))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ModelInSource {
     model_item: syn::ItemStruct,
@@ -647,6 +1217,68 @@ impl ModelInSource {
             model,
         })
     }
+
+    /// Applies the changes made to the fields of the `base` version of this
+    /// model (or to no fields, if the model didn't exist) in the `changed`
+    /// version.
+    fn merge_changes(&mut self, base: Option<&Self>, changed: &Self) {
+        let base_fields = base.map_or(&[][..], |base| base.model.fields.as_slice());
+
+        for base_field in base_fields {
+            if !changed
+                .model
+                .fields
+                .iter()
+                .any(|field| field.field_name == base_field.field_name)
+            {
+                self.remove_field(&base_field.field_name);
+            }
+        }
+        for (item_field, field) in changed.model_item.fields.iter().zip(&changed.model.fields) {
+            if !base_fields.contains(field) {
+                self.set_field(item_field, field);
+            }
+        }
+        if base.is_none_or(|base| base.model.pk_field != changed.model.pk_field) {
+            self.model.pk_field = changed.model.pk_field.clone();
+        }
+    }
+
+    fn remove_field(&mut self, name: &syn::Ident) {
+        if let Some(index) = self.field_index(name) {
+            self.model.fields.remove(index);
+            if let syn::Fields::Named(fields) = &mut self.model_item.fields {
+                fields.named = std::mem::take(&mut fields.named)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(field_index, _)| field_index != index)
+                    .map(|(_, field)| field)
+                    .collect();
+            }
+        }
+    }
+
+    fn set_field(&mut self, item_field: &syn::Field, field: &Field) {
+        let index = self.field_index(&field.field_name);
+        let syn::Fields::Named(fields) = &mut self.model_item.fields else {
+            return;
+        };
+        if let Some(index) = index {
+            self.model.fields[index] = field.clone();
+            fields.named[index] = item_field.clone();
+        } else {
+            self.model.fields.push(field.clone());
+            fields.named.push(item_field.clone());
+        }
+    }
+
+    #[must_use]
+    fn field_index(&self, name: &syn::Ident) -> Option<usize> {
+        self.model
+            .fields
+            .iter()
+            .position(|field| &field.field_name == name)
+    }
 }
 
 /// A migration generated by the CLI and before converting to a Rust
@@ -657,6 +1289,9 @@ pub struct GeneratedMigration {
     pub modified_models: Vec<ModelInSource>,
     pub dependencies: Vec<DynDependency>,
     pub operations: Vec<DynOperation>,
+    /// Names of the migrations replaced by this migration, if this is a
+    /// squashed migration.
+    pub replaces: Vec<String>,
 }
 
 impl GeneratedMigration {
@@ -676,6 +1311,7 @@ impl GeneratedMigration {
             modified_models,
             dependencies,
             operations,
+            replaces: Vec::new(),
         }
     }
 
@@ -944,6 +1580,85 @@ struct Migration {
     app_name: String,
     name: String,
     models: Vec<ModelInSource>,
+    /// Names of the migrations of the same app this migration depends on.
+    dependencies: Vec<String>,
+    /// Names of the migrations replaced by this migration.
+    replaces: Vec<String>,
+}
+
+/// The information read from the `Migration` trait implementation in a
+/// migration file.
+#[derive(Debug, Clone, Default)]
+struct MigrationImpl {
+    dependencies: Vec<String>,
+    replaces: Vec<String>,
+}
+
+/// Returns whether given item is an implementation of the `Migration` trait
+/// for the `Migration` struct, as generated in the migration files.
+fn is_migration_impl(item_impl: &syn::ItemImpl) -> bool {
+    let is_migration_path = |path: &syn::Path| {
+        path.segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Migration")
+    };
+
+    let implements_migration = item_impl
+        .trait_
+        .as_ref()
+        .is_some_and(|(_, path, _)| is_migration_path(path));
+    let is_migration_struct =
+        matches!(&*item_impl.self_ty, syn::Type::Path(ty) if is_migration_path(&ty.path));
+
+    implements_migration && is_migration_struct
+}
+
+/// Returns the elements of an array literal, possibly behind a reference
+/// (e.g. `&[a, b]`).
+fn array_elements(expr: &syn::Expr) -> impl Iterator<Item = &syn::Expr> {
+    let expr = match expr {
+        syn::Expr::Reference(reference) => &*reference.expr,
+        _ => expr,
+    };
+
+    let elements = match expr {
+        syn::Expr::Array(array) => Some(array.elems.iter()),
+        _ => None,
+    };
+    elements.into_iter().flatten()
+}
+
+/// Returns the value of a string literal expression.
+fn string_from_expr(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+/// Returns the app and migration names from a
+/// `MigrationDependency::migration(app, migration)` call expression.
+fn migration_dependency_from_expr(expr: &syn::Expr) -> Option<(String, String)> {
+    let syn::Expr::Call(call) = expr else {
+        return None;
+    };
+    let syn::Expr::Path(func) = &*call.func else {
+        return None;
+    };
+    if func.path.segments.last()?.ident != "migration" {
+        return None;
+    }
+
+    let mut args = call.args.iter();
+    match (args.next(), args.next(), args.next()) {
+        (Some(app), Some(migration), None) => {
+            Some((string_from_expr(app)?, string_from_expr(migration)?))
+        }
+        _ => None,
+    }
 }
 
 impl DynMigration for Migration {
@@ -1012,6 +1727,17 @@ pub enum DynOperation {
     },
 }
 
+impl Display for DynOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateModel { table_name, .. } => write!(f, "create model `{table_name}`"),
+            Self::AddField {
+                table_name, field, ..
+            } => write!(f, "add field `{table_name}.{}`", field.column_name),
+        }
+    }
+}
+
 /// Returns whether given [`Field`] is a foreign key to given type.
 fn is_field_foreign_key_to(field: &Field, ty: &syn::Type) -> bool {
     foreign_key_for_field(field).is_some_and(|to_model| &to_model == ty)
@@ -1123,6 +1849,8 @@ mod tests {
             app_name: "app1".to_string(),
             name: "m0001_initial".to_string(),
             models: vec![],
+            dependencies: vec![],
+            replaces: vec![],
         }];
         let processor = MigrationProcessor::new(migrations).unwrap();
 
@@ -1136,6 +1864,133 @@ mod tests {
         );
     }
 
+    #[test]
+    fn migration_processor_next_migration_name_after_squashed() {
+        let migrations = vec![Migration {
+            app_name: "app1".to_string(),
+            name: "m_0001_squashed_0004".to_string(),
+            models: vec![],
+            dependencies: vec![],
+            replaces: vec![],
+        }];
+        let processor = MigrationProcessor::new(migrations).unwrap();
+
+        let next_migration_name = processor.next_migration_name().unwrap();
+        assert!(next_migration_name.starts_with("m_0005_auto_"));
+    }
+
+    fn test_migration(name: &str, dependencies: &[&str], replaces: &[&str]) -> Migration {
+        Migration {
+            app_name: "app1".to_string(),
+            name: name.to_string(),
+            models: vec![],
+            dependencies: dependencies.iter().map(ToString::to_string).collect(),
+            replaces: replaces.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn leaf_names(processor: &MigrationProcessor) -> Vec<&str> {
+        processor
+            .leaf_migrations()
+            .into_iter()
+            .map(|migration| migration.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn migration_processor_leaf_migrations_linear() {
+        let migrations = vec![
+            test_migration("m_0001_initial", &[], &[]),
+            test_migration("m_0002_auto", &["m_0001_initial"], &[]),
+        ];
+        let processor = MigrationProcessor::new(migrations).unwrap();
+
+        assert_eq!(leaf_names(&processor), vec!["m_0002_auto"]);
+        assert!(processor.ensure_single_leaf().is_ok());
+    }
+
+    #[test]
+    fn migration_processor_leaf_migrations_divergent() {
+        let migrations = vec![
+            test_migration("m_0001_initial", &[], &[]),
+            test_migration("m_0002_auto_a", &["m_0001_initial"], &[]),
+            test_migration("m_0002_auto_b", &["m_0001_initial"], &[]),
+        ];
+        let processor = MigrationProcessor::new(migrations).unwrap();
+
+        assert_eq!(
+            leaf_names(&processor),
+            vec!["m_0002_auto_a", "m_0002_auto_b"]
+        );
+        assert_eq!(
+            processor.ensure_single_leaf().unwrap_err().to_string(),
+            "multiple leaf migrations found: m_0002_auto_a, m_0002_auto_b; run `cot \
+            make-migrations --merge` to merge them"
+        );
+        assert_eq!(processor.merge_migration_name().unwrap(), "m_0003_merge");
+    }
+
+    #[test]
+    fn migration_processor_leaf_migrations_squashed() {
+        let migrations = vec![
+            test_migration("m_0001_initial", &[], &[]),
+            test_migration("m_0002_auto", &["m_0001_initial"], &[]),
+            test_migration(
+                "m_0001_squashed_0002",
+                &[],
+                &["m_0001_initial", "m_0002_auto"],
+            ),
+            test_migration("m_0003_auto", &["m_0002_auto"], &[]),
+        ];
+        let processor = MigrationProcessor::new(migrations).unwrap();
+
+        assert_eq!(leaf_names(&processor), vec!["m_0003_auto"]);
+    }
+
+    #[test]
+    fn migration_processor_find_migration() {
+        let migrations = vec![
+            Migration {
+                app_name: "app1".to_string(),
+                name: "m_0001_initial".to_string(),
+                models: vec![],
+                dependencies: vec![],
+                replaces: vec![],
+            },
+            Migration {
+                app_name: "app1".to_string(),
+                name: "m_0002_auto_20250101_000000".to_string(),
+                models: vec![],
+                dependencies: vec![],
+                replaces: vec![],
+            },
+        ];
+        let processor = MigrationProcessor::new(migrations).unwrap();
+
+        assert_eq!(processor.find_migration("m_0001_initial").unwrap(), 0);
+        assert_eq!(processor.find_migration("m_0002").unwrap(), 1);
+        assert!(processor.find_migration("m_0003").is_err());
+        assert!(processor.find_migration("m").is_err());
+        assert_eq!(
+            processor.squashed_migration_name(0, 1).unwrap(),
+            "m_0001_squashed_0002"
+        );
+    }
+
+    #[test]
+    fn migration_number_range_regular_and_squashed() {
+        assert_eq!(migration_number_range("m_0001_initial").unwrap(), (1, 1));
+        assert_eq!(
+            migration_number_range("m_0003_auto_20250101_000000").unwrap(),
+            (3, 3)
+        );
+        assert_eq!(
+            migration_number_range("m_0002_squashed_0007").unwrap(),
+            (2, 7)
+        );
+        assert!(migration_number_range("initial").is_err());
+    }
+
     #[test]
     fn toposort_operations() {
         let mut operations = vec![
@@ -0,0 +1,225 @@
+use cot::db::migrations::{Field, Migration, MigrationDependency, MigrationEngine, Operation};
+use cot::db::{Database, DatabaseField, Identifier};
+use cot_cli::inspect_db::{generate_models, inspect_db};
+use cot_cli::migration_generator::{make_migrations, MigrationGeneratorOptions};
+
+const CREATE_TABLES: &[&str] = &[
+    "CREATE TABLE author (id INTEGER PRIMARY KEY, email VARCHAR(100) NOT NULL UNIQUE)",
+    "CREATE TABLE book (\
+        id INTEGER PRIMARY KEY, \
+        author INTEGER NOT NULL REFERENCES author(id), \
+        title TEXT NULL, \
+        published DATE NOT NULL\
+    )",
+    "CREATE TABLE no_primary_key (name TEXT NOT NULL)",
+];
+
+async fn create_tables(database: &Database) {
+    for statement in CREATE_TABLES {
+        database.raw(statement).await.unwrap();
+    }
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+async fn generate_models_sqlite() {
+    let database = Database::new("sqlite::memory:").await.unwrap();
+    create_tables(&database).await;
+
+    let tables = database.introspect().await.unwrap();
+    let models = generate_models(&tables).unwrap();
+
+    assert!(models.contains("use cot::db::{Auto, ForeignKey, LimitedString, model};"));
+    assert!(models.contains(
+        "#[model(table_name = \"author\")]\npub struct Author {\n    id: Auto<i32>,\n    \
+        #[model(unique)]\n    email: LimitedString<100>,\n}"
+    ));
+    assert!(models.contains(
+        "#[model(table_name = \"book\")]\npub struct Book {\n    id: Auto<i32>,\n    \
+        author: ForeignKey<Author>,\n    title: Option<String>,\n    \
+        published: chrono::NaiveDate,\n}"
+    ));
+    assert!(!models.contains("NoPrimaryKey"));
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+async fn generate_models_no_tables() {
+    let database = Database::new("sqlite::memory:").await.unwrap();
+
+    let tables = database.introspect().await.unwrap();
+    let result = generate_models(&tables);
+
+    assert!(result.is_err());
+}
+
+struct InitialMigration;
+
+impl Migration for InitialMigration {
+    const APP_NAME: &'static str = "my_crate";
+    const MIGRATION_NAME: &'static str = "m_0001_initial";
+    const DEPENDENCIES: &'static [MigrationDependency] = &[];
+    const OPERATIONS: &'static [Operation] = &[Operation::create_model()
+        .table_name(Identifier::new("author"))
+        .fields(&[
+            Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+                .primary_key()
+                .auto(),
+        ])
+        .build()];
+}
+
+/// Creates a crate in a temporary directory, along with a database containing
+/// the test tables, returning the directory and the URL of the database.
+fn create_crate(runtime: &tokio::runtime::Runtime) -> (tempfile::TempDir, String) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"my_crate\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+    std::fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    let database_url = format!(
+        "sqlite://{}?mode=rwc",
+        temp_dir.path().join("db.sqlite3").display()
+    );
+
+    runtime.block_on(async {
+        let database = Database::new(&database_url).await.unwrap();
+        create_tables(&database).await;
+        database.close().await.unwrap();
+    });
+
+    (temp_dir, database_url)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+fn inspect_db_writes_models_and_migration() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (temp_dir, database_url) = create_crate(&runtime);
+
+    inspect_db(
+        temp_dir.path(),
+        &database_url,
+        MigrationGeneratorOptions::default(),
+    )
+    .unwrap();
+
+    let models = std::fs::read_to_string(temp_dir.path().join("src/models.rs")).unwrap();
+    assert!(models.contains("pub struct Author"));
+    let migration =
+        std::fs::read_to_string(temp_dir.path().join("src/migrations/m_0001_initial.rs")).unwrap();
+    assert!(migration.contains("Identifier::new(\"author\")"));
+    assert!(migration.contains("Identifier::new(\"book\")"));
+
+    // the migration is already applied, so the table isn't created again
+    runtime.block_on(async {
+        let database = Database::new(&database_url).await.unwrap();
+        MigrationEngine::new([InitialMigration])
+            .unwrap()
+            .run(&database)
+            .await
+            .unwrap();
+    });
+
+    // the models file is never overwritten
+    assert!(inspect_db(
+        temp_dir.path(),
+        &database_url,
+        MigrationGeneratorOptions::default(),
+    )
+    .is_err());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+fn inspect_db_output_dir() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (temp_dir, database_url) = create_crate(&runtime);
+
+    // the models outside the source directory couldn't be referred to from the
+    // crate, so nothing is written
+    let result = inspect_db(
+        temp_dir.path(),
+        &database_url,
+        MigrationGeneratorOptions {
+            output_dir: Some(temp_dir.path().join("generated")),
+            ..MigrationGeneratorOptions::default()
+        },
+    );
+    assert!(result.is_err());
+    assert!(!temp_dir.path().join("generated").exists());
+
+    inspect_db(
+        temp_dir.path(),
+        &database_url,
+        MigrationGeneratorOptions {
+            output_dir: Some(temp_dir.path().join("src/legacy")),
+            ..MigrationGeneratorOptions::default()
+        },
+    )
+    .unwrap();
+    assert!(temp_dir.path().join("src/legacy/models.rs").exists());
+    assert!(temp_dir
+        .path()
+        .join("src/legacy/migrations/m_0001_initial.rs")
+        .exists());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+fn inspect_db_existing_models() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (temp_dir, database_url) = create_crate(&runtime);
+    std::fs::write(
+        temp_dir.path().join("src/main.rs"),
+        "#[cot::db::model]\nstruct Article {\n    id: cot::db::Auto<i32>,\n}\n\nfn main() {}\n",
+    )
+    .unwrap();
+
+    // the existing model doesn't have a migration yet, so nothing is written
+    let result = inspect_db(
+        temp_dir.path(),
+        &database_url,
+        MigrationGeneratorOptions::default(),
+    );
+    assert!(result.is_err());
+    assert!(!temp_dir.path().join("src/models.rs").exists());
+    assert!(!temp_dir.path().join("src/migrations").exists());
+
+    make_migrations(temp_dir.path(), MigrationGeneratorOptions::default()).unwrap();
+    inspect_db(
+        temp_dir.path(),
+        &database_url,
+        MigrationGeneratorOptions::default(),
+    )
+    .unwrap();
+
+    // the migration only contains the inspected tables
+    let migration_path = std::fs::read_dir(temp_dir.path().join("src/migrations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            path.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("m_0002_")
+        })
+        .unwrap();
+    let migration = std::fs::read_to_string(migration_path).unwrap();
+    assert!(migration.contains("Identifier::new(\"author\")"));
+    assert!(migration.contains("Identifier::new(\"book\")"));
+    assert!(!migration.contains("Identifier::new(\"article\")"));
+    assert!(migration.contains("\"m_0001_initial\""));
+}
@@ -155,6 +155,268 @@ fn create_models_foreign_key_two_migrations() {
     assert_eq!(table_name, "child");
 }
 
+#[test]
+fn check_migrations_foreign_key_two_migrations() {
+    let mut generator = test_generator();
+
+    let src = include_str!("migration_generator/foreign_key_two_migrations/step_1.rs");
+    let source_files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+    let migration_file = generator
+        .generate_migrations_to_write(source_files)
+        .unwrap()
+        .unwrap();
+
+    let source_files = vec![
+        SourceFile::parse(PathBuf::from("main.rs"), src).unwrap(),
+        SourceFile::parse(PathBuf::from(&migration_file.name), &migration_file.content).unwrap(),
+    ];
+    generator.check_migrations(source_files).unwrap();
+
+    let src = include_str!("migration_generator/foreign_key_two_migrations/step_2.rs");
+    let source_files = vec![
+        SourceFile::parse(PathBuf::from("main.rs"), src).unwrap(),
+        SourceFile::parse(PathBuf::from(&migration_file.name), &migration_file.content).unwrap(),
+    ];
+    let error = generator.check_migrations(source_files).unwrap_err();
+
+    let message = error.to_string();
+    assert!(message.contains("`m_0002_auto_"), "{message}");
+    assert!(
+        message.ends_with(":\n  - create model `child`"),
+        "{message}"
+    );
+}
+
+#[test]
+fn merge_migrations_divergent() {
+    let mut generator = test_generator();
+
+    let src = include_str!("migration_generator/foreign_key_two_migrations/step_1.rs");
+    let source_files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+    let initial_migration = generator
+        .generate_migrations_to_write(source_files)
+        .unwrap()
+        .unwrap();
+
+    let src = include_str!("migration_generator/foreign_key_two_migrations/step_2.rs");
+    let source_files = vec![
+        SourceFile::parse(PathBuf::from("main.rs"), src).unwrap(),
+        SourceFile::parse(
+            PathBuf::from(&initial_migration.name),
+            &initial_migration.content,
+        )
+        .unwrap(),
+    ];
+    let branch_migration = generator
+        .generate_migrations_to_write(source_files)
+        .unwrap()
+        .unwrap();
+
+    // the same migration created on two branches under different names
+    let migration_files = [
+        (initial_migration.name.clone(), &initial_migration.content),
+        ("m_0002_branch_a".to_string(), &branch_migration.content),
+        ("m_0002_branch_b".to_string(), &branch_migration.content),
+    ];
+    let source_files = |extra: Option<&MigrationAsSource>| {
+        let mut files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+        for (name, content) in &migration_files {
+            files.push(SourceFile::parse(PathBuf::from(name), content).unwrap());
+        }
+        if let Some(migration) = extra {
+            files.push(
+                SourceFile::parse(PathBuf::from(&migration.name), &migration.content).unwrap(),
+            );
+        }
+        files
+    };
+
+    let error = generator
+        .generate_migrations(source_files(None))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "multiple leaf migrations found: m_0002_branch_a, m_0002_branch_b; run `cot \
+        make-migrations --merge` to merge them"
+    );
+
+    let migration = generator
+        .generate_merge_migration(source_files(None))
+        .unwrap();
+    assert_eq!(migration.migration_name, "m_0003_merge");
+    assert!(migration.operations.is_empty());
+    assert_eq!(
+        migration.dependencies,
+        vec![
+            DynDependency::Migration {
+                app: "my_crate".to_string(),
+                migration: "m_0002_branch_a".to_string(),
+            },
+            DynDependency::Migration {
+                app: "my_crate".to_string(),
+                migration: "m_0002_branch_b".to_string(),
+            },
+        ]
+    );
+
+    let merge_migration = generator
+        .generate_merge_migration_to_write(source_files(None))
+        .unwrap();
+    let migration = generator
+        .generate_migrations(source_files(Some(&merge_migration)))
+        .unwrap();
+    assert!(migration.is_none());
+    assert!(generator
+        .generate_merge_migration(source_files(Some(&merge_migration)))
+        .is_err());
+}
+
+#[test]
+fn merge_migrations_same_model() {
+    let mut generator = test_generator();
+    let migration_from =
+        |generator: &mut MigrationGenerator, src: &str, previous: &[&MigrationAsSource]| {
+            let mut source_files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+            for migration in previous {
+                source_files.push(
+                    SourceFile::parse(PathBuf::from(&migration.name), &migration.content).unwrap(),
+                );
+            }
+            generator
+                .generate_migrations_to_write(source_files)
+                .unwrap()
+                .unwrap()
+        };
+
+    let initial_migration = migration_from(
+        &mut generator,
+        include_str!("migration_generator/merge_same_model/initial.rs"),
+        &[],
+    );
+    // each branch adds a different field to the same model
+    let mut branch_a = migration_from(
+        &mut generator,
+        include_str!("migration_generator/merge_same_model/branch_a.rs"),
+        &[&initial_migration],
+    );
+    branch_a.name = "m_0002_branch_a".to_string();
+    let mut branch_b = migration_from(
+        &mut generator,
+        include_str!("migration_generator/merge_same_model/branch_b.rs"),
+        &[&initial_migration],
+    );
+    branch_b.name = "m_0002_branch_b".to_string();
+
+    let src = include_str!("migration_generator/merge_same_model/merged.rs");
+    let source_files = |extra: Option<&MigrationAsSource>| {
+        let mut files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+        for migration in [&initial_migration, &branch_a, &branch_b]
+            .into_iter()
+            .chain(extra)
+        {
+            files.push(
+                SourceFile::parse(PathBuf::from(&migration.name), &migration.content).unwrap(),
+            );
+        }
+        files
+    };
+
+    let migration = generator
+        .generate_merge_migration(source_files(None))
+        .unwrap();
+    assert!(migration.operations.is_empty());
+    assert_eq!(migration.modified_models.len(), 1);
+
+    let merge_migration = generator
+        .generate_merge_migration_to_write(source_files(None))
+        .unwrap();
+    assert!(merge_migration.content.contains("author: String"));
+    assert!(merge_migration.content.contains("views: i32"));
+    let migration = generator
+        .generate_migrations(source_files(Some(&merge_migration)))
+        .unwrap();
+    assert!(migration.is_none());
+}
+
+#[test]
+fn squash_migrations_foreign_key_two_migrations() {
+    let mut generator = test_generator();
+
+    let src = include_str!("migration_generator/foreign_key_two_migrations/step_1.rs");
+    let source_files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+    let migration_file_1 = generator
+        .generate_migrations_to_write(source_files)
+        .unwrap()
+        .unwrap();
+
+    let src = include_str!("migration_generator/foreign_key_two_migrations/step_2.rs");
+    let source_files = vec![
+        SourceFile::parse(PathBuf::from("main.rs"), src).unwrap(),
+        SourceFile::parse(
+            PathBuf::from(&migration_file_1.name),
+            &migration_file_1.content,
+        )
+        .unwrap(),
+    ];
+    let migration_file_2 = generator
+        .generate_migrations_to_write(source_files)
+        .unwrap()
+        .unwrap();
+
+    let source_files = vec![
+        SourceFile::parse(PathBuf::from("main.rs"), src).unwrap(),
+        SourceFile::parse(
+            PathBuf::from(&migration_file_1.name),
+            &migration_file_1.content,
+        )
+        .unwrap(),
+        SourceFile::parse(
+            PathBuf::from(&migration_file_2.name),
+            &migration_file_2.content,
+        )
+        .unwrap(),
+    ];
+    let migration = generator
+        .generate_squashed_migration(source_files, None, "m_0002")
+        .unwrap();
+
+    assert_eq!(migration.migration_name, "m_0001_squashed_0002");
+    assert_eq!(
+        migration.replaces,
+        vec!["m_0001_initial".to_string(), migration_file_2.name.clone()]
+    );
+    // the foreign key points to a model created in the same migration
+    assert!(migration.dependencies.is_empty());
+    assert_eq!(migration.modified_models.len(), 2);
+
+    assert_eq!(migration.operations.len(), 2);
+    let (table_name, _fields) = unwrap_create_model(&migration.operations[0]);
+    assert_eq!(table_name, "parent");
+    let (table_name, fields) = unwrap_create_model(&migration.operations[1]);
+    assert_eq!(table_name, "child");
+    assert_eq!(fields.len(), 2);
+}
+
+#[test]
+fn squash_migrations_single_migration() {
+    let mut generator = test_generator();
+
+    let src = include_str!("migration_generator/create_model.rs");
+    let source_files = vec![SourceFile::parse(PathBuf::from("main.rs"), src).unwrap()];
+    let migration_file = generator
+        .generate_migrations_to_write(source_files)
+        .unwrap()
+        .unwrap();
+
+    let source_files = vec![
+        SourceFile::parse(PathBuf::from("main.rs"), src).unwrap(),
+        SourceFile::parse(PathBuf::from(&migration_file.name), &migration_file.content).unwrap(),
+    ];
+    let result = generator.generate_squashed_migration(source_files, None, "m_0001");
+
+    assert!(result.is_err());
+}
+
 /// Test that the migration generator can generate a "create model" migration
 /// for a given model which compiles successfully.
 #[test]
@@ -0,0 +1,10 @@
+use cot::db::{model, Auto};
+
+#[model]
+struct Post {
+    id: Auto<i32>,
+    title: String,
+    views: i32,
+}
+
+fn main() {}
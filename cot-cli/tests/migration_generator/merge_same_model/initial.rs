@@ -0,0 +1,9 @@
+use cot::db::{model, Auto};
+
+#[model]
+struct Post {
+    id: Auto<i32>,
+    title: String,
+}
+
+fn main() {}
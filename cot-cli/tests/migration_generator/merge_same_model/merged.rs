@@ -0,0 +1,11 @@
+use cot::db::{model, Auto};
+
+#[model]
+struct Post {
+    id: Auto<i32>,
+    title: String,
+    author: String,
+    views: i32,
+}
+
+fn main() {}
@@ -0,0 +1,700 @@
+// The impls derived by darling contain redundant `continue`s; they're separate
+// items, so an `#[allow]` on the options structs doesn't apply to them
+#![allow(clippy::needless_continue)]
+
+use convert_case::{Case, Casing};
+use darling::util::PathList;
+use darling::{FromDeriveInput, FromField, FromMeta};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::cot_ident;
+
+/// The default name of the primary key field, which, just like a field
+/// annotated with `#[model(primary_key)]`, is always read-only.
+const ID_FIELD_NAME: &str = "id";
+
+pub(super) fn impl_admin_model_for_struct(ast: &syn::DeriveInput) -> TokenStream {
+    let opts = match AdminModelOpts::from_derive_input(ast) {
+        Ok(val) => val,
+        Err(err) => {
+            return err.write_errors();
+        }
+    };
+
+    match AdminModelBuilder::from_opts(&opts) {
+        Ok(builder) => builder.build_admin_model_impl(),
+        Err(err) => err.write_errors(),
+    }
+}
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(
+    attributes(admin),
+    forward_attrs(allow, doc, cfg),
+    supports(struct_named)
+)]
+struct AdminModelOpts {
+    ident: syn::Ident,
+    data: darling::ast::Data<darling::util::Ignored, AdminModelField>,
+    name: Option<String>,
+    url_name: Option<String>,
+    display: Option<syn::Ident>,
+    #[darling(default)]
+    list_columns: PathList,
+    #[darling(default)]
+    search_fields: PathList,
+    #[darling(default)]
+    read_only: PathList,
+    #[darling(default)]
+    filters: PathList,
+    #[darling(default)]
+    actions: PathList,
+    #[darling(multiple)]
+    inline: Vec<AdminInlineOpts>,
+}
+
+#[derive(Debug, Clone, FromMeta)]
+struct AdminInlineOpts {
+    model: syn::Path,
+    field: syn::Ident,
+}
+
+#[derive(Debug, Clone, FromField)]
+#[darling(attributes(model), allow_unknown_fields)]
+struct AdminModelField {
+    ident: Option<syn::Ident>,
+    ty: syn::Type,
+    primary_key: darling::util::Flag,
+}
+
+impl AdminModelField {
+    /// Returns whether the field is the primary key of the model, the same way
+    /// the `#[model]` attribute determines it.
+    fn is_primary_key(&self) -> bool {
+        self.primary_key.is_present()
+            || self
+                .ident
+                .as_ref()
+                .is_some_and(|ident| ident == ID_FIELD_NAME)
+    }
+}
+
+#[derive(Debug)]
+struct AdminModelBuilder {
+    name: syn::Ident,
+    display_name: String,
+    url_name: String,
+    display_field: Option<syn::Ident>,
+    editable_fields: Vec<AdminModelField>,
+    read_only_fields: Vec<syn::Ident>,
+    list_columns: Vec<syn::Ident>,
+    search_fields: Vec<syn::Ident>,
+    filter_fields: Vec<AdminModelField>,
+    actions: Vec<syn::Path>,
+    inlines: Vec<AdminInlineOpts>,
+}
+
+impl AdminModelBuilder {
+    fn from_opts(opts: &AdminModelOpts) -> Result<Self, darling::Error> {
+        let fields = opts
+            .data
+            .as_ref()
+            .take_struct()
+            .expect("Only structs are supported")
+            .fields;
+        let field_names: Vec<_> = fields
+            .iter()
+            .map(|field| {
+                field
+                    .ident
+                    .clone()
+                    .expect("Only named fields are supported")
+            })
+            .collect();
+
+        let mut errors = darling::Error::accumulator();
+        let mut check_fields = |paths: &PathList| -> Vec<syn::Ident> {
+            paths
+                .iter()
+                .filter_map(|path| {
+                    errors.handle(match path.get_ident() {
+                        Some(ident) if field_names.contains(ident) => Ok(ident.clone()),
+                        Some(ident) => Err(darling::Error::custom(format!(
+                            "`{ident}` is not a field of `{}`",
+                            opts.ident
+                        ))
+                        .with_span(ident)),
+                        None => {
+                            Err(darling::Error::custom("expected a field name").with_span(path))
+                        }
+                    })
+                })
+                .collect()
+        };
+        let list_columns = check_fields(&opts.list_columns);
+        let search_fields = check_fields(&opts.search_fields);
+        let mut read_only_fields = check_fields(&opts.read_only);
+        let filter_names = check_fields(&opts.filters);
+        if let Some(display) = &opts.display {
+            check_fields(&PathList::new(vec![display.clone()]));
+        }
+        errors.finish()?;
+
+        if let Some(primary_key) = fields.iter().find(|field| field.is_primary_key()) {
+            let primary_key = primary_key.ident.clone().expect("checked above");
+            if !read_only_fields.contains(&primary_key) {
+                read_only_fields.insert(0, primary_key);
+            }
+        }
+
+        let editable_fields = fields
+            .iter()
+            .filter(|field| {
+                !read_only_fields.contains(field.ident.as_ref().expect("checked above"))
+            })
+            .map(|&field| field.clone())
+            .collect();
+
+        let filter_fields = filter_names
+            .iter()
+            .filter_map(|name| {
+                fields
+                    .iter()
+                    .find(|field| field.ident.as_ref() == Some(name))
+                    .map(|&field| field.clone())
+            })
+            .collect();
+
+        let name = opts.ident.to_string();
+        Ok(Self {
+            name: opts.ident.clone(),
+            display_name: opts.name.clone().unwrap_or_else(|| name.clone()),
+            url_name: opts
+                .url_name
+                .clone()
+                .unwrap_or_else(|| name.to_case(Case::Snake)),
+            display_field: opts.display.clone(),
+            editable_fields,
+            read_only_fields,
+            list_columns,
+            search_fields,
+            filter_fields,
+            actions: opts.actions.to_vec(),
+            inlines: opts.inline.clone(),
+        })
+    }
+
+    fn build_admin_model_impl(&self) -> TokenStream {
+        let crate_ident = cot_ident();
+        let name = &self.name;
+        let form_name = self.form_name();
+        let editable_names = self.editable_names();
+        let editable_types = self.editable_fields.iter().map(|field| &field.ty);
+
+        let object_fns = self.build_object_fns();
+        let metadata_fns = self.build_metadata_fns();
+        let form_fns = self.build_form_fns();
+
+        quote! {
+            const _: () = {
+                #[derive(#crate_ident::form::Form)]
+                struct #form_name {
+                    #( #editable_names: #editable_types, )*
+                }
+
+                #[#crate_ident::__private::async_trait]
+                #[automatically_derived]
+                impl #crate_ident::admin::AdminModel for #name {
+                    #object_fns
+                    #metadata_fns
+                    #form_fns
+                }
+            };
+        }
+    }
+
+    fn form_name(&self) -> syn::Ident {
+        format_ident!("__{}AdminForm", self.name)
+    }
+
+    fn filter_names(&self) -> Vec<&syn::Ident> {
+        self.filter_fields
+            .iter()
+            .map(|field| {
+                field
+                    .ident
+                    .as_ref()
+                    .expect("checked when creating the builder")
+            })
+            .collect()
+    }
+
+    fn editable_names(&self) -> Vec<&syn::Ident> {
+        self.editable_fields
+            .iter()
+            .map(|field| {
+                field
+                    .ident
+                    .as_ref()
+                    .expect("checked when creating the builder")
+            })
+            .collect()
+    }
+
+    /// Builds the functions retrieving and deleting the objects.
+    fn build_object_fns(&self) -> TokenStream {
+        let crate_ident = cot_ident();
+        let name = &self.name;
+        let get_list = self.build_get_list_fn();
+
+        quote! {
+            #get_list
+
+            async fn get_objects(
+                request: &#crate_ident::request::Request,
+            ) -> #crate_ident::Result<::std::vec::Vec<Self>> {
+                Ok(<Self as #crate_ident::db::Model>::objects()
+                    .all(#crate_ident::request::RequestExt::db(request))
+                    .await?)
+            }
+
+            async fn get_object_by_id(
+                request: &#crate_ident::request::Request,
+                id: &str,
+            ) -> #crate_ident::Result<::core::option::Option<Self>> {
+                type PrimaryKey = <#name as #crate_ident::db::Model>::PrimaryKey;
+                type Field = <PrimaryKey as #crate_ident::form::AsFormField>::Type;
+
+                let mut field = <PrimaryKey as #crate_ident::form::AsFormField>::new_field(
+                    #crate_ident::form::FormFieldOptions {
+                        id: ::std::string::String::from("id"),
+                        required: true,
+                    },
+                    ::core::default::Default::default(),
+                );
+                <Field as #crate_ident::form::FormField>::set_value(
+                    &mut field,
+                    ::std::borrow::Cow::Borrowed(id),
+                );
+                let ::core::result::Result::Ok(primary_key) =
+                    <PrimaryKey as #crate_ident::form::AsFormField>::clean_value(&field)
+                else {
+                    return Ok(None);
+                };
+
+                Ok(<Self as #crate_ident::db::Model>::get_by_primary_key(
+                    #crate_ident::request::RequestExt::db(request),
+                    primary_key,
+                )
+                .await?)
+            }
+
+            fn object_id(&self) -> ::std::string::String {
+                #crate_ident::form::AsFormField::to_field_value(
+                    <Self as #crate_ident::db::Model>::primary_key(self),
+                )
+            }
+
+            async fn delete(
+                &self,
+                request: &#crate_ident::request::Request,
+            ) -> #crate_ident::Result<()> {
+                <Self as #crate_ident::db::Model>::objects()
+                    .filter(#crate_ident::db::query::Expr::eq(
+                        #crate_ident::db::query::Expr::field(
+                            <Self as #crate_ident::db::Model>::PRIMARY_KEY_NAME,
+                        ),
+                        #crate_ident::db::query::Expr::value(
+                            ::core::clone::Clone::clone(
+                                <Self as #crate_ident::db::Model>::primary_key(self),
+                            ),
+                        ),
+                    ))
+                    .delete(#crate_ident::request::RequestExt::db(request))
+                    .await?;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Builds the function retrieving a page of the list view, doing the
+    /// filtering, search, sorting and pagination in the database query.
+    fn build_get_list_fn(&self) -> TokenStream {
+        let crate_ident = cot_ident();
+        let search_fields = &self.search_fields;
+        let filter_names = self.filter_names();
+        let filter_types = self.filter_fields.iter().map(|field| &field.ty);
+
+        let search = if search_fields.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                if let ::core::option::Option::Some(search) = params.search() {
+                    let pattern = ::std::format!(
+                        "%{}%",
+                        #crate_ident::db::query::Expr::escape_like(&search.to_lowercase()),
+                    );
+                    let filter = [#( stringify!(#search_fields), )*]
+                        .into_iter()
+                        .map(|field| {
+                            #crate_ident::db::query::Expr::like(
+                                #crate_ident::db::query::Expr::lower(
+                                    #crate_ident::db::query::Expr::field(field),
+                                ),
+                                ::core::clone::Clone::clone(&pattern),
+                            )
+                        })
+                        .reduce(#crate_ident::db::query::Expr::or)
+                        .expect("there is at least one search field");
+                    conditions.push(filter);
+                }
+            }
+        };
+
+        quote! {
+            async fn get_list(
+                request: &#crate_ident::request::Request,
+                params: &#crate_ident::admin::AdminListParams,
+            ) -> #crate_ident::Result<#crate_ident::admin::AdminList<Self>> {
+                let db = #crate_ident::request::RequestExt::db(request);
+                let mut query = <Self as #crate_ident::db::Model>::objects();
+
+                let mut conditions: ::std::vec::Vec<#crate_ident::db::query::Expr> =
+                    ::std::vec::Vec::new();
+                #(
+                    conditions.extend(
+                        <#filter_types as #crate_ident::admin::filters::AdminFilterField>::admin_filter_expr(
+                            stringify!(#filter_names),
+                            params,
+                        ),
+                    );
+                )*
+                #search
+                if let ::core::option::Option::Some(filter) =
+                    conditions.into_iter().reduce(#crate_ident::db::query::Expr::and)
+                {
+                    query.filter(filter);
+                }
+                let total = query.count(db).await?;
+
+                if let ::core::option::Option::Some(column) = params.sort_column(
+                    <Self as #crate_ident::admin::AdminModel>::list_columns(),
+                ) {
+                    query.order_by(
+                        column,
+                        if params.is_descending() {
+                            #crate_ident::db::query::Order::Desc
+                        } else {
+                            #crate_ident::db::query::Order::Asc
+                        },
+                    );
+                }
+                query
+                    .order_by(
+                        <Self as #crate_ident::db::Model>::PRIMARY_KEY_NAME,
+                        #crate_ident::db::query::Order::Asc,
+                    )
+                    .limit(params.per_page())
+                    .offset(params.offset());
+
+                Ok(#crate_ident::admin::AdminList::new(query.all(db).await?, total))
+            }
+        }
+    }
+
+    /// Builds the function creating the filters shown in the list view.
+    fn build_filters_fn(&self) -> TokenStream {
+        let crate_ident = cot_ident();
+        let filter_names = self.filter_names();
+        let filter_types = self.filter_fields.iter().map(|field| &field.ty);
+
+        quote! {
+            async fn filters(
+                request: &#crate_ident::request::Request,
+            ) -> #crate_ident::Result<::std::vec::Vec<#crate_ident::admin::filters::AdminFilter>> {
+                Ok(::std::vec![
+                    #(
+                        <#filter_types as #crate_ident::admin::filters::AdminFilterField>::admin_filter(
+                            request,
+                            stringify!(#filter_names),
+                        )
+                        .await?,
+                    )*
+                ])
+            }
+        }
+    }
+
+    /// Builds the function returning the related models edited on the change
+    /// page.
+    fn build_inlines_fn(&self) -> TokenStream {
+        if self.inlines.is_empty() {
+            return quote!();
+        }
+
+        let crate_ident = cot_ident();
+        let inline_models = self.inlines.iter().map(|inline| &inline.model);
+        let inline_fields = self.inlines.iter().map(|inline| &inline.field);
+
+        quote! {
+            fn inlines() -> ::std::vec::Vec<#crate_ident::admin::inlines::AdminInline> {
+                ::std::vec![
+                    #(
+                        #crate_ident::admin::inlines::AdminInline::new::<Self, #inline_models>(
+                            stringify!(#inline_fields),
+                        ),
+                    )*
+                ]
+            }
+        }
+    }
+
+    /// Builds the function returning the choices of the foreign key fields of
+    /// the form, i.e. the objects of the referenced models.
+    fn build_form_choices_fn(&self) -> TokenStream {
+        let (choice_names, choice_models): (Vec<_>, Vec<_>) = self
+            .editable_fields
+            .iter()
+            .filter_map(|field| {
+                foreign_key_model(&field.ty)
+                    .map(|model| (field.ident.as_ref().expect("checked above"), model))
+            })
+            .unzip();
+        if choice_names.is_empty() {
+            return quote!();
+        }
+
+        let crate_ident = cot_ident();
+        quote! {
+            async fn form_choices(
+                request: &#crate_ident::request::Request,
+            ) -> #crate_ident::Result<
+                ::std::vec::Vec<(
+                    &'static str,
+                    ::std::vec::Vec<#crate_ident::admin::AdminFormChoice>,
+                )>,
+            > {
+                Ok(::std::vec![
+                    #(
+                        (
+                            stringify!(#choice_names),
+                            #crate_ident::admin::AdminFormChoice::for_objects::<#choice_models>(
+                                request,
+                            )
+                            .await?,
+                        ),
+                    )*
+                ])
+            }
+        }
+    }
+
+    /// Builds the functions describing the model and displaying its fields.
+    fn build_metadata_fns(&self) -> TokenStream {
+        let crate_ident = cot_ident();
+        let display_name = &self.display_name;
+        let url_name = &self.url_name;
+        let list_columns = &self.list_columns;
+        let search_fields = &self.search_fields;
+        let read_only_fields = &self.read_only_fields;
+        let filters_fn = self.build_filters_fn();
+        let inlines_fn = self.build_inlines_fn();
+        let actions = &self.actions;
+        let action_names = self.actions.iter().map(|action| {
+            action
+                .segments
+                .last()
+                .expect("paths have at least one segment")
+                .ident
+                .to_string()
+        });
+
+        let mut displayed_fields = self.list_columns.clone();
+        for field in self.read_only_fields.iter().chain(&self.display_field) {
+            if !displayed_fields.contains(field) {
+                displayed_fields.push(field.clone());
+            }
+        }
+
+        let display = if let Some(display_field) = &self.display_field {
+            quote!(#crate_ident::admin::AdminDisplay::admin_display(&self.#display_field))
+        } else {
+            quote!(::std::format!(
+                "{} #{}",
+                #display_name,
+                #crate_ident::admin::AdminModel::object_id(self)
+            ))
+        };
+
+        quote! {
+            fn name() -> &'static str {
+                #display_name
+            }
+
+            fn url_name() -> &'static str {
+                #url_name
+            }
+
+            fn permission_codename(
+                permission: #crate_ident::admin::AdminPermission,
+            ) -> ::std::string::String {
+                ::std::format!(
+                    "{}_{}",
+                    permission.action(),
+                    <Self as #crate_ident::db::Model>::TABLE_NAME.as_str()
+                )
+            }
+
+            fn display(&self) -> ::std::string::String {
+                #display
+            }
+
+            fn list_columns() -> &'static [&'static str] {
+                &[#( stringify!(#list_columns), )*]
+            }
+
+            fn search_fields() -> &'static [&'static str] {
+                &[#( stringify!(#search_fields), )*]
+            }
+
+            fn read_only_fields() -> &'static [&'static str] {
+                &[#( stringify!(#read_only_fields), )*]
+            }
+
+            #filters_fn
+
+            fn actions() -> ::std::vec::Vec<#crate_ident::admin::actions::AdminAction> {
+                ::std::vec![
+                    #crate_ident::admin::actions::AdminAction::delete_selected::<Self>(),
+                    #crate_ident::admin::actions::AdminAction::export_csv::<Self>(),
+                    #(
+                        #crate_ident::admin::actions::AdminAction::from_fn(
+                            #action_names,
+                            #actions,
+                        ),
+                    )*
+                ]
+            }
+
+            #inlines_fn
+
+            fn field_value(&self, field: &str) -> ::core::option::Option<::std::string::String> {
+                match field {
+                    #(
+                        stringify!(#displayed_fields) => ::core::option::Option::Some(
+                            #crate_ident::admin::AdminDisplay::admin_display(&self.#displayed_fields),
+                        ),
+                    )*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    }
+
+    /// Builds the functions adding and changing the objects through the
+    /// generated form.
+    fn build_form_fns(&self) -> TokenStream {
+        let crate_ident = cot_ident();
+        let form_name = self.form_name();
+        let editable_names = self.editable_names();
+        let read_only_fields = &self.read_only_fields;
+
+        let form_from_request = quote! {
+            match <#form_name as #crate_ident::form::Form>::from_request(request).await? {
+                #crate_ident::form::FormResult::Ok(form) => form,
+                #crate_ident::form::FormResult::ValidationError(context) => {
+                    return Ok(#crate_ident::admin::AdminFormResult::ValidationError(
+                        #crate_ident::admin::AdminForm::from_context(&context),
+                    ));
+                }
+            }
+        };
+
+        let form_choices_fn = self.build_form_choices_fn();
+
+        quote! {
+            #form_choices_fn
+
+            fn add_form() -> ::core::option::Option<#crate_ident::admin::AdminForm> {
+                ::core::option::Option::Some(#crate_ident::admin::AdminForm::from_context(
+                    &<<#form_name as #crate_ident::form::Form>::Context
+                        as #crate_ident::form::FormContext>::new(),
+                ))
+            }
+
+            async fn create_from_request(
+                request: &mut #crate_ident::request::Request,
+            ) -> #crate_ident::Result<#crate_ident::admin::AdminFormResult<Self>> {
+                let form = #form_from_request;
+
+                let mut object = Self {
+                    #( #editable_names: form.#editable_names, )*
+                    #( #read_only_fields: ::core::default::Default::default(), )*
+                };
+                <Self as #crate_ident::db::Model>::insert(
+                    &mut object,
+                    #crate_ident::request::RequestExt::db(request),
+                )
+                .await?;
+
+                Ok(#crate_ident::admin::AdminFormResult::Ok(object))
+            }
+
+            fn change_form(&self) -> ::core::option::Option<#crate_ident::admin::AdminForm> {
+                use #crate_ident::form::FormContext;
+
+                let mut context = <#form_name as #crate_ident::form::Form>::Context::new();
+                #(
+                    let _ = context.set_value(
+                        stringify!(#editable_names),
+                        ::std::borrow::Cow::Owned(
+                            #crate_ident::form::AsFormField::to_field_value(&self.#editable_names),
+                        ),
+                    );
+                )*
+
+                ::core::option::Option::Some(#crate_ident::admin::AdminForm::from_context(&context))
+            }
+
+            async fn update_from_request(
+                &mut self,
+                request: &mut #crate_ident::request::Request,
+            ) -> #crate_ident::Result<#crate_ident::admin::AdminFormResult<()>> {
+                let form = #form_from_request;
+
+                #( self.#editable_names = form.#editable_names; )*
+                <Self as #crate_ident::db::Model>::save(
+                    self,
+                    #crate_ident::request::RequestExt::db(request),
+                )
+                .await?;
+
+                Ok(#crate_ident::admin::AdminFormResult::Ok(()))
+            }
+        }
+    }
+}
+
+/// Returns the model referenced by a field of type `ForeignKey<T>` or
+/// `Option<ForeignKey<T>>`, recognized by the name of the type, as the types
+/// are not resolved in the macro.
+fn foreign_key_model(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    let Some(syn::GenericArgument::Type(inner)) = arguments.args.first() else {
+        return None;
+    };
+
+    match segment.ident.to_string().as_str() {
+        "ForeignKey" => Some(inner),
+        "Option" => foreign_key_model(inner),
+        _ => None,
+    }
+}
@@ -1,3 +1,4 @@
+mod admin;
 mod dbtest;
 mod form;
 mod main_fn;
@@ -11,6 +12,7 @@ use proc_macro_crate::crate_name;
 use quote::quote;
 use syn::{parse_macro_input, ItemFn};
 
+use crate::admin::impl_admin_model_for_struct;
 use crate::dbtest::fn_to_dbtest;
 use crate::form::impl_form_for_struct;
 use crate::main_fn::fn_to_cot_main;
@@ -24,6 +26,13 @@ pub fn derive_form(input: TokenStream) -> TokenStream {
     token_stream.into()
 }
 
+#[proc_macro_derive(AdminModel, attributes(admin))]
+pub fn derive_admin_model(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    let token_stream = impl_admin_model_for_struct(&ast);
+    token_stream.into()
+}
+
 /// Implement the [`Model`] trait for a struct.
 ///
 /// This macro will generate an implementation of the [`Model`] trait for the
@@ -20,6 +20,15 @@ fn attr_model() {
     t.compile_fail("tests/ui/attr_model_multiple_pks.rs");
 }
 
+#[rustversion::attr(not(nightly), ignore)]
+#[test]
+#[cfg_attr(miri, ignore)] // unsupported operation: extern static `pidfd_spawnp` is not supported by Miri
+fn derive_admin_model() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/derive_admin_model.rs");
+    t.compile_fail("tests/ui/derive_admin_model_invalid_field.rs");
+}
+
 #[rustversion::attr(not(nightly), ignore)]
 #[test]
 #[cfg_attr(miri, ignore)] // unsupported operation: extern static `pidfd_spawnp` is not supported by Miri
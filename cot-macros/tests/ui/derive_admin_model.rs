@@ -0,0 +1,41 @@
+use cot::admin::AdminModel;
+use cot::db::{model, Auto, ForeignKey, LimitedString};
+
+#[derive(Debug, AdminModel)]
+#[admin(
+    name = "My model",
+    url_name = "my_models",
+    display = name,
+    list_columns(id, name, visits),
+    search_fields(name, description),
+    read_only(visits),
+    inline(model = MyRelatedModel, field = parent)
+)]
+#[model]
+struct MyModel {
+    id: Auto<i32>,
+    name: LimitedString<100>,
+    description: Option<String>,
+    visits: i32,
+}
+
+#[derive(Debug, AdminModel)]
+#[model]
+struct MyOtherModel {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, AdminModel)]
+#[model]
+struct MyRelatedModel {
+    id: Auto<i32>,
+    parent: ForeignKey<MyModel>,
+    other: Option<ForeignKey<MyOtherModel>>,
+}
+
+fn main() {
+    println!("{} {}", MyModel::name(), MyModel::url_name());
+    println!("{} {}", MyOtherModel::name(), MyOtherModel::url_name());
+    println!("{}", MyModel::inlines()[0].field());
+}
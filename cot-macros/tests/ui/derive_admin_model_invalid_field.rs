@@ -0,0 +1,12 @@
+use cot::admin::AdminModel;
+use cot::db::{model, Auto};
+
+#[derive(Debug, AdminModel)]
+#[admin(list_columns(id, title))]
+#[model]
+struct MyModel {
+    id: Auto<i32>,
+    name: String,
+}
+
+fn main() {}
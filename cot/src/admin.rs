@@ -3,21 +3,127 @@
 //! This module provides an administration panel for managing models
 //! registered in the application, straight from the web interface.
 
+pub mod actions;
+pub mod dashboard;
+pub mod filters;
+mod import_export;
+pub mod inlines;
+#[cfg(feature = "db")]
+pub mod log;
+/// The database migrations of the admin app.
+#[cfg(feature = "db")]
+pub mod migrations;
+pub mod templates;
+
+use std::collections::BTreeMap;
+use std::future::Future;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::Bytes;
+/// Derive the [`AdminModel`] trait for a model, so that it can be managed in
+/// the admin panel.
+///
+/// The model has to be defined with the [`model`](crate::db::model) attribute
+/// macro, and its primary key has to implement
+/// [`AsFormField`](crate::form::AsFormField), so that it can be used in the
+/// admin panel URLs.
+///
+/// The objects can be added and changed through a form containing all the
+/// fields that are not read-only; these have to implement
+/// [`AsFormField`](crate::form::AsFormField). The read-only fields, as well
+/// as the fields shown in the list columns, have to implement
+/// [`AdminDisplay`]. When an object is added, the read-only fields are set to
+/// their [`Default`] value. The [`ForeignKey`](crate::db::ForeignKey) fields
+/// are chosen from the objects of the referenced model, which has to
+/// implement [`AdminModel`] as well. The list view is searched, sorted and
+/// paginated in the database query.
+///
+/// The following options can be specified in the `#[admin(...)]` attribute:
+///
+/// * `name = "..."`: the name of the model shown in the admin panel; defaults
+///   to the name of the struct.
+/// * `url_name = "..."`: the URL slug of the model; defaults to the name of the
+///   struct in snake case.
+/// * `display = field`: the field used as the display text of the objects;
+///   defaults to the model name followed by the primary key.
+/// * `list_columns(field, ...)`: the fields shown as columns in the list view;
+///   the list can be sorted by any of them.
+/// * `search_fields(field, ...)`: the text fields searched, case-insensitively,
+///   in the list view.
+/// * `read_only(field, ...)`: the fields that can't be changed in the admin
+///   panel. The field called `id` is always read-only.
+/// * `filters(field, ...)`: the fields shown as filters in the sidebar of the
+///   list view; their types have to implement
+///   [`AdminFilterField`](filters::AdminFilterField).
+/// * `actions(function, ...)`: the custom [actions](actions::AdminAction) that
+///   can be run on the objects selected in the list view, in addition to the
+///   built-in ones. The functions are async and receive the request and the
+///   primary keys of the selected objects, formatted as in the URLs; the
+///   actions are named after the functions.
+/// * `inline(model = Model, field = field)`: a related model whose objects
+///   refer to this one through the given foreign key field, edited on the
+///   change page (see [`inlines`]); can be specified multiple times.
+///
+/// # Examples
+///
+/// ```
+/// use cot::admin::actions::AdminActionResult;
+/// use cot::admin::AdminModel;
+/// use cot::db::{model, Auto};
+/// use cot::request::Request;
+///
+/// async fn publish(request: Request, ids: Vec<String>) -> cot::Result<AdminActionResult> {
+///     // update the posts in the database...
+///     Ok(AdminActionResult::Message(format!(
+///         "Published {} posts.",
+///         ids.len()
+///     )))
+/// }
+///
+/// #[derive(Debug, Clone, AdminModel)]
+/// #[admin(
+///     name = "Blog post",
+///     display = title,
+///     list_columns(id, title, published),
+///     search_fields(title),
+///     read_only(views),
+///     filters(published),
+///     actions(publish)
+/// )]
+/// #[model]
+/// struct Post {
+///     id: Auto<i64>,
+///     title: String,
+///     published: bool,
+///     views: i64,
+/// }
+///
+/// assert_eq!(Post::name(), "Blog post");
+/// assert_eq!(Post::url_name(), "post");
+/// assert_eq!(Post::read_only_fields(), ["id", "views"]);
+/// assert_eq!(Post::actions()[2].label(), "Publish");
+/// ```
+pub use cot_macros::AdminModel;
 use derive_more::Debug;
 use rinja::Template;
+use tower_sessions::Session;
 
-use crate::auth::AuthRequestExt;
+#[cfg(feature = "db")]
+use crate::auth::db::{set_password_from_form, DatabaseUser, DatabaseUserCredentials};
+use crate::auth::throttling::{Lockout, LockoutTarget};
+use crate::auth::{AuthError, AuthRequestExt, User};
+#[cfg(feature = "db")]
+use crate::db::Model;
 use crate::form::fields::Password;
 use crate::form::{
     Form, FormContext, FormErrorTarget, FormField, FormFieldValidationError, FormResult,
 };
+use crate::handler::RequestHandler;
 use crate::request::{Request, RequestExt};
 use crate::response::{Response, ResponseExt};
-use crate::router::Router;
+use crate::router::{handle_forbidden, handle_method_not_allowed, handle_not_found, Router};
 use crate::{reverse_redirect, static_files, App, Body, StatusCode};
 
 #[derive(Debug, Form)]
@@ -28,40 +134,207 @@ struct LoginForm {
 
 #[derive(Debug, Template)]
 #[template(path = "admin/login.html")]
-struct LoginTemplate<'a> {
-    request: &'a Request,
+struct LoginTemplate {
     form: <LoginForm as Form>::Context,
 }
 
+#[derive(Debug, Form)]
+struct SecondFactorForm {
+    code: String,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "admin/login_verify.html")]
+struct SecondFactorTemplate {
+    form: <SecondFactorForm as Form>::Context,
+}
+
+#[cfg(feature = "db")]
+#[derive(Debug, Form)]
+struct PasswordChangeForm {
+    old_password: Password,
+    new_password: Password,
+    new_password_confirmation: Password,
+}
+
+#[cfg(feature = "db")]
+#[derive(Debug, Template)]
+#[template(path = "admin/password_change.html")]
+struct PasswordChangeTemplate<'a> {
+    request: &'a Request,
+    form: <PasswordChangeForm as Form>::Context,
+}
+
+#[derive(Debug, Form)]
+struct UnlockForm {
+    kind: String,
+    value: String,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "admin/lockouts.html")]
+struct LockoutsTemplate<'a> {
+    request: &'a Request,
+    lockouts: Vec<Lockout>,
+}
+
 #[derive(Debug, Template)]
 #[template(path = "admin/model_list.html")]
 struct ModelListTemplate<'a> {
+    request: &'a Request,
+    apps: Vec<AppModels>,
+    widgets: Vec<dashboard::RenderedWidget>,
+    messages: Vec<String>,
+    show_password_change: bool,
+    can_manage_lockouts: bool,
+}
+
+/// The models of an app listed on the dashboard.
+#[derive(Debug)]
+struct AppModels {
+    label: String,
+    #[debug("...")]
+    models: Vec<(Box<dyn AdminModelManager>, ModelPermissions, u64)>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "admin/model_form.html")]
+struct ModelFormTemplate<'a> {
+    request: &'a Request,
+    #[debug("...")]
+    model: &'a dyn AdminModelManager,
+    permissions: ModelPermissions,
+    #[debug("...")]
+    object: Option<&'a dyn AdminModel>,
+    form: Option<AdminForm>,
+    inlines: Vec<inlines::InlineFormSet>,
+    show_history: bool,
+}
+
+#[cfg(feature = "db")]
+#[derive(Debug, Template)]
+#[template(path = "admin/model_history.html")]
+struct ModelHistoryTemplate<'a> {
     request: &'a Request,
     #[debug("...")]
-    model_managers: Vec<Box<dyn AdminModelManager>>,
+    model: &'a dyn AdminModelManager,
+    #[debug("...")]
+    object: &'a dyn AdminModel,
+    entries: Vec<log::LogEntry>,
 }
 
 #[derive(Debug, Template)]
-#[template(path = "admin/model.html")]
-struct ModelTemplate<'a> {
+#[template(path = "admin/model_delete.html")]
+struct ModelDeleteTemplate<'a> {
     request: &'a Request,
     #[debug("...")]
-    objects: Vec<Box<dyn AdminModel>>,
+    model: &'a dyn AdminModelManager,
+    #[debug("...")]
+    object: &'a dyn AdminModel,
+}
+
+/// The operations the current user is allowed to perform on the objects of a
+/// model.
+#[derive(Debug, Copy, Clone)]
+#[allow(clippy::struct_excessive_bools)] // one flag per admin permission
+struct ModelPermissions {
+    view: bool,
+    add: bool,
+    change: bool,
+    delete: bool,
+}
+
+impl ModelPermissions {
+    fn for_user(user: &dyn User, manager: &dyn AdminModelManager) -> Self {
+        let change = manager.has_permission(user, AdminPermission::Change);
+        Self {
+            // the objects that can be changed can be seen as well
+            view: change || manager.has_permission(user, AdminPermission::View),
+            add: manager.has_permission(user, AdminPermission::Add),
+            change,
+            delete: manager.has_permission(user, AdminPermission::Delete),
+        }
+    }
+
+    fn allows(self, permission: AdminPermission) -> bool {
+        match permission {
+            AdminPermission::View => self.view,
+            AdminPermission::Add => self.add,
+            AdminPermission::Change => self.change,
+            AdminPermission::Delete => self.delete,
+        }
+    }
 }
 
+/// The session key storing the messages to show on the next admin page.
+const MESSAGES_SESSION_KEY: &str = "__cot_admin_messages";
+
+/// The permission needed to see and unlock the login lockouts. Superusers
+/// have it without it being granted explicitly.
+const LOCKOUTS_PERMISSION: &str = "manage_lockouts";
+
 async fn index(mut request: Request) -> crate::Result<Response> {
-    if request.user().await?.is_authenticated() {
-        let template = ModelListTemplate {
-            request: &request,
-            model_managers: admin_model_managers(&request),
-        };
-        Ok(Response::new_html(
-            StatusCode::OK,
-            Body::fixed(template.render()?),
-        ))
-    } else {
-        Ok(reverse_redirect!(request, "login")?)
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+
+    let app_managers: Vec<_> = request
+        .context()
+        .apps()
+        .iter()
+        .map(|app| (app_label(app.name()), app.admin_model_managers()))
+        .collect();
+    let widgets: Vec<_> = request
+        .context()
+        .apps()
+        .iter()
+        .flat_map(|app| app.admin_dashboard_widgets())
+        .collect();
+
+    let user = request.user().await?;
+    let can_manage_lockouts = user.has_perm(LOCKOUTS_PERMISSION);
+    let app_managers: Vec<_> = app_managers
+        .into_iter()
+        .map(|(label, managers)| {
+            let managers: Vec<_> = managers
+                .into_iter()
+                .map(|manager| {
+                    let permissions = ModelPermissions::for_user(user, &*manager);
+                    (manager, permissions)
+                })
+                .filter(|(_, permissions)| permissions.view)
+                .collect();
+            (label, managers)
+        })
+        .filter(|(_, managers)| !managers.is_empty())
+        .collect();
+
+    // the first page of a single object is enough to get the number of objects
+    let count_params = AdminListParams::new().with_per_page(1);
+    let mut apps = Vec::new();
+    for (label, managers) in app_managers {
+        let mut models = Vec::new();
+        for (manager, permissions) in managers {
+            let count = manager.get_list(&request, &count_params).await?.total();
+            models.push((manager, permissions, count));
+        }
+        apps.push(AppModels { label, models });
+    }
+
+    let mut rendered_widgets = Vec::new();
+    for widget in widgets {
+        rendered_widgets.extend(dashboard::RenderedWidget::render(&*widget, &mut request).await?);
     }
+
+    let template = ModelListTemplate {
+        request: &request,
+        apps,
+        widgets: rendered_widgets,
+        messages: take_messages(&request).await?,
+        show_password_change: cfg!(feature = "db"),
+        can_manage_lockouts,
+    };
+    render_page(&request, "Home", "", &template)
 }
 
 async fn login(mut request: Request) -> cot::Result<Response> {
@@ -71,14 +344,22 @@ async fn login(mut request: Request) -> cot::Result<Response> {
         let login_form = LoginForm::from_request(&mut request).await?;
         match login_form {
             FormResult::Ok(login_form) => {
-                if authenticate(&mut request, login_form).await? {
-                    return Ok(reverse_redirect!(request, "index")?);
-                }
+                let error = match authenticate(&mut request, login_form).await {
+                    Ok(true) if request.second_factor_pending().await? => {
+                        return Ok(reverse_redirect!(request, "login_verify")?)
+                    }
+                    Ok(true) => return Ok(reverse_redirect!(request, "index")?),
+                    Ok(false) => "Invalid username or password",
+                    Err(AuthError::TooManyFailedAttempts { .. }) => {
+                        "Too many failed login attempts. Please try again later."
+                    }
+                    Err(error) => return Err(error.into()),
+                };
 
                 let mut context = LoginForm::build_context(&mut request).await?;
                 context.add_error(
                     FormErrorTarget::Form,
-                    FormFieldValidationError::from_static("Invalid username or password"),
+                    FormFieldValidationError::from_static(error),
                 );
                 context
             }
@@ -89,19 +370,131 @@ async fn login(mut request: Request) -> cot::Result<Response> {
     };
 
     let template = LoginTemplate {
-        request: &request,
         form: login_form_context,
     };
-    Ok(Response::new_html(
-        StatusCode::OK,
-        Body::fixed(template.render()?),
-    ))
+    render_page(&request, "Sign in", "login", &template)
+}
+
+async fn login_verify(mut request: Request) -> cot::Result<Response> {
+    if !request.second_factor_pending().await? {
+        return Ok(reverse_redirect!(request, "login")?);
+    }
+
+    let second_factor_form_context = if request.method() == http::Method::GET {
+        SecondFactorForm::build_context(&mut request).await?
+    } else if request.method() == http::Method::POST {
+        let second_factor_form = SecondFactorForm::from_request(&mut request).await?;
+        match second_factor_form {
+            FormResult::Ok(second_factor_form) => {
+                let error = match request.verify_second_factor(&second_factor_form.code).await {
+                    Ok(true) => return Ok(reverse_redirect!(request, "index")?),
+                    Ok(false) => "Invalid verification code",
+                    Err(AuthError::TooManyFailedAttempts { .. }) => {
+                        "Too many failed login attempts. Please try again later."
+                    }
+                    Err(error) => return Err(error.into()),
+                };
+
+                let mut context = SecondFactorForm::build_context(&mut request).await?;
+                context.add_error(
+                    FormErrorTarget::Form,
+                    FormFieldValidationError::from_static(error),
+                );
+                context
+            }
+            FormResult::ValidationError(context) => context,
+        }
+    } else {
+        return Ok(handle_method_not_allowed());
+    };
+
+    let template = SecondFactorTemplate {
+        form: second_factor_form_context,
+    };
+    render_page(&request, "Two-factor authentication", "login", &template)
+}
+
+async fn logout(mut request: Request) -> cot::Result<Response> {
+    if request.method() != http::Method::POST {
+        return Ok(reverse_redirect!(request, "index")?);
+    }
+
+    request.logout().await?;
+    Ok(reverse_redirect!(request, "login")?)
+}
+
+#[cfg(feature = "db")]
+async fn password_change(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    // only the users stored in the database have a password to change
+    let Some(crate::auth::UserId::Int(user_id)) = request.user().await?.id() else {
+        return Ok(handle_not_found());
+    };
+    let Some(user) = DatabaseUser::get_by_id(request.db(), user_id).await? else {
+        return Ok(handle_not_found());
+    };
+
+    let form_context = if request.method() == http::Method::GET {
+        PasswordChangeForm::build_context(&mut request).await?
+    } else if request.method() == http::Method::POST {
+        match PasswordChangeForm::from_request(&mut request).await? {
+            FormResult::Ok(form) => {
+                let mut context = PasswordChangeForm::build_context(&mut request).await?;
+                let credentials = DatabaseUserCredentials::new(
+                    user.username().to_owned(),
+                    crate::auth::Password::new(form.old_password.as_str()),
+                );
+                let password_hasher = &request.project_config().password_hasher;
+                match DatabaseUser::authenticate(request.db(), &credentials, password_hasher)
+                    .await?
+                {
+                    Some(mut user) => {
+                        let errors = set_password_from_form(
+                            &request,
+                            &mut user,
+                            form.new_password.as_str(),
+                            form.new_password_confirmation.as_str(),
+                        )?;
+                        if errors.is_empty() {
+                            user.save(request.db()).await?;
+                            let user = user.with_permissions(request.db()).await?;
+                            request.update_session_auth_hash(Box::new(user)).await?;
+                            add_message(&request, String::from("Your password was changed."))
+                                .await?;
+                            return Ok(reverse_redirect!(request, "index")?);
+                        }
+                        for (field, error) in errors {
+                            context.add_error(FormErrorTarget::Field(field), error);
+                        }
+                    }
+                    None => context.add_error(
+                        FormErrorTarget::Field("old_password"),
+                        FormFieldValidationError::from_static(
+                            "Your old password was entered incorrectly",
+                        ),
+                    ),
+                }
+                context
+            }
+            FormResult::ValidationError(context) => context,
+        }
+    } else {
+        return Ok(handle_method_not_allowed());
+    };
+
+    let template = PasswordChangeTemplate {
+        request: &request,
+        form: form_context,
+    };
+    render_page(&request, "Change password", "", &template)
 }
 
-async fn authenticate(request: &mut Request, login_form: LoginForm) -> cot::Result<bool> {
+async fn authenticate(request: &mut Request, login_form: LoginForm) -> Result<bool, AuthError> {
     #[cfg(feature = "db")]
     let user = request
-        .authenticate(&crate::auth::db::DatabaseUserCredentials::new(
+        .authenticate(&DatabaseUserCredentials::new(
             login_form.username,
             // TODO unify auth::Password and form::fields::Password
             cot::auth::Password::new(login_form.password.into_string()),
@@ -119,110 +512,1826 @@ async fn authenticate(request: &mut Request, login_form: LoginForm) -> cot::Resu
     }
 }
 
-async fn view_model(mut request: Request) -> cot::Result<Response> {
-    if request.user().await?.is_authenticated() {
-        // TODO use a nice URL parser instead of unwrap
-        let model_name = request.path_params().get("model_name").unwrap();
+async fn lockouts(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    if !request.user().await?.has_perm(LOCKOUTS_PERMISSION) {
+        return Ok(handle_forbidden());
+    }
 
-        let model_managers = admin_model_managers(&request);
-        let manager = model_managers
-            .iter()
-            .find(|manager| manager.url_name() == model_name)
-            .unwrap(); // TODO throw 404
+    if request.method() == http::Method::POST {
+        if let FormResult::Ok(unlock_form) = UnlockForm::from_request(&mut request).await? {
+            if let Some(target) =
+                LockoutTarget::from_kind_and_value(&unlock_form.kind, &unlock_form.value)
+            {
+                request.context().login_throttle().unlock(&target);
+            }
+        }
 
-        let template = ModelTemplate {
-            request: &request,
-            objects: manager.get_objects(&request).await?,
-        };
-        Ok(Response::new_html(
-            StatusCode::OK,
-            Body::fixed(template.render()?),
-        ))
-    } else {
-        Ok(reverse_redirect!(request, "login")?)
+        return Ok(reverse_redirect!(request, "lockouts")?);
     }
+
+    let template = LockoutsTemplate {
+        request: &request,
+        lockouts: request.context().login_throttle().lockouts(),
+    };
+    render_page(&request, "Lockouts", "", &template)
 }
 
-#[must_use]
-fn admin_model_managers(request: &Request) -> Vec<Box<dyn AdminModelManager>> {
-    request
-        .context()
-        .apps()
-        .iter()
-        .flat_map(|app| app.admin_model_managers())
-        .collect()
+async fn view_model(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    let permissions = model_permissions(&mut request, &*manager).await?;
+    if !permissions.view {
+        return Ok(handle_forbidden());
+    }
+
+    let params = AdminListParams::from_request(&request);
+    let list = manager.get_list(&request, &params).await?;
+    let total = list.total();
+    let page = templates::ModelListPage {
+        request: &request,
+        model: &*manager,
+        can_add: permissions.add,
+        can_import: cfg!(feature = "db") && permissions.add && manager.add_form().is_some(),
+        objects: list.into_objects(),
+        num_pages: params.num_pages(total),
+        params,
+        total,
+        filters: manager.filters(&request).await?,
+        actions: allowed_actions(&*manager, permissions),
+        messages: take_messages(&request).await?,
+    };
+    let content = admin_templates(&request).render_model(&page)?;
+    render_content(&request, manager.name(), "", &content)
 }
 
-#[async_trait]
-pub trait AdminModelManager: Send + Sync {
-    fn name(&self) -> &str;
+async fn run_model_action(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    let permissions = model_permissions(&mut request, &*manager).await?;
+    if !permissions.view {
+        return Ok(handle_forbidden());
+    }
+    if request.method() != http::Method::POST {
+        return Ok(reverse_redirect!(
+            request,
+            "view_model",
+            model_name = manager.url_name()
+        )?);
+    }
 
-    fn url_name(&self) -> &str;
+    // the actions can read the submitted form again, e.g. to show their own form
+    let form_data = request.form_data().await?;
+    *request.body_mut() = Body::fixed(form_data.clone());
+    let mut action_name = None;
+    let mut ids = Vec::new();
+    for (key, value) in crate::request::query_pairs(&form_data) {
+        match key.as_ref() {
+            "action" => action_name = Some(value.into_owned()),
+            "selected" => ids.push(value.into_owned()),
+            _ => {}
+        }
+    }
 
-    async fn get_objects(&self, request: &Request) -> cot::Result<Vec<Box<dyn AdminModel>>>;
-}
+    // go back to the list view with the same filters, sorting and page
+    let mut list_url = cot::reverse!(request, "view_model", model_name = manager.url_name())?;
+    if let Some(query) = request.uri().query() {
+        list_url = format!("{list_url}?{query}");
+    }
+    let session = request.session().clone();
 
-#[derive(Debug)]
-pub struct DefaultAdminModelManager<T> {
-    phantom_data: PhantomData<T>,
+    let action = allowed_actions(&*manager, permissions)
+        .into_iter()
+        .find(|action| Some(action.name()) == action_name.as_deref());
+    let message = match action {
+        None => String::from("No action was selected."),
+        Some(_) if ids.is_empty() => String::from("No objects were selected."),
+        Some(action) => match action.run(request, ids).await? {
+            actions::AdminActionResult::Message(message) => message,
+            actions::AdminActionResult::Response(response) => return Ok(response),
+        },
+    };
+
+    add_session_message(&session, message).await?;
+    Ok(Response::new_redirect(list_url))
 }
 
-impl<T> Default for DefaultAdminModelManager<T> {
-    fn default() -> Self {
-        Self::new()
+async fn add_model(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    let permissions = model_permissions(&mut request, &*manager).await?;
+    if !permissions.add {
+        return Ok(handle_forbidden());
+    }
+    let Some(mut form) = manager.add_form() else {
+        return Ok(handle_not_found());
+    };
+    let choices = manager.form_choices(&request).await?;
+
+    if request.method() == http::Method::POST {
+        match manager.create_from_request(&mut request).await? {
+            AdminFormResult::Ok(object) => {
+                #[cfg(feature = "db")]
+                log::LogEntry::log(
+                    &mut request,
+                    manager.url_name(),
+                    &*object,
+                    log::LogAction::Add,
+                    &[],
+                )
+                .await?;
+                add_message(
+                    &request,
+                    format!(
+                        "{} `{}` was added successfully.",
+                        manager.name(),
+                        object.display()
+                    ),
+                )
+                .await?;
+                return Ok(reverse_redirect!(
+                    request,
+                    "view_model",
+                    model_name = manager.url_name()
+                )?);
+            }
+            AdminFormResult::ValidationError(invalid_form) => form = invalid_form,
+        }
     }
+
+    let template = ModelFormTemplate {
+        request: &request,
+        model: &*manager,
+        permissions,
+        object: None,
+        form: Some(form.with_choices(&choices)),
+        inlines: Vec::new(),
+        show_history: false,
+    };
+    let title = format!("Add {}", manager.name());
+    render_page(&request, &title, "", &template)
 }
 
-impl<T> DefaultAdminModelManager<T> {
-    #[must_use]
-    pub const fn new() -> Self {
-        Self {
-            phantom_data: PhantomData,
+async fn change_model(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    let permissions = model_permissions(&mut request, &*manager).await?;
+    if !permissions.view {
+        return Ok(handle_forbidden());
+    }
+    let Some(mut object) = get_object(&request, &*manager).await? else {
+        return Ok(handle_not_found());
+    };
+    // users who can only view the object see it as read-only
+    let mut form = object.change_form().filter(|_| permissions.change);
+    let object_id = object.object_id();
+    let mut inline_formsets = None;
+
+    if request.method() == http::Method::POST {
+        if !permissions.change {
+            return Ok(handle_forbidden());
+        }
+        if form.is_none() {
+            return Ok(handle_not_found());
+        }
+
+        // the form data is read again for each inline row
+        let form_data = request.form_data().await?;
+        *request.body_mut() = Body::fixed(form_data.clone());
+
+        // the change form has the values from before the update
+        #[cfg(feature = "db")]
+        let old_form = form.clone();
+        match object.update_from_request(&mut request).await? {
+            AdminFormResult::Ok(()) => {
+                #[cfg(feature = "db")]
+                {
+                    let changes = match (&old_form, object.change_form()) {
+                        (Some(old_form), Some(new_form)) => {
+                            log::FieldChange::between(old_form, &new_form)
+                        }
+                        _ => Vec::new(),
+                    };
+                    log::LogEntry::log(
+                        &mut request,
+                        manager.url_name(),
+                        &*object,
+                        log::LogAction::Change,
+                        &changes,
+                    )
+                    .await?;
+                }
+
+                let formsets =
+                    save_inline_formsets(&mut request, &*manager, &object_id, &form_data).await?;
+                if formsets.iter().all(|formset| !formset.has_errors()) {
+                    add_message(
+                        &request,
+                        format!(
+                            "{} `{}` was changed successfully.",
+                            manager.name(),
+                            object.display()
+                        ),
+                    )
+                    .await?;
+                    return Ok(reverse_redirect!(
+                        request,
+                        "view_model",
+                        model_name = manager.url_name()
+                    )?);
+                }
+
+                // show the rows that failed to validate with the saved object
+                form = object.change_form();
+                inline_formsets = Some(formsets);
+            }
+            AdminFormResult::ValidationError(invalid_form) => form = Some(invalid_form),
         }
     }
+
+    let inline_formsets = match inline_formsets {
+        Some(formsets) => formsets,
+        None => load_inline_formsets(&mut request, &*manager, &object_id).await?,
+    };
+    let choices = manager.form_choices(&request).await?;
+    let template = ModelFormTemplate {
+        request: &request,
+        model: &*manager,
+        permissions,
+        object: Some(&*object),
+        form: form.map(|form| form.with_choices(&choices)),
+        inlines: inline_formsets,
+        show_history: cfg!(feature = "db"),
+    };
+    render_page(&request, &object.display(), "", &template)
 }
 
-#[async_trait]
-impl<T: AdminModel + Send + Sync + 'static> AdminModelManager for DefaultAdminModelManager<T> {
-    fn name(&self) -> &str {
-        T::name()
+async fn delete_model(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    if !model_permissions(&mut request, &*manager).await?.delete {
+        return Ok(handle_forbidden());
     }
+    let Some(object) = get_object(&request, &*manager).await? else {
+        return Ok(handle_not_found());
+    };
 
-    fn url_name(&self) -> &str {
-        T::url_name()
+    if request.method() == http::Method::POST {
+        let display = object.display();
+        object.delete(&request).await?;
+        #[cfg(feature = "db")]
+        log::LogEntry::log(
+            &mut request,
+            manager.url_name(),
+            &*object,
+            log::LogAction::Delete,
+            &[],
+        )
+        .await?;
+
+        add_message(
+            &request,
+            format!("{} `{display}` was deleted successfully.", manager.name()),
+        )
+        .await?;
+        return Ok(reverse_redirect!(
+            request,
+            "view_model",
+            model_name = manager.url_name()
+        )?);
     }
 
-    async fn get_objects(&self, request: &Request) -> cot::Result<Vec<Box<dyn AdminModel>>> {
-        #[allow(trivial_casts)] // Upcast to the correct Box type
-        T::get_objects(request).await.map(|objects| {
-            objects
-                .into_iter()
-                .map(|object| Box::new(object) as Box<dyn AdminModel>)
-                .collect()
-        })
+    let template = ModelDeleteTemplate {
+        request: &request,
+        model: &*manager,
+        object: &*object,
+    };
+    let title = format!("Delete {}", object.display());
+    render_page(&request, &title, "", &template)
+}
+
+#[cfg(feature = "db")]
+async fn model_history(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    if !model_permissions(&mut request, &*manager).await?.view {
+        return Ok(handle_forbidden());
     }
+    let Some(object) = get_object(&request, &*manager).await? else {
+        return Ok(handle_not_found());
+    };
+
+    let entries =
+        log::LogEntry::for_object(request.db(), manager.url_name(), &object.object_id()).await?;
+    let template = ModelHistoryTemplate {
+        request: &request,
+        model: &*manager,
+        object: &*object,
+        entries,
+    };
+    let title = format!("History: {}", object.display());
+    render_page(&request, &title, "", &template)
 }
 
-/// A model that can be managed by the admin panel.
-#[async_trait]
-pub trait AdminModel {
-    /// Get the objects of this model.
-    async fn get_objects(request: &Request) -> cot::Result<Vec<Self>>
-    where
-        Self: Sized;
+/// Loads the rows of the inlines of the object the user is allowed to see.
+async fn load_inline_formsets(
+    request: &mut Request,
+    manager: &dyn AdminModelManager,
+    object_id: &str,
+) -> cot::Result<Vec<inlines::InlineFormSet>> {
+    let mut formsets = Vec::new();
+    for inline in manager.inlines() {
+        formsets.extend(inlines::InlineFormSet::load(request, inline, object_id).await?);
+    }
 
-    /// Get the name of this model.
-    fn name() -> &'static str
-    where
-        Self: Sized;
+    Ok(formsets)
+}
 
-    /// Get the URL slug for this model.
-    fn url_name() -> &'static str
-    where
-        Self: Sized;
+/// Saves the rows of the inlines of the object submitted in the form data.
+async fn save_inline_formsets(
+    request: &mut Request,
+    manager: &dyn AdminModelManager,
+    object_id: &str,
+    form_data: &Bytes,
+) -> cot::Result<Vec<inlines::InlineFormSet>> {
+    let mut formsets = Vec::new();
+    for inline in manager.inlines() {
+        formsets.extend(inlines::InlineFormSet::save(request, inline, object_id, form_data).await?);
+    }
 
-    /// Get the display text of this model instance.
-    fn display(&self) -> String;
+    Ok(formsets)
+}
+
+/// Renders an admin page with the given template as its content.
+pub(crate) fn render_page<T: Template>(
+    request: &Request,
+    title: &str,
+    body_class: &str,
+    template: &T,
+) -> cot::Result<Response> {
+    render_content(request, title, body_class, &template.render()?)
+}
+
+/// Renders an admin page with the given HTML content in the layout of the
+/// [admin templates](templates::AdminTemplates) of the app.
+fn render_content(
+    request: &Request,
+    title: &str,
+    body_class: &str,
+    content: &str,
+) -> cot::Result<Response> {
+    let page = templates::AdminPage::new(request, title, body_class, content);
+    let html = admin_templates(request).render_base(&page)?;
+
+    Ok(Response::new_html(StatusCode::OK, Body::fixed(html)))
+}
+
+/// The [admin templates](templates::AdminTemplates) of the [`AdminApp`]
+/// handling the request, added to the request extensions by its views.
+#[derive(Clone)]
+struct AdminTemplatesExtension(Arc<dyn templates::AdminTemplates>);
+
+fn admin_templates(request: &Request) -> Arc<dyn templates::AdminTemplates> {
+    match request.extensions().get::<AdminTemplatesExtension>() {
+        Some(templates) => Arc::clone(&templates.0),
+        None => Arc::new(templates::DefaultAdminTemplates),
+    }
+}
+
+/// Checks that the user can access the admin panel, i.e. is a logged in staff
+/// member, and returns the response to send instead of the page otherwise: a
+/// redirect to the login page, or a 403 Forbidden error.
+async fn check_staff(request: &mut Request) -> cot::Result<Option<Response>> {
+    let user = request.user().await?;
+    let (is_authenticated, is_staff) = (user.is_authenticated(), user.is_staff());
+    if !is_authenticated {
+        return Ok(Some(reverse_redirect!(request, "login")?));
+    }
+    if !is_staff {
+        return Ok(Some(handle_forbidden()));
+    }
+
+    Ok(None)
+}
+
+async fn model_permissions(
+    request: &mut Request,
+    manager: &dyn AdminModelManager,
+) -> cot::Result<ModelPermissions> {
+    Ok(ModelPermissions::for_user(request.user().await?, manager))
+}
+
+/// Returns the actions of the model the user is allowed to run.
+fn allowed_actions(
+    manager: &dyn AdminModelManager,
+    permissions: ModelPermissions,
+) -> Vec<actions::AdminAction> {
+    manager
+        .actions()
+        .into_iter()
+        .filter(|action| permissions.allows(action.permission()))
+        .collect()
+}
+
+/// Returns the model manager for the `model_name` path parameter.
+fn model_manager(request: &Request) -> Option<Box<dyn AdminModelManager>> {
+    let model_name = request.path_params().get("model_name")?;
+
+    admin_model_managers(request)
+        .into_iter()
+        .find(|manager| manager.url_name() == model_name)
+}
+
+/// Returns the object for the `object_id` path parameter.
+async fn get_object(
+    request: &Request,
+    manager: &dyn AdminModelManager,
+) -> cot::Result<Option<Box<dyn AdminModel>>> {
+    let Some(object_id) = request.path_params().get("object_id") else {
+        return Ok(None);
+    };
+
+    manager.get_object_by_id(request, object_id).await
+}
+
+/// Adds a message to show on the next admin page rendered, typically after a
+/// redirect.
+async fn add_message(request: &Request, message: String) -> cot::Result<()> {
+    add_session_message(request.session(), message).await
+}
+
+async fn add_session_message(session: &Session, message: String) -> cot::Result<()> {
+    let mut messages = session
+        .get::<Vec<String>>(MESSAGES_SESSION_KEY)
+        .await?
+        .unwrap_or_default();
+    messages.push(message);
+    session.insert(MESSAGES_SESSION_KEY, messages).await?;
+
+    Ok(())
+}
+
+/// Removes and returns the messages added with [`add_message`].
+async fn take_messages(request: &Request) -> cot::Result<Vec<String>> {
+    Ok(request
+        .session()
+        .remove::<Vec<String>>(MESSAGES_SESSION_KEY)
+        .await?
+        .unwrap_or_default())
+}
+
+#[must_use]
+fn admin_model_managers(request: &Request) -> Vec<Box<dyn AdminModelManager>> {
+    request
+        .context()
+        .apps()
+        .iter()
+        .flat_map(|app| app.admin_model_managers())
+        .collect()
+}
+
+/// An operation on the objects of a model in the admin panel that requires a
+/// permission.
+///
+/// Whether the user is allowed to perform it is checked with
+/// [`AdminModelManager::has_permission`], which, by default, checks the
+/// user's [permission](crate::auth::User::has_perm) with the codename returned
+/// by [`AdminModel::permission_codename`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AdminPermission {
+    /// Permission to see the objects in the list view and on their change
+    /// pages.
+    View,
+    /// Permission to add new objects.
+    Add,
+    /// Permission to change the existing objects.
+    Change,
+    /// Permission to delete objects.
+    Delete,
+}
+
+impl AdminPermission {
+    /// All the admin permissions.
+    pub const ALL: [Self; 4] = [Self::View, Self::Add, Self::Change, Self::Delete];
+
+    /// Returns the name of the operation the permission allows, e.g. `"add"`.
+    #[must_use]
+    pub fn action(self) -> &'static str {
+        match self {
+            Self::View => "view",
+            Self::Add => "add",
+            Self::Change => "change",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// A manager of the objects of a model in the admin panel.
+///
+/// This is the type-erased version of [`AdminModel`] that the admin views use;
+/// it's typically created with [`DefaultAdminModelManager`] and registered in
+/// [`App::admin_model_managers`].
+#[async_trait]
+pub trait AdminModelManager: Send + Sync {
+    /// Get the name of the model.
+    fn name(&self) -> &str;
+
+    /// Get the URL slug of the model.
+    fn url_name(&self) -> &str;
+
+    /// Get the names of the fields shown as columns in the list view.
+    fn list_columns(&self) -> &'static [&'static str];
+
+    /// Get the names of the fields searched in the list view.
+    fn search_fields(&self) -> &'static [&'static str];
+
+    /// Get the names of the fields that can't be changed in the admin panel.
+    fn read_only_fields(&self) -> &'static [&'static str];
+
+    /// Returns whether the user is allowed to perform the given operation on
+    /// the objects of the model.
+    ///
+    /// This is only checked for the staff members (see
+    /// [`User::is_staff`](crate::auth::User::is_staff)), as no one else can
+    /// access the admin panel. Implement this to apply custom rules.
+    fn has_permission(&self, user: &dyn User, permission: AdminPermission) -> bool;
+
+    /// Get the filters shown in the sidebar of the list view.
+    async fn filters(&self, request: &Request) -> cot::Result<Vec<filters::AdminFilter>>;
+
+    /// Get the actions that can be run on the objects selected in the list
+    /// view.
+    fn actions(&self) -> Vec<actions::AdminAction>;
+
+    /// Get the objects of the model.
+    async fn get_objects(&self, request: &Request) -> cot::Result<Vec<Box<dyn AdminModel>>>;
+
+    /// Get the page of objects shown in the list view, searched and sorted
+    /// according to the given parameters.
+    async fn get_list(
+        &self,
+        request: &Request,
+        params: &AdminListParams,
+    ) -> cot::Result<AdminList<Box<dyn AdminModel>>>;
+
+    /// Get the object with the given ID, or [`None`] if it doesn't exist.
+    async fn get_object_by_id(
+        &self,
+        request: &Request,
+        id: &str,
+    ) -> cot::Result<Option<Box<dyn AdminModel>>>;
+
+    /// Get the form for adding a new object, or [`None`] if the objects can't
+    /// be added in the admin panel.
+    fn add_form(&self) -> Option<AdminForm>;
+
+    /// Create a new object from the form submitted in the request.
+    async fn create_from_request(
+        &self,
+        request: &mut Request,
+    ) -> cot::Result<AdminFormResult<Box<dyn AdminModel>>>;
+
+    /// Get the choices of the form fields that are chosen from a list, such
+    /// as the foreign keys, by field name.
+    async fn form_choices(
+        &self,
+        request: &Request,
+    ) -> cot::Result<Vec<(&'static str, Vec<AdminFormChoice>)>>;
+
+    /// Get the related models edited on the change page of the objects.
+    fn inlines(&self) -> Vec<inlines::AdminInline>;
+}
+
+/// The default [`AdminModelManager`] for a type implementing [`AdminModel`].
+#[derive(Debug)]
+pub struct DefaultAdminModelManager<T> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Default for DefaultAdminModelManager<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DefaultAdminModelManager<T> {
+    /// Create a new model manager.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: AdminModel + 'static> AdminModelManager for DefaultAdminModelManager<T> {
+    fn name(&self) -> &str {
+        T::name()
+    }
+
+    fn url_name(&self) -> &str {
+        T::url_name()
+    }
+
+    fn list_columns(&self) -> &'static [&'static str] {
+        T::list_columns()
+    }
+
+    fn search_fields(&self) -> &'static [&'static str] {
+        T::search_fields()
+    }
+
+    fn read_only_fields(&self) -> &'static [&'static str] {
+        T::read_only_fields()
+    }
+
+    fn has_permission(&self, user: &dyn User, permission: AdminPermission) -> bool {
+        T::has_permission(user, permission)
+    }
+
+    async fn filters(&self, request: &Request) -> cot::Result<Vec<filters::AdminFilter>> {
+        T::filters(request).await
+    }
+
+    fn actions(&self) -> Vec<actions::AdminAction> {
+        T::actions()
+    }
+
+    async fn get_objects(&self, request: &Request) -> cot::Result<Vec<Box<dyn AdminModel>>> {
+        #[allow(trivial_casts)] // Upcast to the correct Box type
+        T::get_objects(request).await.map(|objects| {
+            objects
+                .into_iter()
+                .map(|object| Box::new(object) as Box<dyn AdminModel>)
+                .collect()
+        })
+    }
+
+    async fn get_list(
+        &self,
+        request: &Request,
+        params: &AdminListParams,
+    ) -> cot::Result<AdminList<Box<dyn AdminModel>>> {
+        #[allow(trivial_casts)] // Upcast to the correct Box type
+        T::get_list(request, params)
+            .await
+            .map(|list| list.map(|object| Box::new(object) as Box<dyn AdminModel>))
+    }
+
+    async fn get_object_by_id(
+        &self,
+        request: &Request,
+        id: &str,
+    ) -> cot::Result<Option<Box<dyn AdminModel>>> {
+        #[allow(trivial_casts)] // Upcast to the correct Box type
+        T::get_object_by_id(request, id)
+            .await
+            .map(|object| object.map(|object| Box::new(object) as Box<dyn AdminModel>))
+    }
+
+    fn add_form(&self) -> Option<AdminForm> {
+        T::add_form()
+    }
+
+    async fn create_from_request(
+        &self,
+        request: &mut Request,
+    ) -> cot::Result<AdminFormResult<Box<dyn AdminModel>>> {
+        #[allow(trivial_casts)] // Upcast to the correct Box type
+        T::create_from_request(request)
+            .await
+            .map(|result| result.map(|object| Box::new(object) as Box<dyn AdminModel>))
+    }
+
+    async fn form_choices(
+        &self,
+        request: &Request,
+    ) -> cot::Result<Vec<(&'static str, Vec<AdminFormChoice>)>> {
+        T::form_choices(request).await
+    }
+
+    fn inlines(&self) -> Vec<inlines::AdminInline> {
+        T::inlines()
+    }
+}
+
+/// A model that can be managed by the admin panel.
+///
+/// This trait is typically derived with the
+/// [`AdminModel`](derive@AdminModel) derive macro.
+///
+/// The objects can always be listed and deleted; to allow adding and changing
+/// them, override [`AdminModel::add_form`] and
+/// [`AdminModel::create_from_request`], and [`AdminModel::change_form`] and
+/// [`AdminModel::update_from_request`], respectively. The forms are typically
+/// defined with the [`Form`](derive@Form) derive macro and converted with
+/// [`AdminForm::from_context`].
+#[async_trait]
+pub trait AdminModel: Send + Sync {
+    /// Get the objects of this model.
+    async fn get_objects(request: &Request) -> cot::Result<Vec<Self>>
+    where
+        Self: Sized;
+
+    /// Get the page of objects shown in the list view, searched and sorted
+    /// according to the given parameters.
+    ///
+    /// The default implementation loads all the objects with
+    /// [`AdminModel::get_objects`], then filters, searches, sorts and
+    /// paginates them in memory, using the values returned by
+    /// [`AdminModel::field_value`] for the fields of the
+    /// [`AdminModel::filters`], the [`AdminModel::search_fields`] and the
+    /// [`AdminModel::list_columns`].
+    /// Models backed by a database should override it to do this in the
+    /// query instead, as the [`AdminModel`](derive@AdminModel) derive macro
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objects could not be retrieved.
+    async fn get_list(request: &Request, params: &AdminListParams) -> cot::Result<AdminList<Self>>
+    where
+        Self: Sized,
+    {
+        let mut objects = Self::get_objects(request).await?;
+
+        let filters = Self::filters(request).await?;
+        objects.retain(|object| {
+            filters
+                .iter()
+                .all(|filter| filter.matches(object.field_value(filter.field()).as_deref(), params))
+        });
+        if let Some(search) = params.search() {
+            let search = search.to_lowercase();
+            objects.retain(|object| {
+                Self::search_fields().iter().any(|field| {
+                    object
+                        .field_value(field)
+                        .is_some_and(|value| value.to_lowercase().contains(&search))
+                })
+            });
+        }
+        if let Some(column) = params.sort_column(Self::list_columns()) {
+            objects.sort_by_cached_key(|object| object.field_value(column));
+            if params.is_descending() {
+                objects.reverse();
+            }
+        }
+
+        let total = objects.len() as u64;
+        let objects = objects
+            .into_iter()
+            .skip(usize::try_from(params.offset()).unwrap_or(usize::MAX))
+            .take(usize::try_from(params.per_page()).unwrap_or(usize::MAX))
+            .collect();
+        Ok(AdminList::new(objects, total))
+    }
+
+    /// Get the object of this model with the given ID, or [`None`] if it
+    /// doesn't exist or the ID is invalid.
+    async fn get_object_by_id(request: &Request, id: &str) -> cot::Result<Option<Self>>
+    where
+        Self: Sized;
+
+    /// Get the name of this model.
+    fn name() -> &'static str
+    where
+        Self: Sized;
+
+    /// Get the URL slug for this model.
+    fn url_name() -> &'static str
+    where
+        Self: Sized;
+
+    /// Get the ID of this model instance, as used in the admin panel URLs.
+    fn object_id(&self) -> String;
+
+    /// Get the display text of this model instance.
+    fn display(&self) -> String;
+
+    /// Get the names of the fields shown as columns in the list view; their
+    /// values are retrieved with [`AdminModel::field_value`].
+    ///
+    /// The default implementation returns an empty list, in which case the
+    /// list view only shows [`AdminModel::display`] for each object.
+    #[must_use]
+    fn list_columns() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// Get the names of the fields searched in the list view.
+    ///
+    /// The default implementation returns an empty list.
+    #[must_use]
+    fn search_fields() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// Get the filters shown in the sidebar of the list view.
+    ///
+    /// The default implementation returns an empty list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the choices of the filters could not be retrieved.
+    async fn filters(request: &Request) -> cot::Result<Vec<filters::AdminFilter>>
+    where
+        Self: Sized,
+    {
+        let _ = request;
+        Ok(Vec::new())
+    }
+
+    /// Get the actions that can be run on the objects selected in the list
+    /// view.
+    ///
+    /// The default implementation returns the built-in
+    /// [`AdminAction::delete_selected`](actions::AdminAction::delete_selected)
+    /// and [`AdminAction::export_csv`](actions::AdminAction::export_csv)
+    /// actions; include them in the list when overriding this to keep them
+    /// available.
+    #[must_use]
+    fn actions() -> Vec<actions::AdminAction>
+    where
+        Self: Sized + 'static,
+    {
+        vec![
+            actions::AdminAction::delete_selected::<Self>(),
+            actions::AdminAction::export_csv::<Self>(),
+        ]
+    }
+
+    /// Get the codename of the permission the user needs to perform the given
+    /// operation on the objects of this model.
+    ///
+    /// The default implementation returns the name of the operation followed
+    /// by the [`AdminModel::url_name`], e.g. `"change_blog_post"`; the
+    /// [`AdminModel`](derive@AdminModel) derive macro uses the table name of
+    /// the model instead, to match the
+    /// [`ModelPermission`](crate::auth::db::ModelPermission) codenames.
+    #[must_use]
+    fn permission_codename(permission: AdminPermission) -> String
+    where
+        Self: Sized,
+    {
+        format!("{}_{}", permission.action(), Self::url_name())
+    }
+
+    /// Returns whether the user is allowed to perform the given operation on
+    /// the objects of this model.
+    ///
+    /// The default implementation checks if the user has the permission with
+    /// the [`AdminModel::permission_codename`].
+    fn has_permission(user: &dyn User, permission: AdminPermission) -> bool
+    where
+        Self: Sized,
+    {
+        user.has_perm(&Self::permission_codename(permission))
+    }
+
+    /// Get the names of the fields that can't be changed in the admin panel;
+    /// their values, retrieved with [`AdminModel::field_value`], are shown as
+    /// text on the change page instead.
+    ///
+    /// The default implementation returns an empty list.
+    #[must_use]
+    fn read_only_fields() -> &'static [&'static str]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// Get the value of the field with the given name, as displayed in the
+    /// admin panel, or [`None`] if there is no such field.
+    ///
+    /// The default implementation returns [`None`].
+    fn field_value(&self, field: &str) -> Option<String> {
+        let _ = field;
+        None
+    }
+
+    /// Get the form for adding a new object of this model, or [`None`] if the
+    /// objects can't be added in the admin panel.
+    ///
+    /// The default implementation returns [`None`].
+    #[must_use]
+    fn add_form() -> Option<AdminForm>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Create a new object of this model from the form submitted in the
+    /// request, and save it to the database.
+    ///
+    /// Returns the form with the validation errors if the form is invalid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the form data could not be read from the request.
+    ///
+    /// Returns an error if the object could not be saved.
+    ///
+    /// The default implementation always returns an error, since the objects
+    /// can't be added by default.
+    async fn create_from_request(request: &mut Request) -> cot::Result<AdminFormResult<Self>>
+    where
+        Self: Sized,
+    {
+        let _ = request;
+        Err(cot::Error::custom(format!(
+            "objects of `{}` can't be added in the admin panel",
+            Self::name()
+        )))
+    }
+
+    /// Get the choices of the fields of the add and change forms that are
+    /// chosen from a list, by field name; these fields are rendered as a
+    /// searchable select instead of their own input.
+    ///
+    /// The default implementation returns an empty list. The
+    /// [`AdminModel`](derive@AdminModel) derive macro returns the
+    /// [objects](AdminFormChoice::for_objects) of the referenced model for
+    /// each [`ForeignKey`](crate::db::ForeignKey) field.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the choices could not be retrieved.
+    async fn form_choices(
+        request: &Request,
+    ) -> cot::Result<Vec<(&'static str, Vec<AdminFormChoice>)>>
+    where
+        Self: Sized,
+    {
+        let _ = request;
+        Ok(Vec::new())
+    }
+
+    /// Get the related models whose objects are edited on the change page of
+    /// the objects of this model, below its form.
+    ///
+    /// The default implementation returns an empty list.
+    #[must_use]
+    fn inlines() -> Vec<inlines::AdminInline>
+    where
+        Self: Sized + 'static,
+    {
+        Vec::new()
+    }
+
+    /// Get the form for changing this object, filled with its current values,
+    /// or [`None`] if it can't be changed in the admin panel.
+    ///
+    /// The default implementation returns [`None`].
+    fn change_form(&self) -> Option<AdminForm> {
+        None
+    }
+
+    /// Update this object from the form submitted in the request, and save it
+    /// to the database.
+    ///
+    /// Returns the form with the validation errors if the form is invalid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the form data could not be read from the request.
+    ///
+    /// Returns an error if the object could not be saved.
+    ///
+    /// The default implementation always returns an error, since the objects
+    /// can't be changed by default.
+    async fn update_from_request(
+        &mut self,
+        request: &mut Request,
+    ) -> cot::Result<AdminFormResult<()>> {
+        let _ = request;
+        Err(cot::Error::custom(format!(
+            "object `{}` can't be changed in the admin panel",
+            self.display()
+        )))
+    }
+
+    /// Delete this object from the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object could not be deleted.
+    async fn delete(&self, request: &Request) -> cot::Result<()>;
+}
+
+/// The parameters of the admin list view: the page shown, the column the
+/// objects are sorted by, the search term, and the values selected in the
+/// [filters](filters::AdminFilter).
+///
+/// These are read from the `page`, `sort` and `q` query parameters of the
+/// request, respectively; the `sort` parameter contains the name of a column,
+/// prefixed with `-` for descending order. All the other query parameters are
+/// the filter values.
+///
+/// # Examples
+///
+/// ```
+/// use cot::admin::AdminListParams;
+///
+/// let params = AdminListParams::new()
+///     .with_page(3)
+///     .with_sort("title", true)
+///     .with_search("cot");
+///
+/// assert_eq!(params.offset(), 50);
+/// assert_eq!(params.sort_column(&["id", "title"]), Some("title"));
+/// assert!(params.is_descending());
+/// assert_eq!(params.search(), Some("cot"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdminListParams {
+    page: u64,
+    per_page: u64,
+    sort: Option<String>,
+    descending: bool,
+    search: Option<String>,
+    filters: BTreeMap<String, String>,
+}
+
+impl Default for AdminListParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdminListParams {
+    /// The number of objects shown on a single page by default.
+    pub const DEFAULT_PER_PAGE: u64 = 25;
+
+    /// Create parameters for the first page, with no sorting or search.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            page: 1,
+            per_page: Self::DEFAULT_PER_PAGE,
+            sort: None,
+            descending: false,
+            search: None,
+            filters: BTreeMap::new(),
+        }
+    }
+
+    /// Read the parameters from the query string of the request. Missing or
+    /// invalid parameters are replaced with the defaults.
+    #[must_use]
+    pub fn from_request(request: &Request) -> Self {
+        let mut params = Self::new();
+        let query = request.uri().query().unwrap_or_default();
+
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "page" => {
+                    if let Ok(page) = value.parse() {
+                        params = params.with_page(page);
+                    }
+                }
+                "sort" => {
+                    params = match value.strip_prefix('-') {
+                        Some(column) => params.with_sort(column, true),
+                        None => params.with_sort(value, false),
+                    };
+                }
+                "q" => params = params.with_search(value),
+                _ => params = params.with_filter(key, value),
+            }
+        }
+
+        params
+    }
+
+    /// Set the page shown, starting from 1.
+    #[must_use]
+    pub fn with_page(mut self, page: u64) -> Self {
+        self.page = page.max(1);
+        self
+    }
+
+    /// Set the number of objects shown on a single page.
+    #[must_use]
+    pub fn with_per_page(mut self, per_page: u64) -> Self {
+        self.per_page = per_page.max(1);
+        self
+    }
+
+    /// Set the column the objects are sorted by.
+    #[must_use]
+    pub fn with_sort<T: Into<String>>(mut self, column: T, descending: bool) -> Self {
+        let column = column.into();
+        self.sort = (!column.is_empty()).then_some(column);
+        self.descending = descending;
+        self
+    }
+
+    /// Set the search term; an empty or whitespace-only term disables the
+    /// search.
+    #[must_use]
+    pub fn with_search<T: AsRef<str>>(mut self, search: T) -> Self {
+        let search = search.as_ref().trim();
+        self.search = (!search.is_empty()).then(|| search.to_owned());
+        self
+    }
+
+    /// Set the value of the filter query parameter with the given name; an
+    /// empty value clears it.
+    #[must_use]
+    pub fn with_filter<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        let value = value.into();
+        if value.is_empty() {
+            self.filters.remove(&key.into());
+        } else {
+            self.filters.insert(key.into(), value);
+        }
+        self
+    }
+
+    /// Clear the value of the filter query parameter with the given name.
+    #[must_use]
+    pub fn without_filter(mut self, key: &str) -> Self {
+        self.filters.remove(key);
+        self
+    }
+
+    /// Get the page shown, starting from 1.
+    #[must_use]
+    pub fn page(&self) -> u64 {
+        self.page
+    }
+
+    /// Get the number of objects shown on a single page.
+    #[must_use]
+    pub fn per_page(&self) -> u64 {
+        self.per_page
+    }
+
+    /// Get the number of objects before the page shown.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        (self.page - 1).saturating_mul(self.per_page)
+    }
+
+    /// Get the number of pages needed to show the given number of objects;
+    /// this is always at least 1.
+    #[must_use]
+    pub fn num_pages(&self, total: u64) -> u64 {
+        total.div_ceil(self.per_page).max(1)
+    }
+
+    /// Get the requested sort column, as passed in the request.
+    ///
+    /// This can be any string, so use [`AdminListParams::sort_column`] to
+    /// check it against the columns the objects can actually be sorted by.
+    #[must_use]
+    pub fn sort(&self) -> Option<&str> {
+        self.sort.as_deref()
+    }
+
+    /// Get the sort column if it's one of the given columns, or [`None`]
+    /// otherwise.
+    #[must_use]
+    pub fn sort_column(&self, columns: &[&'static str]) -> Option<&'static str> {
+        let sort = self.sort.as_deref()?;
+        columns.iter().copied().find(|&column| column == sort)
+    }
+
+    /// Returns whether the objects are sorted in descending order.
+    #[must_use]
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    /// Get the search term.
+    #[must_use]
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
+    /// Get the value of the filter query parameter with the given name.
+    #[must_use]
+    pub fn filter_value(&self, key: &str) -> Option<&str> {
+        self.filters.get(key).map(String::as_str)
+    }
+
+    /// Get the value selected in the [boolean
+    /// filter](filters::AdminFilter::boolean) of the given field, or
+    /// [`None`] if there is no valid value.
+    #[must_use]
+    pub fn boolean_filter(&self, field: &str) -> Option<bool> {
+        match self.filter_value(field)? {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Get the earliest and the latest dates selected in the
+    /// [date range filter](filters::AdminFilter::date_range) of the given
+    /// field, read from the `{field}__from` and `{field}__to` query parameters
+    /// in the `YYYY-MM-DD` format.
+    #[must_use]
+    pub fn date_range_filter(
+        &self,
+        field: &str,
+    ) -> (Option<chrono::NaiveDate>, Option<chrono::NaiveDate>) {
+        let date = |key: String| self.filter_value(&key).and_then(filters::parse_date);
+        (
+            date(filters::date_from_key(field)),
+            date(filters::date_to_key(field)),
+        )
+    }
+
+    /// Get the query parameters for these parameters, as name and value pairs.
+    fn query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        if let Some(search) = &self.search {
+            pairs.push((String::from("q"), search.clone()));
+        }
+        if let Some(sort) = self.sort_param() {
+            pairs.push((String::from("sort"), sort));
+        }
+        if self.page > 1 {
+            pairs.push((String::from("page"), self.page.to_string()));
+        }
+        pairs.extend(
+            self.filters
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        pairs
+    }
+
+    /// Get the query string (without the leading `?`) for these parameters.
+    fn query_string(&self) -> String {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(self.query_pairs())
+            .finish()
+    }
+
+    fn sort_param(&self) -> Option<String> {
+        let sort = self.sort.as_deref()?;
+        Some(if self.descending {
+            format!("-{sort}")
+        } else {
+            sort.to_owned()
+        })
+    }
+
+    /// Get the query string for sorting by the given column; if the objects
+    /// are already sorted by it in ascending order, the order is reversed.
+    fn sort_query(&self, column: &str) -> String {
+        let descending = self.sort() == Some(column) && !self.descending;
+        self.clone()
+            .with_sort(column, descending)
+            .with_page(1)
+            .query_string()
+    }
+
+    fn sort_indicator(&self, column: &str) -> &'static str {
+        match (self.sort() == Some(column), self.descending) {
+            (false, _) => "",
+            (true, false) => " \u{25b2}",
+            (true, true) => " \u{25bc}",
+        }
+    }
+
+    fn page_query(&self, page: u64) -> String {
+        self.clone().with_page(page).query_string()
+    }
+}
+
+/// A page of objects shown in the admin list view, along with the total
+/// number of objects matching the search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminList<T> {
+    objects: Vec<T>,
+    total: u64,
+}
+
+impl<T> AdminList<T> {
+    /// Create a new list from the objects on the page and the total number of
+    /// matching objects.
+    #[must_use]
+    pub fn new(objects: Vec<T>, total: u64) -> Self {
+        Self { objects, total }
+    }
+
+    /// Get the objects on the page.
+    #[must_use]
+    pub fn objects(&self) -> &[T] {
+        &self.objects
+    }
+
+    /// Get the objects on the page, consuming the list.
+    #[must_use]
+    pub fn into_objects(self) -> Vec<T> {
+        self.objects
+    }
+
+    /// Get the total number of objects matching the search, on all pages.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Convert the objects on the page with the given function.
+    #[must_use]
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> AdminList<U> {
+        AdminList {
+            objects: self.objects.into_iter().map(f).collect(),
+            total: self.total,
+        }
+    }
+}
+
+/// The result of validating and saving an [`AdminForm`].
+#[must_use]
+#[derive(Debug, Clone)]
+pub enum AdminFormResult<T> {
+    /// The form was valid and the object was saved.
+    Ok(T),
+    /// The form validation failed.
+    ValidationError(AdminForm),
+}
+
+impl<T> AdminFormResult<T> {
+    /// Maps the saved object with the given function.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> AdminFormResult<U> {
+        match self {
+            Self::Ok(value) => AdminFormResult::Ok(f(value)),
+            Self::ValidationError(form) => AdminFormResult::ValidationError(form),
+        }
+    }
+}
+
+impl<T: Form> From<FormResult<T>> for AdminFormResult<T> {
+    fn from(result: FormResult<T>) -> Self {
+        match result {
+            FormResult::Ok(form) => Self::Ok(form),
+            FormResult::ValidationError(context) => {
+                Self::ValidationError(AdminForm::from_context(&context))
+            }
+        }
+    }
+}
+
+/// A form rendered in the admin panel.
+///
+/// This is a rendered snapshot of a [`FormContext`], so that the admin views
+/// can render the forms of any model without knowing their types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminForm {
+    fields: Vec<AdminFormField>,
+    errors: Vec<String>,
+}
+
+impl AdminForm {
+    /// Creates an admin form from a form context, along with its values and
+    /// validation errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// use cot::admin::AdminForm;
+    /// use cot::form::{Form, FormContext};
+    ///
+    /// #[derive(Form)]
+    /// struct PostForm {
+    ///     title: String,
+    /// }
+    ///
+    /// let mut context = <PostForm as Form>::Context::new();
+    /// context.set_value("title", Cow::Borrowed("Hello")).unwrap();
+    ///
+    /// let form = AdminForm::from_context(&context);
+    /// assert_eq!(form.fields()[0].label(), "Title");
+    /// assert!(form.fields()[0].html().contains("value=\"Hello\""));
+    /// ```
+    #[must_use]
+    pub fn from_context<C: FormContext>(context: &C) -> Self {
+        let fields = context
+            .fields()
+            .map(|field| AdminFormField {
+                id: field.dyn_id().to_owned(),
+                prefix: String::new(),
+                required: field.dyn_options().required,
+                value: field.dyn_value().map(ToOwned::to_owned),
+                html: field.to_string(),
+                choices: None,
+                errors: error_messages(context.errors_for(FormErrorTarget::Field(field.dyn_id()))),
+            })
+            .collect();
+
+        Self {
+            fields,
+            errors: error_messages(context.errors_for(FormErrorTarget::Form)),
+        }
+    }
+
+    /// Returns the fields of the form.
+    #[must_use]
+    pub fn fields(&self) -> &[AdminFormField] {
+        &self.fields
+    }
+
+    /// Returns the validation errors of the entire form.
+    #[must_use]
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Returns whether the form has any validation errors.
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty() || self.fields.iter().any(|field| !field.errors.is_empty())
+    }
+
+    /// Sets the choices of the fields, as returned by
+    /// [`AdminModel::form_choices`], so that they are rendered as a select.
+    fn with_choices(mut self, choices: &[(&'static str, Vec<AdminFormChoice>)]) -> Self {
+        for (field_id, field_choices) in choices {
+            if let Some(field) = self.fields.iter_mut().find(|field| field.id == *field_id) {
+                field.choices = Some(field_choices.clone());
+            }
+        }
+        self
+    }
+
+    /// Removes the field with the given ID from the form.
+    fn without_field(mut self, field_id: &str) -> Self {
+        self.fields.retain(|field| field.id != field_id);
+        self
+    }
+
+    /// Prefixes the names and the HTML IDs of the fields, so that several
+    /// forms can be submitted at once, e.g. as the rows of an
+    /// [inline](inlines::AdminInline).
+    ///
+    /// The rendered HTML of a field is only changed if it contains
+    /// `name="{id}"` and `id="{id}"` attributes, as the built-in fields do.
+    fn with_prefix(mut self, prefix: &str) -> Self {
+        for field in &mut self.fields {
+            let prefixed_id = format!("{prefix}{}", field.id);
+            field.html = field
+                .html
+                .replace(
+                    &format!(" name=\"{}\"", field.id),
+                    &format!(" name=\"{prefixed_id}\""),
+                )
+                .replace(
+                    &format!(" id=\"{}\"", field.id),
+                    &format!(" id=\"{prefixed_id}\""),
+                );
+            prefix.clone_into(&mut field.prefix);
+        }
+        self
+    }
+}
+
+/// A field of an [`AdminForm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminFormField {
+    id: String,
+    prefix: String,
+    required: bool,
+    value: Option<String>,
+    html: String,
+    choices: Option<Vec<AdminFormChoice>>,
+    errors: Vec<String>,
+}
+
+impl AdminFormField {
+    /// Returns the ID of the field, i.e. its name in the form.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the HTML ID of the field, which is also the name it's
+    /// submitted with.
+    ///
+    /// This is the same as [`AdminFormField::id`], except for the fields of
+    /// the [inline](inlines::AdminInline) rows, which are prefixed with the
+    /// inline and the row number.
+    #[must_use]
+    pub fn html_id(&self) -> String {
+        format!("{}{}", self.prefix, self.id)
+    }
+
+    /// Returns whether the field is required.
+    #[must_use]
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Returns the human-readable label of the field, created from its ID.
+    #[must_use]
+    pub fn label(&self) -> String {
+        field_label(&self.id)
+    }
+
+    /// Returns the current string value of the field, if it has one.
+    #[must_use]
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Returns the rendered HTML of the field.
+    ///
+    /// Fields with [choices](AdminFormField::choices) are rendered as a select
+    /// by the admin templates instead.
+    #[must_use]
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+
+    /// Returns the values the field can be set to, as returned by
+    /// [`AdminModel::form_choices`], or [`None`] if it can be set to any
+    /// value.
+    #[must_use]
+    pub fn choices(&self) -> Option<&[AdminFormChoice]> {
+        self.choices.as_deref()
+    }
+
+    /// Returns the validation errors of the field.
+    #[must_use]
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+/// A value that can be chosen in a field of an [`AdminForm`], such as one of
+/// the objects a foreign key can refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminFormChoice {
+    value: String,
+    label: String,
+}
+
+impl AdminFormChoice {
+    /// Create a new choice with the given value, submitted in the form, and
+    /// the label shown to the user.
+    #[must_use]
+    pub fn new<V: Into<String>, L: Into<String>>(value: V, label: L) -> Self {
+        Self {
+            value: value.into(),
+            label: label.into(),
+        }
+    }
+
+    /// Get the choices of all the objects of a model, with their
+    /// [IDs](AdminModel::object_id) as the values and their
+    /// [display texts](AdminModel::display) as the labels.
+    ///
+    /// These are the choices of the [`ForeignKey`](crate::db::ForeignKey)
+    /// fields in the forms generated by the [`AdminModel`](derive@AdminModel)
+    /// derive macro.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objects could not be retrieved.
+    pub async fn for_objects<T: AdminModel>(request: &Request) -> cot::Result<Vec<Self>> {
+        Ok(T::get_objects(request)
+            .await?
+            .iter()
+            .map(|object| Self::new(object.object_id(), object.display()))
+            .collect())
+    }
+
+    /// Get the value of this choice.
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the label of this choice.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// A value that can be displayed in the admin panel, e.g. in the list view
+/// columns or as a read-only field.
+///
+/// # Examples
+///
+/// ```
+/// use cot::admin::AdminDisplay;
+///
+/// assert_eq!(42.admin_display(), "42");
+/// assert_eq!(Some(true).admin_display(), "true");
+/// assert_eq!(None::<String>.admin_display(), "-");
+/// ```
+pub trait AdminDisplay {
+    /// Returns the text displayed in the admin panel for the value.
+    fn admin_display(&self) -> String;
+}
+
+macro_rules! impl_admin_display_with_to_string {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl AdminDisplay for $ty {
+                fn admin_display(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_admin_display_with_to_string!(
+    String,
+    &str,
+    bool,
+    char,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    f32,
+    f64,
+    chrono::NaiveDate,
+    chrono::NaiveTime,
+    chrono::NaiveDateTime,
+);
+
+impl<Tz: chrono::TimeZone> AdminDisplay for chrono::DateTime<Tz>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    fn admin_display(&self) -> String {
+        self.to_rfc3339()
+    }
+}
+
+impl<T: AdminDisplay> AdminDisplay for Option<T> {
+    fn admin_display(&self) -> String {
+        self.as_ref()
+            .map_or_else(|| String::from("-"), AdminDisplay::admin_display)
+    }
+}
+
+#[cfg(feature = "db")]
+impl<T: AdminDisplay> AdminDisplay for crate::db::Auto<T> {
+    fn admin_display(&self) -> String {
+        match self {
+            Self::Fixed(value) => value.admin_display(),
+            Self::Auto => String::from("-"),
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl<const LIMIT: u32> AdminDisplay for crate::db::LimitedString<LIMIT> {
+    fn admin_display(&self) -> String {
+        self.as_str().to_owned()
+    }
+}
+
+#[cfg(feature = "db")]
+impl<T: Model> AdminDisplay for crate::db::ForeignKey<T>
+where
+    T::PrimaryKey: AdminDisplay,
+{
+    fn admin_display(&self) -> String {
+        self.primary_key().admin_display()
+    }
+}
+
+/// Creates a human-readable label from a field name, e.g. `Is active` from
+/// `is_active`.
+fn field_label(name: &str) -> String {
+    let label = name.replace('_', " ");
+    let mut chars = label.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Creates a human-readable label from an app name, e.g. `My blog` from
+/// `my-blog`.
+fn app_label(name: &str) -> String {
+    field_label(&name.replace('-', "_"))
+}
+
+/// Parses a value submitted in the admin panel (e.g. the ID in the URL) the
+/// same way as a form field of the given type, or returns [`None`] if it's
+/// invalid.
+#[cfg(feature = "db")]
+fn parse_form_value<T: crate::form::AsFormField>(value: &str) -> Option<T> {
+    let mut field = T::new_field(
+        crate::form::FormFieldOptions {
+            id: String::from("value"),
+            required: true,
+        },
+        Default::default(),
+    );
+    field.set_value(std::borrow::Cow::Borrowed(value));
+
+    T::clean_value(&field).ok()
+}
+
+/// Gets the page of objects of a database model shown in the list view,
+/// doing the filtering, search, sorting and pagination in the database query,
+/// like the [`AdminModel::get_list`] implementation generated by the
+/// [`AdminModel`](derive@AdminModel) derive macro does.
+///
+/// `conditions` are the expressions of the active filters of the model.
+#[cfg(feature = "db")]
+pub(crate) async fn get_model_list<T: Model + AdminModel>(
+    request: &Request,
+    params: &AdminListParams,
+    mut conditions: Vec<crate::db::query::Expr>,
+) -> cot::Result<AdminList<T>> {
+    use crate::db::query::{Expr, Order};
+
+    let db = request.db();
+    let mut query = T::objects();
+
+    if let Some(search) = params.search() {
+        let pattern = format!("%{}%", Expr::escape_like(&search.to_lowercase()));
+        if let Some(filter) = T::search_fields()
+            .iter()
+            .map(|&field| Expr::like(Expr::lower(Expr::field(field)), pattern.clone()))
+            .reduce(Expr::or)
+        {
+            conditions.push(filter);
+        }
+    }
+    if let Some(filter) = conditions.into_iter().reduce(Expr::and) {
+        query.filter(filter);
+    }
+    let total = query.count(db).await?;
+
+    if let Some(column) = params.sort_column(T::list_columns()) {
+        query.order_by(
+            column,
+            if params.is_descending() {
+                Order::Desc
+            } else {
+                Order::Asc
+            },
+        );
+    }
+    query
+        .order_by(T::PRIMARY_KEY_NAME, Order::Asc)
+        .limit(params.per_page())
+        .offset(params.offset());
+
+    Ok(AdminList::new(query.all(db).await?, total))
+}
+
+fn error_messages(errors: &[FormFieldValidationError]) -> Vec<String> {
+    errors.iter().map(ToString::to_string).collect()
 }
 
 /// The admin app.
@@ -245,8 +2354,11 @@ pub trait AdminModel {
 ///     }
 /// }
 /// ```
-#[derive(Debug, Copy, Clone)]
-pub struct AdminApp;
+#[derive(Debug, Clone)]
+pub struct AdminApp {
+    #[debug("..")]
+    templates: Arc<dyn templates::AdminTemplates>,
+}
 
 impl Default for AdminApp {
     fn default() -> Self {
@@ -277,7 +2389,33 @@ impl AdminApp {
     /// ```
     #[must_use]
     pub fn new() -> Self {
-        Self {}
+        Self {
+            templates: Arc::new(templates::DefaultAdminTemplates),
+        }
+    }
+
+    /// Sets the templates the admin pages are rendered with, e.g. to brand the
+    /// admin panel. By default, the built-in templates are used.
+    ///
+    /// See the [`templates`] module for an example.
+    #[must_use]
+    pub fn with_templates<T: templates::AdminTemplates + 'static>(mut self, templates: T) -> Self {
+        self.templates = Arc::new(templates);
+        self
+    }
+
+    /// Wraps an admin view so that it renders the pages with the templates of
+    /// the app.
+    fn view<F, R>(&self, view: F) -> impl RequestHandler + Send + Sync + 'static
+    where
+        F: Fn(Request) -> R + Clone + Send + Sync + 'static,
+        R: Future<Output = cot::Result<Response>> + Send,
+    {
+        let templates = AdminTemplatesExtension(Arc::clone(&self.templates));
+        move |mut request: Request| {
+            request.extensions_mut().insert(templates.clone());
+            view(request)
+        }
     }
 }
 
@@ -286,15 +2424,475 @@ impl App for AdminApp {
         "cot_admin"
     }
 
+    #[cfg(feature = "db")]
+    fn admin_dashboard_widgets(&self) -> Vec<Box<dyn dashboard::AdminWidget>> {
+        vec![Box::new(dashboard::RecentActionsWidget::new())]
+    }
+
     fn router(&self) -> Router {
         Router::with_urls([
-            crate::router::Route::with_handler_and_name("/", index, "index"),
-            crate::router::Route::with_handler_and_name("/login", login, "login"),
-            crate::router::Route::with_handler_and_name("/{model_name}", view_model, "view_model"),
+            crate::router::Route::with_handler_and_name("/", self.view(index), "index"),
+            crate::router::Route::with_handler_and_name("/login", self.view(login), "login"),
+            crate::router::Route::with_handler_and_name(
+                "/login/verify",
+                self.view(login_verify),
+                "login_verify",
+            ),
+            crate::router::Route::with_handler_and_name("/logout", self.view(logout), "logout"),
+            #[cfg(feature = "db")]
+            crate::router::Route::with_handler_and_name(
+                "/password",
+                self.view(password_change),
+                "password_change",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/lockouts",
+                self.view(lockouts),
+                "lockouts",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}",
+                self.view(view_model),
+                "view_model",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/add",
+                self.view(add_model),
+                "add_model",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/action",
+                self.view(run_model_action),
+                "model_action",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/export/{format}",
+                self.view(import_export::export_model),
+                "export_model",
+            ),
+            #[cfg(feature = "db")]
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/import",
+                self.view(import_export::import_model),
+                "import_model",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/{object_id}",
+                self.view(change_model),
+                "change_model",
+            ),
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/{object_id}/delete",
+                self.view(delete_model),
+                "delete_model",
+            ),
+            #[cfg(feature = "db")]
+            crate::router::Route::with_handler_and_name(
+                "/{model_name}/{object_id}/history",
+                self.view(model_history),
+                "model_history",
+            ),
         ])
     }
 
+    #[cfg(feature = "db")]
+    fn migrations(&self) -> Vec<Box<crate::db::migrations::SyncDynMigration>> {
+        // TODO: this is way too complicated for the user-facing API
+        #[allow(trivial_casts)]
+        migrations::MIGRATIONS
+            .iter()
+            .copied()
+            .map(|x| Box::new(x) as Box<crate::db::migrations::SyncDynMigration>)
+            .collect()
+    }
+
     fn static_files(&self) -> Vec<(String, Bytes)> {
-        static_files!("admin/admin.css")
+        static_files!("admin/admin.css", "admin/admin.js")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::test::TestRequestBuilder;
+
+    #[derive(Form)]
+    struct TestForm {
+        first_name: String,
+        is_active: bool,
+    }
+
+    #[test]
+    fn admin_form_from_context() {
+        let mut context = <TestForm as Form>::Context::new();
+        context
+            .set_value("first_name", Cow::Borrowed("John"))
+            .unwrap();
+        context.add_error(
+            FormErrorTarget::Field("first_name"),
+            FormFieldValidationError::from_static("name is taken"),
+        );
+        context.add_error(
+            FormErrorTarget::Form,
+            FormFieldValidationError::from_static("form is invalid"),
+        );
+
+        let form = AdminForm::from_context(&context);
+
+        assert!(form.has_errors());
+        assert_eq!(form.errors(), ["form is invalid"]);
+        assert_eq!(form.fields().len(), 2);
+        assert_eq!(form.fields()[0].id(), "first_name");
+        assert_eq!(form.fields()[0].label(), "First name");
+        assert_eq!(form.fields()[0].value(), Some("John"));
+        assert!(form.fields()[0].html().contains("value=\"John\""));
+        assert_eq!(form.fields()[0].errors(), ["name is taken"]);
+        assert_eq!(form.fields()[1].label(), "Is active");
+        assert!(form.fields()[1].errors().is_empty());
+    }
+
+    #[test]
+    fn admin_form_inline_row() {
+        let mut context = <TestForm as Form>::Context::new();
+        context
+            .set_value("first_name", Cow::Borrowed("John"))
+            .unwrap();
+
+        let form = AdminForm::from_context(&context)
+            .without_field("is_active")
+            .with_choices(&[("first_name", vec![AdminFormChoice::new("John", "John")])])
+            .with_prefix("user-team-0-");
+
+        assert_eq!(form.fields().len(), 1);
+        let field = &form.fields()[0];
+        assert_eq!(field.id(), "first_name");
+        assert_eq!(field.html_id(), "user-team-0-first_name");
+        assert_eq!(field.label(), "First name");
+        assert!(field.is_required());
+        assert!(field.html().contains("name=\"user-team-0-first_name\""));
+        assert!(field.html().contains("id=\"user-team-0-first_name\""));
+        assert_eq!(field.choices().unwrap()[0].label(), "John");
+    }
+
+    #[test]
+    fn admin_form_result_from_form_result() {
+        let context = <TestForm as Form>::Context::new();
+        let result: AdminFormResult<TestForm> = FormResult::ValidationError(context).into();
+        assert!(matches!(result, AdminFormResult::ValidationError(form) if !form.has_errors()));
+
+        let result: AdminFormResult<TestForm> = FormResult::Ok(TestForm {
+            first_name: String::from("John"),
+            is_active: true,
+        })
+        .into();
+        let result = result.map(|form| (form.first_name, form.is_active));
+        assert!(matches!(result, AdminFormResult::Ok((name, true)) if name == "John"));
+    }
+
+    #[test]
+    fn admin_list_params_from_request() {
+        let request = TestRequestBuilder::get("/?page=3&sort=-title&q=+hello%20world+").build();
+        let params = AdminListParams::from_request(&request);
+        assert_eq!(params.page(), 3);
+        assert_eq!(params.offset(), 50);
+        assert_eq!(params.sort(), Some("title"));
+        assert!(params.is_descending());
+        assert_eq!(params.search(), Some("hello world"));
+
+        let request = TestRequestBuilder::get("/?page=0&sort=&q=").build();
+        assert_eq!(
+            AdminListParams::from_request(&request),
+            AdminListParams::new()
+        );
+        let request = TestRequestBuilder::get("/?page=invalid").build();
+        assert_eq!(AdminListParams::from_request(&request).page(), 1);
+    }
+
+    #[test]
+    fn admin_list_params_queries() {
+        let params = AdminListParams::new()
+            .with_page(2)
+            .with_sort("title", false)
+            .with_search("a&b");
+        assert_eq!(params.query_string(), "q=a%26b&sort=title&page=2");
+        assert_eq!(params.sort_query("title"), "q=a%26b&sort=-title");
+        assert_eq!(params.sort_query("id"), "q=a%26b&sort=id");
+        assert_eq!(params.page_query(3), "q=a%26b&sort=title&page=3");
+        assert_eq!(params.sort_column(&["id", "title"]), Some("title"));
+        assert_eq!(params.sort_column(&["id"]), None);
+        assert_eq!(params.num_pages(0), 1);
+        assert_eq!(params.num_pages(26), 2);
+    }
+
+    struct Fruit {
+        name: &'static str,
+    }
+
+    #[async_trait]
+    impl AdminModel for Fruit {
+        async fn get_objects(_request: &Request) -> cot::Result<Vec<Self>> {
+            Ok(["banana", "Apple", "cherry", "pineapple"]
+                .into_iter()
+                .map(|name| Self { name })
+                .collect())
+        }
+
+        async fn get_object_by_id(_request: &Request, _id: &str) -> cot::Result<Option<Self>> {
+            Ok(None)
+        }
+
+        fn name() -> &'static str {
+            "Fruit"
+        }
+
+        fn url_name() -> &'static str {
+            "fruit"
+        }
+
+        fn object_id(&self) -> String {
+            self.name.to_owned()
+        }
+
+        fn display(&self) -> String {
+            self.name.to_owned()
+        }
+
+        fn list_columns() -> &'static [&'static str] {
+            &["name"]
+        }
+
+        fn search_fields() -> &'static [&'static str] {
+            &["name"]
+        }
+
+        fn field_value(&self, field: &str) -> Option<String> {
+            (field == "name").then(|| self.name.to_owned())
+        }
+
+        async fn delete(&self, _request: &Request) -> cot::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_model_default_get_list() {
+        let request = TestRequestBuilder::get("/").build();
+        let names = |list: AdminList<Fruit>| -> Vec<&'static str> {
+            list.into_objects().iter().map(|fruit| fruit.name).collect()
+        };
+
+        let params = AdminListParams::new().with_per_page(3);
+        let list = Fruit::get_list(&request, &params).await.unwrap();
+        assert_eq!(list.total(), 4);
+        assert_eq!(names(list), ["banana", "Apple", "cherry"]);
+
+        let params = params.with_page(2);
+        assert_eq!(
+            names(Fruit::get_list(&request, &params).await.unwrap()),
+            ["pineapple"]
+        );
+
+        let params = AdminListParams::new()
+            .with_search("APPLE")
+            .with_sort("name", true);
+        let list = Fruit::get_list(&request, &params).await.unwrap();
+        assert_eq!(list.total(), 2);
+        assert_eq!(names(list), ["pineapple", "Apple"]);
+    }
+
+    struct StaffUser {
+        is_staff: bool,
+    }
+
+    impl User for StaffUser {
+        fn is_active(&self) -> bool {
+            true
+        }
+
+        fn is_authenticated(&self) -> bool {
+            true
+        }
+
+        fn is_staff(&self) -> bool {
+            self.is_staff
+        }
+
+        fn has_perm(&self, perm: &str) -> bool {
+            perm == "view_fruit" || perm == "delete_fruit"
+        }
+    }
+
+    #[tokio::test]
+    async fn check_staff_user() {
+        let mut request = TestRequestBuilder::get("/").with_session().build();
+        request
+            .login(Box::new(StaffUser { is_staff: false }))
+            .await
+            .unwrap();
+        let response = check_staff(&mut request).await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let mut request = TestRequestBuilder::get("/").with_session().build();
+        request
+            .login(Box::new(StaffUser { is_staff: true }))
+            .await
+            .unwrap();
+        assert!(check_staff(&mut request).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn lockouts_require_permission() {
+        let mut request = TestRequestBuilder::get("/").with_session().build();
+        request
+            .login(Box::new(StaffUser { is_staff: true }))
+            .await
+            .unwrap();
+
+        let response = lockouts(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    /// An auth backend requiring the second factor from every user.
+    struct SecondFactorAuthBackend;
+
+    #[async_trait]
+    impl crate::auth::AuthBackend for SecondFactorAuthBackend {
+        async fn authenticate(
+            &self,
+            _request: &Request,
+            _credentials: &(dyn std::any::Any + Send + Sync),
+        ) -> crate::auth::Result<Option<Box<dyn User + Send + Sync>>> {
+            Ok(None)
+        }
+
+        async fn get_by_id(
+            &self,
+            _request: &Request,
+            _id: crate::auth::UserId,
+        ) -> crate::auth::Result<Option<Box<dyn User + Send + Sync>>> {
+            Ok(None)
+        }
+
+        async fn requires_second_factor(
+            &self,
+            _request: &Request,
+            _user: &(dyn User + Send + Sync),
+        ) -> crate::auth::Result<bool> {
+            Ok(true)
+        }
+    }
+
+    struct UserWithId;
+
+    impl User for UserWithId {
+        fn id(&self) -> Option<crate::auth::UserId> {
+            Some(crate::auth::UserId::Int(1))
+        }
+    }
+
+    #[tokio::test]
+    async fn login_verify_unexpected_method() {
+        let mut request = TestRequestBuilder::get("/")
+            .with_session()
+            .auth_backend(SecondFactorAuthBackend)
+            .build();
+        request.login(Box::new(UserWithId)).await.unwrap();
+        assert!(request.second_factor_pending().await.unwrap());
+        *request.method_mut() = http::Method::PUT;
+
+        let response = login_verify(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[cfg(feature = "db")]
+    #[cot_macros::dbtest]
+    async fn password_change_unexpected_method(test_db: &mut crate::test::TestDatabase) {
+        test_db.with_auth().run_migrations().await;
+        let user = DatabaseUser::create_superuser(
+            &**test_db,
+            "admin",
+            &crate::auth::Password::new("password123"),
+            &crate::config::PasswordValidators::none(),
+            &crate::config::PasswordHasherConfig::default(),
+        )
+        .await
+        .unwrap();
+        let mut request = TestRequestBuilder::get("/")
+            .with_db_auth(test_db.database())
+            .with_session()
+            .build();
+        request.login(Box::new(user)).await.unwrap();
+        *request.method_mut() = http::Method::PUT;
+
+        let response = password_change(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    struct TestTemplates;
+
+    impl templates::AdminTemplates for TestTemplates {
+        fn render_base(&self, page: &templates::AdminPage<'_>) -> cot::Result<String> {
+            Ok(format!("<h1>{}</h1>{}", page.title(), page.content()))
+        }
+    }
+
+    async fn response_text(response: Response) -> String {
+        let bytes = response.into_body().into_bytes().await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn render_content_with_templates() {
+        let request = TestRequestBuilder::get("/").build();
+        let response = render_content(&request, "Home", "", "<p>Hello</p>").unwrap();
+        let html = response_text(response).await;
+        assert!(html.contains("<title>Home | Cot Admin</title>"));
+        assert!(html.contains("<p>Hello</p>"));
+
+        let mut request = TestRequestBuilder::get("/").build();
+        request
+            .extensions_mut()
+            .insert(AdminTemplatesExtension(Arc::new(TestTemplates)));
+        let response = render_content(&request, "Home", "", "<p>Hello</p>").unwrap();
+        assert_eq!(response_text(response).await, "<h1>Home</h1><p>Hello</p>");
+    }
+
+    #[test]
+    fn app_labels() {
+        assert_eq!(app_label("cot_admin"), "Cot admin");
+        assert_eq!(app_label("my-blog"), "My blog");
+    }
+
+    #[test]
+    fn model_permissions_for_user() {
+        assert_eq!(
+            Fruit::permission_codename(AdminPermission::Change),
+            "change_fruit"
+        );
+
+        let manager = DefaultAdminModelManager::<Fruit>::new();
+        let permissions = ModelPermissions::for_user(&StaffUser { is_staff: true }, &manager);
+        assert!(permissions.view);
+        assert!(!permissions.add);
+        assert!(!permissions.change);
+        assert!(permissions.delete);
+
+        let action_names: Vec<_> = allowed_actions(&manager, permissions)
+            .iter()
+            .map(actions::AdminAction::name)
+            .collect();
+        assert_eq!(action_names, ["delete_selected", "export_csv"]);
+
+        let permissions = ModelPermissions {
+            delete: false,
+            ..permissions
+        };
+        let action_names: Vec<_> = allowed_actions(&manager, permissions)
+            .iter()
+            .map(actions::AdminAction::name)
+            .collect();
+        assert_eq!(action_names, ["export_csv"]);
     }
 }
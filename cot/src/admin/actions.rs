@@ -0,0 +1,411 @@
+//! Bulk actions run on the objects selected in the admin list view.
+//!
+//! An [`AdminAction`] is an async function receiving the request and the IDs
+//! of the selected objects, as returned by
+//! [`AdminModel::object_id`](crate::admin::AdminModel::object_id) (the primary
+//! keys, for the models using the [`AdminModel`](derive@AdminModel) derive
+//! macro). It can either report a message shown on the list view, or return
+//! its own response, such as a file to download.
+//!
+//! Every model has the built-in [`AdminAction::delete_selected`] and
+//! [`AdminAction::export_csv`] actions; custom actions are added by overriding
+//! [`AdminModel::actions`], or with the `actions(...)` option of the derive
+//! macro.
+//!
+//! An action is only offered to the users having the
+//! [permission](AdminAction::permission) it requires on the model.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_more::Debug;
+
+use crate::admin::{field_label, AdminModel, AdminPermission};
+use crate::error::ErrorRepr;
+use crate::request::Request;
+use crate::response::{Response, ResponseExt};
+use crate::{Body, StatusCode};
+
+/// The result of running an [`AdminAction`].
+#[derive(Debug)]
+pub enum AdminActionResult {
+    /// The action was run; the user is redirected back to the list view,
+    /// which shows the given message.
+    Message(String),
+    /// The action was run and the given response is returned to the user.
+    Response(Response),
+}
+
+/// A function running an [`AdminAction`].
+///
+/// You shouldn't usually need to implement this directly, as it is already
+/// implemented for closures and functions that take a [`Request`] and the
+/// IDs of the selected objects, and return a
+/// [`Result<AdminActionResult>`](AdminActionResult).
+#[async_trait]
+pub trait AdminActionHandler: Send + Sync {
+    /// Run the action on the objects with the given IDs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the action fails.
+    async fn handle(&self, request: Request, ids: Vec<String>) -> crate::Result<AdminActionResult>;
+}
+
+#[async_trait]
+impl<T, R> AdminActionHandler for T
+where
+    T: Fn(Request, Vec<String>) -> R + Send + Sync,
+    R: Future<Output = crate::Result<AdminActionResult>> + Send,
+{
+    async fn handle(&self, request: Request, ids: Vec<String>) -> crate::Result<AdminActionResult> {
+        self(request, ids).await
+    }
+}
+
+/// An action that can be run on the objects selected in the admin list view.
+///
+/// # Examples
+///
+/// ```
+/// use cot::admin::actions::{AdminAction, AdminActionResult};
+/// use cot::request::Request;
+///
+/// async fn mark_active(request: Request, ids: Vec<String>) -> cot::Result<AdminActionResult> {
+///     // update the objects in the database...
+///     Ok(AdminActionResult::Message(format!(
+///         "{} objects were marked as active.",
+///         ids.len()
+///     )))
+/// }
+///
+/// let action = AdminAction::new("mark_active", "Mark active", mark_active);
+/// assert_eq!(action.name(), "mark_active");
+/// assert_eq!(action.label(), "Mark active");
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdminAction {
+    name: &'static str,
+    label: String,
+    permission: AdminPermission,
+    #[debug("..")]
+    handler: Arc<dyn AdminActionHandler>,
+}
+
+impl AdminAction {
+    /// Create a new action with the given name, used to identify it in the
+    /// submitted form, the label shown to the user, and the function running
+    /// it.
+    ///
+    /// The action requires the [`AdminPermission::Change`] permission; use
+    /// [`AdminAction::with_permission`] to change that.
+    #[must_use]
+    pub fn new<L, H>(name: &'static str, label: L, handler: H) -> Self
+    where
+        L: Into<String>,
+        H: AdminActionHandler + 'static,
+    {
+        Self {
+            name,
+            label: label.into(),
+            permission: AdminPermission::Change,
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Set the permission the user needs on the model to run this action.
+    #[must_use]
+    pub fn with_permission(mut self, permission: AdminPermission) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    /// Create a new action named after the given function, with the label
+    /// generated from the name (e.g. `Mark active` for `mark_active`).
+    ///
+    /// This is used by the [`AdminModel`](derive@AdminModel) derive macro.
+    #[must_use]
+    pub fn from_fn<H: AdminActionHandler + 'static>(name: &'static str, handler: H) -> Self {
+        Self::new(name, field_label(name), handler)
+    }
+
+    /// Create the action deleting the selected objects.
+    ///
+    /// The action requires the [`AdminPermission::Delete`] permission.
+    #[must_use]
+    pub fn delete_selected<T: AdminModel + 'static>() -> Self {
+        Self::new("delete_selected", "Delete selected", delete_selected::<T>)
+            .with_permission(AdminPermission::Delete)
+    }
+
+    /// Create the action downloading the selected objects as a CSV file.
+    ///
+    /// The file contains the [`AdminModel::list_columns`] of the objects, or
+    /// only their [`AdminModel::display`] text if the model has no list
+    /// columns.
+    ///
+    /// The action requires the [`AdminPermission::View`] permission.
+    #[must_use]
+    pub fn export_csv<T: AdminModel + 'static>() -> Self {
+        Self::new("export_csv", "Export selected as CSV", export_csv::<T>)
+            .with_permission(AdminPermission::View)
+    }
+
+    /// Get the name of this action.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the label of this action.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Get the permission the user needs on the model to run this action.
+    #[must_use]
+    pub fn permission(&self) -> AdminPermission {
+        self.permission
+    }
+
+    /// Run this action on the objects with the given IDs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the action fails.
+    pub async fn run(
+        &self,
+        request: Request,
+        ids: Vec<String>,
+    ) -> crate::Result<AdminActionResult> {
+        self.handler.handle(request, ids).await
+    }
+}
+
+async fn get_objects_by_ids<T: AdminModel>(
+    request: &Request,
+    ids: &[String],
+) -> crate::Result<Vec<T>> {
+    let mut objects = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(object) = T::get_object_by_id(request, id).await? {
+            objects.push(object);
+        }
+    }
+
+    Ok(objects)
+}
+
+async fn delete_selected<T: AdminModel>(
+    #[cfg_attr(not(feature = "db"), allow(unused_mut))] // only needed to log the deletion
+    mut request: Request,
+    ids: Vec<String>,
+) -> crate::Result<AdminActionResult> {
+    let objects = get_objects_by_ids::<T>(&request, &ids).await?;
+    for object in &objects {
+        object.delete(&request).await?;
+        #[cfg(feature = "db")]
+        crate::admin::log::LogEntry::log(
+            &mut request,
+            T::url_name(),
+            object,
+            crate::admin::log::LogAction::Delete,
+            &[],
+        )
+        .await?;
+    }
+
+    Ok(AdminActionResult::Message(format!(
+        "{} {} {} deleted successfully.",
+        objects.len(),
+        T::name(),
+        if objects.len() == 1 {
+            "object was"
+        } else {
+            "objects were"
+        }
+    )))
+}
+
+async fn export_csv<T: AdminModel>(
+    request: Request,
+    ids: Vec<String>,
+) -> crate::Result<AdminActionResult> {
+    let objects = get_objects_by_ids::<T>(&request, &ids).await?;
+    let csv = objects_to_csv(
+        T::list_columns(),
+        objects.iter().map(|object| -> &dyn AdminModel { object }),
+    );
+
+    Ok(AdminActionResult::Response(csv_response(
+        T::url_name(),
+        csv,
+    )?))
+}
+
+/// Write the given objects as CSV, with a header row and a row per object
+/// containing the values of the given columns, or only the display text of the
+/// objects if there are no columns.
+pub(super) fn objects_to_csv<'a, I>(columns: &[&str], objects: I) -> String
+where
+    I: IntoIterator<Item = &'a dyn AdminModel>,
+{
+    let mut csv = String::new();
+    if columns.is_empty() {
+        write_csv_row(&mut csv, ["Object"]);
+        for object in objects {
+            write_csv_row(&mut csv, [object.display()]);
+        }
+    } else {
+        write_csv_row(&mut csv, columns.iter().map(|&column| field_label(column)));
+        for object in objects {
+            write_csv_row(
+                &mut csv,
+                columns
+                    .iter()
+                    .map(|column| object.field_value(column).unwrap_or_default()),
+            );
+        }
+    }
+
+    csv
+}
+
+/// The characters that make spreadsheet applications treat a cell as a
+/// formula, along with the `'` used to escape them.
+const CSV_FORMULA_CHARS: [char; 7] = ['=', '+', '-', '@', '\t', '\r', '\''];
+
+/// Writes a row of CSV values.
+///
+/// The files are typically opened in spreadsheet applications, which would
+/// run the values starting with `=`, `+`, `-` or `@` as formulas, so these
+/// values are prefixed with `'` (except for numbers), which the
+/// applications hide; [`unescape_csv_formula`] removes the prefix again when
+/// importing the file.
+fn write_csv_row<I, T>(csv: &mut String, values: I)
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+{
+    for (index, value) in values.into_iter().enumerate() {
+        if index > 0 {
+            csv.push(',');
+        }
+        let escaped;
+        let mut value = value.as_ref();
+        if value.starts_with(CSV_FORMULA_CHARS) && value.parse::<f64>().is_err() {
+            escaped = format!("'{value}");
+            value = &escaped;
+        }
+        if value.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&value.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(value);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+/// Removes the `'` prefix added by [`write_csv_row`] to the values that
+/// spreadsheet applications would treat as formulas.
+#[cfg(feature = "db")]
+pub(super) fn unescape_csv_formula(value: &mut String) {
+    if value.starts_with('\'') && value[1..].starts_with(CSV_FORMULA_CHARS) {
+        value.remove(0);
+    }
+}
+
+/// Create a response downloading the given CSV contents as a file.
+pub(super) fn csv_response(file_name: &str, csv: String) -> crate::Result<Response> {
+    file_response(&format!("{file_name}.csv"), "text/csv; charset=utf-8", csv)
+}
+
+/// Create a response downloading the given contents as a file with the given
+/// name and content type.
+pub(super) fn file_response(
+    file_name: &str,
+    content_type: &str,
+    contents: String,
+) -> crate::Result<Response> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(http::header::CONTENT_TYPE, content_type)
+        .header(
+            http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{file_name}\""),
+        )
+        .body(Body::fixed(contents))
+        .map_err(|error| ErrorRepr::ResponseBuilder(error).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows() {
+        let mut csv = String::new();
+        write_csv_row(&mut csv, ["Title", "Views"]);
+        write_csv_row(&mut csv, ["Hello, \"world\"", "5"]);
+        write_csv_row(&mut csv, ["multi\nline", ""]);
+
+        assert_eq!(
+            csv,
+            "Title,Views\r\n\"Hello, \"\"world\"\"\",5\r\n\"multi\nline\",\r\n"
+        );
+    }
+
+    #[cfg(feature = "db")]
+    #[test]
+    fn csv_formulas() {
+        let values = [
+            "=1+1", "+1", "-1", "-1+1", "@SUM(A1)", "\tx", "'x", "'=1", "x=1",
+        ];
+        let mut csv = String::new();
+        write_csv_row(&mut csv, values);
+
+        assert_eq!(csv, "'=1+1,+1,-1,'-1+1,'@SUM(A1),'\tx,''x,''=1,x=1\r\n");
+        for (value, written) in values.iter().zip(csv.trim_end().split(',')) {
+            let mut written = written.to_owned();
+            unescape_csv_formula(&mut written);
+            assert_eq!(written, *value);
+        }
+
+        let mut value = String::from("'quoted");
+        unescape_csv_formula(&mut value);
+        assert_eq!(value, "'quoted");
+    }
+
+    #[tokio::test]
+    async fn action_from_fn() {
+        async fn mark_active(
+            _request: Request,
+            ids: Vec<String>,
+        ) -> crate::Result<AdminActionResult> {
+            Ok(AdminActionResult::Message(ids.join(",")))
+        }
+
+        let action = AdminAction::from_fn("mark_active", mark_active);
+        assert_eq!(action.name(), "mark_active");
+        assert_eq!(action.label(), "Mark active");
+        assert_eq!(action.permission(), AdminPermission::Change);
+        assert_eq!(
+            action
+                .clone()
+                .with_permission(AdminPermission::View)
+                .permission(),
+            AdminPermission::View
+        );
+
+        let request = crate::test::TestRequestBuilder::get("/").build();
+        let result = action
+            .run(request, vec![String::from("1"), String::from("2")])
+            .await
+            .unwrap();
+        assert!(matches!(result, AdminActionResult::Message(message) if message == "1,2"));
+    }
+}
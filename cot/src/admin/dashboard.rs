@@ -0,0 +1,173 @@
+//! Widgets shown on the dashboard, i.e. the index page of the admin panel.
+//!
+//! The dashboard lists the models of every app and, below them, the widgets
+//! returned by [`App::admin_dashboard_widgets`] of all the apps of the
+//! project. The [`AdminApp`](crate::admin::AdminApp) itself provides the
+//! [`RecentActionsWidget`], listing the latest changes made in the admin
+//! panel.
+//!
+//! # Examples
+//!
+//! ```
+//! use async_trait::async_trait;
+//! use cot::admin::dashboard::AdminWidget;
+//! use cot::request::Request;
+//! use cot::App;
+//!
+//! struct WelcomeWidget;
+//!
+//! #[async_trait]
+//! impl AdminWidget for WelcomeWidget {
+//!     fn title(&self) -> String {
+//!         String::from("Welcome")
+//!     }
+//!
+//!     async fn render(&self, _request: &mut Request) -> cot::Result<Option<String>> {
+//!         Ok(Some(String::from("<p>Have a nice day!</p>")))
+//!     }
+//! }
+//!
+//! struct MyApp;
+//!
+//! impl App for MyApp {
+//!     fn name(&self) -> &str {
+//!         "my_app"
+//!     }
+//!
+//!     fn admin_dashboard_widgets(&self) -> Vec<Box<dyn AdminWidget>> {
+//!         vec![Box::new(WelcomeWidget)]
+//!     }
+//! }
+//! ```
+//!
+//! [`App::admin_dashboard_widgets`]: crate::App::admin_dashboard_widgets
+
+use async_trait::async_trait;
+#[cfg(feature = "db")]
+use rinja::Template;
+
+#[cfg(feature = "db")]
+use crate::admin::log::LogEntry;
+#[cfg(feature = "db")]
+use crate::auth::AuthRequestExt;
+use crate::request::Request;
+#[cfg(feature = "db")]
+use crate::request::RequestExt;
+
+/// A widget shown on the admin dashboard.
+///
+/// See the [module documentation](self) for an example.
+#[async_trait]
+pub trait AdminWidget: Send + Sync {
+    /// Returns the title of the widget.
+    fn title(&self) -> String;
+
+    /// Renders the HTML content of the widget, which is not escaped; returns
+    /// [`None`] to hide the widget, e.g. if the user isn't allowed to see it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the widget could not be rendered.
+    async fn render(&self, request: &mut Request) -> crate::Result<Option<String>>;
+}
+
+/// A widget rendered for the dashboard page.
+#[derive(Debug)]
+pub(super) struct RenderedWidget {
+    title: String,
+    content: String,
+}
+
+impl RenderedWidget {
+    pub(super) async fn render(
+        widget: &dyn AdminWidget,
+        request: &mut Request,
+    ) -> crate::Result<Option<Self>> {
+        Ok(widget.render(request).await?.map(|content| Self {
+            title: widget.title(),
+            content,
+        }))
+    }
+
+    pub(super) fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub(super) fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// A widget listing the latest objects added, changed and deleted in the admin
+/// panel, as recorded in the [admin log](crate::admin::log).
+///
+/// Only the changes of the models the user is allowed to view are shown; the
+/// widget is hidden if the project doesn't use a database.
+#[cfg(feature = "db")]
+#[derive(Debug, Copy, Clone)]
+pub struct RecentActionsWidget {
+    limit: u64,
+}
+
+#[cfg(feature = "db")]
+impl Default for RecentActionsWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "db")]
+impl RecentActionsWidget {
+    /// The number of actions shown by default.
+    pub const DEFAULT_LIMIT: u64 = 10;
+
+    /// Creates a widget showing the [default](Self::DEFAULT_LIMIT) number of
+    /// actions.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+
+    /// Sets the maximum number of actions shown.
+    #[must_use]
+    pub const fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+#[cfg(feature = "db")]
+#[derive(Debug, Template)]
+#[template(path = "admin/recent_actions.html")]
+struct RecentActionsTemplate<'a> {
+    request: &'a Request,
+    entries: Vec<LogEntry>,
+}
+
+#[cfg(feature = "db")]
+#[async_trait]
+impl AdminWidget for RecentActionsWidget {
+    fn title(&self) -> String {
+        String::from("Recent actions")
+    }
+
+    async fn render(&self, request: &mut Request) -> crate::Result<Option<String>> {
+        if request.context().try_database().is_none() {
+            return Ok(None);
+        }
+
+        let managers = super::admin_model_managers(request);
+        let user = request.user().await?;
+        let model_names: Vec<_> = managers
+            .iter()
+            .filter(|manager| super::ModelPermissions::for_user(user, &***manager).view)
+            .map(|manager| manager.url_name())
+            .collect();
+        let entries = LogEntry::recent(request.db(), &model_names, self.limit).await?;
+
+        let template = RecentActionsTemplate { request, entries };
+        Ok(Some(template.render()?))
+    }
+}
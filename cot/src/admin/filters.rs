@@ -0,0 +1,425 @@
+//! Filters shown in the sidebar of the admin list view.
+//!
+//! Each [`AdminFilter`] narrows down the list to the objects with a given
+//! value of a field: either `true` or `false` ([`AdminFilterKind::Boolean`]),
+//! a date between two bounds ([`AdminFilterKind::DateRange`]), or one of a
+//! list of choices, such as the objects of a related model
+//! ([`AdminFilterKind::Choices`]). The values selected by the user are passed
+//! in the query string and read with [`AdminListParams`].
+//!
+//! The filters of a model are returned by [`AdminModel::filters`]; with the
+//! [`AdminModel`](derive@AdminModel) derive macro, they are listed in the
+//! `filters(...)` option and their kind is chosen based on the type of the
+//! field through the [`AdminFilterField`] trait.
+
+#[cfg(feature = "db")]
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+#[cfg(feature = "db")]
+use crate::admin::AdminModel;
+use crate::admin::{field_label, AdminListParams};
+#[cfg(feature = "db")]
+use crate::db::query::Expr;
+#[cfg(feature = "db")]
+use crate::db::{ForeignKey, Model};
+#[cfg(feature = "db")]
+use crate::form::AsFormField;
+#[cfg(feature = "db")]
+use crate::request::Request;
+
+/// The format of the dates passed to the date range filters.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A filter shown in the sidebar of the admin list view.
+///
+/// # Examples
+///
+/// ```
+/// use cot::admin::filters::{AdminFilter, AdminFilterChoice};
+/// use cot::admin::AdminListParams;
+///
+/// let filter = AdminFilter::choices(
+///     "status",
+///     vec![
+///         AdminFilterChoice::new("draft", "Draft"),
+///         AdminFilterChoice::new("published", "Published"),
+///     ],
+/// );
+/// let params = AdminListParams::new().with_filter("status", "draft");
+///
+/// assert_eq!(filter.label(), "Status");
+/// assert!(filter.matches(Some("draft"), &params));
+/// assert!(!filter.matches(Some("published"), &params));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminFilter {
+    field: &'static str,
+    kind: AdminFilterKind,
+}
+
+impl AdminFilter {
+    /// Create a new filter for the given field.
+    #[must_use]
+    pub fn new(field: &'static str, kind: AdminFilterKind) -> Self {
+        Self { field, kind }
+    }
+
+    /// Create a new filter letting the user choose between `true` and `false`
+    /// values of the given field.
+    #[must_use]
+    pub fn boolean(field: &'static str) -> Self {
+        Self::new(field, AdminFilterKind::Boolean)
+    }
+
+    /// Create a new filter letting the user choose the range of dates of the
+    /// given field.
+    #[must_use]
+    pub fn date_range(field: &'static str) -> Self {
+        Self::new(field, AdminFilterKind::DateRange)
+    }
+
+    /// Create a new filter letting the user choose one of the given values of
+    /// the given field.
+    #[must_use]
+    pub fn choices(field: &'static str, choices: Vec<AdminFilterChoice>) -> Self {
+        Self::new(field, AdminFilterKind::Choices(choices))
+    }
+
+    /// Get the name of the field this filter applies to.
+    #[must_use]
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Get the kind of this filter.
+    #[must_use]
+    pub fn kind(&self) -> &AdminFilterKind {
+        &self.kind
+    }
+
+    /// Get the label of this filter, as shown in the sidebar.
+    #[must_use]
+    pub fn label(&self) -> String {
+        field_label(self.field)
+    }
+
+    /// Get the names of the query parameters this filter is read from.
+    #[must_use]
+    pub fn param_keys(&self) -> Vec<String> {
+        match self.kind {
+            AdminFilterKind::Boolean | AdminFilterKind::Choices(_) => vec![self.field.to_owned()],
+            AdminFilterKind::DateRange => {
+                vec![date_from_key(self.field), date_to_key(self.field)]
+            }
+        }
+    }
+
+    /// Returns whether the user has selected a value for this filter.
+    #[must_use]
+    pub fn is_active(&self, params: &AdminListParams) -> bool {
+        match self.kind {
+            AdminFilterKind::Boolean => params.boolean_filter(self.field).is_some(),
+            AdminFilterKind::DateRange => params.date_range_filter(self.field) != (None, None),
+            AdminFilterKind::Choices(_) => params.filter_value(self.field).is_some(),
+        }
+    }
+
+    /// Returns whether the field value, as returned by
+    /// [`AdminModel::field_value`](crate::admin::AdminModel::field_value),
+    /// matches the values selected for this filter.
+    ///
+    /// This is used when filtering the objects in memory; the date range
+    /// filters compare the date at the start of the value, so they work with
+    /// dates and both naive and RFC 3339 date-times.
+    #[must_use]
+    pub fn matches(&self, value: Option<&str>, params: &AdminListParams) -> bool {
+        if !self.is_active(params) {
+            return true;
+        }
+        let Some(value) = value else {
+            return false;
+        };
+
+        match self.kind {
+            AdminFilterKind::Boolean => params
+                .boolean_filter(self.field)
+                .is_some_and(|selected| value == selected.to_string()),
+            AdminFilterKind::DateRange => {
+                let Some(date) = value.get(..10).and_then(parse_date) else {
+                    return false;
+                };
+                let (from, to) = params.date_range_filter(self.field);
+                from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to)
+            }
+            AdminFilterKind::Choices(_) => params.filter_value(self.field) == Some(value),
+        }
+    }
+
+    /// Get the query string for selecting the given value of a boolean or
+    /// choice filter, or clearing the filter if `value` is [`None`].
+    pub(super) fn select_query(&self, params: &AdminListParams, value: Option<&str>) -> String {
+        let mut params = params.clone().with_page(1);
+        for key in self.param_keys() {
+            params = params.without_filter(&key);
+        }
+        if let Some(value) = value {
+            params = params.with_filter(self.field, value);
+        }
+        params.query_string()
+    }
+
+    /// Get the query parameters to keep as hidden inputs in the form of a date
+    /// range filter.
+    pub(super) fn other_params(&self, params: &AdminListParams) -> Vec<(String, String)> {
+        let mut params = params.clone().with_page(1);
+        for key in self.param_keys() {
+            params = params.without_filter(&key);
+        }
+        params.query_pairs()
+    }
+}
+
+/// The kind of an [`AdminFilter`], determining how the values are chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminFilterKind {
+    /// The user can choose either `true` or `false` values.
+    Boolean,
+    /// The user can choose the earliest and the latest date; both are
+    /// optional and inclusive.
+    DateRange,
+    /// The user can choose one of the given values.
+    Choices(Vec<AdminFilterChoice>),
+}
+
+/// A value that can be chosen in an [`AdminFilterKind::Choices`] filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminFilterChoice {
+    value: String,
+    label: String,
+}
+
+impl AdminFilterChoice {
+    /// Create a new choice with the given value, passed in the query string
+    /// and compared with the field value, and the label shown to the user.
+    #[must_use]
+    pub fn new<V: Into<String>, L: Into<String>>(value: V, label: L) -> Self {
+        Self {
+            value: value.into(),
+            label: label.into(),
+        }
+    }
+
+    /// Get the value of this choice.
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the label of this choice.
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+pub(super) fn date_from_key(field: &str) -> String {
+    format!("{field}__from")
+}
+
+pub(super) fn date_to_key(field: &str) -> String {
+    format!("{field}__to")
+}
+
+pub(super) fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT).ok()
+}
+
+/// A field type that can be used in the `filters(...)` option of the
+/// [`AdminModel`](derive@AdminModel) derive macro.
+///
+/// This is implemented for `bool` (as a boolean filter), for the date and
+/// date-time types (as a date range filter), for [`ForeignKey`]s to models
+/// implementing [`AdminModel`] (as a choice of the related objects), and for
+/// [`Option`]s of these.
+#[cfg(feature = "db")]
+#[async_trait]
+pub trait AdminFilterField {
+    /// Create the filter for a field of this type with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the choices of the filter could not be retrieved.
+    async fn admin_filter(request: &Request, field: &'static str) -> crate::Result<AdminFilter>;
+
+    /// Get the expression filtering the database query by the values selected
+    /// for the field with the given name, or [`None`] if no values are
+    /// selected.
+    fn admin_filter_expr(field: &'static str, params: &AdminListParams) -> Option<Expr>;
+}
+
+#[cfg(feature = "db")]
+#[async_trait]
+impl AdminFilterField for bool {
+    async fn admin_filter(_request: &Request, field: &'static str) -> crate::Result<AdminFilter> {
+        Ok(AdminFilter::boolean(field))
+    }
+
+    fn admin_filter_expr(field: &'static str, params: &AdminListParams) -> Option<Expr> {
+        params
+            .boolean_filter(field)
+            .map(|value| Expr::eq(Expr::field(field), Expr::value(value)))
+    }
+}
+
+#[cfg(feature = "db")]
+fn date_range_expr<T, F>(field: &'static str, params: &AdminListParams, start_of: F) -> Option<Expr>
+where
+    T: crate::db::ToDbFieldValue,
+    F: Fn(NaiveDate) -> T,
+{
+    let (from, to) = params.date_range_filter(field);
+    let from = from.map(|from| Expr::gte(Expr::field(field), Expr::value(start_of(from))));
+    // the upper bound is inclusive, so compare with the start of the next day
+    let to = to
+        .and_then(|to| to.succ_opt())
+        .map(|after| Expr::lt(Expr::field(field), Expr::value(start_of(after))));
+
+    match (from, to) {
+        (Some(from), Some(to)) => Some(Expr::and(from, to)),
+        (from, to) => from.or(to),
+    }
+}
+
+#[cfg(feature = "db")]
+macro_rules! impl_admin_filter_field_for_date {
+    ($ty:ty, $start_of:expr) => {
+        #[async_trait]
+        impl AdminFilterField for $ty {
+            async fn admin_filter(
+                _request: &Request,
+                field: &'static str,
+            ) -> crate::Result<AdminFilter> {
+                Ok(AdminFilter::date_range(field))
+            }
+
+            fn admin_filter_expr(field: &'static str, params: &AdminListParams) -> Option<Expr> {
+                date_range_expr(field, params, $start_of)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "db")]
+impl_admin_filter_field_for_date!(NaiveDate, |date| date);
+#[cfg(feature = "db")]
+impl_admin_filter_field_for_date!(chrono::NaiveDateTime, |date: NaiveDate| date
+    .and_time(chrono::NaiveTime::MIN));
+#[cfg(feature = "db")]
+impl_admin_filter_field_for_date!(chrono::DateTime<chrono::FixedOffset>, |date: NaiveDate| {
+    date.and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+        .fixed_offset()
+});
+
+#[cfg(feature = "db")]
+#[async_trait]
+impl<T: AdminFilterField> AdminFilterField for Option<T> {
+    async fn admin_filter(request: &Request, field: &'static str) -> crate::Result<AdminFilter> {
+        T::admin_filter(request, field).await
+    }
+
+    fn admin_filter_expr(field: &'static str, params: &AdminListParams) -> Option<Expr> {
+        T::admin_filter_expr(field, params)
+    }
+}
+
+#[cfg(feature = "db")]
+#[async_trait]
+impl<T> AdminFilterField for ForeignKey<T>
+where
+    T: Model + AdminModel + Send + Sync,
+    T::PrimaryKey: AsFormField,
+{
+    async fn admin_filter(request: &Request, field: &'static str) -> crate::Result<AdminFilter> {
+        let choices = T::get_objects(request)
+            .await?
+            .iter()
+            .map(|object| AdminFilterChoice::new(object.object_id(), object.display()))
+            .collect();
+
+        Ok(AdminFilter::choices(field, choices))
+    }
+
+    fn admin_filter_expr(field: &'static str, params: &AdminListParams) -> Option<Expr> {
+        let value = params.filter_value(field)?;
+        let primary_key: T::PrimaryKey = crate::admin::parse_form_value(value)?;
+
+        Some(Expr::eq(Expr::field(field), Expr::value(primary_key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boolean_filter_matches() {
+        let filter = AdminFilter::boolean("is_active");
+        let params = AdminListParams::new();
+        assert!(!filter.is_active(&params));
+        assert!(filter.matches(None, &params));
+
+        let params = params.with_filter("is_active", "false");
+        assert!(filter.is_active(&params));
+        assert!(filter.matches(Some("false"), &params));
+        assert!(!filter.matches(Some("true"), &params));
+        assert!(!filter.matches(None, &params));
+    }
+
+    #[test]
+    fn date_range_filter_matches() {
+        let filter = AdminFilter::date_range("created");
+        assert_eq!(filter.param_keys(), ["created__from", "created__to"]);
+
+        let params = AdminListParams::new()
+            .with_filter("created__from", "2024-01-10")
+            .with_filter("created__to", "2024-01-20");
+        assert!(filter.matches(Some("2024-01-10"), &params));
+        assert!(filter.matches(Some("2024-01-20T23:59:59+00:00"), &params));
+        assert!(!filter.matches(Some("2024-01-21 00:00:00"), &params));
+        assert!(!filter.matches(Some("2024-01-09"), &params));
+        assert!(!filter.matches(Some("-"), &params));
+
+        let params = AdminListParams::new().with_filter("created__to", "invalid");
+        assert!(!filter.is_active(&params));
+    }
+
+    #[test]
+    fn filter_queries() {
+        let filter = AdminFilter::date_range("created");
+        let params = AdminListParams::new()
+            .with_page(2)
+            .with_search("a")
+            .with_filter("created__from", "2024-01-10")
+            .with_filter("published", "true");
+
+        assert_eq!(
+            filter.other_params(&params),
+            [
+                (String::from("q"), String::from("a")),
+                (String::from("published"), String::from("true"))
+            ]
+        );
+
+        let filter = AdminFilter::boolean("published");
+        assert_eq!(
+            filter.select_query(&params, Some("false")),
+            "q=a&created__from=2024-01-10&published=false"
+        );
+        assert_eq!(
+            filter.select_query(&params, None),
+            "q=a&created__from=2024-01-10"
+        );
+    }
+}
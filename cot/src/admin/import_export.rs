@@ -0,0 +1,877 @@
+//! Export of the objects of a model to a file, and import of new objects from
+//! one.
+//!
+//! The list view links to the export of all the objects matching its search
+//! and filters, on all the pages, as CSV or, with the `json` feature, JSON.
+//! The files contain the [`AdminModel::list_columns`] of the objects (or their
+//! [`AdminModel::display`] text if the model has no list columns): the CSV
+//! files have a header row with the labels of the columns, and the JSON files
+//! contain an array with an object per row, mapping the column names to the
+//! values. The CSV values that spreadsheet applications would run as formulas
+//! (starting with `=`, `+`, `-` or `@`, other than numbers) are prefixed with
+//! `'`.
+//!
+//! The import page adds new objects from the data in the same formats, with a
+//! column per field of the [add form](AdminModel::add_form) of the model,
+//! named after either the field (`published_at`) or its label
+//! (`Published at`), so that the exported files can be imported back (the
+//! `'` prefixes added to the CSV values are removed); the other columns are
+//! ignored. Every row is validated through the form, in a
+//! single database transaction: the preview (dry run) shows the object each
+//! row adds, or its validation errors, and rolls the transaction back, and the
+//! import commits it only if every row is valid.
+
+#[cfg(feature = "db")]
+use std::sync::Arc;
+
+use derive_more::Debug;
+#[cfg(feature = "db")]
+use rinja::Template;
+#[cfg(feature = "db")]
+use thiserror::Error;
+
+#[cfg(feature = "db")]
+use crate::admin::log::{LogAction, LogEntry};
+use crate::admin::{
+    actions, check_staff, model_manager, model_permissions, AdminListParams, AdminModel,
+    AdminModelManager,
+};
+#[cfg(feature = "db")]
+use crate::admin::{add_message, AdminForm, AdminFormField, AdminFormResult};
+#[cfg(feature = "db")]
+use crate::headers::FORM_CONTENT_TYPE;
+#[cfg(feature = "db")]
+use crate::request::{query_pairs, RequestDatabase};
+use crate::request::{Request, RequestExt};
+use crate::response::Response;
+use crate::router::{handle_forbidden, handle_not_found};
+#[cfg(feature = "db")]
+use crate::{reverse_redirect, Body};
+
+/// The number of objects retrieved at once when exporting.
+const EXPORT_BATCH_SIZE: u64 = 500;
+
+/// The format of the exported and imported files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum FileFormat {
+    Csv,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl FileFormat {
+    /// All the formats supported.
+    pub(super) const ALL: &'static [Self] = &[
+        Self::Csv,
+        #[cfg(feature = "json")]
+        Self::Json,
+    ];
+
+    /// Returns the format with the given name, as used in the URLs.
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name() == name)
+    }
+
+    /// Returns the name of the format, which is also the extension of the
+    /// files.
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+        }
+    }
+
+    /// Returns the name of the format shown to the user.
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            #[cfg(feature = "json")]
+            Self::Json => "JSON",
+        }
+    }
+}
+
+pub(super) async fn export_model(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    let Some(format) = request
+        .path_params()
+        .get("format")
+        .and_then(FileFormat::from_name)
+    else {
+        return Ok(handle_not_found());
+    };
+    let permissions = model_permissions(&mut request, &*manager).await?;
+    if !permissions.view {
+        return Ok(handle_forbidden());
+    }
+
+    let params = AdminListParams::from_request(&request);
+    let objects = get_all_objects(&request, &*manager, params).await?;
+    let objects = objects.iter().map(|object| &**object);
+    match format {
+        FileFormat::Csv => actions::csv_response(
+            manager.url_name(),
+            actions::objects_to_csv(manager.list_columns(), objects),
+        ),
+        #[cfg(feature = "json")]
+        FileFormat::Json => actions::file_response(
+            &format!("{}.json", manager.url_name()),
+            crate::headers::JSON_CONTENT_TYPE,
+            objects_to_json(manager.list_columns(), objects)?,
+        ),
+    }
+}
+
+/// Returns the objects matching the search and the filters of the given
+/// parameters, on all the pages.
+async fn get_all_objects(
+    request: &Request,
+    manager: &dyn AdminModelManager,
+    params: AdminListParams,
+) -> cot::Result<Vec<Box<dyn AdminModel>>> {
+    let mut params = params.with_page(1).with_per_page(EXPORT_BATCH_SIZE);
+    let mut objects = Vec::new();
+    loop {
+        let list = manager.get_list(request, &params).await?;
+        let total = list.total();
+        let batch = list.into_objects();
+        let done = batch.is_empty() || (objects.len() + batch.len()) as u64 >= total;
+        objects.extend(batch);
+        if done {
+            return Ok(objects);
+        }
+        let next_page = params.page() + 1;
+        params = params.with_page(next_page);
+    }
+}
+
+/// Write the given objects as a JSON array, with an object per row mapping
+/// the given columns to their values, or only containing the display text of
+/// the objects if there are no columns.
+#[cfg(feature = "json")]
+fn objects_to_json<'a, I>(columns: &[&str], objects: I) -> cot::Result<String>
+where
+    I: IntoIterator<Item = &'a dyn AdminModel>,
+{
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> = objects
+        .into_iter()
+        .map(|object| {
+            if columns.is_empty() {
+                [(String::from("object"), object.display().into())]
+                    .into_iter()
+                    .collect()
+            } else {
+                columns
+                    .iter()
+                    .map(|&column| {
+                        let value = object.field_value(column).unwrap_or_default();
+                        (column.to_owned(), value.into())
+                    })
+                    .collect()
+            }
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&rows)?)
+}
+
+/// An error in the data to import.
+#[cfg(feature = "db")]
+#[derive(Debug, Error)]
+enum ImportError {
+    #[error("There are no rows to import.")]
+    NoRows,
+    #[error("None of the columns match the fields of the form.")]
+    NoColumns,
+    #[error("Line {line}: the quoted value is not terminated.")]
+    UnterminatedQuote { line: usize },
+    #[error("Line {line}: expected {expected} values, found {found}.")]
+    RowLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[cfg(feature = "json")]
+    #[error("The data is not a JSON array of objects: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "json")]
+    #[error("Row {row}: the value of `{column}` is not a string, a number or a boolean.")]
+    JsonValue { row: usize, column: String },
+}
+
+/// The rows read from the data to import.
+#[cfg(feature = "db")]
+#[derive(Debug, PartialEq, Eq)]
+struct ImportData {
+    rows: Vec<ImportRow>,
+    /// The columns that don't match any field of the form.
+    ignored_columns: Vec<String>,
+}
+
+/// A row of the data to import.
+#[cfg(feature = "db")]
+#[derive(Debug, PartialEq, Eq)]
+struct ImportRow {
+    /// The line number of the row in the CSV data, or its position in the
+    /// JSON array, starting from 1.
+    number: usize,
+    /// The values of the fields of the form, by field ID.
+    values: Vec<(String, String)>,
+}
+
+#[cfg(feature = "db")]
+impl ImportData {
+    fn parse(
+        format: FileFormat,
+        data: &str,
+        fields: &[AdminFormField],
+    ) -> Result<Self, ImportError> {
+        let (columns, rows) = match format {
+            FileFormat::Csv => parse_csv(data)?,
+            #[cfg(feature = "json")]
+            FileFormat::Json => parse_json(data)?,
+        };
+
+        let field_ids: Vec<_> = columns
+            .iter()
+            .map(|column| field_for_column(fields, column))
+            .collect();
+        if field_ids.iter().all(Option::is_none) {
+            return Err(ImportError::NoColumns);
+        }
+        let ignored_columns = columns
+            .iter()
+            .zip(&field_ids)
+            .filter(|(_, field_id)| field_id.is_none())
+            .map(|(column, _)| column.clone())
+            .collect();
+
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|(number, values)| ImportRow {
+                number,
+                values: field_ids
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(field_id, value)| Some(((*field_id)?.to_owned(), value)))
+                    .collect(),
+            })
+            .collect();
+        if rows.is_empty() {
+            return Err(ImportError::NoRows);
+        }
+
+        Ok(Self {
+            rows,
+            ignored_columns,
+        })
+    }
+}
+
+/// Returns the ID of the form field the column is named after, either by the
+/// ID itself or by the label of the field, ignoring case.
+#[cfg(feature = "db")]
+fn field_for_column<'a>(fields: &'a [AdminFormField], column: &str) -> Option<&'a str> {
+    let column = column.trim();
+    fields
+        .iter()
+        .find(|field| field.id() == column || field.label().eq_ignore_ascii_case(column))
+        .map(AdminFormField::id)
+}
+
+/// The columns and the rows (along with their numbers) of the data to import.
+#[cfg(feature = "db")]
+type ParsedData = (Vec<String>, Vec<(usize, Vec<String>)>);
+
+/// Reads the CSV data, whose first row contains the names of the columns; the
+/// blank lines are skipped.
+#[cfg(feature = "db")]
+fn parse_csv(data: &str) -> Result<ParsedData, ImportError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut value = String::new();
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut quoted = false;
+    let mut chars = data.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if quoted {
+            match char {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    value.push('"');
+                }
+                '"' => quoted = false,
+                _ => {
+                    if char == '\n' {
+                        line += 1;
+                    }
+                    value.push(char);
+                }
+            }
+            continue;
+        }
+
+        match char {
+            '"' => quoted = true,
+            ',' => row.push(std::mem::take(&mut value)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut value));
+                if row.iter().any(|value| !value.is_empty()) {
+                    rows.push((row_line, std::mem::take(&mut row)));
+                }
+                row.clear();
+                line += 1;
+                row_line = line;
+            }
+            _ => value.push(char),
+        }
+    }
+    if quoted {
+        return Err(ImportError::UnterminatedQuote { line: row_line });
+    }
+    row.push(value);
+    if row.iter().any(|value| !value.is_empty()) {
+        rows.push((row_line, row));
+    }
+
+    for value in rows.iter_mut().flat_map(|(_, row)| row) {
+        actions::unescape_csv_formula(value);
+    }
+
+    let mut rows = rows.into_iter();
+    let Some((_, columns)) = rows.next() else {
+        return Err(ImportError::NoRows);
+    };
+    let rows: Vec<_> = rows.collect();
+    if let Some((line, row)) = rows.iter().find(|(_, row)| row.len() != columns.len()) {
+        return Err(ImportError::RowLength {
+            line: *line,
+            expected: columns.len(),
+            found: row.len(),
+        });
+    }
+
+    Ok((columns, rows))
+}
+
+/// Reads the JSON data, an array of objects mapping the names of the columns
+/// to the values; the missing values are left empty.
+#[cfg(all(feature = "db", feature = "json"))]
+fn parse_json(data: &str) -> Result<ParsedData, ImportError> {
+    let objects: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(data)?;
+
+    let mut columns: Vec<String> = Vec::new();
+    for object in &objects {
+        for key in object.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let rows = objects
+        .iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let values = columns
+                .iter()
+                .map(|column| match object.get(column) {
+                    None | Some(serde_json::Value::Null) => Ok(String::new()),
+                    Some(serde_json::Value::String(value)) => Ok(value.clone()),
+                    Some(value @ (serde_json::Value::Bool(_) | serde_json::Value::Number(_))) => {
+                        Ok(value.to_string())
+                    }
+                    Some(_) => Err(ImportError::JsonValue {
+                        row: index + 1,
+                        column: column.clone(),
+                    }),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok((index + 1, values))
+        })
+        .collect::<Result<_, ImportError>>()?;
+
+    Ok((columns, rows))
+}
+
+/// The result of validating a row of the data to import.
+#[cfg(feature = "db")]
+#[derive(Debug)]
+struct RowResult {
+    number: usize,
+    /// The display text of the object added, if the row is valid.
+    display: Option<String>,
+    errors: Vec<String>,
+}
+
+#[cfg(feature = "db")]
+impl RowResult {
+    fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[cfg(feature = "db")]
+#[derive(Debug, Template)]
+#[template(path = "admin/model_import.html")]
+struct ModelImportTemplate<'a> {
+    request: &'a Request,
+    #[debug("...")]
+    model: &'a dyn AdminModelManager,
+    fields: &'a [AdminFormField],
+    format: FileFormat,
+    data: String,
+    errors: Vec<String>,
+    ignored_columns: Vec<String>,
+    results: Vec<RowResult>,
+    summary: String,
+}
+
+#[cfg(feature = "db")]
+pub(super) async fn import_model(mut request: Request) -> cot::Result<Response> {
+    if let Some(response) = check_staff(&mut request).await? {
+        return Ok(response);
+    }
+    let Some(manager) = model_manager(&request) else {
+        return Ok(handle_not_found());
+    };
+    let permissions = model_permissions(&mut request, &*manager).await?;
+    if !permissions.add {
+        return Ok(handle_forbidden());
+    }
+    let Some(form) = manager.add_form() else {
+        return Ok(handle_not_found());
+    };
+    if request.context().try_database().is_none() {
+        return Ok(handle_not_found());
+    }
+
+    let mut format = FileFormat::Csv;
+    let mut data = String::new();
+    let mut errors = Vec::new();
+    let mut ignored_columns = Vec::new();
+    let mut results = Vec::new();
+    let mut summary = String::new();
+
+    if request.method() == http::Method::POST {
+        let form_data = request.form_data().await?;
+        let mut dry_run = false;
+        for (key, value) in query_pairs(&form_data) {
+            match key.as_ref() {
+                "format" => match FileFormat::from_name(&value) {
+                    Some(value) => format = value,
+                    None => errors.push(format!("Unknown format `{value}`.")),
+                },
+                "data" => data = value.into_owned(),
+                "dry_run" => dry_run = true,
+                _ => {}
+            }
+        }
+
+        if errors.is_empty() {
+            match ImportData::parse(format, &data, form.fields()) {
+                Ok(import) => {
+                    results = import_rows(&request, &*manager, &import.rows, !dry_run).await?;
+                    let invalid = results.iter().filter(|row| !row.is_valid()).count();
+                    if !dry_run && invalid == 0 {
+                        add_message(&request, imported_message(&*manager, results.len())).await?;
+                        return Ok(reverse_redirect!(
+                            request,
+                            "view_model",
+                            model_name = manager.url_name()
+                        )?);
+                    }
+
+                    ignored_columns = import.ignored_columns;
+                    summary = import_summary(results.len(), invalid, dry_run);
+                }
+                Err(error) => errors.push(error.to_string()),
+            }
+        }
+    }
+
+    let template = ModelImportTemplate {
+        request: &request,
+        model: &*manager,
+        fields: form.fields(),
+        format,
+        data,
+        errors,
+        ignored_columns,
+        results,
+        summary,
+    };
+    let title = format!("Import {}", manager.name());
+    crate::admin::render_page(&request, &title, "", &template)
+}
+
+/// Adds an object from each row through the add form of the model, in a
+/// transaction that is committed only if `commit` is `true` and all the rows
+/// are valid.
+#[cfg(feature = "db")]
+async fn import_rows(
+    request: &Request,
+    manager: &dyn AdminModelManager,
+    rows: &[ImportRow],
+    commit: bool,
+) -> cot::Result<Vec<RowResult>> {
+    let transaction = Arc::new(request.db().begin_transaction().await?);
+
+    let mut results = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut row_request = row_request(request, &transaction, &row.values);
+        let result = match manager.create_from_request(&mut row_request).await? {
+            AdminFormResult::Ok(object) => {
+                LogEntry::log(
+                    &mut row_request,
+                    manager.url_name(),
+                    &*object,
+                    LogAction::Add,
+                    &[],
+                )
+                .await?;
+                RowResult {
+                    number: row.number,
+                    display: Some(object.display()),
+                    errors: Vec::new(),
+                }
+            }
+            AdminFormResult::ValidationError(form) => RowResult {
+                number: row.number,
+                display: None,
+                errors: form_errors(&form),
+            },
+        };
+        results.push(result);
+    }
+
+    // the row requests, holding the other references, have been dropped
+    let transaction = Arc::into_inner(transaction).expect("the transaction should not be shared");
+    if commit && results.iter().all(RowResult::is_valid) {
+        transaction.commit().await?;
+    } else {
+        transaction.rollback().await?;
+    }
+
+    Ok(results)
+}
+
+/// Creates a request submitting the given values as the add form of the
+/// model, with its queries run in the given transaction.
+#[cfg(feature = "db")]
+fn row_request(
+    request: &Request,
+    transaction: &Arc<crate::db::Database>,
+    values: &[(String, String)],
+) -> Request {
+    let body = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(values)
+        .finish();
+
+    let mut row_request = http::Request::builder()
+        .method(http::Method::POST)
+        .uri(request.uri().clone())
+        .header(http::header::CONTENT_TYPE, FORM_CONTENT_TYPE)
+        .body(Body::fixed(body))
+        .expect("the request parts are valid");
+    // the session, the database and the user are stored in the extensions
+    *row_request.extensions_mut() = request.extensions().clone();
+    row_request
+        .extensions_mut()
+        .insert(RequestDatabase(Arc::clone(transaction)));
+
+    row_request
+}
+
+/// Returns the validation errors of the form, prefixed with the labels of
+/// their fields.
+#[cfg(feature = "db")]
+fn form_errors(form: &AdminForm) -> Vec<String> {
+    form.errors()
+        .iter()
+        .cloned()
+        .chain(form.fields().iter().flat_map(|field| {
+            field
+                .errors()
+                .iter()
+                .map(move |error| format!("{}: {error}", field.label()))
+        }))
+        .collect()
+}
+
+#[cfg(feature = "db")]
+fn imported_message(manager: &dyn AdminModelManager, count: usize) -> String {
+    format!(
+        "{count} {} {} imported successfully.",
+        manager.name(),
+        if count == 1 {
+            "object was"
+        } else {
+            "objects were"
+        }
+    )
+}
+
+#[cfg(feature = "db")]
+fn import_summary(total: usize, invalid: usize, dry_run: bool) -> String {
+    let rows = if total == 1 { "row" } else { "rows" };
+    let have = if invalid == 1 { "has" } else { "have" };
+
+    match (invalid, dry_run) {
+        (0, _) if total == 1 => String::from("The row is valid and can be imported."),
+        (0, _) => format!("All the {total} rows are valid and can be imported."),
+        (_, true) => format!("{invalid} of {total} {rows} {have} errors."),
+        (_, false) => {
+            format!("Nothing was imported, as {invalid} of {total} {rows} {have} errors.")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "db")]
+    use crate::form::{Form, FormContext};
+
+    #[cfg(feature = "db")]
+    #[derive(Form)]
+    #[allow(dead_code)] // only the fields of the form are used, not its values
+    struct PostForm {
+        title: String,
+        published_at: String,
+    }
+
+    #[cfg(feature = "db")]
+    fn post_fields() -> Vec<AdminFormField> {
+        AdminForm::from_context(&<PostForm as Form>::Context::new())
+            .fields()
+            .to_vec()
+    }
+
+    #[cfg(feature = "db")]
+    fn values(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn file_format_names() {
+        assert_eq!(FileFormat::from_name("csv"), Some(FileFormat::Csv));
+        assert_eq!(FileFormat::from_name("xml"), None);
+        for &format in FileFormat::ALL {
+            assert_eq!(FileFormat::from_name(format.name()), Some(format));
+        }
+    }
+
+    #[cfg(feature = "db")]
+    #[test]
+    fn csv_parsing() {
+        let (columns, rows) =
+            parse_csv("Title,Views\r\n\"Hello, \"\"world\"\"\",5\r\n\r\n\"multi\nline\",\n")
+                .unwrap();
+        assert_eq!(columns, ["Title", "Views"]);
+        assert_eq!(
+            rows,
+            [
+                (2, vec![String::from("Hello, \"world\""), String::from("5")]),
+                (4, vec![String::from("multi\nline"), String::new()]),
+            ]
+        );
+
+        assert!(matches!(
+            parse_csv("Title\n\"Hello"),
+            Err(ImportError::UnterminatedQuote { line: 2 })
+        ));
+        assert!(matches!(
+            parse_csv("Title,Views\nHello"),
+            Err(ImportError::RowLength {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(parse_csv("\n\n"), Err(ImportError::NoRows)));
+    }
+
+    #[cfg(all(feature = "db", feature = "json"))]
+    #[test]
+    fn json_parsing() {
+        let (columns, rows) =
+            parse_json(r#"[{"title": "Hello", "views": 5}, {"draft": true, "title": null}]"#)
+                .unwrap();
+        assert_eq!(columns, ["title", "views", "draft"]);
+        assert_eq!(
+            rows,
+            [
+                (
+                    1,
+                    vec![String::from("Hello"), String::from("5"), String::new()]
+                ),
+                (2, vec![String::new(), String::new(), String::from("true")]),
+            ]
+        );
+
+        assert!(matches!(
+            parse_json(r#"[{"title": ["Hello"]}]"#),
+            Err(ImportError::JsonValue { row: 1, .. })
+        ));
+        assert!(matches!(
+            parse_json(r#"{"title": "Hello"}"#),
+            Err(ImportError::Json(_))
+        ));
+    }
+
+    #[cfg(feature = "db")]
+    #[test]
+    fn import_data_columns() {
+        let fields = post_fields();
+        let import = ImportData::parse(
+            FileFormat::Csv,
+            "Id,title,Published At\n1,Hello,2025-01-01\n",
+            &fields,
+        )
+        .unwrap();
+
+        assert_eq!(import.ignored_columns, ["Id"]);
+        assert_eq!(
+            import.rows,
+            [ImportRow {
+                number: 2,
+                values: values(&[("title", "Hello"), ("published_at", "2025-01-01")]),
+            }]
+        );
+
+        assert!(matches!(
+            ImportData::parse(FileFormat::Csv, "Id,Views\n1,5", &fields),
+            Err(ImportError::NoColumns)
+        ));
+        assert!(matches!(
+            ImportData::parse(FileFormat::Csv, "Title", &fields),
+            Err(ImportError::NoRows)
+        ));
+    }
+
+    #[cfg(feature = "db")]
+    #[tokio::test]
+    async fn row_request_values() {
+        let database = Arc::new(crate::db::Database::new("sqlite::memory:").await.unwrap());
+        let request = crate::test::TestRequestBuilder::get("/admin/post/import").build();
+
+        let mut row_request =
+            row_request(&request, &database, &values(&[("title", "Hello & bye")]));
+        assert_eq!(row_request.method(), http::Method::POST);
+        assert!(std::ptr::eq(row_request.db(), Arc::as_ptr(&database)));
+        assert_eq!(
+            row_request.form_data().await.unwrap(),
+            bytes::Bytes::from_static(b"title=Hello+%26+bye")
+        );
+    }
+
+    #[cfg(feature = "db")]
+    #[derive(std::fmt::Debug, Clone, crate::admin::AdminModel)]
+    #[admin(display = title)]
+    #[crate::db::model]
+    struct Book {
+        id: crate::db::Auto<i32>,
+        title: crate::db::LimitedString<10>,
+    }
+
+    #[cfg(feature = "db")]
+    #[tokio::test]
+    async fn import_rows_transaction() {
+        use crate::db::migrations::{Field, Operation};
+        use crate::db::{Auto, DatabaseField, Identifier, LimitedString, Model};
+
+        const CREATE_BOOK: Operation = Operation::create_model()
+            .table_name(Identifier::new("book"))
+            .fields(&[
+                Field::new(Identifier::new("id"), <Auto<i32> as DatabaseField>::TYPE)
+                    .primary_key()
+                    .auto(),
+                Field::new(
+                    Identifier::new("title"),
+                    <LimitedString<10> as DatabaseField>::TYPE,
+                ),
+            ])
+            .build();
+
+        let mut test_db = crate::test::TestDatabase::new_sqlite().await.unwrap();
+        test_db.with_admin().run_migrations().await;
+        CREATE_BOOK.forwards(&test_db.database()).await.unwrap();
+        let request = crate::test::TestRequestBuilder::post("/admin/book/import")
+            .database(test_db.database())
+            .build();
+        let manager = crate::admin::DefaultAdminModelManager::<Book>::new();
+
+        let rows = [
+            ImportRow {
+                number: 2,
+                values: values(&[("title", "Dune")]),
+            },
+            ImportRow {
+                number: 3,
+                values: values(&[("title", "A title that is too long")]),
+            },
+        ];
+        let results = import_rows(&request, &manager, &rows, true).await.unwrap();
+        assert!(results[0].is_valid());
+        assert_eq!(results[0].display.as_deref(), Some("Dune"));
+        assert!(!results[1].is_valid());
+        assert_eq!(results[1].number, 3);
+        assert!(Book::objects().all(&*test_db).await.unwrap().is_empty());
+
+        let results = import_rows(&request, &manager, &rows[..1], false)
+            .await
+            .unwrap();
+        assert!(results[0].is_valid());
+        assert!(Book::objects().all(&*test_db).await.unwrap().is_empty());
+        assert!(LogEntry::recent(&*test_db, &["book"], 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        import_rows(&request, &manager, &rows[..1], true)
+            .await
+            .unwrap();
+        let books = Book::objects().all(&*test_db).await.unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].title, "Dune");
+        let entries = LogEntry::recent(&*test_db, &["book"], 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action(), LogAction::Add);
+
+        test_db.cleanup().await.unwrap();
+    }
+
+    #[cfg(feature = "db")]
+    #[test]
+    fn import_summaries() {
+        assert_eq!(
+            import_summary(1, 0, true),
+            "The row is valid and can be imported."
+        );
+        assert_eq!(
+            import_summary(3, 0, true),
+            "All the 3 rows are valid and can be imported."
+        );
+        assert_eq!(import_summary(3, 1, true), "1 of 3 rows has errors.");
+        assert_eq!(
+            import_summary(3, 2, false),
+            "Nothing was imported, as 2 of 3 rows have errors."
+        );
+    }
+}
@@ -0,0 +1,648 @@
+//! Related objects edited on the change page of an object in the admin panel.
+//!
+//! An [`AdminInline`] shows the objects of another model that refer to the
+//! object through a [`ForeignKey`](crate::db::ForeignKey) as rows below its
+//! form. The rows are changed, deleted and added along with the object itself;
+//! the foreign key field is not shown, as it's always set to the object.
+//!
+//! The inlines of a model are returned by [`AdminModel::inlines`]; with the
+//! [`AdminModel`](derive@AdminModel) derive macro, they are listed in the
+//! `inline(model = ..., field = ...)` options.
+//!
+//! # Examples
+//!
+//! ```
+//! use cot::admin::AdminModel;
+//! use cot::db::{model, Auto, ForeignKey};
+//!
+//! #[derive(Debug, Clone, AdminModel)]
+//! #[admin(display = title, inline(model = Comment, field = post))]
+//! #[model]
+//! struct Post {
+//!     id: Auto<i64>,
+//!     title: String,
+//! }
+//!
+//! #[derive(Debug, Clone, AdminModel)]
+//! #[model]
+//! struct Comment {
+//!     id: Auto<i64>,
+//!     post: ForeignKey<Post>,
+//!     text: String,
+//! }
+//!
+//! let inlines = Post::inlines();
+//! assert_eq!(inlines[0].field(), "post");
+//! assert_eq!(inlines[0].model().name(), "Comment");
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+#[cfg(feature = "db")]
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use derive_more::Debug;
+
+#[cfg(feature = "db")]
+use crate::admin::{log, parse_form_value, DefaultAdminModelManager};
+use crate::admin::{
+    model_permissions, AdminForm, AdminFormResult, AdminModel, AdminModelManager, ModelPermissions,
+};
+#[cfg(feature = "db")]
+use crate::db::query::{Expr, Order};
+#[cfg(feature = "db")]
+use crate::db::Model;
+#[cfg(feature = "db")]
+use crate::form::AsFormField;
+use crate::headers::FORM_CONTENT_TYPE;
+#[cfg(feature = "db")]
+use crate::request::RequestExt;
+use crate::request::{query_pairs, Request};
+use crate::Body;
+
+/// The number of empty rows shown for adding new objects by default.
+#[cfg(feature = "db")]
+const DEFAULT_EXTRA: usize = 1;
+
+/// The name of the hidden input with the ID of the object of a row; it's empty
+/// for the new objects.
+const ID_KEY: &str = "__id";
+/// The name of the checkbox deleting the object of a row.
+const DELETE_KEY: &str = "__delete";
+/// The name of the hidden input with the number of rows of an inline.
+const TOTAL_KEY: &str = "TOTAL";
+/// The row number of the template of the added rows; the admin script replaces
+/// it with the actual number.
+const EMPTY_ROW_INDEX: &str = "__prefix__";
+
+/// The objects of a related model edited on the change page of an object.
+#[derive(Debug, Clone)]
+pub struct AdminInline {
+    field: &'static str,
+    extra: usize,
+    #[debug("...")]
+    model: Arc<dyn AdminModelManager>,
+    #[debug("...")]
+    objects: Arc<dyn InlineObjects>,
+}
+
+impl AdminInline {
+    /// Creates an inline of the objects of the model `C` that refer to the
+    /// objects of the model `P` through the foreign key field with the given
+    /// name.
+    #[cfg(feature = "db")]
+    #[must_use]
+    pub fn new<P, C>(field: &'static str) -> Self
+    where
+        P: Model + 'static,
+        P::PrimaryKey: AsFormField,
+        C: Model + AdminModel + 'static,
+    {
+        Self {
+            field,
+            extra: DEFAULT_EXTRA,
+            model: Arc::new(DefaultAdminModelManager::<C>::new()),
+            objects: Arc::new(ForeignKeyObjects::<P, C> {
+                field,
+                phantom_data: PhantomData,
+            }),
+        }
+    }
+
+    /// Set the number of empty rows shown for adding new objects; more can be
+    /// added with the "Add another" button.
+    #[must_use]
+    pub fn with_extra(mut self, extra: usize) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// Get the name of the foreign key field referring to the parent object.
+    #[must_use]
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Get the number of empty rows shown for adding new objects.
+    #[must_use]
+    pub fn extra(&self) -> usize {
+        self.extra
+    }
+
+    /// Get the model manager of the related model.
+    #[must_use]
+    pub fn model(&self) -> &dyn AdminModelManager {
+        &*self.model
+    }
+
+    /// Get the objects referring to the parent object with the given
+    /// [ID](AdminModel::object_id).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the objects could not be retrieved.
+    pub async fn get_objects(
+        &self,
+        request: &Request,
+        parent_id: &str,
+    ) -> cot::Result<Vec<Box<dyn AdminModel>>> {
+        self.objects.get_objects(request, parent_id).await
+    }
+
+    /// Get the prefix of the names of the form fields of the inline.
+    fn prefix(&self) -> String {
+        format!("{}-{}", self.model.url_name(), self.field)
+    }
+}
+
+/// Retrieves the objects of an inline.
+#[async_trait]
+trait InlineObjects: Send + Sync {
+    async fn get_objects(
+        &self,
+        request: &Request,
+        parent_id: &str,
+    ) -> cot::Result<Vec<Box<dyn AdminModel>>>;
+}
+
+#[cfg(feature = "db")]
+struct ForeignKeyObjects<P, C> {
+    field: &'static str,
+    phantom_data: PhantomData<fn() -> (P, C)>,
+}
+
+#[cfg(feature = "db")]
+#[async_trait]
+impl<P, C> InlineObjects for ForeignKeyObjects<P, C>
+where
+    P: Model + 'static,
+    P::PrimaryKey: AsFormField,
+    C: Model + AdminModel + 'static,
+{
+    async fn get_objects(
+        &self,
+        request: &Request,
+        parent_id: &str,
+    ) -> cot::Result<Vec<Box<dyn AdminModel>>> {
+        let Some(primary_key) = parse_form_value::<P::PrimaryKey>(parent_id) else {
+            return Ok(Vec::new());
+        };
+
+        let objects = C::objects()
+            .filter(Expr::eq(Expr::field(self.field), Expr::value(primary_key)))
+            .order_by(C::PRIMARY_KEY_NAME, Order::Asc)
+            .all(request.db())
+            .await?;
+
+        #[allow(trivial_casts)] // Upcast to the correct Box type
+        Ok(objects
+            .into_iter()
+            .map(|object| Box::new(object) as Box<dyn AdminModel>)
+            .collect())
+    }
+}
+
+/// The rows of an inline shown on the change page of an object.
+#[derive(Debug)]
+pub(super) struct InlineFormSet {
+    inline: AdminInline,
+    permissions: ModelPermissions,
+    rows: Vec<InlineRow>,
+    empty_row: Option<InlineRow>,
+    has_errors: bool,
+}
+
+impl InlineFormSet {
+    /// Loads the rows of the objects referring to the parent object, or
+    /// returns [`None`] if the user is not allowed to see them.
+    pub(super) async fn load(
+        request: &mut Request,
+        inline: AdminInline,
+        parent_id: &str,
+    ) -> cot::Result<Option<Self>> {
+        let permissions = model_permissions(request, inline.model()).await?;
+        if !permissions.view {
+            return Ok(None);
+        }
+
+        Self::build(
+            request,
+            inline,
+            parent_id,
+            permissions,
+            HashMap::new(),
+            Vec::new(),
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Saves the rows submitted in the form data of the change page, as far
+    /// as the user is allowed to, and returns the rows to show again if
+    /// there were validation errors.
+    ///
+    /// Each row is saved on its own, so the valid rows are saved even if some
+    /// of the others are invalid.
+    pub(super) async fn save(
+        request: &mut Request,
+        inline: AdminInline,
+        parent_id: &str,
+        form_data: &Bytes,
+    ) -> cot::Result<Option<Self>> {
+        let permissions = model_permissions(request, inline.model()).await?;
+        if !permissions.view {
+            return Ok(None);
+        }
+
+        let prefix = inline.prefix();
+        let total_key = total_key(&prefix);
+        let total = query_pairs(form_data)
+            .find(|(key, _)| *key == total_key)
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(0);
+        let mut objects = inline.get_objects(request, parent_id).await?;
+        let mut invalid_forms = HashMap::new();
+        let mut new_forms = Vec::new();
+
+        for index in row_indices(form_data, &prefix, total) {
+            let Some(row) = RowData::from_form_data(form_data, &row_prefix(&prefix, index)) else {
+                continue;
+            };
+
+            if let Some(object_id) = &row.object_id {
+                // only the objects of this parent can be changed
+                let Some(object) = objects
+                    .iter_mut()
+                    .find(|object| object.object_id() == *object_id)
+                else {
+                    continue;
+                };
+
+                if row.delete {
+                    if permissions.delete {
+                        object.delete(request).await?;
+                        #[cfg(feature = "db")]
+                        log::LogEntry::log(
+                            request,
+                            inline.model().url_name(),
+                            &**object,
+                            log::LogAction::Delete,
+                            &[],
+                        )
+                        .await?;
+                    }
+                } else if permissions.change {
+                    #[cfg(feature = "db")]
+                    let old_form = object.change_form();
+                    let mut row_request = row.to_request(request, inline.field, parent_id);
+                    match object.update_from_request(&mut row_request).await? {
+                        AdminFormResult::Ok(()) => {
+                            #[cfg(feature = "db")]
+                            {
+                                let changes = match (&old_form, object.change_form()) {
+                                    (Some(old_form), Some(new_form)) => {
+                                        log::FieldChange::between(old_form, &new_form)
+                                    }
+                                    _ => Vec::new(),
+                                };
+                                if !changes.is_empty() {
+                                    log::LogEntry::log(
+                                        request,
+                                        inline.model().url_name(),
+                                        &**object,
+                                        log::LogAction::Change,
+                                        &changes,
+                                    )
+                                    .await?;
+                                }
+                            }
+                        }
+                        AdminFormResult::ValidationError(form) => {
+                            invalid_forms.insert(object_id.clone(), form);
+                        }
+                    }
+                }
+            } else if !row.delete && !row.is_blank() && permissions.add {
+                let mut row_request = row.to_request(request, inline.field, parent_id);
+                match inline.model().create_from_request(&mut row_request).await? {
+                    AdminFormResult::Ok(object) => {
+                        #[cfg(feature = "db")]
+                        log::LogEntry::log(
+                            request,
+                            inline.model().url_name(),
+                            &*object,
+                            log::LogAction::Add,
+                            &[],
+                        )
+                        .await?;
+                        #[cfg(not(feature = "db"))]
+                        let _ = object;
+                    }
+                    AdminFormResult::ValidationError(form) => new_forms.push(form),
+                }
+            }
+        }
+
+        Self::build(
+            request,
+            inline,
+            parent_id,
+            permissions,
+            invalid_forms,
+            new_forms,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Creates the rows of the current objects, replacing the forms of the
+    /// ones that failed to validate, followed by the rows of the new objects
+    /// that failed to validate or, if there are none, the empty rows.
+    async fn build(
+        request: &Request,
+        inline: AdminInline,
+        parent_id: &str,
+        permissions: ModelPermissions,
+        mut invalid_forms: HashMap<String, AdminForm>,
+        new_forms: Vec<AdminForm>,
+    ) -> cot::Result<Self> {
+        let has_errors = !invalid_forms.is_empty() || !new_forms.is_empty();
+        let choices = inline.model().form_choices(request).await?;
+        let prefix = inline.prefix();
+        let row = |object_id: Option<String>, display: String, form: Option<AdminForm>, index| {
+            let prefix = row_prefix(&prefix, index);
+            InlineRow {
+                form: form.map(|form| {
+                    form.without_field(inline.field)
+                        .with_choices(&choices)
+                        .with_prefix(&prefix)
+                }),
+                prefix,
+                object_id,
+                display,
+            }
+        };
+
+        let mut rows = Vec::new();
+        for object in inline.get_objects(request, parent_id).await? {
+            let object_id = object.object_id();
+            let form = invalid_forms
+                .remove(&object_id)
+                .or_else(|| object.change_form())
+                .filter(|_| permissions.change);
+            rows.push(row(
+                Some(object_id),
+                object.display(),
+                form,
+                rows.len().to_string(),
+            ));
+        }
+
+        let empty_form = inline.model().add_form().filter(|_| permissions.add);
+        let mut empty_row = None;
+        if let Some(empty_form) = empty_form {
+            let new_forms = if new_forms.is_empty() {
+                vec![empty_form.clone(); inline.extra]
+            } else {
+                new_forms
+            };
+            for form in new_forms {
+                rows.push(row(None, String::new(), Some(form), rows.len().to_string()));
+            }
+            empty_row = Some(row(
+                None,
+                String::new(),
+                Some(empty_form),
+                EMPTY_ROW_INDEX.to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            inline,
+            permissions,
+            rows,
+            empty_row,
+            has_errors,
+        })
+    }
+
+    /// Returns the name of the related model.
+    pub(super) fn name(&self) -> &str {
+        self.inline.model().name()
+    }
+
+    /// Returns the name of the hidden input with the number of rows.
+    pub(super) fn total_key(&self) -> String {
+        total_key(&self.inline.prefix())
+    }
+
+    /// Returns the rows of the existing objects, followed by the rows of the
+    /// objects to add.
+    pub(super) fn rows(&self) -> &[InlineRow] {
+        &self.rows
+    }
+
+    /// Returns the template of the rows added with the "Add another"
+    /// button, or [`None`] if the user is not allowed to add objects.
+    pub(super) fn empty_row(&self) -> Option<&InlineRow> {
+        self.empty_row.as_ref()
+    }
+
+    /// Returns whether the user is allowed to delete the objects.
+    pub(super) fn can_delete(&self) -> bool {
+        self.permissions.delete
+    }
+
+    /// Returns whether any of the rows failed to validate.
+    pub(super) fn has_errors(&self) -> bool {
+        self.has_errors
+    }
+}
+
+/// A row of an [`InlineFormSet`].
+#[derive(Debug)]
+pub(super) struct InlineRow {
+    prefix: String,
+    object_id: Option<String>,
+    display: String,
+    form: Option<AdminForm>,
+}
+
+impl InlineRow {
+    /// Returns the name of the hidden input with the ID of the object.
+    pub(super) fn id_key(&self) -> String {
+        format!("{}{ID_KEY}", self.prefix)
+    }
+
+    /// Returns the name of the checkbox deleting the object.
+    pub(super) fn delete_key(&self) -> String {
+        format!("{}{DELETE_KEY}", self.prefix)
+    }
+
+    /// Returns the ID of the object, or [`None`] for the objects to add.
+    pub(super) fn object_id(&self) -> Option<&str> {
+        self.object_id.as_deref()
+    }
+
+    /// Returns the display text of the object.
+    pub(super) fn display(&self) -> &str {
+        &self.display
+    }
+
+    /// Returns the form of the row, or [`None`] if the user can only view
+    /// the object.
+    pub(super) fn form(&self) -> Option<&AdminForm> {
+        self.form.as_ref()
+    }
+}
+
+fn total_key(prefix: &str) -> String {
+    format!("{prefix}-{TOTAL_KEY}")
+}
+
+fn row_prefix<T: Display>(prefix: &str, index: T) -> String {
+    format!("{prefix}-{index}-")
+}
+
+/// Returns the indices below `total` of the rows with any values in the form
+/// data, in ascending order; the rows removed in the browser are missing.
+///
+/// The total is sent by the client, so it can't be trusted to be the actual
+/// number of the rows.
+fn row_indices(form_data: &Bytes, prefix: &str, total: usize) -> BTreeSet<usize> {
+    query_pairs(form_data)
+        .filter_map(|(key, _)| {
+            let (index, _) = key
+                .strip_prefix(prefix)?
+                .strip_prefix('-')?
+                .split_once('-')?;
+            index.parse().ok()
+        })
+        .filter(|&index| index < total)
+        .collect()
+}
+
+/// The values of a row submitted in the form data of the change page.
+#[derive(Debug, PartialEq, Eq)]
+struct RowData {
+    object_id: Option<String>,
+    delete: bool,
+    values: Vec<(String, String)>,
+}
+
+impl RowData {
+    /// Reads the values of the fields with the given prefix, or returns
+    /// [`None`] if there are none.
+    fn from_form_data(form_data: &Bytes, prefix: &str) -> Option<Self> {
+        let mut row = Self {
+            object_id: None,
+            delete: false,
+            values: Vec::new(),
+        };
+        let mut found = false;
+
+        for (key, value) in query_pairs(form_data) {
+            let Some(key) = key.strip_prefix(prefix) else {
+                continue;
+            };
+            found = true;
+
+            match key {
+                ID_KEY => row.object_id = Some(value.into_owned()).filter(|id| !id.is_empty()),
+                DELETE_KEY => row.delete = true,
+                _ => row.values.push((key.to_owned(), value.into_owned())),
+            }
+        }
+
+        found.then_some(row)
+    }
+
+    /// Returns whether none of the fields were filled in, i.e. the row is
+    /// one of the empty rows left unused.
+    fn is_blank(&self) -> bool {
+        self.values.iter().all(|(_, value)| value.is_empty())
+    }
+
+    /// Creates a request submitting the values of the row as the form of the
+    /// related model, with the foreign key set to the parent object.
+    fn to_request(&self, request: &Request, field: &str, parent_id: &str) -> Request {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.values)
+            .append_pair(field, parent_id)
+            .finish();
+
+        let mut row_request = http::Request::builder()
+            .method(http::Method::POST)
+            .uri(request.uri().clone())
+            .header(http::header::CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Body::fixed(body))
+            .expect("the request parts are valid");
+        // the session, the database and the user are stored in the extensions
+        *row_request.extensions_mut() = request.extensions().clone();
+
+        row_request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestExt;
+    use crate::test::TestRequestBuilder;
+
+    #[test]
+    fn row_data_from_form_data() {
+        let form_data = Bytes::from_static(
+            b"title=Hello&tag-post-TOTAL=3&tag-post-0-__id=5&tag-post-0-name=rust\
+              &tag-post-0-__delete=on&tag-post-2-__id=&tag-post-2-name=",
+        );
+
+        let row = RowData::from_form_data(&form_data, "tag-post-0-").unwrap();
+        assert_eq!(row.object_id.as_deref(), Some("5"));
+        assert!(row.delete);
+        assert_eq!(row.values, [(String::from("name"), String::from("rust"))]);
+        assert!(!row.is_blank());
+
+        assert_eq!(RowData::from_form_data(&form_data, "tag-post-1-"), None);
+
+        let row = RowData::from_form_data(&form_data, "tag-post-2-").unwrap();
+        assert_eq!(row.object_id, None);
+        assert!(!row.delete);
+        assert!(row.is_blank());
+    }
+
+    #[test]
+    fn row_indices_from_form_data() {
+        let form_data = Bytes::from_static(
+            b"tag-post-TOTAL=1000000000&tag-post-0-__id=5&tag-post-0-name=rust\
+              &tag-post-2-name=cot&tag-post-x-name=&tag-post-3000000000-name=\
+              &tag-comment-1-name=",
+        );
+
+        assert_eq!(
+            row_indices(&form_data, "tag-post", 1_000_000_000),
+            BTreeSet::from([0, 2])
+        );
+        assert_eq!(row_indices(&form_data, "tag-post", 1), BTreeSet::from([0]));
+    }
+
+    #[tokio::test]
+    async fn row_data_to_request() {
+        let request = TestRequestBuilder::get("/admin/post/1").build();
+        let row = RowData {
+            object_id: None,
+            delete: false,
+            values: vec![(String::from("name"), String::from("rust & cot"))],
+        };
+
+        let mut row_request = row.to_request(&request, "post", "1");
+        assert_eq!(row_request.method(), http::Method::POST);
+        assert_eq!(
+            row_request.form_data().await.unwrap(),
+            Bytes::from_static(b"name=rust+%26+cot&post=1")
+        );
+    }
+}
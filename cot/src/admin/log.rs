@@ -0,0 +1,443 @@
+//! The log of the changes made in the admin panel.
+//!
+//! Every object added, changed or deleted in the admin panel is recorded as a
+//! [`LogEntry`], along with the user who did it and, for the changes, the old
+//! and new values of the changed fields. The entries of an object are shown on
+//! its history page.
+//!
+//! The entries are stored in the database, so the migrations of the
+//! [`AdminApp`](crate::admin::AdminApp) need to be applied; if the project
+//! doesn't use a database, nothing is recorded.
+
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use thiserror::Error;
+
+use crate::admin::{AdminForm, AdminModel};
+use crate::auth::{AuthRequestExt, UserId};
+use crate::db::query::{Expr, Order, Query};
+use crate::db::{
+    model, query, Auto, ColumnType, DatabaseBackend, DatabaseField, DbValue, FromDbValue, Model,
+    SqlxValueRef, ToDbValue,
+};
+use crate::request::{Request, RequestExt};
+
+pub(crate) const MAX_LOG_ACTION_LENGTH: u32 = 16;
+
+/// An entry of the admin log, recording an action performed on an object in
+/// the admin panel.
+#[derive(Debug, Clone)]
+#[model]
+pub struct LogEntry {
+    id: Auto<i64>,
+    user_id: Option<String>,
+    username: Option<String>,
+    model: String,
+    object_id: String,
+    object_repr: String,
+    action: LogAction,
+    changes: String,
+    timestamp: DateTime<FixedOffset>,
+}
+
+impl LogEntry {
+    /// Records the given action performed on the object by the user of the
+    /// request.
+    ///
+    /// The admin views call this whenever an object is added, changed or
+    /// deleted; call it from custom [admin actions](crate::admin::actions)
+    /// to record their changes as well. Does nothing if the project doesn't
+    /// use a database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the current user could not be retrieved.
+    ///
+    /// Returns an error if the entry could not be saved to the database.
+    pub async fn log(
+        request: &mut Request,
+        model_name: &str,
+        object: &dyn AdminModel,
+        action: LogAction,
+        changes: &[FieldChange],
+    ) -> crate::Result<()> {
+        if request.context().try_database().is_none() {
+            return Ok(());
+        }
+
+        let user = request.user().await?;
+        let mut entry = Self {
+            id: Auto::auto(),
+            user_id: user.id().map(|id| match id {
+                UserId::Int(id) => id.to_string(),
+                UserId::String(id) => id,
+            }),
+            username: user.username().map(Cow::into_owned),
+            model: model_name.to_owned(),
+            object_id: object.object_id(),
+            object_repr: object.display(),
+            action,
+            changes: encode_changes(changes),
+            timestamp: Utc::now().into(),
+        };
+        entry.insert(request.db()).await?;
+
+        Ok(())
+    }
+
+    /// Returns the entries of the object with the given ID, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn for_object<DB: DatabaseBackend>(
+        db: &DB,
+        model_name: &str,
+        object_id: &str,
+    ) -> crate::db::Result<Vec<Self>> {
+        let model_name = model_name.to_owned();
+        let object_id = object_id.to_owned();
+        let entries = query!(LogEntry, $model == model_name && $object_id == object_id)
+            .order_by("timestamp", Order::Desc)
+            .order_by("id", Order::Desc)
+            .all(db)
+            .await?;
+
+        Ok(entries)
+    }
+
+    /// Returns at most `limit` latest entries of the given models, newest
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn recent<DB: DatabaseBackend>(
+        db: &DB,
+        model_names: &[&str],
+        limit: u64,
+    ) -> crate::db::Result<Vec<Self>> {
+        let Some(filter) = model_names
+            .iter()
+            .map(|&model_name| Expr::eq(Expr::field("model"), Expr::value(model_name.to_owned())))
+            .reduce(Expr::or)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let entries = Query::<Self>::new()
+            .filter(filter)
+            .order_by("timestamp", Order::Desc)
+            .order_by("id", Order::Desc)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(entries)
+    }
+
+    /// Returns the ID of the entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry hasn't been saved to the database yet.
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        match self.id {
+            Auto::Fixed(id) => id,
+            Auto::Auto => panic!("LogEntry constructed with an unknown ID"),
+        }
+    }
+
+    /// Returns the ID of the user who performed the action, if they had one.
+    #[must_use]
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// Returns the username of the user who performed the action, at the time
+    /// it was performed.
+    #[must_use]
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    /// Returns the [URL name](AdminModel::url_name) of the model of the
+    /// object.
+    #[must_use]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the [ID](AdminModel::object_id) of the object.
+    #[must_use]
+    pub fn object_id(&self) -> &str {
+        &self.object_id
+    }
+
+    /// Returns how the object was [displayed](AdminModel::display) when the
+    /// action was performed.
+    #[must_use]
+    pub fn object_repr(&self) -> &str {
+        &self.object_repr
+    }
+
+    /// Returns the action performed on the object.
+    #[must_use]
+    pub fn action(&self) -> LogAction {
+        self.action
+    }
+
+    /// Returns the fields changed by the action.
+    ///
+    /// This is only recorded for the [`LogAction::Change`] actions.
+    #[must_use]
+    pub fn changes(&self) -> Vec<FieldChange> {
+        decode_changes(&self.changes)
+    }
+
+    /// Returns the time the action was performed.
+    #[must_use]
+    pub fn timestamp(&self) -> DateTime<FixedOffset> {
+        self.timestamp
+    }
+}
+
+/// An action performed on an object in the admin panel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LogAction {
+    /// The object was added.
+    Add,
+    /// The object was changed.
+    Change,
+    /// The object was deleted.
+    Delete,
+}
+
+impl LogAction {
+    /// Returns the name of the action, e.g. `"add"`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Change => "change",
+            Self::Delete => "delete",
+        }
+    }
+
+    /// Returns the label of the action shown on the history page, e.g.
+    /// `"Added"`.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Add => "Added",
+            Self::Change => "Changed",
+            Self::Delete => "Deleted",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, UnknownLogActionError> {
+        match name {
+            "add" => Ok(Self::Add),
+            "change" => Ok(Self::Change),
+            "delete" => Ok(Self::Delete),
+            _ => Err(UnknownLogActionError(name.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown admin log action: `{0}`")]
+struct UnknownLogActionError(String);
+
+impl DatabaseField for LogAction {
+    const TYPE: ColumnType = ColumnType::String(MAX_LOG_ACTION_LENGTH);
+}
+
+impl FromDbValue for LogAction {
+    #[cfg(feature = "sqlite")]
+    fn from_sqlite(value: crate::db::impl_sqlite::SqliteValueRef<'_>) -> crate::db::Result<Self> {
+        Self::from_name(&value.get::<String>()?).map_err(crate::db::DatabaseError::value_decode)
+    }
+
+    #[cfg(feature = "postgres")]
+    fn from_postgres(
+        value: crate::db::impl_postgres::PostgresValueRef<'_>,
+    ) -> crate::db::Result<Self> {
+        Self::from_name(&value.get::<String>()?).map_err(crate::db::DatabaseError::value_decode)
+    }
+
+    #[cfg(feature = "mysql")]
+    fn from_mysql(value: crate::db::impl_mysql::MySqlValueRef<'_>) -> crate::db::Result<Self> {
+        Self::from_name(&value.get::<String>()?).map_err(crate::db::DatabaseError::value_decode)
+    }
+}
+
+impl ToDbValue for LogAction {
+    fn to_db_value(&self) -> DbValue {
+        self.as_str().into()
+    }
+}
+
+/// A field changed in the admin panel, along with its old and new value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    field: String,
+    old_value: String,
+    new_value: String,
+}
+
+impl FieldChange {
+    /// Creates a new field change.
+    #[must_use]
+    pub fn new<F, O, N>(field: F, old_value: O, new_value: N) -> Self
+    where
+        F: Into<String>,
+        O: Into<String>,
+        N: Into<String>,
+    {
+        Self {
+            field: field.into(),
+            old_value: old_value.into(),
+            new_value: new_value.into(),
+        }
+    }
+
+    /// Returns the changes between the values of two forms of the same object,
+    /// e.g. its change form before and after it was changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// use cot::admin::log::FieldChange;
+    /// use cot::admin::AdminForm;
+    /// use cot::form::{Form, FormContext};
+    ///
+    /// #[derive(Form)]
+    /// struct PostForm {
+    ///     title: String,
+    ///     published: bool,
+    /// }
+    ///
+    /// let form = |title: &'static str| {
+    ///     let mut context = <PostForm as Form>::Context::new();
+    ///     context.set_value("title", Cow::Borrowed(title)).unwrap();
+    ///     context
+    ///         .set_value("published", Cow::Borrowed("true"))
+    ///         .unwrap();
+    ///     AdminForm::from_context(&context)
+    /// };
+    ///
+    /// let changes = FieldChange::between(&form("Hello"), &form("Hello, world"));
+    /// assert_eq!(
+    ///     changes,
+    ///     [FieldChange::new("title", "Hello", "Hello, world")]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn between(old: &AdminForm, new: &AdminForm) -> Vec<Self> {
+        new.fields()
+            .iter()
+            .filter_map(|new_field| {
+                let old_value = old
+                    .fields()
+                    .iter()
+                    .find(|old_field| old_field.id() == new_field.id())
+                    .and_then(|old_field| old_field.value())
+                    .unwrap_or_default();
+                let new_value = new_field.value().unwrap_or_default();
+
+                (old_value != new_value).then(|| Self::new(new_field.id(), old_value, new_value))
+            })
+            .collect()
+    }
+
+    /// Returns the name of the changed field.
+    #[must_use]
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the value of the field before the change.
+    #[must_use]
+    pub fn old_value(&self) -> &str {
+        &self.old_value
+    }
+
+    /// Returns the value of the field after the change.
+    #[must_use]
+    pub fn new_value(&self) -> &str {
+        &self.new_value
+    }
+}
+
+/// Encodes the changes as a URL-encoded list of `field`, `old` and `new`
+/// triples, so that they can be stored in a single text column.
+fn encode_changes(changes: &[FieldChange]) -> String {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for change in changes {
+        serializer
+            .append_pair("field", &change.field)
+            .append_pair("old", &change.old_value)
+            .append_pair("new", &change.new_value);
+    }
+
+    serializer.finish()
+}
+
+fn decode_changes(changes: &str) -> Vec<FieldChange> {
+    let mut changes_list = Vec::new();
+    for (key, value) in form_urlencoded::parse(changes.as_bytes()) {
+        match key.as_ref() {
+            "field" => changes_list.push(FieldChange::new(value, "", "")),
+            "old" => {
+                if let Some(change) = changes_list.last_mut() {
+                    change.old_value = value.into_owned();
+                }
+            }
+            "new" => {
+                if let Some(change) = changes_list.last_mut() {
+                    change.new_value = value.into_owned();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    changes_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_encoding() {
+        let changes = [
+            FieldChange::new("title", "Hello & welcome", "Hello, world"),
+            FieldChange::new("published", "false", "true"),
+            FieldChange::new("views", "", "5"),
+        ];
+
+        let encoded = encode_changes(&changes);
+        assert_eq!(
+            encoded,
+            "field=title&old=Hello+%26+welcome&new=Hello%2C+world\
+             &field=published&old=false&new=true&field=views&old=&new=5"
+        );
+        assert_eq!(decode_changes(&encoded), changes);
+        assert_eq!(decode_changes(""), []);
+    }
+
+    #[test]
+    fn log_action_names() {
+        for action in [LogAction::Add, LogAction::Change, LogAction::Delete] {
+            assert_eq!(LogAction::from_name(action.as_str()).unwrap(), action);
+        }
+        assert!(LogAction::from_name("publish").is_err());
+        assert_eq!(LogAction::Change.label(), "Changed");
+    }
+}
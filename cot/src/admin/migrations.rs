@@ -0,0 +1,4 @@
+mod m_0001_initial;
+
+/// The list of all the migrations of the admin app, in order.
+pub const MIGRATIONS: &[&::cot::db::migrations::SyncDynMigration] = &[&m_0001_initial::Migration];
@@ -0,0 +1,255 @@
+//! Customization of the admin panel templates.
+//!
+//! The admin pages are rendered with the built-in templates by default. To
+//! brand the admin panel, implement [`AdminTemplates`] with the templates of
+//! the project and pass it to [`AdminApp::with_templates`]: overriding
+//! [`AdminTemplates::render_base`] replaces the layout of all the pages
+//! (`admin/base.html`), e.g. to add a logo or a stylesheet, and overriding
+//! [`AdminTemplates::render_model`] replaces the list view of the models
+//! (`admin/model.html`).
+//!
+//! # Examples
+//!
+//! ```
+//! use cot::admin::templates::{AdminPage, AdminTemplates};
+//! use cot::admin::AdminApp;
+//! use rinja::Template;
+//!
+//! #[derive(Template)]
+//! #[template(
+//!     source = "<html><body><h1>My Company</h1>{{ page.content()|safe }}</body></html>",
+//!     ext = "html"
+//! )]
+//! struct BaseTemplate<'a> {
+//!     page: &'a AdminPage<'a>,
+//! }
+//!
+//! struct MyTemplates;
+//!
+//! impl AdminTemplates for MyTemplates {
+//!     fn render_base(&self, page: &AdminPage<'_>) -> cot::Result<String> {
+//!         Ok(BaseTemplate { page }.render()?)
+//!     }
+//! }
+//!
+//! let admin = AdminApp::new().with_templates(MyTemplates);
+//! ```
+//!
+//! [`AdminApp::with_templates`]: crate::admin::AdminApp::with_templates
+
+use derive_more::Debug;
+use rinja::Template;
+
+use crate::admin::actions::AdminAction;
+use crate::admin::filters::{self, AdminFilter};
+use crate::admin::{AdminListParams, AdminModel, AdminModelManager};
+use crate::request::Request;
+
+/// The templates the admin pages are rendered with.
+///
+/// All the methods render the built-in templates by default; see the
+/// [module documentation](self) for an example.
+pub trait AdminTemplates: Send + Sync {
+    /// Renders the layout of the admin pages, i.e. the whole HTML document
+    /// around the content of the page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template could not be rendered.
+    fn render_base(&self, page: &AdminPage<'_>) -> crate::Result<String> {
+        Ok(BaseTemplate { page }.render()?)
+    }
+
+    /// Renders the content of the list view of a model, which is then placed
+    /// in the [layout](AdminTemplates::render_base).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template could not be rendered.
+    fn render_model(&self, page: &ModelListPage<'_>) -> crate::Result<String> {
+        let template = ModelTemplate {
+            request: page.request,
+            model: page.model,
+            can_add: page.can_add,
+            can_import: page.can_import,
+            objects: &page.objects,
+            params: &page.params,
+            total: page.total,
+            num_pages: page.num_pages,
+            filters: &page.filters,
+            actions: &page.actions,
+            messages: &page.messages,
+        };
+        Ok(template.render()?)
+    }
+}
+
+/// The built-in templates of the admin panel.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DefaultAdminTemplates;
+
+impl AdminTemplates for DefaultAdminTemplates {}
+
+/// An admin page passed to [`AdminTemplates::render_base`].
+#[derive(Debug)]
+pub struct AdminPage<'a> {
+    request: &'a Request,
+    title: &'a str,
+    body_class: &'a str,
+    content: &'a str,
+}
+
+impl<'a> AdminPage<'a> {
+    pub(super) fn new(
+        request: &'a Request,
+        title: &'a str,
+        body_class: &'a str,
+        content: &'a str,
+    ) -> Self {
+        Self {
+            request,
+            title,
+            body_class,
+            content,
+        }
+    }
+
+    /// Returns the request the page is rendered for, e.g. to reverse the URLs
+    /// of the admin panel.
+    #[must_use]
+    pub fn request(&self) -> &Request {
+        self.request
+    }
+
+    /// Returns the title of the page.
+    #[must_use]
+    pub fn title(&self) -> &str {
+        self.title
+    }
+
+    /// Returns the CSS class of the page body, e.g. `"login"` for the login
+    /// pages; it's empty for most pages.
+    #[must_use]
+    pub fn body_class(&self) -> &str {
+        self.body_class
+    }
+
+    /// Returns the rendered HTML content of the page; it must not be escaped.
+    #[must_use]
+    pub fn content(&self) -> &str {
+        self.content
+    }
+}
+
+/// The list view of a model passed to [`AdminTemplates::render_model`].
+#[derive(Debug)]
+pub struct ModelListPage<'a> {
+    pub(super) request: &'a Request,
+    #[debug("...")]
+    pub(super) model: &'a dyn AdminModelManager,
+    pub(super) can_add: bool,
+    pub(super) can_import: bool,
+    #[debug("...")]
+    pub(super) objects: Vec<Box<dyn AdminModel>>,
+    pub(super) params: AdminListParams,
+    pub(super) total: u64,
+    pub(super) num_pages: u64,
+    pub(super) filters: Vec<AdminFilter>,
+    pub(super) actions: Vec<AdminAction>,
+    pub(super) messages: Vec<String>,
+}
+
+impl ModelListPage<'_> {
+    /// Returns the request the page is rendered for.
+    #[must_use]
+    pub fn request(&self) -> &Request {
+        self.request
+    }
+
+    /// Returns the model manager of the model listed.
+    #[must_use]
+    pub fn model(&self) -> &dyn AdminModelManager {
+        self.model
+    }
+
+    /// Returns whether the user is allowed to add objects of the model.
+    #[must_use]
+    pub fn can_add(&self) -> bool {
+        self.can_add
+    }
+
+    /// Returns whether the user is allowed to import objects of the model from
+    /// a file.
+    #[must_use]
+    pub fn can_import(&self) -> bool {
+        self.can_import
+    }
+
+    /// Returns the objects on the current page.
+    #[must_use]
+    pub fn objects(&self) -> &[Box<dyn AdminModel>] {
+        &self.objects
+    }
+
+    /// Returns the parameters of the list view: the page, the sort order, the
+    /// search term and the selected filters.
+    #[must_use]
+    pub fn params(&self) -> &AdminListParams {
+        &self.params
+    }
+
+    /// Returns the number of objects matching the search and the filters.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the number of pages.
+    #[must_use]
+    pub fn num_pages(&self) -> u64 {
+        self.num_pages
+    }
+
+    /// Returns the filters shown in the sidebar.
+    #[must_use]
+    pub fn filters(&self) -> &[AdminFilter] {
+        &self.filters
+    }
+
+    /// Returns the actions the user is allowed to run on the selected
+    /// objects.
+    #[must_use]
+    pub fn actions(&self) -> &[AdminAction] {
+        &self.actions
+    }
+
+    /// Returns the messages to show, e.g. the result of the last action.
+    #[must_use]
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+}
+
+#[derive(Debug, Template)]
+#[template(path = "admin/base.html")]
+struct BaseTemplate<'a> {
+    page: &'a AdminPage<'a>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "admin/model.html")]
+struct ModelTemplate<'a> {
+    request: &'a Request,
+    #[debug("...")]
+    model: &'a dyn AdminModelManager,
+    can_add: bool,
+    can_import: bool,
+    #[debug("...")]
+    objects: &'a [Box<dyn AdminModel>],
+    params: &'a AdminListParams,
+    total: u64,
+    num_pages: u64,
+    filters: &'a [AdminFilter],
+    actions: &'a [AdminAction],
+    messages: &'a [String],
+}
@@ -5,9 +5,30 @@
 //! verification.
 //!
 //! For the default way to store users in the database, see the [`db`] module.
+//! Resetting forgotten passwords is handled by the [`password_reset`] module,
+//! and checking the password strength by the [`password_validation`] module.
+//! Authenticating API clients with bearer tokens is provided by the
+//! [`api_token`] module, and with JSON Web Tokens issued by an external
+//! identity provider by the [`jwt`] module. Several backends can be combined
+//! with the [`chain`] module. The number of failed login attempts is limited
+//! by the [`throttling`] module, and two-factor authentication with one-time
+//! passwords is provided by the [`two_factor`] module.
 
+#[cfg(feature = "db")]
+pub mod api_token;
+pub mod chain;
 #[cfg(feature = "db")]
 pub mod db;
+#[cfg(feature = "jwt")]
+pub mod jwt;
+mod password_hashing;
+#[cfg(feature = "db")]
+pub mod password_reset;
+#[cfg(feature = "db")]
+pub mod password_validation;
+pub mod throttling;
+#[cfg(feature = "db")]
+pub mod two_factor;
 
 use std::any::Any;
 use std::borrow::Cow;
@@ -15,15 +36,14 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, Utc};
 #[cfg(test)]
 use mockall::automock;
-use password_auth::VerifyError;
 use serde::{Deserialize, Serialize};
 use subtle::ConstantTimeEq;
 use thiserror::Error;
 
-use crate::config::SecretKey;
+use crate::config::{PasswordHasherConfig, SecretKey};
 #[cfg(feature = "db")]
 use crate::db::{ColumnType, DatabaseField, DbValue, FromDbValue, SqlxValueRef, ToDbValue};
 use crate::request::{Request, RequestExt};
@@ -34,6 +54,11 @@ pub enum AuthError {
     /// The password hash that is passed to [`PasswordHash::new`] is invalid.
     #[error("Password hash is invalid")]
     PasswordHashInvalid,
+    /// The password could not be hashed with the configured
+    /// [`PasswordHasherConfig`], typically because its parameters are out of
+    /// the range supported by the algorithm.
+    #[error("Could not hash the password: {0}")]
+    PasswordHasherInvalid(String),
     /// An error occurred while accessing the session object.
     #[error("Error while accessing the session object")]
     SessionAccess(#[from] tower_sessions::session::Error),
@@ -48,6 +73,18 @@ pub enum AuthError {
     /// supported.
     #[error("Tried to get a user by an unsupported user ID type")]
     UserIdTypeNotSupported,
+    /// There were too many failed login attempts with the username or from
+    /// the client IP address, so they are locked out for a while; see
+    /// [`throttling`].
+    #[error("Too many failed login attempts; locked out until {locked_until}")]
+    TooManyFailedAttempts {
+        /// The time the lockout ends at.
+        locked_until: DateTime<Utc>,
+    },
+    /// The user passed to [`AuthRequestExt::update_session_auth_hash`] is not
+    /// the one logged in.
+    #[error("The user is not the one logged in")]
+    UserNotLoggedIn,
 }
 
 impl AuthError {
@@ -126,6 +163,18 @@ pub trait User {
         false
     }
 
+    /// Returns whether the user is a staff member.
+    ///
+    /// Only the staff members can log in to the
+    /// [admin panel](crate::admin::AdminApp); what they can see and do there
+    /// is further limited by their permissions (see
+    /// [`has_perm`](Self::has_perm)).
+    ///
+    /// [`AnonymousUser`] always returns `false`.
+    fn is_staff(&self) -> bool {
+        false
+    }
+
     /// Returns the user's last login time.
     ///
     /// This is the time when the user last logged in to the system. Can be
@@ -146,6 +195,67 @@ pub trait User {
         None
     }
 
+    /// Returns whether the user has given permission.
+    ///
+    /// Permissions are identified by their codenames, such as
+    /// `"add_database_user"`. What permissions exist, and which of them are
+    /// granted to the user, is up to the user implementation. For instance,
+    /// [`DatabaseUserWithPermissions`](db::DatabaseUserWithPermissions)
+    /// checks the permissions granted to the user directly and through its
+    /// groups, and grants all permissions to superusers.
+    ///
+    /// [`AnonymousUser`] always returns `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::User;
+    ///
+    /// struct AdminUser;
+    ///
+    /// impl User for AdminUser {
+    ///     fn is_active(&self) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn is_authenticated(&self) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn has_perm(&self, perm: &str) -> bool {
+    ///         perm.starts_with("view_")
+    ///     }
+    /// }
+    ///
+    /// assert!(AdminUser.has_perm("view_article"));
+    /// assert!(!AdminUser.has_perm("delete_article"));
+    /// ```
+    #[allow(unused_variables)]
+    fn has_perm(&self, perm: &str) -> bool {
+        false
+    }
+
+    /// Returns whether the user has all the given permissions.
+    ///
+    /// By default, this calls [`has_perm`](Self::has_perm) for each of the
+    /// permissions.
+    ///
+    /// [`AnonymousUser`] always returns `false`, unless `perms` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::{AnonymousUser, User};
+    ///
+    /// assert!(!AnonymousUser().has_perms(&["view_article", "add_article"]));
+    /// ```
+    // mockall requires lifetimes to be specified here
+    // (see related issue: https://github.com/asomers/mockall/issues/571)
+    #[allow(clippy::needless_lifetimes)]
+    fn has_perms<'a>(&self, perms: &[&'a str]) -> bool {
+        perms.iter().all(|perm| self.has_perm(perm))
+    }
+
     /// Returns the user's session authentication hash.
     ///
     /// This used to verify that the session hash stored in the session
@@ -439,9 +549,14 @@ impl Debug for SessionAuthHash {
 
 /// A hashed password.
 ///
-/// This is used to store a hashed user password in the database. The password
-/// hash is created using the `password_auth` crate internally, so by default,
-/// it generates the hash using the latest recommended algorithm.
+/// This is used to store a hashed user password in the database. The hash is
+/// stored in the [PHC string format]. New hashes are created with Argon2id by
+/// default, or with the algorithm given in [`PasswordHasherConfig`] (see
+/// [`ProjectConfig::password_hasher`]). Hashes created with Argon2, scrypt,
+/// and PBKDF2-SHA256 can all be verified, regardless of the configuration.
+///
+/// [PHC string format]: https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+/// [`ProjectConfig::password_hasher`]: crate::config::ProjectConfig::password_hasher
 ///
 /// # Security
 ///
@@ -469,25 +584,34 @@ impl PasswordHash {
     /// let hash = PasswordHash::from_password(&Password::new("password"));
     /// let stored_hash = hash.into_string();
     /// let hash = PasswordHash::new(stored_hash).unwrap();
+    ///
+    /// // hashes created by other systems can be imported as well
+    /// let hash = PasswordHash::new(
+    ///     "$pbkdf2-sha256$i=4096,l=32$c2FsdA$xeR41ZKIyEGqUw22hFxMjZYok6ABzk4RpJY4c6qYE0o",
+    /// )
+    /// .unwrap();
     /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if the password hash is invalid.
+    /// Returns an error if the password hash is invalid or uses an unsupported
+    /// algorithm.
     pub fn new<T: Into<String>>(hash: T) -> Result<Self> {
         let hash = hash.into();
 
         if hash.len() > MAX_PASSWORD_HASH_LENGTH as usize {
             return Err(AuthError::PasswordHashInvalid);
         }
-        password_auth::is_hash_obsolete(&hash).map_err(|_| AuthError::PasswordHashInvalid)?;
+        password_hashing::validate_hash(&hash).map_err(|_| AuthError::PasswordHashInvalid)?;
 
         Ok(Self(hash))
     }
 
     /// Creates a new password hash from a password.
     ///
-    /// The password is hashed using the latest recommended algorithm.
+    /// The password is hashed using the default algorithm (Argon2id with the
+    /// default parameters). Use [`PasswordHash::from_password_with`] to hash
+    /// it with the algorithm configured for the project.
     ///
     /// # Examples
     ///
@@ -498,12 +622,51 @@ impl PasswordHash {
     /// ```
     #[must_use]
     pub fn from_password(password: &Password) -> Self {
-        let hash = password_auth::generate_hash(password.as_str());
+        let Ok(hash) = Self::from_password_with(password, &PasswordHasherConfig::default()) else {
+            unreachable!("password hashing with the default parameters should never fail");
+        };
+        hash
+    }
+
+    /// Creates a new password hash from a password, using the given hasher.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::{Password, PasswordHash};
+    /// use cot::config::PasswordHasherConfig;
+    ///
+    /// let hash = PasswordHash::from_password_with(
+    ///     &Password::new("password"),
+    ///     &PasswordHasherConfig::Pbkdf2 { iterations: 1000 },
+    /// )?;
+    /// assert!(hash.as_str().starts_with("$pbkdf2-sha256$"));
+    /// # Ok::<(), cot::auth::AuthError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AuthError::PasswordHasherInvalid`] if the parameters of the
+    /// hasher are invalid.
+    pub fn from_password_with(password: &Password, hasher: &PasswordHasherConfig) -> Result<Self> {
+        Self::generate(password, hasher).map_err(AuthError::PasswordHasherInvalid)
+    }
+
+    /// Hashes the password like [`PasswordHash::from_password_with`], returning
+    /// the reason why the hasher is invalid as the error.
+    pub(crate) fn generate(
+        password: &Password,
+        hasher: &PasswordHasherConfig,
+    ) -> std::result::Result<Self, String> {
+        let hash = password_hashing::generate_hash(password.as_str().as_bytes(), hasher)
+            .map_err(|error| error.to_string())?;
 
         if hash.len() > MAX_PASSWORD_HASH_LENGTH as usize {
-            unreachable!("password hash should never exceed {MAX_PASSWORD_HASH_LENGTH} bytes");
+            return Err(format!(
+                "password hash exceeds {MAX_PASSWORD_HASH_LENGTH} bytes"
+            ));
         }
-        Self(hash)
+        Ok(Self(hash))
     }
 
     /// Verifies a password against the hash.
@@ -516,6 +679,10 @@ impl PasswordHash {
     /// * If the password is invalid, returns
     ///   [`PasswordVerificationResult::Invalid`].
     ///
+    /// The hash is considered obsolete if it wasn't created with the default
+    /// algorithm and parameters. Use [`PasswordHash::verify_with`] to compare
+    /// it against the algorithm configured for the project instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -534,23 +701,68 @@ impl PasswordHash {
     /// }
     /// ```
     pub fn verify(&self, password: &Password) -> PasswordVerificationResult {
+        let Ok(result) = self.verify_with(password, &PasswordHasherConfig::default()) else {
+            unreachable!("password hashing with the default parameters should never fail");
+        };
+        result
+    }
+
+    /// Verifies a password against the hash, using the given hasher as the
+    /// currently preferred one.
+    ///
+    /// This works the same as [`PasswordHash::verify`], except that the hash
+    /// is considered obsolete if it was created with a different algorithm or
+    /// different parameters than `hasher`. In that case, the new hash
+    /// returned in [`PasswordVerificationResult::OkObsolete`] is created with
+    /// `hasher`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::{Password, PasswordHash, PasswordVerificationResult};
+    /// use cot::config::PasswordHasherConfig;
+    ///
+    /// let password = Password::new("password");
+    /// let hash = PasswordHash::new(
+    ///     "$pbkdf2-sha256$i=4096,l=32$c2FsdA$xeR41ZKIyEGqUw22hFxMjZYok6ABzk4RpJY4c6qYE0o",
+    /// )?;
+    ///
+    /// let hasher = PasswordHasherConfig::Pbkdf2 { iterations: 4096 };
+    /// assert!(matches!(
+    ///     hash.verify_with(&password, &hasher)?,
+    ///     PasswordVerificationResult::Ok
+    /// ));
+    /// # Ok::<(), cot::auth::AuthError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AuthError::PasswordHasherInvalid`] if the hash is obsolete
+    /// and the parameters of the hasher are invalid.
+    pub fn verify_with(
+        &self,
+        password: &Password,
+        hasher: &PasswordHasherConfig,
+    ) -> Result<PasswordVerificationResult> {
         const VALID_ERROR_STR: &str = "password hash should always be valid if created with `PasswordHash::new` or `PasswordHash::from_password`";
 
-        match password_auth::verify_password(password.as_str(), &self.0) {
-            Ok(()) => {
-                let Ok(is_obsolete) = password_auth::is_hash_obsolete(&self.0) else {
-                    unreachable!("{VALID_ERROR_STR}");
-                };
-                if is_obsolete {
-                    PasswordVerificationResult::OkObsolete(PasswordHash::from_password(password))
-                } else {
-                    PasswordVerificationResult::Ok
-                }
-            }
-            Err(error) => match error {
-                VerifyError::PasswordInvalid => PasswordVerificationResult::Invalid,
-                VerifyError::Parse(_) => unreachable!("{VALID_ERROR_STR}"),
-            },
+        let Ok(is_valid) = password_hashing::verify_password(password.as_str().as_bytes(), &self.0)
+        else {
+            unreachable!("{VALID_ERROR_STR}");
+        };
+        if !is_valid {
+            return Ok(PasswordVerificationResult::Invalid);
+        }
+
+        let Ok(is_obsolete) = password_hashing::is_hash_obsolete(&self.0, hasher) else {
+            unreachable!("{VALID_ERROR_STR}");
+        };
+        if is_obsolete {
+            Ok(PasswordVerificationResult::OkObsolete(
+                Self::from_password_with(password, hasher)?,
+            ))
+        } else {
+            Ok(PasswordVerificationResult::Ok)
         }
     }
 
@@ -773,7 +985,11 @@ pub trait AuthRequestExt: private::Sealed {
     /// returned.
     ///
     /// This method caches the user object in the request extensions, so it
-    /// doesn't need to be fetched from the backend on every call.
+    /// doesn't need to be fetched from the backend on every call. If the
+    /// request doesn't have a session, and the user hasn't been authenticated
+    /// by a middleware (such as
+    /// [`ApiTokenMiddleware`](crate::middleware::ApiTokenMiddleware)), the
+    /// [`AnonymousUser`] object is returned.
     ///
     /// # Errors
     ///
@@ -793,10 +1009,20 @@ pub trait AuthRequestExt: private::Sealed {
     /// credentials are valid and returns the user object. To log the user
     /// in the current session, use the [`login`](Self::login) method.
     ///
+    /// The failed attempts to authenticate with a username and a password
+    /// (such as [`DatabaseUserCredentials`](db::DatabaseUserCredentials)) are
+    /// counted by the project's
+    /// [`LoginThrottle`](throttling::LoginThrottle), and when there are too
+    /// many of them, the credentials are not checked at all until the lockout
+    /// ends.
+    ///
     /// # Errors
     ///
     /// Returns an error if the AuthBackend accepts the credentials but fails
     /// to fetch the user object.
+    ///
+    /// Returns [`AuthError::TooManyFailedAttempts`] if the username or the
+    /// client IP address is locked out.
     async fn authenticate(
         &mut self,
         credentials: &(dyn Any + Send + Sync),
@@ -808,12 +1034,51 @@ pub trait AuthRequestExt: private::Sealed {
     /// in the session object and can be accessed using the [`user`](Self::user)
     /// method.
     ///
+    /// If the auth backend
+    /// [requires a second factor](AuthBackend::requires_second_factor) for the
+    /// user (e.g. because they have enabled
+    /// [two-factor authentication](two_factor)), the user is not logged in
+    /// yet. Instead, the login is pending until the second factor is verified
+    /// with [`verify_second_factor`](Self::verify_second_factor), which can be
+    /// checked with [`second_factor_pending`](Self::second_factor_pending).
+    ///
     /// # Errors
     ///
     /// Returns an error if the user object cannot be stored in the session
     /// object.
+    ///
+    /// Returns an error if the auth backend fails to
+    /// [log in](AuthBackend::login) the user or to check whether the user
+    /// requires a second factor.
     async fn login(&mut self, user: Box<dyn User + Send + Sync + 'static>) -> Result<()>;
 
+    /// Returns whether there is a login waiting for the second factor to be
+    /// verified with [`verify_second_factor`](Self::verify_second_factor).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying session backend fails.
+    async fn second_factor_pending(&mut self) -> Result<bool>;
+
+    /// Verifies the second factor code of the user whose login is pending, and
+    /// if it's valid, logs them in.
+    ///
+    /// Returns `false` if the code is invalid, or if there is no pending login.
+    /// The failed attempts are counted by the project's
+    /// [`LoginThrottle`](throttling::LoginThrottle), just like the failed
+    /// attempts to [`authenticate`](Self::authenticate).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the auth backend fails to fetch the user object or
+    /// to verify the code.
+    ///
+    /// Returns [`AuthError::TooManyFailedAttempts`] if the username or the
+    /// client IP address is locked out.
+    ///
+    /// Returns an error if the underlying session backend fails.
+    async fn verify_second_factor(&mut self, code: &str) -> Result<bool>;
+
     /// Logs out the current user.
     ///
     /// This removes the user object from the session object and logs the user
@@ -825,12 +1090,35 @@ pub trait AuthRequestExt: private::Sealed {
     /// Returns an error if the user object cannot be removed from the session
     /// object.
     async fn logout(&mut self) -> Result<()>;
+
+    /// Keeps the given user logged in after their password was changed.
+    ///
+    /// Changing the password changes the user's
+    /// [session auth hash](User::session_auth_hash), which logs the user out
+    /// of all their sessions; this updates the hash stored in the current
+    /// session, so that the user who changed their own password stays logged
+    /// in. Unlike [`login`](Self::login), this never requires verifying the
+    /// second factor again, so it only accepts the user who is already logged
+    /// in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AuthError::UserNotLoggedIn`] if the user doesn't have the ID
+    /// of the user logged in within the current session.
+    ///
+    /// Returns an error if the user object cannot be stored in the session
+    /// object.
+    async fn update_session_auth_hash(
+        &mut self,
+        user: Box<dyn User + Send + Sync + 'static>,
+    ) -> Result<()>;
 }
 
 const USER_ID_SESSION_KEY: &str = "__cot_auth_user_id";
 const SESSION_HASH_SESSION_KEY: &str = "__cot_auth_session_hash";
+const PENDING_USER_ID_SESSION_KEY: &str = "__cot_auth_pending_user_id";
 
-type UserExtension = Arc<dyn User + Send + Sync + 'static>;
+pub(crate) type UserExtension = Arc<dyn User + Send + Sync + 'static>;
 
 impl private::Sealed for Request {}
 
@@ -838,8 +1126,17 @@ impl private::Sealed for Request {}
 impl AuthRequestExt for Request {
     async fn user(&mut self) -> Result<&dyn User> {
         if self.extensions().get::<UserExtension>().is_none() {
-            if let Some(user) = get_user_with_saved_id(self).await? {
+            if self.extensions().get::<tower_sessions::Session>().is_none() {
+                // there's no session (e.g. the request was authenticated with an API
+                // token, or failed to), so there's no logged in user to restore either
+                self.extensions_mut()
+                    .insert::<UserExtension>(Arc::new(AnonymousUser()));
+            } else if let Some(user) = get_user_with_saved_id(self).await? {
                 self.extensions_mut().insert(UserExtension::from(user));
+            } else if self.second_factor_pending().await? {
+                // don't cancel the login waiting for the second factor
+                self.extensions_mut()
+                    .insert::<UserExtension>(Arc::new(AnonymousUser()));
             } else {
                 self.logout().await?;
             }
@@ -855,28 +1152,112 @@ impl AuthRequestExt for Request {
         &mut self,
         credentials: &(dyn Any + Send + Sync),
     ) -> Result<Option<Box<dyn User + Send + Sync>>> {
-        self.context()
+        let throttle = self.context().login_throttle();
+        let targets = credentials_username(credentials)
+            .map(|username| throttle.targets(self, username))
+            .unwrap_or_default();
+        if let Some(lockout) = throttle.lockout(&targets) {
+            return Err(AuthError::TooManyFailedAttempts {
+                locked_until: lockout.locked_until(),
+            });
+        }
+
+        let user = self
+            .context()
             .auth_backend()
             .authenticate(self, credentials)
-            .await
+            .await?;
+        if user.is_some() {
+            throttle.record_success(&targets);
+        } else {
+            throttle.record_failure(self, &targets).await;
+        }
+        Ok(user)
     }
 
     async fn login(&mut self, user: Box<dyn User + Send + Sync + 'static>) -> Result<()> {
-        let user = UserExtension::from(user);
+        self.context().auth_backend().login(self, &*user).await?;
+
+        // a user without an ID can't be looked up after verifying the second factor
         if let Some(user_id) = user.id() {
-            self.session_mut()
-                .insert(USER_ID_SESSION_KEY, user_id)
-                .await?;
+            if self
+                .context()
+                .auth_backend()
+                .requires_second_factor(self, &*user)
+                .await?
+            {
+                self.session_mut().remove_value(USER_ID_SESSION_KEY).await?;
+                self.session_mut()
+                    .remove_value(SESSION_HASH_SESSION_KEY)
+                    .await?;
+                self.session_mut()
+                    .insert(PENDING_USER_ID_SESSION_KEY, user_id)
+                    .await?;
+                self.extensions_mut()
+                    .insert::<UserExtension>(Arc::new(AnonymousUser()));
+
+                return Ok(());
+            }
         }
-        let secret_key = &self.project_config().secret_key;
-        if let Some(session_auth_hash) = user.session_auth_hash(secret_key) {
+
+        complete_login(self, user).await
+    }
+
+    async fn second_factor_pending(&mut self) -> Result<bool> {
+        Ok(self
+            .session()
+            .get::<UserId>(PENDING_USER_ID_SESSION_KEY)
+            .await?
+            .is_some())
+    }
+
+    async fn verify_second_factor(&mut self, code: &str) -> Result<bool> {
+        let Some(user_id) = self
+            .session()
+            .get::<UserId>(PENDING_USER_ID_SESSION_KEY)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let Some(user) = self
+            .context()
+            .auth_backend()
+            .get_by_id(self, user_id)
+            .await?
+        else {
             self.session_mut()
-                .insert(SESSION_HASH_SESSION_KEY, session_auth_hash.as_bytes())
+                .remove_value(PENDING_USER_ID_SESSION_KEY)
                 .await?;
+            return Ok(false);
+        };
+
+        let throttle = self.context().login_throttle();
+        let targets = user
+            .username()
+            .map(|username| throttle.targets(self, &username))
+            .unwrap_or_default();
+        if let Some(lockout) = throttle.lockout(&targets) {
+            return Err(AuthError::TooManyFailedAttempts {
+                locked_until: lockout.locked_until(),
+            });
         }
-        self.extensions_mut().insert(user);
 
-        Ok(())
+        if !self
+            .context()
+            .auth_backend()
+            .verify_second_factor(self, &*user, code)
+            .await?
+        {
+            throttle.record_failure(self, &targets).await;
+            return Ok(false);
+        }
+        throttle.record_success(&targets);
+
+        self.session_mut()
+            .remove_value(PENDING_USER_ID_SESSION_KEY)
+            .await?;
+        complete_login(self, user).await?;
+        Ok(true)
     }
 
     async fn logout(&mut self) -> Result<()> {
@@ -884,11 +1265,65 @@ impl AuthRequestExt for Request {
         self.session_mut()
             .remove_value(SESSION_HASH_SESSION_KEY)
             .await?;
+        self.session_mut()
+            .remove_value(PENDING_USER_ID_SESSION_KEY)
+            .await?;
+        self.session_mut()
+            .remove_value(chain::BACKEND_SESSION_KEY)
+            .await?;
         self.extensions_mut()
             .insert::<UserExtension>(Arc::new(AnonymousUser()));
 
         Ok(())
     }
+
+    async fn update_session_auth_hash(
+        &mut self,
+        user: Box<dyn User + Send + Sync + 'static>,
+    ) -> Result<()> {
+        let logged_in_user_id = self.session().get::<UserId>(USER_ID_SESSION_KEY).await?;
+        if user.id().is_none() || user.id() != logged_in_user_id {
+            return Err(AuthError::UserNotLoggedIn);
+        }
+
+        complete_login(self, user).await
+    }
+}
+
+/// Logs in the user without checking whether a second factor is required.
+async fn complete_login(
+    request: &mut Request,
+    user: Box<dyn User + Send + Sync + 'static>,
+) -> Result<()> {
+    let user = UserExtension::from(user);
+    if let Some(user_id) = user.id() {
+        request
+            .session_mut()
+            .insert(USER_ID_SESSION_KEY, user_id)
+            .await?;
+    }
+    let secret_key = &request.project_config().secret_key;
+    if let Some(session_auth_hash) = user.session_auth_hash(secret_key) {
+        request
+            .session_mut()
+            .insert(SESSION_HASH_SESSION_KEY, session_auth_hash.as_bytes())
+            .await?;
+    }
+    request.extensions_mut().insert(user);
+
+    Ok(())
+}
+
+/// Returns the username from the credentials that are throttled by the
+/// [`LoginThrottle`](throttling::LoginThrottle).
+#[cfg_attr(not(feature = "db"), allow(unused_variables))]
+fn credentials_username(credentials: &(dyn Any + Send + Sync)) -> Option<&str> {
+    #[cfg(feature = "db")]
+    if let Some(credentials) = credentials.downcast_ref::<db::DatabaseUserCredentials>() {
+        return Some(credentials.username());
+    }
+
+    None
 }
 
 async fn get_user_with_saved_id(
@@ -1017,6 +1452,67 @@ pub trait AuthBackend: Send + Sync {
         request: &Request,
         id: UserId,
     ) -> Result<Option<Box<dyn User + Send + Sync>>>;
+
+    /// Called when a user returned by [`authenticate`](Self::authenticate) is
+    /// logged in with [`AuthRequestExt::login`], before checking whether the
+    /// user has to verify a second factor.
+    ///
+    /// This is useful for backends that need to store some state in the
+    /// session of the logged in user. It's not called for the requests that
+    /// are only authenticated, e.g. with a bearer token.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state of the user cannot be stored.
+    async fn login(&self, request: &Request, user: &(dyn User + Send + Sync)) -> Result<()> {
+        let _ = (request, user);
+        Ok(())
+    }
+
+    /// Returns whether the user has to verify a second authentication factor
+    /// (such as a one-time password) before being logged in.
+    ///
+    /// If this returns `true`, [`AuthRequestExt::login`] doesn't log the user
+    /// in until the second factor is verified with
+    /// [`AuthRequestExt::verify_second_factor`].
+    ///
+    /// The default implementation returns `false`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the second factor settings of the user cannot be
+    /// fetched.
+    async fn requires_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+    ) -> Result<bool> {
+        let _ = (request, user);
+        Ok(false)
+    }
+
+    /// Verifies the code of the user's second authentication factor.
+    ///
+    /// This method returns `true` if the code is valid, and `false`
+    /// otherwise.
+    ///
+    /// The default implementation returns `false`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the second factor settings of the user cannot be
+    /// fetched or updated.
+    async fn verify_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+        code: &str,
+    ) -> Result<bool> {
+        let _ = (request, user, code);
+        Ok(false)
+    }
 }
 
 /// A no-op authentication backend.
@@ -1124,6 +1620,9 @@ mod tests {
             anonymous_user.session_auth_hash(&SecretKey::new(b"key")),
             None
         );
+        assert!(!anonymous_user.has_perm("view_database_user"));
+        assert!(!anonymous_user.has_perms(&["view_database_user"]));
+        assert!(anonymous_user.has_perms(&[]));
 
         let anonymous_user2 = AnonymousUser();
         assert_eq!(anonymous_user, anonymous_user2);
@@ -1265,6 +1764,91 @@ mod tests {
         assert!(user.username().is_none());
     }
 
+    struct SecondFactorAuthBackend;
+
+    fn second_factor_user() -> MockUser {
+        let mut mock_user = MockUser::new();
+        mock_user.expect_id().return_const(UserId::Int(1));
+        mock_user.expect_session_auth_hash().return_const(None);
+        mock_user
+            .expect_username()
+            .return_const(Some(Cow::from("mockuser")));
+        mock_user
+    }
+
+    #[async_trait]
+    impl AuthBackend for SecondFactorAuthBackend {
+        async fn authenticate(
+            &self,
+            _request: &Request,
+            _credentials: &(dyn Any + Send + Sync),
+        ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+            Ok(Some(Box::new(second_factor_user())))
+        }
+
+        async fn get_by_id(
+            &self,
+            _request: &Request,
+            _id: UserId,
+        ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+            Ok(Some(Box::new(second_factor_user())))
+        }
+
+        async fn requires_second_factor(
+            &self,
+            _request: &Request,
+            _user: &(dyn User + Send + Sync),
+        ) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn verify_second_factor(
+            &self,
+            _request: &Request,
+            _user: &(dyn User + Send + Sync),
+            code: &str,
+        ) -> Result<bool> {
+            Ok(code == "123456")
+        }
+    }
+
+    #[tokio::test]
+    async fn login_pending_second_factor() {
+        let mut request = test_request_with_auth_backend(SecondFactorAuthBackend);
+
+        request.login(Box::new(second_factor_user())).await.unwrap();
+        assert!(request.second_factor_pending().await.unwrap());
+        assert!(!request.user().await.unwrap().is_authenticated());
+
+        assert!(!request.verify_second_factor("000000").await.unwrap());
+        assert!(request.second_factor_pending().await.unwrap());
+
+        assert!(request.verify_second_factor("123456").await.unwrap());
+        assert!(!request.second_factor_pending().await.unwrap());
+        let user = request.user().await.unwrap();
+        assert_eq!(user.username(), Some(Cow::from("mockuser")));
+    }
+
+    #[tokio::test]
+    async fn logout_cancels_pending_second_factor() {
+        let mut request = test_request_with_auth_backend(SecondFactorAuthBackend);
+
+        request.login(Box::new(second_factor_user())).await.unwrap();
+        request.logout().await.unwrap();
+
+        assert!(!request.second_factor_pending().await.unwrap());
+        assert!(!request.verify_second_factor("123456").await.unwrap());
+        assert!(!request.user().await.unwrap().is_authenticated());
+    }
+
+    #[tokio::test]
+    async fn verify_second_factor_without_pending_login() {
+        let mut request = test_request_with_auth_backend(SecondFactorAuthBackend);
+
+        assert!(!request.verify_second_factor("123456").await.unwrap());
+        assert!(!request.user().await.unwrap().is_authenticated());
+    }
+
     /// Test that the user is logged out when there is an invalid user ID in the
     /// session (can happen if the user is deleted from the database)
     #[tokio::test]
@@ -1318,6 +1902,64 @@ mod tests {
         assert_eq!(user.username(), None);
     }
 
+    #[tokio::test]
+    async fn update_session_auth_hash_keeps_user_logged_in() {
+        let session_auth_hash = Arc::new(Mutex::new(SessionAuthHash::new(&[1, 2, 3])));
+        let session_auth_hash_clone = Arc::clone(&session_auth_hash);
+        let create_user = move || {
+            let session_auth_hash_clone = Arc::clone(&session_auth_hash_clone);
+            let mut mock_user = MockUser::new();
+            mock_user.expect_id().return_const(UserId::Int(1));
+            mock_user
+                .expect_session_auth_hash()
+                .returning(move |_| Some(session_auth_hash_clone.lock().unwrap().clone()));
+            mock_user
+                .expect_username()
+                .return_const(Some(Cow::from("mockuser")));
+            mock_user
+        };
+
+        let mut request = test_request(create_user.clone());
+        request.login(Box::new(create_user())).await.unwrap();
+
+        *session_auth_hash.lock().unwrap() = SessionAuthHash::new(&[4, 5, 6]);
+        request
+            .update_session_auth_hash(Box::new(create_user()))
+            .await
+            .unwrap();
+        request.extensions_mut().remove::<UserExtension>();
+        let user = request.user().await.unwrap();
+        assert_eq!(user.username(), Some(Cow::from("mockuser")));
+    }
+
+    #[tokio::test]
+    async fn update_session_auth_hash_requires_logged_in_user() {
+        let create_user = |id| {
+            move || {
+                let mut mock_user = MockUser::new();
+                mock_user.expect_id().return_const(UserId::Int(id));
+                mock_user
+                    .expect_session_auth_hash()
+                    .returning(|_| Some(SessionAuthHash::new(&[1, 2, 3])));
+                mock_user
+            }
+        };
+
+        let mut request = test_request(create_user(1));
+        let result = request
+            .update_session_auth_hash(Box::new(create_user(1)()))
+            .await;
+        assert!(matches!(result, Err(AuthError::UserNotLoggedIn)));
+        assert!(!request.user().await.unwrap().is_authenticated());
+
+        request.login(Box::new(create_user(1)())).await.unwrap();
+        let result = request
+            .update_session_auth_hash(Box::new(create_user(2)()))
+            .await;
+        assert!(matches!(result, Err(AuthError::UserNotLoggedIn)));
+        assert_eq!(request.user().await.unwrap().id(), Some(UserId::Int(1)));
+    }
+
     #[tokio::test]
     async fn user_secret_key_change() {
         let create_user = move || {
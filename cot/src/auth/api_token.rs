@@ -0,0 +1,716 @@
+//! Token-based authentication for API clients.
+//!
+//! This module provides the [`ApiToken`] model, which stores bearer tokens
+//! issued to [`DatabaseUser`]s, and the [`ApiTokenBackend`] authentication
+//! backend that authenticates users with them. Tokens are meant for clients
+//! that can't (or don't want to) use cookie-based sessions, such as mobile
+//! apps or command line tools.
+//!
+//! Only a SHA-256 hash of each token is stored in the database; the token
+//! itself is returned only once, when it is created with
+//! [`ApiToken::create`]. Every token can be limited to a set of scopes, can
+//! expire, and records the time it was last used.
+//!
+//! The easiest way to authenticate the requests is to add the
+//! [`ApiTokenMiddleware`](crate::middleware::ApiTokenMiddleware) to the
+//! project. It reads the token from the `Authorization: Bearer <token>`
+//! header and makes the token owner available through
+//! [`AuthRequestExt::user`](crate::auth::AuthRequestExt::user), without
+//! touching the session.
+
+use std::any::Any;
+use std::borrow::Cow;
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::admin::{get_model_list, AdminList, AdminListParams, AdminModel};
+use crate::auth::db::{
+    DatabaseUser, DatabaseUserBackend, DatabaseUserCredentials, DatabaseUserWithPermissions,
+};
+use crate::auth::{AuthBackend, AuthError, Result, SessionAuthHash, User, UserId};
+use crate::config::SecretKey;
+use crate::db::{model, query, Auto, DatabaseBackend, ForeignKey, LimitedString, Model};
+use crate::request::{Request, RequestExt};
+
+pub(crate) const MAX_API_TOKEN_NAME_LENGTH: u32 = 255;
+pub(crate) const API_TOKEN_HASH_LENGTH: u32 = 64;
+pub(crate) const API_TOKEN_PREFIX_LENGTH: u32 = 12;
+
+/// The string every token generated by Cot starts with. It makes the tokens
+/// easy to recognize, e.g. by secret scanners.
+pub(crate) const TOKEN_PREFIX: &str = "cot_";
+const TOKEN_RANDOM_BYTES: usize = 32;
+
+/// An API token issued to a [`DatabaseUser`].
+///
+/// # Security
+///
+/// The token itself is not stored; only its SHA-256 hash and its first few
+/// characters (so that the user can tell their tokens apart) are. Since the
+/// tokens are long random strings, a fast hash is sufficient here, unlike for
+/// passwords.
+#[derive(Debug, Clone)]
+#[model]
+pub struct ApiToken {
+    id: Auto<i64>,
+    user: ForeignKey<DatabaseUser>,
+    name: LimitedString<MAX_API_TOKEN_NAME_LENGTH>,
+    #[model(unique)]
+    token_hash: LimitedString<API_TOKEN_HASH_LENGTH>,
+    token_prefix: LimitedString<API_TOKEN_PREFIX_LENGTH>,
+    scopes: String,
+    created_at: DateTime<FixedOffset>,
+    expires_at: Option<DateTime<FixedOffset>>,
+    last_used_at: Option<DateTime<FixedOffset>>,
+}
+
+/// An error that occurs when creating an API token.
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum CreateApiTokenError {
+    /// The token name is too long.
+    #[error("token name is too long (max {MAX_API_TOKEN_NAME_LENGTH} characters, got {0})")]
+    NameTooLong(usize),
+    /// A scope contains whitespace, or is empty.
+    #[error("invalid token scope: `{0}`")]
+    InvalidScope(String),
+}
+
+impl ApiToken {
+    /// Creates a new token for the user and saves it to the database.
+    ///
+    /// Returns the token object along with the token string that should be
+    /// passed to the client. The token string can't be retrieved later, as
+    /// only its hash is stored.
+    ///
+    /// `scopes` can be used to limit what the token can be used for; they are
+    /// not interpreted by Cot in any way, so it's up to the views to check
+    /// them with [`ApiToken::has_scope`]. Scopes can't contain whitespace.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is too long or a scope is invalid, in
+    /// which case the error source is a [`CreateApiTokenError`].
+    ///
+    /// Returns an error if the token could not be saved to the database.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::api_token::ApiToken;
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     "password123",
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    ///
+    /// let (token, token_string) =
+    ///     ApiToken::create(&*db, &user, "CLI", &["articles:read"], None).await?;
+    ///
+    /// assert!(token.has_scope("articles:read"));
+    /// assert!(ApiToken::get_by_token(&*db, &token_string).await?.is_some());
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn create<DB: DatabaseBackend, T: Into<String>>(
+        db: &DB,
+        user: &DatabaseUser,
+        name: T,
+        scopes: &[&str],
+        expires_at: Option<DateTime<FixedOffset>>,
+    ) -> Result<(Self, String)> {
+        let name = name.into();
+        let name_length = name.len();
+        let name = LimitedString::new(name)
+            .map_err(|_| AuthError::backend_error(CreateApiTokenError::NameTooLong(name_length)))?;
+        if let Some(scope) = scopes
+            .iter()
+            .find(|scope| scope.is_empty() || scope.contains(char::is_whitespace))
+        {
+            return Err(AuthError::backend_error(CreateApiTokenError::InvalidScope(
+                (*scope).to_owned(),
+            )));
+        }
+
+        let token_string = generate_token();
+        let Ok(token_prefix) =
+            LimitedString::new(&token_string[..API_TOKEN_PREFIX_LENGTH as usize])
+        else {
+            unreachable!("token prefix has the correct length");
+        };
+        let mut token = Self {
+            id: Auto::auto(),
+            user: ForeignKey::from(user),
+            name,
+            token_hash: hash_token(&token_string),
+            token_prefix,
+            scopes: scopes.join(" "),
+            created_at: Utc::now().fixed_offset(),
+            expires_at,
+            last_used_at: None,
+        };
+        token.insert(db).await.map_err(AuthError::backend_error)?;
+
+        Ok((token, token_string))
+    }
+
+    /// Gets a token by the token string passed by the client. Returns
+    /// [`None`] if the token does not exist.
+    ///
+    /// Note that this returns expired tokens as well; use
+    /// [`ApiToken::is_expired`] to check whether the token is still valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn get_by_token<DB: DatabaseBackend>(db: &DB, token: &str) -> Result<Option<Self>> {
+        let token_hash = hash_token(token);
+        let token = query!(ApiToken, $token_hash == token_hash)
+            .get(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(token)
+    }
+
+    /// Returns all the tokens issued to the user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn for_user<DB: DatabaseBackend>(db: &DB, user: &DatabaseUser) -> Result<Vec<Self>> {
+        let tokens = query!(ApiToken, $user == user)
+            .all(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(tokens)
+    }
+
+    /// Deletes the token from the database, so that it can't be used anymore.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn revoke<DB: DatabaseBackend>(self, db: &DB) -> Result<()> {
+        let id = self.id();
+        query!(ApiToken, $id == id)
+            .delete(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+
+    /// Sets the time the token was last used to the current time and saves
+    /// the token to the database.
+    ///
+    /// This is called by [`ApiTokenBackend`] whenever a user is authenticated
+    /// with the token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token could not be saved to the database.
+    pub async fn update_last_used<DB: DatabaseBackend>(&mut self, db: &DB) -> Result<()> {
+        self.last_used_at = Some(Utc::now().fixed_offset());
+        self.save(db).await.map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+
+    /// Get the ID of the token.
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        match self.id {
+            Auto::Fixed(id) => id,
+            Auto::Auto => unreachable!("ApiToken constructed with an unknown ID"),
+        }
+    }
+
+    /// Get the ID of the user the token was issued to.
+    #[must_use]
+    pub fn user_id(&self) -> i64 {
+        match self.user.primary_key() {
+            Auto::Fixed(id) => *id,
+            Auto::Auto => unreachable!("ApiToken constructed with an unknown user ID"),
+        }
+    }
+
+    /// Get the human-readable name of the token.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the first few characters of the token, which can be shown to the
+    /// user so that they can tell their tokens apart.
+    #[must_use]
+    pub fn token_prefix(&self) -> &str {
+        &self.token_prefix
+    }
+
+    /// Returns the scopes of the token.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scopes.split_whitespace()
+    }
+
+    /// Returns whether the token has given scope.
+    #[must_use]
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().any(|token_scope| token_scope == scope)
+    }
+
+    /// Returns the time the token was created.
+    #[must_use]
+    pub fn created_at(&self) -> DateTime<FixedOffset> {
+        self.created_at
+    }
+
+    /// Returns the time the token expires, or [`None`] if it never expires.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<DateTime<FixedOffset>> {
+        self.expires_at
+    }
+
+    /// Returns the time the token was last used to authenticate, or [`None`]
+    /// if it has never been used.
+    #[must_use]
+    pub fn last_used_at(&self) -> Option<DateTime<FixedOffset>> {
+        self.last_used_at
+    }
+
+    /// Returns whether the token has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+}
+
+#[async_trait]
+impl AdminModel for ApiToken {
+    async fn get_objects(request: &Request) -> crate::Result<Vec<Self>> {
+        Ok(ApiToken::objects()
+            .all(request.db())
+            .await
+            .map_err(AuthError::backend_error)?)
+    }
+
+    async fn get_list(
+        request: &Request,
+        params: &AdminListParams,
+    ) -> crate::Result<AdminList<Self>> {
+        get_model_list(request, params, Vec::new()).await
+    }
+
+    async fn get_object_by_id(request: &Request, id: &str) -> crate::Result<Option<Self>> {
+        let Ok(id) = id.parse::<i64>() else {
+            return Ok(None);
+        };
+
+        Ok(query!(ApiToken, $id == id)
+            .get(request.db())
+            .await
+            .map_err(AuthError::backend_error)?)
+    }
+
+    fn name() -> &'static str {
+        "ApiToken"
+    }
+
+    fn url_name() -> &'static str {
+        "api_token"
+    }
+
+    fn object_id(&self) -> String {
+        self.id().to_string()
+    }
+
+    fn display(&self) -> String {
+        let expires = self.expires_at.map_or_else(
+            || String::from("never"),
+            |expires_at| expires_at.to_rfc3339(),
+        );
+        format!(
+            "{} ({}...) of user #{}, scopes: [{}], expires: {}",
+            self.name.as_str(),
+            self.token_prefix.as_str(),
+            self.user_id(),
+            self.scopes,
+            expires
+        )
+    }
+
+    async fn delete(&self, request: &Request) -> crate::Result<()> {
+        let id = self.id();
+        query!(ApiToken, $id == id)
+            .delete(request.db())
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0; TOKEN_RANDOM_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+
+    format!("{TOKEN_PREFIX}{}", hex::encode(bytes))
+}
+
+fn hash_token(token: &str) -> LimitedString<API_TOKEN_HASH_LENGTH> {
+    LimitedString::new(hex::encode(Sha256::digest(token.as_bytes())))
+        .expect("SHA-256 hex digest has the correct length")
+}
+
+/// Credentials for authenticating with an [`ApiToken`].
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::api_token::ApiTokenCredentials;
+///
+/// let credentials = ApiTokenCredentials::new("cot_0123abcd".to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApiTokenCredentials {
+    token: String,
+}
+
+impl ApiTokenCredentials {
+    /// Create a new instance of the credentials.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::api_token::ApiTokenCredentials;
+    ///
+    /// let credentials = ApiTokenCredentials::new("cot_0123abcd".to_string());
+    /// ```
+    #[must_use]
+    pub fn new(token: String) -> Self {
+        Self { token }
+    }
+
+    /// Get the token string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::api_token::ApiTokenCredentials;
+    ///
+    /// let credentials = ApiTokenCredentials::new("cot_0123abcd".to_string());
+    /// assert_eq!(credentials.token(), "cot_0123abcd");
+    /// ```
+    #[must_use]
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+/// A user authenticated with an [`ApiToken`].
+///
+/// This is the user object returned by [`ApiTokenBackend`] for
+/// [`ApiTokenCredentials`]. It behaves like the
+/// [`DatabaseUserWithPermissions`] it wraps, except that it doesn't provide a
+/// session auth hash, as it's not meant to be stored in a session.
+#[derive(Debug, Clone)]
+pub struct ApiTokenUser {
+    user: DatabaseUserWithPermissions,
+    token: ApiToken,
+}
+
+impl ApiTokenUser {
+    /// Returns the user the token was issued to.
+    #[must_use]
+    pub fn user(&self) -> &DatabaseUserWithPermissions {
+        &self.user
+    }
+
+    /// Returns the token the user was authenticated with.
+    #[must_use]
+    pub fn token(&self) -> &ApiToken {
+        &self.token
+    }
+
+    /// Returns whether the token the user was authenticated with has given
+    /// scope.
+    #[must_use]
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.token.has_scope(scope)
+    }
+}
+
+impl User for ApiTokenUser {
+    fn id(&self) -> Option<UserId> {
+        self.user.id()
+    }
+
+    fn username(&self) -> Option<Cow<'_, str>> {
+        self.user.username()
+    }
+
+    fn is_active(&self) -> bool {
+        self.user.is_active()
+    }
+
+    fn is_authenticated(&self) -> bool {
+        self.user.is_authenticated()
+    }
+
+    fn is_staff(&self) -> bool {
+        self.user.is_staff()
+    }
+
+    fn has_perm(&self, perm: &str) -> bool {
+        self.user.has_perm(perm)
+    }
+
+    fn last_login(&self) -> Option<DateTime<FixedOffset>> {
+        self.user.last_login()
+    }
+
+    fn session_auth_hash(&self, _secret_key: &SecretKey) -> Option<SessionAuthHash> {
+        None
+    }
+}
+
+/// An authentication backend for [`ApiToken`]s.
+///
+/// It authenticates [`ApiTokenCredentials`] by looking the token up in the
+/// database, returning an [`ApiTokenUser`] if the token exists and hasn't
+/// expired. All the other operations, including authenticating with
+/// [`DatabaseUserCredentials`], are passed to [`DatabaseUserBackend`], so that
+/// the users can still log in with their username and password.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::api_token::ApiTokenBackend;
+/// use cot::auth::AuthBackend;
+/// use cot::project::WithApps;
+/// use cot::{Project, ProjectContext};
+///
+/// struct MyProject;
+/// impl Project for MyProject {
+///     fn auth_backend(&self, context: &ProjectContext<WithApps>) -> Box<dyn AuthBackend> {
+///         Box::new(ApiTokenBackend::new())
+///         // note that it's usually better to just set the auth backend in the config
+///     }
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct ApiTokenBackend;
+
+impl Default for ApiTokenBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiTokenBackend {
+    /// Create a new instance of the API token backend.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::api_token::ApiTokenBackend;
+    ///
+    /// let backend = ApiTokenBackend::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Authenticates a user with a token string.
+    ///
+    /// Returns [`None`] if the token does not exist, has expired, or the user
+    /// it was issued to doesn't exist anymore. Otherwise, the time the token
+    /// was last used is updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn authenticate_token(
+        &self,
+        request: &Request,
+        token: &str,
+    ) -> Result<Option<ApiTokenUser>> {
+        let db = request.db();
+        let Some(mut token) = ApiToken::get_by_token(db, token).await? else {
+            return Ok(None);
+        };
+        if token.is_expired() {
+            return Ok(None);
+        }
+        // tokens of deactivated users are rejected
+        let Some(user) = DatabaseUser::get_by_id(db, token.user_id())
+            .await?
+            .filter(DatabaseUser::is_active)
+        else {
+            return Ok(None);
+        };
+
+        token.update_last_used(db).await?;
+        let user = user.with_permissions(db).await?;
+
+        Ok(Some(ApiTokenUser { user, token }))
+    }
+}
+
+#[async_trait]
+impl AuthBackend for ApiTokenBackend {
+    async fn authenticate(
+        &self,
+        request: &Request,
+        credentials: &(dyn Any + Send + Sync),
+    ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+        if let Some(credentials) = credentials.downcast_ref::<ApiTokenCredentials>() {
+            #[allow(trivial_casts)] // Upcast to the correct Box type
+            Ok(self
+                .authenticate_token(request, credentials.token())
+                .await?
+                .map(|user| Box::new(user) as Box<dyn User + Send + Sync>))
+        } else if credentials.is::<DatabaseUserCredentials>() {
+            DatabaseUserBackend::new()
+                .authenticate(request, credentials)
+                .await
+        } else {
+            Err(AuthError::CredentialsTypeNotSupported)
+        }
+    }
+
+    async fn get_by_id(
+        &self,
+        request: &Request,
+        id: UserId,
+    ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+        DatabaseUserBackend::new().get_by_id(request, id).await
+    }
+
+    async fn requires_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+    ) -> Result<bool> {
+        DatabaseUserBackend::new()
+            .requires_second_factor(request, user)
+            .await
+    }
+
+    async fn verify_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+        code: &str,
+    ) -> Result<bool> {
+        DatabaseUserBackend::new()
+            .verify_second_factor(request, user, code)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::auth::{Password, PasswordHash};
+
+    fn test_token(scopes: &str, expires_at: Option<DateTime<FixedOffset>>) -> ApiToken {
+        ApiToken {
+            id: Auto::fixed(1),
+            user: ForeignKey::PrimaryKey(Auto::fixed(2)),
+            name: LimitedString::new("test").unwrap(),
+            token_hash: hash_token("cot_test"),
+            token_prefix: LimitedString::new("cot_test").unwrap(),
+            scopes: scopes.to_owned(),
+            created_at: Utc::now().fixed_offset(),
+            expires_at,
+            last_used_at: None,
+        }
+    }
+
+    #[test]
+    fn generate_token_format() {
+        let token = generate_token();
+
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(token.len(), TOKEN_PREFIX.len() + 2 * TOKEN_RANDOM_BYTES);
+        assert_ne!(token, generate_token());
+    }
+
+    #[test]
+    fn hash_token_is_hex_sha256() {
+        assert_eq!(
+            hash_token("abc").as_str(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn scopes() {
+        let token = test_token("articles:read articles:write", None);
+
+        assert_eq!(
+            token.scopes().collect::<Vec<_>>(),
+            ["articles:read", "articles:write"]
+        );
+        assert!(token.has_scope("articles:write"));
+        assert!(!token.has_scope("articles"));
+        assert!(!test_token("", None).has_scope(""));
+    }
+
+    #[test]
+    fn is_expired() {
+        let now = Utc::now().fixed_offset();
+
+        assert!(!test_token("", None).is_expired());
+        assert!(!test_token("", Some(now + TimeDelta::hours(1))).is_expired());
+        assert!(test_token("", Some(now - TimeDelta::seconds(1))).is_expired());
+    }
+
+    #[test]
+    fn api_token_user_has_no_session_hash() {
+        let user = DatabaseUser::new(
+            Auto::fixed(2),
+            LimitedString::new("testuser").unwrap(),
+            PasswordHash::from_password(&Password::new("password123")),
+        );
+        let user = ApiTokenUser {
+            user: DatabaseUserWithPermissions {
+                user,
+                permissions: HashSet::from(["view_article".to_owned()]),
+            },
+            token: test_token("articles:read", None),
+        };
+
+        assert_eq!(User::id(&user), Some(UserId::Int(2)));
+        assert!(user.has_perm("view_article"));
+        assert!(user.has_scope("articles:read"));
+        assert!(user
+            .session_auth_hash(&SecretKey::new(b"supersecretkey"))
+            .is_none());
+    }
+}
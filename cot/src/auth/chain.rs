@@ -0,0 +1,477 @@
+//! Combining several authentication backends.
+//!
+//! This module provides the [`ChainAuthBackend`], which tries several
+//! authentication backends in order. It's useful when the users can come from
+//! different sources, such as the users stored in the database and the users
+//! of an external directory.
+
+use std::any::Any;
+
+use async_trait::async_trait;
+use derive_more::Debug;
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+
+use crate::auth::{AuthBackend, AuthError, Result, User, UserId};
+use crate::request::Request;
+
+/// The session key storing the name of the backend that authenticated the
+/// user most recently.
+pub(crate) const BACKEND_SESSION_KEY: &str = "__cot_auth_chain_backend";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedBackend {
+    name: String,
+    user_id: UserId,
+}
+
+/// An authentication backend that tries several backends in order.
+///
+/// When authenticating, the credentials are passed to each backend in the
+/// order they were added, and the first user returned is used. Backends that
+/// don't support the credentials type are skipped.
+///
+/// When the user is logged in with [`AuthRequestExt::login`], the name of the
+/// first backend that has a user with the same ID and username is saved in
+/// the session, so that [`AuthBackend::get_by_id`] (used e.g. to get the
+/// logged in user in the subsequent requests) asks the same backend for the
+/// user. Otherwise, the backends are asked in order, and the first user found
+/// is returned.
+///
+/// [`AuthRequestExt::login`]: crate::auth::AuthRequestExt::login
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::api_token::ApiTokenBackend;
+/// use cot::auth::chain::ChainAuthBackend;
+/// use cot::auth::db::DatabaseUserBackend;
+/// use cot::auth::AuthBackend;
+/// use cot::project::WithApps;
+/// use cot::{Project, ProjectContext};
+///
+/// struct MyProject;
+/// impl Project for MyProject {
+///     fn auth_backend(&self, context: &ProjectContext<WithApps>) -> Box<dyn AuthBackend> {
+///         Box::new(
+///             ChainAuthBackend::new()
+///                 .backend("api_token", Box::new(ApiTokenBackend::new()))
+///                 .backend("database", Box::new(DatabaseUserBackend::new())),
+///         )
+///         // note that it's usually better to just set the auth backend in the config
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ChainAuthBackend {
+    #[debug("{:?}", backends.iter().map(|(name, _)| name).collect::<Vec<_>>())]
+    backends: Vec<(String, Box<dyn AuthBackend>)>,
+}
+
+impl ChainAuthBackend {
+    /// Create a new, empty chain of authentication backends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::chain::ChainAuthBackend;
+    ///
+    /// let backend = ChainAuthBackend::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a backend to the end of the chain.
+    ///
+    /// The name identifies the backend in the session, so it should not
+    /// change between the deployments of the project, as otherwise the
+    /// backends will have to be asked in order for the users that are already
+    /// logged in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a backend with the same name has already been added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::chain::ChainAuthBackend;
+    /// use cot::auth::NoAuthBackend;
+    ///
+    /// let backend = ChainAuthBackend::new().backend("none", Box::new(NoAuthBackend));
+    /// ```
+    #[must_use]
+    pub fn backend<T: Into<String>>(mut self, name: T, backend: Box<dyn AuthBackend>) -> Self {
+        let name = name.into();
+        assert!(
+            self.backends
+                .iter()
+                .all(|(backend_name, _)| *backend_name != name),
+            "auth backend `{name}` has already been added to the chain"
+        );
+
+        self.backends.push((name, backend));
+        self
+    }
+
+    async fn saved_backend(request: &Request, id: &UserId) -> Result<Option<String>> {
+        let Some(session) = request.extensions().get::<Session>() else {
+            return Ok(None);
+        };
+
+        let saved_backend = session.get::<SavedBackend>(BACKEND_SESSION_KEY).await?;
+        Ok(saved_backend
+            .filter(|saved_backend| saved_backend.user_id == *id)
+            .map(|saved_backend| saved_backend.name))
+    }
+
+    /// Returns the backend saved in the session as the one that authenticated
+    /// the user with given ID.
+    async fn backend_for(
+        &self,
+        request: &Request,
+        id: &UserId,
+    ) -> Result<Option<&dyn AuthBackend>> {
+        let Some(name) = Self::saved_backend(request, id).await? else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .backends
+            .iter()
+            .find(|(backend_name, _)| *backend_name == name)
+            .map(|(_, backend)| &**backend))
+    }
+
+    /// Returns the first backend that has the given user, i.e. a user with the
+    /// same ID and username.
+    async fn find_backend(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+        user_id: &UserId,
+    ) -> Result<Option<(&str, &dyn AuthBackend)>> {
+        for (name, backend) in &self.backends {
+            match backend.get_by_id(request, user_id.clone()).await {
+                Ok(Some(found)) if found.username() == user.username() => {
+                    return Ok(Some((name, &**backend)));
+                }
+                Ok(_) | Err(AuthError::UserIdTypeNotSupported) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl AuthBackend for ChainAuthBackend {
+    async fn authenticate(
+        &self,
+        request: &Request,
+        credentials: &(dyn Any + Send + Sync),
+    ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+        let mut supported = false;
+        for (_, backend) in &self.backends {
+            match backend.authenticate(request, credentials).await {
+                Ok(Some(user)) => return Ok(Some(user)),
+                Ok(None) => supported = true,
+                Err(AuthError::CredentialsTypeNotSupported) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        if supported {
+            Ok(None)
+        } else {
+            Err(AuthError::CredentialsTypeNotSupported)
+        }
+    }
+
+    async fn get_by_id(
+        &self,
+        request: &Request,
+        id: UserId,
+    ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+        if let Some(backend) = self.backend_for(request, &id).await? {
+            return backend.get_by_id(request, id).await;
+        }
+
+        let mut supported = false;
+        for (_, backend) in &self.backends {
+            match backend.get_by_id(request, id.clone()).await {
+                Ok(Some(user)) => return Ok(Some(user)),
+                Ok(None) => supported = true,
+                Err(AuthError::UserIdTypeNotSupported) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        if supported {
+            Ok(None)
+        } else {
+            Err(AuthError::UserIdTypeNotSupported)
+        }
+    }
+
+    async fn login(&self, request: &Request, user: &(dyn User + Send + Sync)) -> Result<()> {
+        let (Some(session), Some(user_id)) = (request.extensions().get::<Session>(), user.id())
+        else {
+            return Ok(());
+        };
+
+        let Some((name, backend)) = self.find_backend(request, user, &user_id).await? else {
+            session.remove_value(BACKEND_SESSION_KEY).await?;
+            return Ok(());
+        };
+
+        session
+            .insert(
+                BACKEND_SESSION_KEY,
+                SavedBackend {
+                    name: name.to_owned(),
+                    user_id,
+                },
+            )
+            .await?;
+        backend.login(request, user).await
+    }
+
+    async fn requires_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+    ) -> Result<bool> {
+        if let Some(id) = user.id() {
+            if let Some(backend) = self.backend_for(request, &id).await? {
+                return backend.requires_second_factor(request, user).await;
+            }
+        }
+
+        for (_, backend) in &self.backends {
+            if backend.requires_second_factor(request, user).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn verify_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+        code: &str,
+    ) -> Result<bool> {
+        if let Some(id) = user.id() {
+            if let Some(backend) = self.backend_for(request, &id).await? {
+                return backend.verify_second_factor(request, user, code).await;
+            }
+        }
+
+        for (_, backend) in &self.backends {
+            if backend.verify_second_factor(request, user, code).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::auth::AuthRequestExt;
+    use crate::request::RequestExt;
+    use crate::test::TestRequestBuilder;
+
+    struct TestCredentials(&'static str);
+
+    #[derive(Clone)]
+    struct TestUser {
+        id: i64,
+        username: &'static str,
+    }
+
+    impl User for TestUser {
+        fn id(&self) -> Option<UserId> {
+            Some(UserId::Int(self.id))
+        }
+
+        fn username(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::from(self.username))
+        }
+
+        fn is_active(&self) -> bool {
+            true
+        }
+
+        fn is_authenticated(&self) -> bool {
+            true
+        }
+    }
+
+    /// A backend that knows about the given users, all sharing the same
+    /// password.
+    struct TestBackend {
+        users: Vec<TestUser>,
+    }
+
+    impl TestBackend {
+        fn new(users: &[(i64, &'static str)]) -> Self {
+            Self {
+                users: users
+                    .iter()
+                    .map(|&(id, username)| TestUser { id, username })
+                    .collect(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AuthBackend for TestBackend {
+        async fn authenticate(
+            &self,
+            _request: &Request,
+            credentials: &(dyn Any + Send + Sync),
+        ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+            let Some(TestCredentials(username)) = credentials.downcast_ref() else {
+                return Err(AuthError::CredentialsTypeNotSupported);
+            };
+
+            #[allow(trivial_casts)] // Upcast to the correct Box type
+            Ok(self
+                .users
+                .iter()
+                .find(|user| user.username == *username)
+                .map(|user| Box::new(user.clone()) as Box<dyn User + Send + Sync>))
+        }
+
+        async fn get_by_id(
+            &self,
+            _request: &Request,
+            id: UserId,
+        ) -> Result<Option<Box<dyn User + Send + Sync>>> {
+            let UserId::Int(id) = id else {
+                return Err(AuthError::UserIdTypeNotSupported);
+            };
+
+            #[allow(trivial_casts)] // Upcast to the correct Box type
+            Ok(self
+                .users
+                .iter()
+                .find(|user| user.id == id)
+                .map(|user| Box::new(user.clone()) as Box<dyn User + Send + Sync>))
+        }
+    }
+
+    fn chain() -> ChainAuthBackend {
+        ChainAuthBackend::new()
+            .backend("database", Box::new(TestBackend::new(&[(1, "alice")])))
+            .backend(
+                "directory",
+                Box::new(TestBackend::new(&[(1, "bob"), (2, "carol")])),
+            )
+    }
+
+    #[tokio::test]
+    async fn authenticate_tries_backends_in_order() {
+        let backend = chain();
+        let request = TestRequestBuilder::get("/").build();
+
+        let user = backend
+            .authenticate(&request, &TestCredentials("bob"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.username(), Some(Cow::from("bob")));
+
+        let user = backend
+            .authenticate(&request, &TestCredentials("nobody"))
+            .await
+            .unwrap();
+        assert!(user.is_none());
+
+        let result = backend.authenticate(&request, &"unsupported").await;
+        assert!(matches!(
+            result,
+            Err(AuthError::CredentialsTypeNotSupported)
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_by_id_uses_saved_backend() {
+        let mut request = TestRequestBuilder::get("/")
+            .with_session()
+            .auth_backend(chain())
+            .build();
+
+        let user = request
+            .authenticate(&TestCredentials("bob"))
+            .await
+            .unwrap()
+            .unwrap();
+        request.login(user).await.unwrap();
+
+        // bob has the same ID as alice, who is in the first backend
+        let mut request = TestRequestBuilder::get("/")
+            .with_session_from(&request)
+            .auth_backend(chain())
+            .build();
+        let user = request.user().await.unwrap();
+        assert_eq!(user.username(), Some(Cow::from("bob")));
+    }
+
+    #[tokio::test]
+    async fn authenticate_does_not_save_backend() {
+        let mut request = TestRequestBuilder::get("/")
+            .with_session()
+            .auth_backend(chain())
+            .build();
+
+        let user = request.authenticate(&TestCredentials("bob")).await.unwrap();
+        assert!(user.is_some());
+
+        let saved_backend = request
+            .session()
+            .get::<SavedBackend>(BACKEND_SESSION_KEY)
+            .await
+            .unwrap();
+        assert_eq!(saved_backend, None);
+    }
+
+    #[tokio::test]
+    async fn get_by_id_without_session() {
+        let backend = chain();
+        let request = TestRequestBuilder::get("/").build();
+
+        let user = backend
+            .get_by_id(&request, UserId::Int(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.username(), Some(Cow::from("alice")));
+
+        let user = backend
+            .get_by_id(&request, UserId::Int(2))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.username(), Some(Cow::from("carol")));
+
+        let result = backend
+            .get_by_id(&request, UserId::String("1".to_owned()))
+            .await;
+        assert!(matches!(result, Err(AuthError::UserIdTypeNotSupported)));
+    }
+
+    #[test]
+    #[should_panic(expected = "auth backend `database` has already been added to the chain")]
+    fn duplicate_backend_name() {
+        let _ = chain().backend("database", Box::new(TestBackend::new(&[])));
+    }
+}
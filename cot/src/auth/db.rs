@@ -5,26 +5,48 @@
 
 use std::any::Any;
 use std::borrow::Cow;
+use std::collections::HashSet;
 
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
 use hmac::{Hmac, KeyInit, Mac};
+use rinja::Template;
 use sha2::Sha512;
 use thiserror::Error;
 
-use crate::admin::{AdminModel, AdminModelManager, DefaultAdminModelManager};
+use crate::admin::actions::{AdminAction, AdminActionResult};
+use crate::admin::filters::{AdminFilter, AdminFilterField};
+use crate::admin::log::{FieldChange, LogAction, LogEntry};
+use crate::admin::{
+    get_model_list, AdminDisplay, AdminForm, AdminFormResult, AdminList, AdminListParams,
+    AdminModel, AdminModelManager, DefaultAdminModelManager,
+};
+use crate::auth::api_token::ApiToken;
+use crate::auth::password_validation::{PasswordError, PasswordValidator};
+use crate::auth::two_factor::{self, TotpDevice};
 use crate::auth::{
     AuthBackend, AuthError, Password, PasswordHash, PasswordVerificationResult, Result,
     SessionAuthHash, User, UserId,
 };
-use crate::config::SecretKey;
-use crate::db::migrations::SyncDynMigration;
-use crate::db::{model, query, Auto, DatabaseBackend, LimitedString, Model};
+use crate::config::{PasswordHasherConfig, SecretKey};
+use crate::db::migrations::{DynMigration, Operation, SyncDynMigration};
+use crate::db::query::{Expr, Query};
+use crate::db::{model, query, Auto, DatabaseBackend, ForeignKey, LimitedString, Model};
+use crate::form::{
+    Form, FormContext, FormErrorTarget, FormField, FormFieldValidationError, FormResult,
+};
 use crate::request::{Request, RequestExt};
 use crate::App;
 
 pub mod migrations;
 
 pub(crate) const MAX_USERNAME_LENGTH: u32 = 255;
+pub(crate) const MAX_GROUP_NAME_LENGTH: u32 = 150;
+pub(crate) const MAX_PERMISSION_CODENAME_LENGTH: u32 = 255;
+pub(crate) const MAX_PERMISSION_NAME_LENGTH: u32 = 255;
+
+/// The name of the app the authentication migrations belong to.
+const MIGRATIONS_APP_NAME: &str = "cot_auth";
 
 /// A user stored in the database.
 #[derive(Debug, Clone)]
@@ -34,6 +56,10 @@ pub struct DatabaseUser {
     #[model(unique)]
     username: LimitedString<MAX_USERNAME_LENGTH>,
     password: PasswordHash,
+    is_staff: bool,
+    is_superuser: bool,
+    last_login: Option<DateTime<FixedOffset>>,
+    is_active: bool,
 }
 
 /// An error that occurs when creating a user.
@@ -43,26 +69,43 @@ pub enum CreateUserError {
     /// The username is too long.
     #[error("username is too long (max {MAX_USERNAME_LENGTH} characters, got {0})")]
     UsernameTooLong(usize),
+    /// The password can't be set for the user.
+    #[error(transparent)]
+    Password(#[from] PasswordError),
 }
 
 impl DatabaseUser {
     #[must_use]
-    fn new(
+    pub(crate) fn new(
         id: Auto<i64>,
         username: LimitedString<MAX_USERNAME_LENGTH>,
-        password: &Password,
+        password: PasswordHash,
     ) -> Self {
         Self {
             id,
             username,
-            password: PasswordHash::from_password(password),
+            password,
+            is_staff: false,
+            is_superuser: false,
+            last_login: None,
+            is_active: true,
         }
     }
 
     /// Create a new user and save it to the database.
     ///
+    /// The password is checked with the given validator first; typically,
+    /// this is
+    /// [`ProjectConfig::password_validators`](crate::config::ProjectConfig::password_validators).
+    /// It is then hashed with the given hasher, typically
+    /// [`ProjectConfig::password_hasher`](crate::config::ProjectConfig::password_hasher).
+    ///
     /// # Errors
     ///
+    /// Returns an error if the password was rejected by the validator or
+    /// could not be hashed, in which case the error source is a
+    /// [`CreateUserError`].
+    ///
     /// Returns an error if the user could not be saved to the database.
     ///
     /// # Example
@@ -78,7 +121,9 @@ impl DatabaseUser {
     ///     let user = DatabaseUser::create_user(
     ///         request.db(),
     ///         "testuser".to_string(),
-    ///         &Password::new("password123"),
+    ///         &Password::new("correct horse battery staple"),
+    ///         &request.project_config().password_validators,
+    ///         &request.project_config().password_hasher,
     ///     )
     ///     .await?;
     ///
@@ -105,15 +150,98 @@ impl DatabaseUser {
         db: &DB,
         username: T,
         password: U,
+        password_validator: &dyn PasswordValidator,
+        password_hasher: &PasswordHasherConfig,
+    ) -> Result<Self> {
+        let mut user = Self::new_with_username(
+            username.into(),
+            &password.into(),
+            password_validator,
+            password_hasher,
+        )?;
+        user.insert(db).await.map_err(AuthError::backend_error)?;
+
+        Ok(user)
+    }
+
+    /// Create a new superuser and save it to the database.
+    ///
+    /// A superuser is a staff member that has all the permissions without
+    /// them being explicitly granted. The password is checked with the given
+    /// validator and hashed with the given hasher, like in
+    /// [`DatabaseUser::create_user`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password was rejected by the validator or
+    /// could not be hashed, in which case the error source is a
+    /// [`CreateUserError`].
+    ///
+    /// Returns an error if the user could not be saved to the database.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::auth::{Password, User};
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_superuser(
+    ///     &*db,
+    ///     "admin",
+    ///     &Password::new("password123"),
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    ///
+    /// assert!(user.is_staff());
+    /// assert!(user.has_perm("delete_database_user"));
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn create_superuser<DB: DatabaseBackend, T: Into<String>, U: Into<Password>>(
+        db: &DB,
+        username: T,
+        password: U,
+        password_validator: &dyn PasswordValidator,
+        password_hasher: &PasswordHasherConfig,
+    ) -> Result<Self> {
+        let mut user = Self::new_with_username(
+            username.into(),
+            &password.into(),
+            password_validator,
+            password_hasher,
+        )?;
+        user.is_staff = true;
+        user.is_superuser = true;
+        user.insert(db).await.map_err(AuthError::backend_error)?;
+
+        Ok(user)
+    }
+
+    fn new_with_username(
+        username: String,
+        password: &Password,
+        password_validator: &dyn PasswordValidator,
+        password_hasher: &PasswordHasherConfig,
     ) -> Result<Self> {
-        let username = username.into();
         let username_length = username.len();
         let username = LimitedString::<MAX_USERNAME_LENGTH>::new(username).map_err(|_| {
             AuthError::backend_error(CreateUserError::UsernameTooLong(username_length))
         })?;
 
-        let mut user = Self::new(Auto::auto(), username, &password.into());
-        user.insert(db).await.map_err(AuthError::backend_error)?;
+        // the password is hashed only after it's validated
+        let mut user = Self::new(Auto::auto(), username, PasswordHash(String::new()));
+        user.set_password(password, password_validator, password_hasher)
+            .map_err(|error| AuthError::backend_error(CreateUserError::from(error)))?;
 
         Ok(user)
     }
@@ -142,7 +270,9 @@ impl DatabaseUser {
     ///     let user = DatabaseUser::create_user(
     ///         request.db(),
     ///         "testuser".to_string(),
-    ///         &Password::new("password123"),
+    ///         &Password::new("correct horse battery staple"),
+    ///         &request.project_config().password_validators,
+    ///         &request.project_config().password_hasher,
     ///     )
     ///     .await?;
     ///
@@ -196,7 +326,9 @@ impl DatabaseUser {
     ///     let user = DatabaseUser::create_user(
     ///         request.db(),
     ///         "testuser".to_string(),
-    ///         &Password::new("password123"),
+    ///         &Password::new("correct horse battery staple"),
+    ///         &request.project_config().password_validators,
+    ///         &request.project_config().password_hasher,
     ///     )
     ///     .await?;
     ///
@@ -238,12 +370,23 @@ impl DatabaseUser {
 
     /// Authenticate a user.
     ///
+    /// If the password is correct, but the stored hash was created with a
+    /// different algorithm or different parameters than `password_hasher`,
+    /// the password is rehashed with `password_hasher` and the user is saved
+    /// to the database. This way, the hashes are transparently upgraded when
+    /// the hasher configuration changes, or when users are imported from
+    /// another system.
+    ///
     /// # Errors
     ///
     /// Returns an error if there was an error querying the database.
+    ///
+    /// Returns an error if the stored hash had to be upgraded, but the
+    /// password could not be hashed with `password_hasher`.
     pub async fn authenticate<DB: DatabaseBackend>(
         db: &DB,
         credentials: &DatabaseUserCredentials,
+        password_hasher: &PasswordHasherConfig,
     ) -> Result<Option<Self>> {
         let username = credentials.username();
         let username_limited = LimitedString::<MAX_USERNAME_LENGTH>::new(username.to_string())
@@ -257,7 +400,7 @@ impl DatabaseUser {
 
         if let Some(mut user) = user {
             let password_hash = &user.password;
-            match password_hash.verify(credentials.password()) {
+            match password_hash.verify_with(credentials.password(), password_hasher)? {
                 PasswordVerificationResult::Ok => Ok(Some(user)),
                 PasswordVerificationResult::OkObsolete(new_hash) => {
                     user.password = new_hash;
@@ -272,8 +415,11 @@ impl DatabaseUser {
             // do something with the result to prevent the compiler from optimizing out the
             // operation.
             // TODO: benchmark this to make sure it works as expected
-            let dummy_hash = PasswordHash::from_password(credentials.password());
-            if let PasswordVerificationResult::Invalid = dummy_hash.verify(credentials.password()) {
+            let dummy_hash =
+                PasswordHash::from_password_with(credentials.password(), password_hasher)?;
+            if let PasswordVerificationResult::Invalid =
+                dummy_hash.verify_with(credentials.password(), password_hasher)?
+            {
                 unreachable!(
                     "Password hash verification should never fail for a newly generated hash"
                 );
@@ -297,7 +443,9 @@ impl DatabaseUser {
     ///     let user = DatabaseUser::create_user(
     ///         request.db(),
     ///         "testuser".to_string(),
-    ///         &Password::new("password123"),
+    ///         &Password::new("correct horse battery staple"),
+    ///         &request.project_config().password_validators,
+    ///         &request.project_config().password_hasher,
     ///     )
     ///     .await?;
     ///
@@ -343,7 +491,9 @@ impl DatabaseUser {
     ///     let user = DatabaseUser::create_user(
     ///         request.db(),
     ///         "testuser".to_string(),
-    ///         &Password::new("password123"),
+    ///         &Password::new("correct horse battery staple"),
+    ///         &request.project_config().password_validators,
+    ///         &request.project_config().password_hasher,
     ///     )
     ///     .await?;
     ///
@@ -370,6 +520,336 @@ impl DatabaseUser {
     pub fn username(&self) -> &str {
         &self.username
     }
+
+    /// Returns whether the user is a staff member, i.e. whether they can
+    /// access the admin panel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::auth::Password;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     &Password::new("password123"),
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    ///
+    /// assert!(!user.is_staff());
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn is_staff(&self) -> bool {
+        self.is_staff
+    }
+
+    /// Sets whether the user is a staff member.
+    ///
+    /// Note that this doesn't save the user to the database; use
+    /// [`Model::save`] to persist the change.
+    pub fn set_staff(&mut self, is_staff: bool) {
+        self.is_staff = is_staff;
+    }
+
+    /// Returns whether the user is a superuser, i.e. whether they have all the
+    /// permissions without them being explicitly granted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::auth::Password;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     &Password::new("password123"),
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    ///
+    /// assert!(!user.is_superuser());
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn is_superuser(&self) -> bool {
+        self.is_superuser
+    }
+
+    /// Sets whether the user is a superuser.
+    ///
+    /// Note that this doesn't save the user to the database; use
+    /// [`Model::save`] to persist the change.
+    pub fn set_superuser(&mut self, is_superuser: bool) {
+        self.is_superuser = is_superuser;
+    }
+
+    /// Returns whether the user account is active. Inactive users can't log
+    /// in, and their existing sessions are ended when they're deactivated.
+    ///
+    /// Users are active when created; deactivating a user instead of deleting
+    /// it keeps the objects referencing it.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    /// Sets whether the user account is active.
+    ///
+    /// Note that this doesn't save the user to the database; use
+    /// [`Model::save`] to persist the change.
+    pub fn set_active(&mut self, is_active: bool) {
+        self.is_active = is_active;
+    }
+
+    /// Sets the password of the user.
+    ///
+    /// The password is checked with the given validator first; typically,
+    /// this is
+    /// [`ProjectConfig::password_validators`](crate::config::ProjectConfig::password_validators).
+    /// It is then hashed with the given hasher, typically
+    /// [`ProjectConfig::password_hasher`](crate::config::ProjectConfig::password_hasher).
+    /// Setting the password invalidates all the sessions of the user, as well
+    /// as the password reset tokens generated for them. Note that this doesn't
+    /// save the user to the database; use [`Model::save`] to persist the
+    /// change.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password was rejected by the validator or
+    /// could not be hashed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::{DatabaseUser, DatabaseUserCredentials};
+    /// use cot::auth::password_validation::PasswordError;
+    /// use cot::auth::Password;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    /// use cot::db::Model;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let validators = PasswordValidators::recommended();
+    /// let hasher = PasswordHasherConfig::default();
+    /// let mut user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     &Password::new("correct horse battery"),
+    ///     &validators,
+    ///     &hasher,
+    /// )
+    /// .await?;
+    ///
+    /// assert!(matches!(
+    ///     user.set_password(&Password::new("qwerty"), &validators, &hasher),
+    ///     Err(PasswordError::TooShort(8))
+    /// ));
+    /// assert!(user
+    ///     .set_password(&Password::new("staple battery horse"), &validators, &hasher)
+    ///     .is_ok());
+    /// user.save(&*db).await?;
+    ///
+    /// let credentials = DatabaseUserCredentials::new(
+    ///     String::from("testuser"),
+    ///     Password::new("staple battery horse"),
+    /// );
+    /// assert!(DatabaseUser::authenticate(&*db, &credentials, &hasher)
+    ///     .await?
+    ///     .is_some());
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn set_password(
+        &mut self,
+        password: &Password,
+        password_validator: &dyn PasswordValidator,
+        password_hasher: &PasswordHasherConfig,
+    ) -> std::result::Result<(), PasswordError> {
+        password_validator.validate(password, Some(self))?;
+        self.password = PasswordHash::generate(password, password_hasher)
+            .map_err(PasswordError::HasherInvalid)?;
+
+        Ok(())
+    }
+
+    /// Sets the last login time of the user to the current time and saves
+    /// the user to the database.
+    ///
+    /// This is called by [`DatabaseUserBackend`] whenever the user is
+    /// authenticated successfully.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user could not be saved to the database.
+    pub async fn update_last_login<DB: DatabaseBackend>(&mut self, db: &DB) -> Result<()> {
+        self.last_login = Some(Utc::now().fixed_offset());
+        self.save(db).await.map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+
+    /// Grants a permission to the user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn add_permission<DB: DatabaseBackend>(
+        &self,
+        db: &DB,
+        permission: &Permission,
+    ) -> Result<()> {
+        let mut user_permission = UserPermission {
+            id: Auto::auto(),
+            user: ForeignKey::from(self),
+            permission: ForeignKey::from(permission),
+        };
+        user_permission
+            .insert(db)
+            .await
+            .map_err(AuthError::backend_error)
+    }
+
+    /// Adds the user to a group, granting them all the permissions of the
+    /// group.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn add_to_group<DB: DatabaseBackend>(&self, db: &DB, group: &Group) -> Result<()> {
+        let mut user_group = UserGroup {
+            id: Auto::auto(),
+            user: ForeignKey::from(self),
+            group: ForeignKey::from(group),
+        };
+        user_group
+            .insert(db)
+            .await
+            .map_err(AuthError::backend_error)
+    }
+
+    /// Returns the codenames of the permissions granted to the user, either
+    /// directly or through the groups they belong to.
+    ///
+    /// Note that superusers have all the permissions, regardless of the
+    /// permissions returned by this method.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::{DatabaseUser, Group, ModelPermission, Permission};
+    /// use cot::auth::Password;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     &Password::new("password123"),
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    /// let editors = Group::create(&*db, "editors").await?;
+    /// let permission = Permission::get_by_codename(
+    ///     &*db,
+    ///     &ModelPermission::Change.codename_for::<DatabaseUser>(),
+    /// )
+    /// .await?
+    /// .expect("model permissions are registered when running migrations");
+    /// editors.add_permission(&*db, &permission).await?;
+    /// user.add_to_group(&*db, &editors).await?;
+    ///
+    /// let permissions = user.permissions(&*db).await?;
+    /// assert!(permissions.contains("change_database_user"));
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn permissions<DB: DatabaseBackend>(&self, db: &DB) -> Result<HashSet<String>> {
+        let user = self;
+        let mut group_permissions = Query::<GroupPermission>::new();
+        group_permissions.filter(Expr::in_subquery(
+            Expr::field("group"),
+            "group",
+            query!(UserGroup, $user == user),
+        ));
+
+        // a single query selecting the permissions granted to the user directly
+        // or through the groups
+        let permissions = Permission::objects()
+            .filter(Expr::or(
+                Expr::in_subquery(
+                    Expr::field("id"),
+                    "permission",
+                    query!(UserPermission, $user == user),
+                ),
+                Expr::in_subquery(Expr::field("id"), "permission", &group_permissions),
+            ))
+            .all(db)
+            .await
+            .map_err(AuthError::backend_error)?
+            .into_iter()
+            .map(|permission| permission.codename.as_str().to_owned())
+            .collect();
+
+        Ok(permissions)
+    }
+
+    /// Loads the permissions granted to the user and returns the user along
+    /// with them, so that they can be checked with [`User::has_perm`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn with_permissions<DB: DatabaseBackend>(
+        self,
+        db: &DB,
+    ) -> Result<DatabaseUserWithPermissions> {
+        let permissions = self.permissions(db).await?;
+
+        Ok(DatabaseUserWithPermissions {
+            user: self,
+            permissions,
+        })
+    }
 }
 
 type SessionAuthHmac = Hmac<Sha512>;
@@ -384,13 +864,31 @@ impl User for DatabaseUser {
     }
 
     fn is_active(&self) -> bool {
-        true
+        self.is_active
     }
 
     fn is_authenticated(&self) -> bool {
         true
     }
 
+    fn is_staff(&self) -> bool {
+        self.is_staff
+    }
+
+    /// Returns whether the user has given permission.
+    ///
+    /// As the permissions are stored in the database, only superusers are
+    /// known to have any permissions here. Use
+    /// [`DatabaseUser::with_permissions`] to check the permissions granted to
+    /// the user explicitly.
+    fn has_perm(&self, _perm: &str) -> bool {
+        self.is_superuser
+    }
+
+    fn last_login(&self) -> Option<DateTime<FixedOffset>> {
+        self.last_login
+    }
+
     fn session_auth_hash(&self, secret_key: &SecretKey) -> Option<SessionAuthHash> {
         let mut mac = SessionAuthHmac::new_from_slice(secret_key.as_bytes())
             .expect("HMAC can take key of any size");
@@ -401,6 +899,197 @@ impl User for DatabaseUser {
     }
 }
 
+/// The form used to add a [`DatabaseUser`] in the admin panel.
+#[derive(Debug, Form)]
+struct DatabaseUserAddForm {
+    username: String,
+    password: crate::form::fields::Password,
+}
+
+/// The form used to change a [`DatabaseUser`] in the admin panel.
+#[derive(Debug, Form)]
+struct DatabaseUserChangeForm {
+    username: LimitedString<MAX_USERNAME_LENGTH>,
+    is_staff: bool,
+    is_superuser: bool,
+    is_active: bool,
+}
+
+/// The form used to set the password of a [`DatabaseUser`] in the admin panel.
+#[derive(Debug, Form)]
+struct DatabaseUserSetPasswordForm {
+    new_password: crate::form::fields::Password,
+    new_password_confirmation: crate::form::fields::Password,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "admin/set_password.html")]
+struct SetPasswordTemplate<'a> {
+    request: &'a Request,
+    user: &'a DatabaseUser,
+    form: <DatabaseUserSetPasswordForm as Form>::Context,
+}
+
+/// Sets the new password entered twice in a form, checking it with the
+/// project's password validators; the user is not saved.
+///
+/// Returns the errors to show in the form, along with the names of the fields
+/// they belong to, if the password was rejected.
+pub(crate) fn set_password_from_form(
+    request: &Request,
+    user: &mut DatabaseUser,
+    new_password: &str,
+    new_password_confirmation: &str,
+) -> Result<Vec<(&'static str, FormFieldValidationError)>> {
+    if new_password != new_password_confirmation {
+        return Ok(vec![(
+            "new_password_confirmation",
+            FormFieldValidationError::from_static("The two password fields didn't match"),
+        )]);
+    }
+
+    let password = Password::new(new_password);
+    let password_validators = &request.project_config().password_validators;
+    let errors = password_validators.validate_all(&password, Some(&*user));
+    if !errors.is_empty() {
+        return Ok(errors
+            .into_iter()
+            .map(|error| {
+                (
+                    "new_password",
+                    FormFieldValidationError::from_string(error.to_string()),
+                )
+            })
+            .collect());
+    }
+
+    user.set_password(
+        &password,
+        password_validators,
+        &request.project_config().password_hasher,
+    )
+    .map_err(AuthError::backend_error)?;
+
+    Ok(Vec::new())
+}
+
+/// The admin action setting the password of the selected user.
+///
+/// The action shows a form for the new password, which is submitted back to
+/// the action along with the ID of the user.
+async fn set_password(mut request: Request, ids: Vec<String>) -> crate::Result<AdminActionResult> {
+    let [id] = ids.as_slice() else {
+        return Ok(AdminActionResult::Message(String::from(
+            "Select a single user to set the password of.",
+        )));
+    };
+    let Some(mut user) = <DatabaseUser as AdminModel>::get_object_by_id(&request, id).await? else {
+        return Ok(AdminActionResult::Message(String::from(
+            "The selected user doesn't exist.",
+        )));
+    };
+
+    // the action is run from the list view first, without the password fields
+    let form_data = request.form_data().await?;
+    let submitted = crate::request::query_pairs(&form_data).any(|(key, _)| key == "new_password");
+    *request.body_mut() = crate::Body::fixed(form_data);
+
+    let context = if submitted {
+        match DatabaseUserSetPasswordForm::from_request(&mut request).await? {
+            FormResult::Ok(form) => {
+                let errors = set_password_from_form(
+                    &request,
+                    &mut user,
+                    form.new_password.as_str(),
+                    form.new_password_confirmation.as_str(),
+                )?;
+                if errors.is_empty() {
+                    user.save(request.db())
+                        .await
+                        .map_err(AuthError::backend_error)?;
+                    LogEntry::log(
+                        &mut request,
+                        <DatabaseUser as AdminModel>::url_name(),
+                        &user,
+                        LogAction::Change,
+                        &[],
+                    )
+                    .await?;
+                    return Ok(AdminActionResult::Message(format!(
+                        "The password of user `{}` was changed successfully.",
+                        user.username()
+                    )));
+                }
+
+                let mut context = DatabaseUserSetPasswordForm::build_context(&mut request).await?;
+                for (field, error) in errors {
+                    context.add_error(FormErrorTarget::Field(field), error);
+                }
+                context
+            }
+            FormResult::ValidationError(context) => context,
+        }
+    } else {
+        DatabaseUserSetPasswordForm::build_context(&mut request).await?
+    };
+
+    let template = SetPasswordTemplate {
+        request: &request,
+        user: &user,
+        form: context,
+    };
+    let title = format!("Set password: {}", user.username());
+    Ok(AdminActionResult::Response(crate::admin::render_page(
+        &request, &title, "", &template,
+    )?))
+}
+
+/// The admin action activating or deactivating the selected users.
+async fn set_users_active(
+    mut request: Request,
+    ids: Vec<String>,
+    is_active: bool,
+) -> crate::Result<AdminActionResult> {
+    let mut count = 0;
+    for id in &ids {
+        let Some(mut user) = <DatabaseUser as AdminModel>::get_object_by_id(&request, id).await?
+        else {
+            continue;
+        };
+        if user.is_active == is_active {
+            continue;
+        }
+
+        user.is_active = is_active;
+        user.save(request.db())
+            .await
+            .map_err(AuthError::backend_error)?;
+        LogEntry::log(
+            &mut request,
+            <DatabaseUser as AdminModel>::url_name(),
+            &user,
+            LogAction::Change,
+            &[FieldChange::new(
+                "is_active",
+                (!is_active).to_string(),
+                is_active.to_string(),
+            )],
+        )
+        .await?;
+        count += 1;
+    }
+
+    Ok(AdminActionResult::Message(format!(
+        "{count} {} {}.",
+        if count == 1 { "user was" } else { "users were" },
+        if is_active {
+            "activated"
+        } else {
+            "deactivated"
+        }
+    )))
+}
+
 #[async_trait]
 impl AdminModel for DatabaseUser {
     async fn get_objects(request: &Request) -> crate::Result<Vec<Self>> {
@@ -410,6 +1099,26 @@ impl AdminModel for DatabaseUser {
             .map_err(AuthError::backend_error)?)
     }
 
+    async fn get_list(
+        request: &Request,
+        params: &AdminListParams,
+    ) -> crate::Result<AdminList<Self>> {
+        let conditions = ["is_staff", "is_superuser", "is_active"]
+            .into_iter()
+            .filter_map(|field| bool::admin_filter_expr(field, params))
+            .collect();
+
+        get_model_list(request, params, conditions).await
+    }
+
+    async fn get_object_by_id(request: &Request, id: &str) -> crate::Result<Option<Self>> {
+        let Ok(id) = id.parse::<i64>() else {
+            return Ok(None);
+        };
+
+        Ok(DatabaseUser::get_by_id(request.db(), id).await?)
+    }
+
     fn name() -> &'static str {
         "DatabaseUser"
     }
@@ -418,34 +1127,626 @@ impl AdminModel for DatabaseUser {
         "database_user"
     }
 
+    fn object_id(&self) -> String {
+        self.id().to_string()
+    }
+
     fn display(&self) -> String {
-        format!("{self:?}")
+        self.username().to_owned()
     }
-}
 
-/// Credentials for authenticating a user stored in the database.
-///
-/// This struct is used to authenticate a user stored in the database. It
-/// contains the username and password of the user.
-///
-/// Can be passed to
-/// [`AuthRequestExt::authenticate`](crate::auth::AuthRequestExt::authenticate)
-/// to authenticate a user when using the [`DatabaseUserBackend`].
-#[derive(Debug, Clone)]
-pub struct DatabaseUserCredentials {
-    username: String,
-    password: Password,
-}
+    fn list_columns() -> &'static [&'static str] {
+        &[
+            "id",
+            "username",
+            "is_staff",
+            "is_superuser",
+            "is_active",
+            "last_login",
+        ]
+    }
 
-impl DatabaseUserCredentials {
-    /// Create a new instance of the database user credentials.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use cot::auth::db::DatabaseUserCredentials;
-    /// use cot::auth::Password;
-    ///
+    fn search_fields() -> &'static [&'static str] {
+        &["username"]
+    }
+
+    async fn filters(_request: &Request) -> crate::Result<Vec<AdminFilter>> {
+        Ok(vec![
+            AdminFilter::boolean("is_staff"),
+            AdminFilter::boolean("is_superuser"),
+            AdminFilter::boolean("is_active"),
+        ])
+    }
+
+    fn actions() -> Vec<AdminAction> {
+        vec![
+            AdminAction::delete_selected::<Self>(),
+            AdminAction::export_csv::<Self>(),
+            AdminAction::new("set_password", "Set password", set_password),
+            AdminAction::new("activate_users", "Activate selected", |request, ids| {
+                set_users_active(request, ids, true)
+            }),
+            AdminAction::new("deactivate_users", "Deactivate selected", |request, ids| {
+                set_users_active(request, ids, false)
+            }),
+        ]
+    }
+
+    fn read_only_fields() -> &'static [&'static str] {
+        &["last_login"]
+    }
+
+    fn field_value(&self, field: &str) -> Option<String> {
+        match field {
+            "id" => Some(self.id.admin_display()),
+            "username" => Some(self.username.admin_display()),
+            "is_staff" => Some(self.is_staff.admin_display()),
+            "is_superuser" => Some(self.is_superuser.admin_display()),
+            "is_active" => Some(self.is_active.admin_display()),
+            "last_login" => Some(self.last_login.admin_display()),
+            _ => None,
+        }
+    }
+
+    fn add_form() -> Option<AdminForm> {
+        Some(AdminForm::from_context(
+            &<DatabaseUserAddForm as Form>::Context::new(),
+        ))
+    }
+
+    async fn create_from_request(request: &mut Request) -> crate::Result<AdminFormResult<Self>> {
+        let form = match DatabaseUserAddForm::from_request(request).await? {
+            FormResult::Ok(form) => form,
+            FormResult::ValidationError(context) => {
+                return Ok(AdminFormResult::ValidationError(AdminForm::from_context(
+                    &context,
+                )));
+            }
+        };
+
+        let invalid_form = |field: &str, error: String| {
+            let mut context = <DatabaseUserAddForm as Form>::Context::new();
+            // the password is not filled in again, so it doesn't end up in the HTML
+            let _ = context.set_value("username", Cow::Borrowed(form.username.as_str()));
+            context.add_error(
+                FormErrorTarget::Field(field),
+                FormFieldValidationError::from_string(error),
+            );
+            AdminFormResult::ValidationError(AdminForm::from_context(&context))
+        };
+
+        if DatabaseUser::get_by_username(request.db(), &form.username)
+            .await?
+            .is_some()
+        {
+            return Ok(invalid_form(
+                "username",
+                String::from("a user with this username already exists"),
+            ));
+        }
+
+        let config = request.project_config();
+        match DatabaseUser::create_user(
+            request.db(),
+            form.username.as_str(),
+            Password::new(form.password.as_str()),
+            &config.password_validators,
+            &config.password_hasher,
+        )
+        .await
+        {
+            Ok(user) => Ok(AdminFormResult::Ok(user)),
+            Err(AuthError::UserBackend(error)) => match error.downcast_ref::<CreateUserError>() {
+                Some(error @ CreateUserError::UsernameTooLong(_)) => {
+                    Ok(invalid_form("username", error.to_string()))
+                }
+                Some(CreateUserError::Password(PasswordError::HasherInvalid(_))) | None => {
+                    Err(AuthError::UserBackend(error).into())
+                }
+                Some(error) => Ok(invalid_form("password", error.to_string())),
+            },
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn change_form(&self) -> Option<AdminForm> {
+        let mut context = <DatabaseUserChangeForm as Form>::Context::new();
+        let _ = context.set_value("username", Cow::Borrowed(self.username()));
+        let _ = context.set_value("is_staff", Cow::Owned(self.is_staff.to_string()));
+        let _ = context.set_value("is_superuser", Cow::Owned(self.is_superuser.to_string()));
+        let _ = context.set_value("is_active", Cow::Owned(self.is_active.to_string()));
+
+        Some(AdminForm::from_context(&context))
+    }
+
+    async fn update_from_request(
+        &mut self,
+        request: &mut Request,
+    ) -> crate::Result<AdminFormResult<()>> {
+        let form = match DatabaseUserChangeForm::from_request(request).await? {
+            FormResult::Ok(form) => form,
+            FormResult::ValidationError(context) => {
+                return Ok(AdminFormResult::ValidationError(AdminForm::from_context(
+                    &context,
+                )));
+            }
+        };
+
+        if form.username.as_str() != self.username()
+            && DatabaseUser::get_by_username(request.db(), form.username.as_str())
+                .await?
+                .is_some()
+        {
+            let mut context = <DatabaseUserChangeForm as Form>::Context::new();
+            let _ = context.set_value("username", Cow::Borrowed(form.username.as_str()));
+            let _ = context.set_value("is_staff", Cow::Owned(form.is_staff.to_string()));
+            let _ = context.set_value("is_superuser", Cow::Owned(form.is_superuser.to_string()));
+            let _ = context.set_value("is_active", Cow::Owned(form.is_active.to_string()));
+            context.add_error(
+                FormErrorTarget::Field("username"),
+                FormFieldValidationError::from_static("a user with this username already exists"),
+            );
+            return Ok(AdminFormResult::ValidationError(AdminForm::from_context(
+                &context,
+            )));
+        }
+
+        self.username = form.username;
+        self.is_staff = form.is_staff;
+        self.is_superuser = form.is_superuser;
+        self.is_active = form.is_active;
+        self.save(request.db())
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(AdminFormResult::Ok(()))
+    }
+
+    async fn delete(&self, request: &Request) -> crate::Result<()> {
+        let id = self.id();
+        query!(DatabaseUser, $id == id)
+            .delete(request.db())
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+}
+
+/// A [`DatabaseUser`] along with the codenames of all the permissions granted
+/// to it.
+///
+/// This is the user object returned by [`DatabaseUserBackend`], so that the
+/// permissions can be checked with [`User::has_perm`] without querying the
+/// database. It can be created with [`DatabaseUser::with_permissions`].
+#[derive(Debug, Clone)]
+pub struct DatabaseUserWithPermissions {
+    pub(crate) user: DatabaseUser,
+    pub(crate) permissions: HashSet<String>,
+}
+
+impl DatabaseUserWithPermissions {
+    /// Returns the user.
+    #[must_use]
+    pub fn user(&self) -> &DatabaseUser {
+        &self.user
+    }
+
+    /// Consumes the object and returns the user.
+    #[must_use]
+    pub fn into_user(self) -> DatabaseUser {
+        self.user
+    }
+
+    /// Returns the codenames of the permissions granted to the user, either
+    /// directly or through the groups they belong to.
+    #[must_use]
+    pub fn permissions(&self) -> &HashSet<String> {
+        &self.permissions
+    }
+}
+
+impl User for DatabaseUserWithPermissions {
+    fn id(&self) -> Option<UserId> {
+        User::id(&self.user)
+    }
+
+    fn username(&self) -> Option<Cow<'_, str>> {
+        User::username(&self.user)
+    }
+
+    fn is_active(&self) -> bool {
+        self.user.is_active()
+    }
+
+    fn is_authenticated(&self) -> bool {
+        self.user.is_authenticated()
+    }
+
+    fn is_staff(&self) -> bool {
+        self.user.is_staff
+    }
+
+    fn has_perm(&self, perm: &str) -> bool {
+        self.user.is_superuser || self.permissions.contains(perm)
+    }
+
+    fn last_login(&self) -> Option<DateTime<FixedOffset>> {
+        self.user.last_login()
+    }
+
+    fn session_auth_hash(&self, secret_key: &SecretKey) -> Option<SessionAuthHash> {
+        self.user.session_auth_hash(secret_key)
+    }
+}
+
+/// An error that occurs when creating a permission or a group.
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum CreatePermissionError {
+    /// The permission codename is too long.
+    #[error(
+        "permission codename is too long (max {MAX_PERMISSION_CODENAME_LENGTH} characters, got {0})"
+    )]
+    CodenameTooLong(usize),
+    /// The permission name is too long.
+    #[error("permission name is too long (max {MAX_PERMISSION_NAME_LENGTH} characters, got {0})")]
+    NameTooLong(usize),
+    /// The group name is too long.
+    #[error("group name is too long (max {MAX_GROUP_NAME_LENGTH} characters, got {0})")]
+    GroupNameTooLong(usize),
+}
+
+/// A permission stored in the database.
+///
+/// Permissions can be granted to users directly, with
+/// [`DatabaseUser::add_permission`], or through [`Group`]s. The add, change,
+/// delete and view permissions for every model (see [`ModelPermission`]) are
+/// registered automatically after the migrations are applied.
+#[derive(Debug, Clone)]
+#[model]
+pub struct Permission {
+    id: Auto<i64>,
+    #[model(unique)]
+    codename: LimitedString<MAX_PERMISSION_CODENAME_LENGTH>,
+    name: LimitedString<MAX_PERMISSION_NAME_LENGTH>,
+}
+
+impl Permission {
+    /// Create a new permission and save it to the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the codename or the name is too long.
+    ///
+    /// Returns an error if the permission could not be saved to the database.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::Permission;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let permission = Permission::create(&*db, "publish_article", "Can publish article").await?;
+    ///
+    /// assert_eq!(permission.codename(), "publish_article");
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn create<DB: DatabaseBackend, T: Into<String>, U: Into<String>>(
+        db: &DB,
+        codename: T,
+        name: U,
+    ) -> Result<Self> {
+        let codename = codename.into();
+        let codename_length = codename.len();
+        let codename = LimitedString::new(codename).map_err(|_| {
+            AuthError::backend_error(CreatePermissionError::CodenameTooLong(codename_length))
+        })?;
+        let name = name.into();
+        let name_length = name.len();
+        let name = LimitedString::new(name).map_err(|_| {
+            AuthError::backend_error(CreatePermissionError::NameTooLong(name_length))
+        })?;
+
+        let mut permission = Self {
+            id: Auto::auto(),
+            codename,
+            name,
+        };
+        permission
+            .insert(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(permission)
+    }
+
+    /// Get a permission by its codename. Returns [`None`] if the permission
+    /// does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn get_by_codename<DB: DatabaseBackend>(
+        db: &DB,
+        codename: &str,
+    ) -> Result<Option<Self>> {
+        let codename =
+            LimitedString::<MAX_PERMISSION_CODENAME_LENGTH>::new(codename).map_err(|_| {
+                AuthError::backend_error(CreatePermissionError::CodenameTooLong(codename.len()))
+            })?;
+        let permission = query!(Permission, $codename == codename)
+            .get(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(permission)
+    }
+
+    /// Get the ID of the permission.
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        match self.id {
+            Auto::Fixed(id) => id,
+            Auto::Auto => unreachable!("Permission constructed with an unknown ID"),
+        }
+    }
+
+    /// Get the codename of the permission, which is the string passed to
+    /// [`User::has_perm`].
+    #[must_use]
+    pub fn codename(&self) -> &str {
+        &self.codename
+    }
+
+    /// Get the human-readable name of the permission.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A group of users stored in the database.
+///
+/// All the permissions granted to a group are also granted to the users
+/// belonging to it.
+#[derive(Debug, Clone)]
+#[model]
+pub struct Group {
+    id: Auto<i64>,
+    #[model(unique)]
+    name: LimitedString<MAX_GROUP_NAME_LENGTH>,
+}
+
+impl Group {
+    /// Create a new group and save it to the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is too long.
+    ///
+    /// Returns an error if the group could not be saved to the database.
+    pub async fn create<DB: DatabaseBackend, T: Into<String>>(db: &DB, name: T) -> Result<Self> {
+        let name = name.into();
+        let name_length = name.len();
+        let name = LimitedString::new(name).map_err(|_| {
+            AuthError::backend_error(CreatePermissionError::GroupNameTooLong(name_length))
+        })?;
+
+        let mut group = Self {
+            id: Auto::auto(),
+            name,
+        };
+        group.insert(db).await.map_err(AuthError::backend_error)?;
+
+        Ok(group)
+    }
+
+    /// Get a group by its name. Returns [`None`] if the group does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn get_by_name<DB: DatabaseBackend>(db: &DB, name: &str) -> Result<Option<Self>> {
+        let name = LimitedString::<MAX_GROUP_NAME_LENGTH>::new(name).map_err(|_| {
+            AuthError::backend_error(CreatePermissionError::GroupNameTooLong(name.len()))
+        })?;
+        let group = query!(Group, $name == name)
+            .get(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(group)
+    }
+
+    /// Grants a permission to the group and all the users belonging to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn add_permission<DB: DatabaseBackend>(
+        &self,
+        db: &DB,
+        permission: &Permission,
+    ) -> Result<()> {
+        let mut group_permission = GroupPermission {
+            id: Auto::auto(),
+            group: ForeignKey::from(self),
+            permission: ForeignKey::from(permission),
+        };
+        group_permission
+            .insert(db)
+            .await
+            .map_err(AuthError::backend_error)
+    }
+
+    /// Get the ID of the group.
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        match self.id {
+            Auto::Fixed(id) => id,
+            Auto::Auto => unreachable!("Group constructed with an unknown ID"),
+        }
+    }
+
+    /// Get the name of the group.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Clone)]
+#[model]
+struct UserPermission {
+    id: Auto<i64>,
+    user: ForeignKey<DatabaseUser>,
+    permission: ForeignKey<Permission>,
+}
+
+#[derive(Debug, Clone)]
+#[model]
+struct UserGroup {
+    id: Auto<i64>,
+    user: ForeignKey<DatabaseUser>,
+    group: ForeignKey<Group>,
+}
+
+#[derive(Debug, Clone)]
+#[model]
+struct GroupPermission {
+    id: Auto<i64>,
+    group: ForeignKey<Group>,
+    permission: ForeignKey<Permission>,
+}
+
+/// A permission that is registered automatically for every model.
+///
+/// # Example
+///
+/// ```
+/// use cot::auth::db::{DatabaseUser, ModelPermission};
+///
+/// assert_eq!(
+///     ModelPermission::View.codename_for::<DatabaseUser>(),
+///     "view_database_user"
+/// );
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ModelPermission {
+    /// Permission to create new instances of the model.
+    Add,
+    /// Permission to modify the existing instances of the model.
+    Change,
+    /// Permission to delete instances of the model.
+    Delete,
+    /// Permission to view instances of the model.
+    View,
+}
+
+impl ModelPermission {
+    /// All the model permissions.
+    pub const ALL: [Self; 4] = [Self::Add, Self::Change, Self::Delete, Self::View];
+
+    /// Returns the name of the action the permission allows, e.g. `"add"`.
+    #[must_use]
+    pub fn action(self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Change => "change",
+            Self::Delete => "delete",
+            Self::View => "view",
+        }
+    }
+
+    /// Returns the codename of the permission for given model, e.g.
+    /// `"add_database_user"`.
+    #[must_use]
+    pub fn codename_for<M: Model>(self) -> String {
+        self.codename(M::TABLE_NAME.as_str())
+    }
+
+    #[must_use]
+    fn codename(self, table_name: &str) -> String {
+        format!("{}_{table_name}", self.action())
+    }
+
+    #[must_use]
+    fn name(self, table_name: &str) -> String {
+        format!("Can {} {}", self.action(), table_name.replace('_', " "))
+    }
+}
+
+/// Creates the [`ModelPermission`]s for all the models created by given
+/// migrations, unless they already exist.
+///
+/// Does nothing unless the migrations include the ones creating the permission
+/// table, i.e. when [`DatabaseUserApp`] is not used.
+pub(crate) async fn register_model_permissions<DB: DatabaseBackend, T: DynMigration + Sync>(
+    db: &DB,
+    migrations: &[T],
+) -> Result<()> {
+    if !migrations
+        .iter()
+        .any(|migration| migration.app_name() == MIGRATIONS_APP_NAME)
+    {
+        return Ok(());
+    }
+
+    let mut codenames: HashSet<_> = Permission::objects()
+        .all(db)
+        .await
+        .map_err(AuthError::backend_error)?
+        .into_iter()
+        .map(|permission| permission.codename.as_str().to_owned())
+        .collect();
+    let table_names = migrations
+        .iter()
+        .flat_map(DynMigration::operations)
+        .filter_map(Operation::created_table_name);
+    for table_name in table_names {
+        for model_permission in ModelPermission::ALL {
+            let codename = model_permission.codename(table_name.as_str());
+            if codenames.insert(codename.clone()) {
+                Permission::create(db, codename, model_permission.name(table_name.as_str()))
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Credentials for authenticating a user stored in the database.
+///
+/// This struct is used to authenticate a user stored in the database. It
+/// contains the username and password of the user.
+///
+/// Can be passed to
+/// [`AuthRequestExt::authenticate`](crate::auth::AuthRequestExt::authenticate)
+/// to authenticate a user when using the [`DatabaseUserBackend`].
+#[derive(Debug, Clone)]
+pub struct DatabaseUserCredentials {
+    username: String,
+    password: Password,
+}
+
+impl DatabaseUserCredentials {
+    /// Create a new instance of the database user credentials.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::auth::db::DatabaseUserCredentials;
+    /// use cot::auth::Password;
+    ///
     /// let credentials =
     ///     DatabaseUserCredentials::new(String::from("testuser"), Password::new("password123"));
     /// ```
@@ -496,6 +1797,8 @@ impl DatabaseUserCredentials {
 ///
 /// This backend supports authenticating users using the
 /// [`DatabaseUserCredentials`] struct and ignores all other credential types.
+/// [Inactive](DatabaseUser::is_active) users are never authenticated, and are
+/// logged out once they're deactivated.
 #[derive(Debug, Copy, Clone)]
 pub struct DatabaseUserBackend;
 
@@ -539,10 +1842,18 @@ impl AuthBackend for DatabaseUserBackend {
         credentials: &(dyn Any + Send + Sync),
     ) -> Result<Option<Box<dyn User + Send + Sync>>> {
         if let Some(credentials) = credentials.downcast_ref::<DatabaseUserCredentials>() {
-            #[allow(trivial_casts)] // Upcast to the correct Box type
-            Ok(DatabaseUser::authenticate(request.db(), credentials)
-                .await
-                .map(|user| user.map(|user| Box::new(user) as Box<dyn User + Send + Sync>))?)
+            let password_hasher = &request.project_config().password_hasher;
+            let Some(mut user) =
+                DatabaseUser::authenticate(request.db(), credentials, password_hasher)
+                    .await?
+                    .filter(DatabaseUser::is_active)
+            else {
+                return Ok(None);
+            };
+            user.update_last_login(request.db()).await?;
+            let user = user.with_permissions(request.db()).await?;
+
+            Ok(Some(Box::new(user)))
         } else {
             Err(AuthError::CredentialsTypeNotSupported)
         }
@@ -557,10 +1868,41 @@ impl AuthBackend for DatabaseUserBackend {
             return Err(AuthError::UserIdTypeNotSupported);
         };
 
-        #[allow(trivial_casts)] // Upcast to the correct Box type
-        Ok(DatabaseUser::get_by_id(request.db(), id)
+        // deactivated users are logged out
+        let Some(user) = DatabaseUser::get_by_id(request.db(), id)
             .await?
-            .map(|user| Box::new(user) as Box<dyn User + Send + Sync>))
+            .filter(DatabaseUser::is_active)
+        else {
+            return Ok(None);
+        };
+        let user = user.with_permissions(request.db()).await?;
+
+        Ok(Some(Box::new(user)))
+    }
+
+    async fn requires_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+    ) -> Result<bool> {
+        let Some(UserId::Int(id)) = user.id() else {
+            return Ok(false);
+        };
+
+        two_factor::is_enabled_for(request.db(), ForeignKey::PrimaryKey(Auto::fixed(id))).await
+    }
+
+    async fn verify_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+        code: &str,
+    ) -> Result<bool> {
+        let Some(UserId::Int(id)) = user.id() else {
+            return Ok(false);
+        };
+
+        two_factor::verify_for(request.db(), ForeignKey::PrimaryKey(Auto::fixed(id)), code).await
     }
 }
 
@@ -615,7 +1957,11 @@ impl App for DatabaseUserApp {
     }
 
     fn admin_model_managers(&self) -> Vec<Box<dyn AdminModelManager>> {
-        vec![Box::new(DefaultAdminModelManager::<DatabaseUser>::new())]
+        vec![
+            Box::new(DefaultAdminModelManager::<DatabaseUser>::new()),
+            Box::new(DefaultAdminModelManager::<ApiToken>::new()),
+            Box::new(DefaultAdminModelManager::<TotpDevice>::new()),
+        ]
     }
 
     fn migrations(&self) -> Vec<Box<SyncDynMigration>> {
@@ -632,7 +1978,7 @@ impl App for DatabaseUserApp {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::SecretKey;
+    use crate::config::{PasswordHasherConfig, PasswordValidators, SecretKey};
     use crate::db::MockDatabaseBackend;
 
     #[test]
@@ -641,7 +1987,7 @@ mod tests {
         let user = DatabaseUser::new(
             Auto::fixed(1),
             LimitedString::new("testuser").unwrap(),
-            &Password::new("password123"),
+            PasswordHash::from_password(&Password::new("password123")),
         );
         let secret_key = SecretKey::new(b"supersecretkey");
 
@@ -655,7 +2001,7 @@ mod tests {
         let user = DatabaseUser::new(
             Auto::fixed(1),
             LimitedString::new("testuser").unwrap(),
-            &Password::new("password123"),
+            PasswordHash::from_password(&Password::new("password123")),
         );
         let user_ref: &dyn User = &user;
         assert_eq!(user_ref.id(), Some(UserId::Int(1)));
@@ -667,6 +2013,56 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn database_user_has_perm() {
+        let mut user = DatabaseUser::new(
+            Auto::fixed(1),
+            LimitedString::new("testuser").unwrap(),
+            PasswordHash::from_password(&Password::new("password123")),
+        );
+        assert!(!user.is_staff());
+        assert!(!user.is_superuser());
+        assert!(!User::has_perm(&user, "view_database_user"));
+
+        user.set_staff(true);
+        user.set_superuser(true);
+        assert!(user.is_staff());
+        assert!(User::has_perm(&user, "view_database_user"));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn database_user_with_permissions_traits() {
+        let user = DatabaseUserWithPermissions {
+            user: DatabaseUser::new(
+                Auto::fixed(1),
+                LimitedString::new("testuser").unwrap(),
+                PasswordHash::from_password(&Password::new("password123")),
+            ),
+            permissions: HashSet::from(["view_database_user".to_string()]),
+        };
+        let user_ref: &dyn User = &user;
+        assert_eq!(user_ref.id(), Some(UserId::Int(1)));
+        assert_eq!(user_ref.username(), Some(Cow::from("testuser")));
+        assert!(user_ref.is_authenticated());
+        assert!(user_ref.has_perm("view_database_user"));
+        assert!(!user_ref.has_perms(&["view_database_user", "add_database_user"]));
+    }
+
+    #[test]
+    fn model_permission_codename() {
+        assert_eq!(
+            ModelPermission::Add.codename_for::<DatabaseUser>(),
+            "add_database_user"
+        );
+        assert_eq!(ModelPermission::Delete.codename("group"), "delete_group");
+        assert_eq!(
+            ModelPermission::Change.name("database_user"),
+            "Can change database user"
+        );
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn create_user() {
@@ -678,12 +2074,97 @@ mod tests {
         let username = "testuser".to_string();
         let password = Password::new("password123");
 
-        let user = DatabaseUser::create_user(&mock_db, username.clone(), &password)
-            .await
-            .unwrap();
+        let user = DatabaseUser::create_user(
+            &mock_db,
+            username.clone(),
+            &password,
+            &PasswordValidators::none(),
+            &PasswordHasherConfig::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(user.username(), username);
     }
 
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn create_user_invalid_password() {
+        let mock_db = MockDatabaseBackend::new();
+
+        let error = DatabaseUser::create_user(
+            &mock_db,
+            "testuser",
+            &Password::new("password123"),
+            &PasswordValidators::recommended(),
+            &PasswordHasherConfig::default(),
+        )
+        .await
+        .unwrap_err();
+        let AuthError::UserBackend(error) = error else {
+            panic!("Unexpected error: {error:?}");
+        };
+        assert!(matches!(
+            error.downcast_ref::<CreateUserError>(),
+            Some(CreateUserError::Password(PasswordError::TooCommon))
+        ));
+    }
+
+    #[test]
+    fn new_with_username_validates_before_hashing() {
+        let invalid_hasher = PasswordHasherConfig::Pbkdf2 { iterations: 0 };
+        let create_user_error = |password: &str| {
+            let error = DatabaseUser::new_with_username(
+                String::from("testuser"),
+                &Password::new(password),
+                &PasswordValidators::recommended(),
+                &invalid_hasher,
+            )
+            .unwrap_err();
+            let AuthError::UserBackend(error) = error else {
+                panic!("Unexpected error: {error:?}");
+            };
+            error.downcast_ref::<CreateUserError>().unwrap().clone()
+        };
+
+        assert!(matches!(
+            create_user_error("password123"),
+            CreateUserError::Password(PasswordError::TooCommon)
+        ));
+        assert!(matches!(
+            create_user_error("correct horse battery staple"),
+            CreateUserError::Password(PasswordError::HasherInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn set_password() {
+        let mut user = DatabaseUser::new(
+            Auto::fixed(1),
+            LimitedString::new("testuser").unwrap(),
+            PasswordHash::from_password(&Password::new("password123")),
+        );
+
+        assert!(matches!(
+            user.set_password(
+                &Password::new("testuser1"),
+                &PasswordValidators::recommended(),
+                &PasswordHasherConfig::default(),
+            ),
+            Err(PasswordError::TooSimilar)
+        ));
+        assert!(user
+            .set_password(
+                &Password::new("new password"),
+                &PasswordValidators::recommended(),
+                &PasswordHasherConfig::default(),
+            )
+            .is_ok());
+        assert!(matches!(
+            user.password.verify(&Password::new("new password")),
+            PasswordVerificationResult::Ok
+        ));
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn get_by_id() {
@@ -691,7 +2172,7 @@ mod tests {
         let user = DatabaseUser::new(
             Auto::fixed(1),
             LimitedString::new("testuser").unwrap(),
-            &Password::new("password123"),
+            PasswordHash::from_password(&Password::new("password123")),
         );
 
         mock_db
@@ -710,7 +2191,7 @@ mod tests {
         let user = DatabaseUser::new(
             Auto::fixed(1),
             LimitedString::new("testuser").unwrap(),
-            &Password::new("password123"),
+            PasswordHash::from_password(&Password::new("password123")),
         );
 
         mock_db
@@ -719,9 +2200,10 @@ mod tests {
 
         let credentials =
             DatabaseUserCredentials::new("testuser".to_string(), Password::new("password123"));
-        let result = DatabaseUser::authenticate(&mock_db, &credentials)
-            .await
-            .unwrap();
+        let result =
+            DatabaseUser::authenticate(&mock_db, &credentials, &PasswordHasherConfig::default())
+                .await
+                .unwrap();
         assert!(result.is_some());
         assert_eq!(result.unwrap().username(), "testuser");
     }
@@ -737,9 +2219,10 @@ mod tests {
 
         let credentials =
             DatabaseUserCredentials::new("testuser".to_string(), Password::new("password123"));
-        let result = DatabaseUser::authenticate(&mock_db, &credentials)
-            .await
-            .unwrap();
+        let result =
+            DatabaseUser::authenticate(&mock_db, &credentials, &PasswordHasherConfig::default())
+                .await
+                .unwrap();
         assert!(result.is_none());
     }
 
@@ -750,7 +2233,7 @@ mod tests {
         let user = DatabaseUser::new(
             Auto::fixed(1),
             LimitedString::new("testuser").unwrap(),
-            &Password::new("password123"),
+            PasswordHash::from_password(&Password::new("password123")),
         );
 
         mock_db
@@ -759,9 +2242,47 @@ mod tests {
 
         let credentials =
             DatabaseUserCredentials::new("testuser".to_string(), Password::new("invalid"));
-        let result = DatabaseUser::authenticate(&mock_db, &credentials)
-            .await
-            .unwrap();
+        let result =
+            DatabaseUser::authenticate(&mock_db, &credentials, &PasswordHasherConfig::default())
+                .await
+                .unwrap();
         assert!(result.is_none());
     }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn authenticate_rehashes_obsolete_hash() {
+        let mut mock_db = MockDatabaseBackend::new();
+        // a hash imported from a system using PBKDF2
+        let user = DatabaseUser::new(
+            Auto::fixed(1),
+            LimitedString::new("testuser").unwrap(),
+            PasswordHash::new(
+                "$pbkdf2-sha256$i=4096,l=32$c2FsdA$xeR41ZKIyEGqUw22hFxMjZYok6ABzk4RpJY4c6qYE0o",
+            )
+            .unwrap(),
+        );
+
+        mock_db
+            .expect_get::<DatabaseUser>()
+            .returning(move |_| Ok(Some(user.clone())));
+        mock_db
+            .expect_insert_or_update::<DatabaseUser>()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let credentials =
+            DatabaseUserCredentials::new("testuser".to_string(), Password::new("password"));
+        let user =
+            DatabaseUser::authenticate(&mock_db, &credentials, &PasswordHasherConfig::default())
+                .await
+                .unwrap()
+                .unwrap();
+
+        assert!(user.password.as_str().starts_with("$argon2id$"));
+        assert!(matches!(
+            user.password.verify(&Password::new("password")),
+            PasswordVerificationResult::Ok
+        ));
+    }
 }
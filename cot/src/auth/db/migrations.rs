@@ -1,3 +1,15 @@
 mod m_0001_initial;
+mod m_0002_permissions;
+mod m_0003_last_login;
+mod m_0004_api_tokens;
+mod m_0005_two_factor;
+mod m_0006_is_active;
 
-pub const MIGRATIONS: &[&::cot::db::migrations::SyncDynMigration] = &[&m_0001_initial::Migration];
+pub const MIGRATIONS: &[&::cot::db::migrations::SyncDynMigration] = &[
+    &m_0001_initial::Migration,
+    &m_0002_permissions::Migration,
+    &m_0003_last_login::Migration,
+    &m_0004_api_tokens::Migration,
+    &m_0005_two_factor::Migration,
+    &m_0006_is_active::Migration,
+];
@@ -0,0 +1,84 @@
+//! Generated by cot CLI 0.1.0 on 2026-10-18 12:00:00+00:00
+
+#[derive(Debug, Copy, Clone)]
+pub(super) struct Migration;
+impl ::cot::db::migrations::Migration for Migration {
+    const APP_NAME: &'static str = "cot_auth";
+    const MIGRATION_NAME: &'static str = "m_0003_last_login";
+    const DEPENDENCIES: &'static [::cot::db::migrations::MigrationDependency] =
+        &[::cot::db::migrations::MigrationDependency::migration(
+            "cot_auth",
+            "m_0002_permissions",
+        )];
+    const OPERATIONS: &'static [::cot::db::migrations::Operation] = &[
+        ::cot::db::migrations::Operation::add_field()
+            .table_name(::cot::db::Identifier::new("database_user"))
+            .field(
+                ::cot::db::migrations::Field::new(
+                        ::cot::db::Identifier::new("last_login"),
+                        <Option<
+                            chrono::DateTime<chrono::FixedOffset>,
+                        > as ::cot::db::DatabaseField>::TYPE,
+                    )
+                    .set_null(
+                        <Option<
+                            chrono::DateTime<chrono::FixedOffset>,
+                        > as ::cot::db::DatabaseField>::NULLABLE,
+                    ),
+            )
+            .build(),
+    ];
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _DatabaseUser {
+    id: i64,
+    #[model(unique)]
+    username: crate::db::LimitedString<{ crate::auth::db::MAX_USERNAME_LENGTH }>,
+    password: crate::auth::PasswordHash,
+    is_staff: bool,
+    is_superuser: bool,
+    last_login: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _Permission {
+    id: i64,
+    #[model(unique)]
+    codename: crate::db::LimitedString<{ crate::auth::db::MAX_PERMISSION_CODENAME_LENGTH }>,
+    name: crate::db::LimitedString<{ crate::auth::db::MAX_PERMISSION_NAME_LENGTH }>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _Group {
+    id: i64,
+    #[model(unique)]
+    name: crate::db::LimitedString<{ crate::auth::db::MAX_GROUP_NAME_LENGTH }>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _UserPermission {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    permission: crate::db::ForeignKey<crate::auth::db::Permission>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _UserGroup {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    group: crate::db::ForeignKey<crate::auth::db::Group>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _GroupPermission {
+    id: i64,
+    group: crate::db::ForeignKey<crate::auth::db::Group>,
+    permission: crate::db::ForeignKey<crate::auth::db::Permission>,
+}
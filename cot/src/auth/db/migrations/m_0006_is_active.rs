@@ -0,0 +1,115 @@
+//! Generated by cot CLI 0.1.0 on 2026-10-18 12:00:00+00:00
+
+#[derive(Debug, Copy, Clone)]
+pub(super) struct Migration;
+impl ::cot::db::migrations::Migration for Migration {
+    const APP_NAME: &'static str = "cot_auth";
+    const MIGRATION_NAME: &'static str = "m_0006_is_active";
+    const DEPENDENCIES: &'static [::cot::db::migrations::MigrationDependency] =
+        &[::cot::db::migrations::MigrationDependency::migration(
+            "cot_auth",
+            "m_0005_two_factor",
+        )];
+    const OPERATIONS: &'static [::cot::db::migrations::Operation] =
+        &[::cot::db::migrations::Operation::add_field()
+            .table_name(::cot::db::Identifier::new("database_user"))
+            .field(
+                ::cot::db::migrations::Field::new(
+                    ::cot::db::Identifier::new("is_active"),
+                    <bool as ::cot::db::DatabaseField>::TYPE,
+                )
+                .set_null(<bool as ::cot::db::DatabaseField>::NULLABLE)
+                .default_value(::cot::db::migrations::FieldDefault::Bool(true)),
+            )
+            .build()];
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _DatabaseUser {
+    id: i64,
+    #[model(unique)]
+    username: crate::db::LimitedString<{ crate::auth::db::MAX_USERNAME_LENGTH }>,
+    password: crate::auth::PasswordHash,
+    is_staff: bool,
+    is_superuser: bool,
+    last_login: Option<chrono::DateTime<chrono::FixedOffset>>,
+    is_active: bool,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _Permission {
+    id: i64,
+    #[model(unique)]
+    codename: crate::db::LimitedString<{ crate::auth::db::MAX_PERMISSION_CODENAME_LENGTH }>,
+    name: crate::db::LimitedString<{ crate::auth::db::MAX_PERMISSION_NAME_LENGTH }>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _Group {
+    id: i64,
+    #[model(unique)]
+    name: crate::db::LimitedString<{ crate::auth::db::MAX_GROUP_NAME_LENGTH }>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _UserPermission {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    permission: crate::db::ForeignKey<crate::auth::db::Permission>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _UserGroup {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    group: crate::db::ForeignKey<crate::auth::db::Group>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _GroupPermission {
+    id: i64,
+    group: crate::db::ForeignKey<crate::auth::db::Group>,
+    permission: crate::db::ForeignKey<crate::auth::db::Permission>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _ApiToken {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    name: crate::db::LimitedString<{ crate::auth::api_token::MAX_API_TOKEN_NAME_LENGTH }>,
+    #[model(unique)]
+    token_hash: crate::db::LimitedString<{ crate::auth::api_token::API_TOKEN_HASH_LENGTH }>,
+    token_prefix: crate::db::LimitedString<{ crate::auth::api_token::API_TOKEN_PREFIX_LENGTH }>,
+    scopes: String,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+    expires_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    last_used_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _TotpDevice {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    name: crate::db::LimitedString<{ crate::auth::two_factor::MAX_TOTP_DEVICE_NAME_LENGTH }>,
+    secret: crate::db::LimitedString<{ crate::auth::two_factor::TOTP_SECRET_LENGTH }>,
+    confirmed: bool,
+    last_used_step: i64,
+    created_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+#[derive(::core::fmt::Debug)]
+#[::cot::db::model(model_type = "migration")]
+struct _RecoveryCode {
+    id: i64,
+    user: crate::db::ForeignKey<crate::auth::db::DatabaseUser>,
+    code_hash: crate::db::LimitedString<{ crate::auth::two_factor::RECOVERY_CODE_HASH_LENGTH }>,
+    used_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
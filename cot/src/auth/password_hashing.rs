@@ -0,0 +1,476 @@
+//! Password hashing algorithms supported by [`PasswordHash`].
+//!
+//! Hashes are stored in the [PHC string format]. The algorithms are
+//! implemented by the `argon2`, `scrypt` and `pbkdf2` crates, and the hashes
+//! are compatible with the ones generated by other implementations using the
+//! same PHC format, so that users imported from other systems can log in.
+//!
+//! As the hashes of imported users are not generated by Cot, the cost
+//! parameters of the hashes are limited, so that a hash
+//! can't make verifying a password take an unreasonable amount of time or
+//! memory.
+//!
+//! [`PasswordHash`]: crate::auth::PasswordHash
+//! [PHC string format]: https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+
+use argon2::Argon2;
+use password_hash::errors::InvalidValue;
+use password_hash::rand_core::OsRng;
+use password_hash::{Ident, Output, ParamsString, PasswordVerifier, SaltString};
+use sha2_digest_0_10::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::config::PasswordHasherConfig;
+
+const PBKDF2_SHA256_IDENT: &str = "pbkdf2-sha256";
+const SCRYPT_IDENT: &str = "scrypt";
+const OUTPUT_LENGTH: u32 = 32;
+const MAX_SALT_LENGTH: usize = 64;
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+/// The maximum memory used by Argon2, in kibibytes (1 gibibyte).
+const MAX_ARGON2_MEMORY_COST: u32 = 1 << 20;
+const MAX_ARGON2_TIME_COST: u32 = 100;
+const MAX_ARGON2_PARALLELISM: u32 = 16;
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_BLOCK_SIZE: u32 = 32;
+const MAX_SCRYPT_PARALLELISM: u32 = 16;
+/// The maximum memory used by scrypt, which is `128 * r * N` bytes.
+const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+
+/// An algorithm of a parsed password hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Argon2(argon2::Algorithm),
+    Scrypt,
+    Pbkdf2Sha256,
+}
+
+impl Algorithm {
+    fn from_ident(ident: Ident<'_>) -> password_hash::Result<Self> {
+        match ident.as_str() {
+            SCRYPT_IDENT => Ok(Self::Scrypt),
+            PBKDF2_SHA256_IDENT => Ok(Self::Pbkdf2Sha256),
+            _ => argon2::Algorithm::try_from(ident).map(Self::Argon2),
+        }
+    }
+}
+
+fn parse(hash: &str) -> password_hash::Result<(password_hash::PasswordHash<'_>, Algorithm)> {
+    let hash = password_hash::PasswordHash::new(hash)?;
+    let algorithm = Algorithm::from_ident(hash.algorithm)?;
+    if hash.salt.is_none() || hash.hash.is_none() {
+        return Err(password_hash::Error::PhcStringField);
+    }
+    match algorithm {
+        Algorithm::Argon2(_) => {
+            let params = argon2::Params::try_from(&hash)?;
+            check_argon2_cost(params.m_cost(), params.t_cost(), params.p_cost())?;
+        }
+        Algorithm::Scrypt => {
+            let (log_n, block_size, parallelism) = scrypt_params(&hash)?;
+            check_scrypt_cost(log_n, block_size, parallelism)?;
+        }
+        Algorithm::Pbkdf2Sha256 => check_pbkdf2_cost(decimal_param(&hash, "i")?)?,
+    }
+
+    Ok((hash, algorithm))
+}
+
+fn scrypt_params(hash: &password_hash::PasswordHash<'_>) -> password_hash::Result<(u8, u32, u32)> {
+    let log_n = decimal_param(hash, "ln")?;
+    let log_n = u8::try_from(log_n).map_err(|_| InvalidValue::TooLong.param_error())?;
+
+    Ok((log_n, decimal_param(hash, "r")?, decimal_param(hash, "p")?))
+}
+
+fn check_argon2_cost(
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+) -> password_hash::Result<()> {
+    if memory_cost > MAX_ARGON2_MEMORY_COST
+        || time_cost > MAX_ARGON2_TIME_COST
+        || parallelism > MAX_ARGON2_PARALLELISM
+    {
+        Err(InvalidValue::TooLong.param_error())
+    } else {
+        Ok(())
+    }
+}
+
+fn check_scrypt_cost(log_n: u8, block_size: u32, parallelism: u32) -> password_hash::Result<()> {
+    if log_n == 0 || block_size == 0 || parallelism == 0 {
+        Err(InvalidValue::TooShort.param_error())
+    } else if log_n > MAX_SCRYPT_LOG_N
+        || block_size > MAX_SCRYPT_BLOCK_SIZE
+        || parallelism > MAX_SCRYPT_PARALLELISM
+        || (128 * u64::from(block_size)) << log_n > MAX_SCRYPT_MEMORY
+    {
+        Err(InvalidValue::TooLong.param_error())
+    } else {
+        Ok(())
+    }
+}
+
+fn check_pbkdf2_cost(iterations: u32) -> password_hash::Result<()> {
+    if iterations == 0 {
+        Err(InvalidValue::TooShort.param_error())
+    } else if iterations > MAX_PBKDF2_ITERATIONS {
+        Err(InvalidValue::TooLong.param_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks whether the hash is a valid PHC string of one of the supported
+/// algorithms, with the cost parameters within the limits.
+pub(crate) fn validate_hash(hash: &str) -> password_hash::Result<()> {
+    parse(hash).map(|_| ())
+}
+
+/// Hashes the password with a random salt using the algorithm and parameters
+/// given in the config.
+pub(crate) fn generate_hash(
+    password: &[u8],
+    config: &PasswordHasherConfig,
+) -> password_hash::Result<String> {
+    let salt = SaltString::generate(OsRng);
+
+    match *config {
+        PasswordHasherConfig::Argon2 {
+            memory_cost,
+            time_cost,
+            parallelism,
+        } => {
+            check_argon2_cost(memory_cost, time_cost, parallelism)?;
+            let params = argon2::Params::new(memory_cost, time_cost, parallelism, None)?;
+            let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+            password_hash::PasswordHasher::hash_password(&argon2, password, &salt)
+                .map(|hash| hash.to_string())
+        }
+        PasswordHasherConfig::Scrypt {
+            log_n,
+            r: block_size,
+            p: parallelism,
+        } => {
+            let mut salt_buf = [0; MAX_SALT_LENGTH];
+            let salt_bytes = salt.decode_b64(&mut salt_buf)?;
+            let output = Output::init_with(OUTPUT_LENGTH as usize, |out| {
+                scrypt(password, salt_bytes, log_n, block_size, parallelism, out)
+            })?;
+
+            let mut params = ParamsString::new();
+            params.add_decimal("ln", u32::from(log_n))?;
+            params.add_decimal("r", block_size)?;
+            params.add_decimal("p", parallelism)?;
+            Ok(phc_string(SCRYPT_IDENT, params, &salt, output))
+        }
+        PasswordHasherConfig::Pbkdf2 { iterations } => {
+            let mut salt_buf = [0; MAX_SALT_LENGTH];
+            let salt_bytes = salt.decode_b64(&mut salt_buf)?;
+            let output = Output::init_with(OUTPUT_LENGTH as usize, |out| {
+                pbkdf2_hmac_sha256(password, salt_bytes, iterations, out)
+            })?;
+
+            let mut params = ParamsString::new();
+            params.add_decimal("i", iterations)?;
+            params.add_decimal("l", OUTPUT_LENGTH)?;
+            Ok(phc_string(PBKDF2_SHA256_IDENT, params, &salt, output))
+        }
+    }
+}
+
+fn phc_string(ident: &str, params: ParamsString, salt: &SaltString, output: Output) -> String {
+    password_hash::PasswordHash {
+        algorithm: Ident::new_unwrap(ident),
+        version: None,
+        params,
+        salt: Some(salt.as_salt()),
+        hash: Some(output),
+    }
+    .to_string()
+}
+
+/// Verifies the password against the hash.
+///
+/// Returns `Ok(false)` if the password doesn't match the hash, and an error
+/// if the hash is invalid.
+pub(crate) fn verify_password(password: &[u8], hash: &str) -> password_hash::Result<bool> {
+    let (hash, algorithm) = parse(hash)?;
+
+    let result = match algorithm {
+        Algorithm::Argon2(_) => Argon2::default().verify_password(password, &hash),
+        Algorithm::Scrypt => {
+            let (log_n, block_size, parallelism) = scrypt_params(&hash)?;
+            verify_output(&hash, |salt, out| {
+                scrypt(password, salt, log_n, block_size, parallelism, out)
+            })
+        }
+        Algorithm::Pbkdf2Sha256 => {
+            let iterations = decimal_param(&hash, "i")?;
+            verify_output(&hash, |salt, out| {
+                pbkdf2_hmac_sha256(password, salt, iterations, out)
+            })
+        }
+    };
+
+    match result {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+fn decimal_param(hash: &password_hash::PasswordHash<'_>, name: &str) -> password_hash::Result<u32> {
+    hash.params
+        .get_decimal(name)
+        .ok_or(password_hash::Error::ParamNameInvalid)
+}
+
+fn verify_output<F>(hash: &password_hash::PasswordHash<'_>, f: F) -> password_hash::Result<()>
+where
+    F: FnOnce(&[u8], &mut [u8]) -> password_hash::Result<()>,
+{
+    let (Some(salt), Some(expected)) = (&hash.salt, &hash.hash) else {
+        return Err(password_hash::Error::PhcStringField);
+    };
+    let mut salt_buf = [0; MAX_SALT_LENGTH];
+    let salt = salt.decode_b64(&mut salt_buf)?;
+
+    let mut computed = vec![0; expected.len()];
+    f(salt, &mut computed)?;
+
+    if bool::from(computed.ct_eq(expected.as_bytes())) {
+        Ok(())
+    } else {
+        Err(password_hash::Error::Password)
+    }
+}
+
+/// Checks whether the hash was created with a different algorithm or with
+/// different parameters than the ones given in the config.
+pub(crate) fn is_hash_obsolete(
+    hash: &str,
+    config: &PasswordHasherConfig,
+) -> password_hash::Result<bool> {
+    let (hash, algorithm) = parse(hash)?;
+    let param = |name: &str| hash.params.get_decimal(name);
+
+    let up_to_date = match *config {
+        PasswordHasherConfig::Argon2 {
+            memory_cost,
+            time_cost,
+            parallelism,
+        } => {
+            algorithm == Algorithm::Argon2(argon2::Algorithm::Argon2id)
+                && hash.version == Some(argon2::Version::V0x13.into())
+                && param("m") == Some(memory_cost)
+                && param("t") == Some(time_cost)
+                && param("p") == Some(parallelism)
+        }
+        PasswordHasherConfig::Scrypt {
+            log_n,
+            r: block_size,
+            p: parallelism,
+        } => {
+            algorithm == Algorithm::Scrypt
+                && param("ln") == Some(u32::from(log_n))
+                && param("r") == Some(block_size)
+                && param("p") == Some(parallelism)
+        }
+        PasswordHasherConfig::Pbkdf2 { iterations } => {
+            algorithm == Algorithm::Pbkdf2Sha256 && param("i") == Some(iterations)
+        }
+    };
+
+    Ok(!up_to_date)
+}
+
+/// PBKDF2 with HMAC-SHA256 as the pseudorandom function.
+fn pbkdf2_hmac_sha256(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    out: &mut [u8],
+) -> password_hash::Result<()> {
+    check_pbkdf2_cost(iterations)?;
+
+    pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, out);
+    Ok(())
+}
+
+/// The scrypt key derivation function.
+fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    log_n: u8,
+    block_size: u32,
+    parallelism: u32,
+    out: &mut [u8],
+) -> password_hash::Result<()> {
+    check_scrypt_cost(log_n, block_size, parallelism)?;
+    let params = scrypt::Params::new(log_n, block_size, parallelism, OUTPUT_LENGTH as usize)
+        .map_err(|_| InvalidValue::Malformed.param_error())?;
+
+    scrypt::scrypt(password, salt, &params, out).map_err(|_| password_hash::Error::Crypto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARGON2I_HASH: &str = "$argon2i$v=19$m=65536,t=1,p=1$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo";
+    const PBKDF2_HASH: &str =
+        "$pbkdf2-sha256$i=4096,l=32$c2FsdA$xeR41ZKIyEGqUw22hFxMjZYok6ABzk4RpJY4c6qYE0o";
+    // the second test vector from RFC 7914, encoded as a PHC string
+    const SCRYPT_HASH: &str = "$scrypt$ln=10,r=8,p=16$TmFDbA$/bq+HJ00cgB4VucZDQHp/nxq18vII3gw53N2Y0s3MWIurzDZLiKjiG/xCSedmDDaxyevuUqD7m2DYMvfoswGQA";
+
+    #[test]
+    fn pbkdf2_rfc_vector() {
+        // https://datatracker.ietf.org/doc/html/rfc7914#section-11
+        let mut out = [0; 64];
+        pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut out).unwrap();
+
+        assert_eq!(
+            hex::encode(out),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+    }
+
+    #[test]
+    fn scrypt_rfc_vector() {
+        // https://datatracker.ietf.org/doc/html/rfc7914#section-12
+        let mut out = [0; 64];
+        scrypt(b"", b"", 4, 1, 1, &mut out).unwrap();
+
+        assert_eq!(
+            hex::encode(out),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+    }
+
+    #[test]
+    fn scrypt_invalid_params() {
+        let mut out = [0; 32];
+
+        assert!(scrypt(b"password", b"salt", 0, 8, 1, &mut out).is_err());
+        assert!(scrypt(b"password", b"salt", 4, 0, 1, &mut out).is_err());
+        assert!(scrypt(b"password", b"salt", 4, 8, 0, &mut out).is_err());
+    }
+
+    #[test]
+    fn oversized_params() {
+        let mut out = [0; 32];
+        assert!(scrypt(b"password", b"salt", 21, 8, 1, &mut out).is_err());
+        assert!(scrypt(b"password", b"salt", 4, 33, 1, &mut out).is_err());
+        assert!(scrypt(b"password", b"salt", 4, 8, 17, &mut out).is_err());
+        // 128 * 32 * 2^20 bytes = 4 GiB
+        assert!(scrypt(b"password", b"salt", 20, 32, 1, &mut out).is_err());
+        assert!(pbkdf2_hmac_sha256(b"password", b"salt", 10_000_001, &mut out).is_err());
+
+        for hash in [
+            "$scrypt$ln=40,r=8,p=1$TmFDbA$/bq+HJ00cgB4VucZDQHp/nxq18vII3gw53N2Y0s3MWI",
+            "$scrypt$ln=10,r=8,p=4000$TmFDbA$/bq+HJ00cgB4VucZDQHp/nxq18vII3gw53N2Y0s3MWI",
+            "$pbkdf2-sha256$i=4294967295,l=32$c2FsdA$xeR41ZKIyEGqUw22hFxMjZYok6ABzk4RpJY4c6qYE0o",
+            "$argon2id$v=19$m=4294967295,t=1,p=1$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+            "$argon2id$v=19$m=65536,t=4294967295,p=1$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+            "$argon2id$v=19$m=65536,t=1,p=4000$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+        ] {
+            assert!(validate_hash(hash).is_err(), "{hash}");
+            assert!(verify_password(b"password", hash).is_err(), "{hash}");
+        }
+
+        let config = PasswordHasherConfig::Pbkdf2 {
+            iterations: u32::MAX,
+        };
+        assert!(generate_hash(b"password", &config).is_err());
+        let config = PasswordHasherConfig::Argon2 {
+            memory_cost: u32::MAX,
+            time_cost: 1,
+            parallelism: 1,
+        };
+        assert!(generate_hash(b"password", &config).is_err());
+    }
+
+    #[test]
+    fn argon2_invalid_params() {
+        for hash in [
+            "$argon2id$v=19$m=1,t=1,p=1$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+            "$argon2id$v=19$m=65536,t=0,p=1$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+            "$argon2id$v=19$m=65536,t=1,p=0$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+            "$argon2id$v=19$m=65536,t=1,p=1,x=1$c29tZXNhbHQAAAAAAAAAAA$+r0d29hqEB0yasKr55ZgICsQGSkl0v0kgwhd+U3wyRo",
+        ] {
+            assert!(validate_hash(hash).is_err(), "{hash}");
+            assert!(verify_password(b"password", hash).is_err(), "{hash}");
+        }
+    }
+
+    #[test]
+    fn verify_external_hashes() {
+        for hash in [ARGON2I_HASH, PBKDF2_HASH, SCRYPT_HASH] {
+            assert!(verify_password(b"password", hash).unwrap(), "{hash}");
+            assert!(!verify_password(b"bogus", hash).unwrap(), "{hash}");
+        }
+    }
+
+    #[test]
+    fn validate_hash_unsupported() {
+        assert!(validate_hash(PBKDF2_HASH).is_ok());
+        assert!(validate_hash(
+            "$pbkdf2-sha512$i=4096$c2FsdA$xeR41ZKIyEGqUw22hFxMjZYok6ABzk4RpJY4c6qYE0o"
+        )
+        .is_err());
+        assert!(validate_hash("$scrypt$ln=16,r=8,p=1$aM15713r3Xsvxbi31lqr1Q").is_err());
+        assert!(validate_hash("password").is_err());
+    }
+
+    #[test]
+    fn generate_and_verify() {
+        let configs = [
+            PasswordHasherConfig::Argon2 {
+                memory_cost: 1024,
+                time_cost: 1,
+                parallelism: 1,
+            },
+            PasswordHasherConfig::Scrypt {
+                log_n: 4,
+                r: 8,
+                p: 1,
+            },
+            PasswordHasherConfig::Pbkdf2 { iterations: 1000 },
+        ];
+
+        for config in &configs {
+            let hash = generate_hash(b"password", config).unwrap();
+
+            assert!(verify_password(b"password", &hash).unwrap(), "{hash}");
+            assert!(!verify_password(b"bogus", &hash).unwrap(), "{hash}");
+            assert!(!is_hash_obsolete(&hash, config).unwrap(), "{hash}");
+        }
+    }
+
+    #[test]
+    fn generate_invalid_config() {
+        let config = PasswordHasherConfig::Argon2 {
+            memory_cost: 0,
+            time_cost: 1,
+            parallelism: 1,
+        };
+
+        assert!(generate_hash(b"password", &config).is_err());
+    }
+
+    #[test]
+    fn obsolete_hashes() {
+        let argon2 = PasswordHasherConfig::argon2();
+        let pbkdf2 = PasswordHasherConfig::Pbkdf2 { iterations: 4096 };
+
+        assert!(is_hash_obsolete(ARGON2I_HASH, &argon2).unwrap());
+        assert!(is_hash_obsolete(PBKDF2_HASH, &argon2).unwrap());
+        assert!(is_hash_obsolete(SCRYPT_HASH, &argon2).unwrap());
+        assert!(!is_hash_obsolete(PBKDF2_HASH, &pbkdf2).unwrap());
+        assert!(is_hash_obsolete(PBKDF2_HASH, &PasswordHasherConfig::pbkdf2()).unwrap());
+    }
+}
@@ -0,0 +1,742 @@
+//! Password reset.
+//!
+//! This module provides a way for the users to recover a forgotten password.
+//! [`PasswordResetTokenGenerator`] creates signed, expiring tokens that are
+//! only valid until the user's password or last login time changes, and
+//! [`PasswordResetApp`] provides ready-made views for requesting a password
+//! reset and setting a new password for [`DatabaseUser`]s.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{TimeDelta, Utc};
+use derive_more::Debug;
+use hmac::{Hmac, KeyInit, Mac};
+use rinja::Template;
+use sha2::Sha256;
+
+use crate::auth::db::{set_password_from_form, DatabaseUser, MAX_USERNAME_LENGTH};
+use crate::auth::{User, UserId};
+use crate::config::{ProjectConfig, SecretKey};
+use crate::db::Model;
+use crate::form::fields::Password;
+use crate::form::{Form, FormContext, FormErrorTarget, FormField, FormResult};
+use crate::request::{Request, RequestExt};
+use crate::response::{Response, ResponseExt};
+use crate::router::{handle_method_not_allowed, Route, Router};
+use crate::{reverse, reverse_redirect, App, Body, StatusCode};
+
+/// The value mixed into the HMAC so that the password reset tokens can't be
+/// used as signatures for any other purpose.
+const KEY_SALT: &[u8] = b"cot.auth.password_reset";
+
+type PasswordResetHmac = Hmac<Sha256>;
+
+/// Generates and checks password reset tokens.
+///
+/// A token is signed with
+/// [`ProjectConfig::secret_key`](crate::config::ProjectConfig::secret_key)
+/// and contains the time it was created at. It is valid until it expires
+/// (after 3 days by default), or until the user's password or last login time
+/// changes, whichever happens first. This means that a token can only be used
+/// once, as setting a new password invalidates it.
+///
+/// Tokens signed with one of the
+/// [`ProjectConfig::fallback_secret_keys`](crate::config::ProjectConfig::fallback_secret_keys)
+/// are accepted as well, so that rotating the secret key doesn't invalidate
+/// the tokens that have already been sent to the users.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::password_reset::PasswordResetTokenGenerator;
+/// use cot::auth::{User, UserId};
+/// use cot::config::ProjectConfig;
+///
+/// struct MyUser;
+///
+/// impl User for MyUser {
+///     fn id(&self) -> Option<UserId> {
+///         Some(UserId::Int(1))
+///     }
+/// }
+///
+/// let config = ProjectConfig::default();
+/// let generator = PasswordResetTokenGenerator::new();
+///
+/// let token = generator.make_token(&MyUser, &config);
+/// assert!(generator.check_token(&MyUser, &token, &config));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PasswordResetTokenGenerator {
+    timeout: TimeDelta,
+}
+
+impl Default for PasswordResetTokenGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PasswordResetTokenGenerator {
+    /// Creates a new token generator with the default timeout of 3 days.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::password_reset::PasswordResetTokenGenerator;
+    ///
+    /// let generator = PasswordResetTokenGenerator::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            timeout: TimeDelta::days(3),
+        }
+    }
+
+    /// Sets the time after which the generated tokens expire.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::TimeDelta;
+    /// use cot::auth::password_reset::PasswordResetTokenGenerator;
+    ///
+    /// let generator = PasswordResetTokenGenerator::new().with_timeout(TimeDelta::hours(1));
+    /// ```
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: TimeDelta) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Creates a password reset token for the given user.
+    ///
+    /// The token is signed with the primary secret key from the config.
+    #[must_use]
+    pub fn make_token(&self, user: &dyn User, config: &ProjectConfig) -> String {
+        Self::make_token_with_timestamp(user, &config.secret_key, Utc::now().timestamp())
+    }
+
+    /// Checks whether the token is a valid password reset token for the given
+    /// user.
+    ///
+    /// This returns `false` if the token is malformed, has expired, was signed
+    /// with a secret key that is no longer in the config, or if the user's
+    /// password or last login time has changed since the token was created.
+    #[must_use]
+    pub fn check_token(&self, user: &dyn User, token: &str, config: &ProjectConfig) -> bool {
+        self.check_token_at(user, token, config, Utc::now().timestamp())
+    }
+
+    fn make_token_with_timestamp(
+        user: &dyn User,
+        secret_key: &SecretKey,
+        timestamp: i64,
+    ) -> String {
+        let hash = Self::mac(user, secret_key, timestamp)
+            .finalize()
+            .into_bytes();
+
+        format!("{timestamp:x}-{}", hex::encode(hash))
+    }
+
+    fn check_token_at(
+        &self,
+        user: &dyn User,
+        token: &str,
+        config: &ProjectConfig,
+        now: i64,
+    ) -> bool {
+        let Some((timestamp, hash)) = token.split_once('-') else {
+            return false;
+        };
+        let (Ok(timestamp), Ok(hash)) = (i64::from_str_radix(timestamp, 16), hex::decode(hash))
+        else {
+            return false;
+        };
+        if timestamp > now || now - timestamp > self.timeout.num_seconds() {
+            return false;
+        }
+
+        std::iter::once(&config.secret_key)
+            .chain(&config.fallback_secret_keys)
+            .any(|secret_key| {
+                Self::mac(user, secret_key, timestamp)
+                    .verify_slice(&hash)
+                    .is_ok()
+            })
+    }
+
+    fn mac(user: &dyn User, secret_key: &SecretKey, timestamp: i64) -> PasswordResetHmac {
+        let mut mac = PasswordResetHmac::new_from_slice(secret_key.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(KEY_SALT);
+
+        // Each value is prefixed with its length, so that the values can't be
+        // shifted between the fields to get the same hash.
+        let mut update_field = |value: &[u8]| {
+            mac.update(&(value.len() as u64).to_be_bytes());
+            mac.update(value);
+        };
+        match user.id() {
+            Some(UserId::Int(id)) => update_field(id.to_string().as_bytes()),
+            Some(UserId::String(id)) => update_field(id.as_bytes()),
+            None => update_field(&[]),
+        }
+        // The session auth hash changes when the password changes
+        match user.session_auth_hash(secret_key) {
+            Some(session_auth_hash) => update_field(session_auth_hash.as_bytes()),
+            None => update_field(&[]),
+        }
+        // Database backends store the time with different precision, so only
+        // the whole seconds are used
+        match user.last_login() {
+            Some(last_login) => update_field(last_login.timestamp().to_string().as_bytes()),
+            None => update_field(&[]),
+        }
+        update_field(timestamp.to_string().as_bytes());
+
+        mac
+    }
+}
+
+/// Delivers the password reset links to the users.
+///
+/// This is used by [`PasswordResetApp`] when a user requests a password
+/// reset. Typically, this would send an email containing the link to the
+/// user.
+#[async_trait]
+pub trait PasswordResetSender: Send + Sync {
+    /// Sends the password reset link to the user.
+    ///
+    /// The `reset_url` is the path of the view where the user can set a new
+    /// password; it needs to be prefixed with the site's scheme and host to
+    /// be used outside the site.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the link could not be delivered.
+    async fn send(
+        &self,
+        request: &Request,
+        user: &DatabaseUser,
+        reset_url: &str,
+    ) -> crate::Result<()>;
+}
+
+/// A [`PasswordResetSender`] that writes the password reset links to the log.
+///
+/// This is the default sender of [`PasswordResetApp`]; it is only useful
+/// during development, as the users never get to see the links.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LogPasswordResetSender;
+
+#[async_trait]
+impl PasswordResetSender for LogPasswordResetSender {
+    async fn send(
+        &self,
+        _request: &Request,
+        user: &DatabaseUser,
+        reset_url: &str,
+    ) -> crate::Result<()> {
+        tracing::info!(
+            "Password reset requested for user `{}`: {}",
+            user.username(),
+            reset_url
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Form)]
+struct PasswordResetRequestForm {
+    username: String,
+}
+
+#[derive(Debug, Form)]
+struct PasswordResetConfirmForm {
+    new_password: Password,
+    new_password_confirmation: Password,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "password_reset/request.html")]
+struct RequestTemplate {
+    form: <PasswordResetRequestForm as Form>::Context,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "password_reset/request_done.html")]
+struct RequestDoneTemplate;
+
+#[derive(Debug, Template)]
+#[template(path = "password_reset/confirm.html")]
+struct ConfirmTemplate {
+    /// The form to set the new password, or [`None`] if the link is invalid.
+    form: Option<<PasswordResetConfirmForm as Form>::Context>,
+}
+
+#[derive(Debug, Template)]
+#[template(path = "password_reset/complete.html")]
+struct CompleteTemplate;
+
+async fn request_view(mut request: Request, app: &PasswordResetApp) -> crate::Result<Response> {
+    let form_context = if request.method() == http::Method::GET {
+        PasswordResetRequestForm::build_context(&mut request).await?
+    } else if request.method() == http::Method::POST {
+        match PasswordResetRequestForm::from_request(&mut request).await? {
+            FormResult::Ok(form) => {
+                send_reset_link(&request, app, &form.username).await?;
+
+                // SECURITY: Redirect to the same page whether the user exists or not
+                // so that the view can't be used to find out which usernames are taken.
+                return Ok(reverse_redirect!(request, "password_reset_done")?);
+            }
+            FormResult::ValidationError(context) => context,
+        }
+    } else {
+        return Ok(handle_method_not_allowed());
+    };
+
+    let template = RequestTemplate { form: form_context };
+    Ok(Response::new_html(
+        StatusCode::OK,
+        Body::fixed(template.render()?),
+    ))
+}
+
+async fn send_reset_link(
+    request: &Request,
+    app: &PasswordResetApp,
+    username: &str,
+) -> crate::Result<()> {
+    if username.len() > MAX_USERNAME_LENGTH as usize {
+        return Ok(());
+    }
+    // deactivated users can't reset their password
+    let Some(user) = DatabaseUser::get_by_username(request.db(), username)
+        .await?
+        .filter(DatabaseUser::is_active)
+    else {
+        return Ok(());
+    };
+
+    let token = app
+        .token_generator
+        .make_token(&user, request.project_config());
+    let reset_url = reverse!(
+        request,
+        "password_reset_confirm",
+        user_id = user.id(),
+        token = token
+    )?;
+    app.sender.send(request, &user, &reset_url).await
+}
+
+async fn request_done_view(_request: Request) -> crate::Result<Response> {
+    let template = RequestDoneTemplate;
+    Ok(Response::new_html(
+        StatusCode::OK,
+        Body::fixed(template.render()?),
+    ))
+}
+
+async fn confirm_view(mut request: Request, app: &PasswordResetApp) -> crate::Result<Response> {
+    let user = user_for_token(&request, app).await?;
+
+    let form_context = match user {
+        Some(mut user) => {
+            if request.method() == http::Method::GET {
+                Some(PasswordResetConfirmForm::build_context(&mut request).await?)
+            } else if request.method() == http::Method::POST {
+                match PasswordResetConfirmForm::from_request(&mut request).await? {
+                    FormResult::Ok(form) => {
+                        match set_new_password(&mut request, &mut user, form).await? {
+                            Some(context) => Some(context),
+                            None => {
+                                return Ok(reverse_redirect!(request, "password_reset_complete")?)
+                            }
+                        }
+                    }
+                    FormResult::ValidationError(context) => Some(context),
+                }
+            } else {
+                return Ok(handle_method_not_allowed());
+            }
+        }
+        None => None,
+    };
+
+    let template = ConfirmTemplate { form: form_context };
+    Ok(Response::new_html(
+        StatusCode::OK,
+        Body::fixed(template.render()?),
+    ))
+}
+
+/// Sets the password from the form and saves the user. Returns the form
+/// context with the errors if the password can't be set, or [`None`] if it was
+/// set successfully.
+async fn set_new_password(
+    request: &mut Request,
+    user: &mut DatabaseUser,
+    form: PasswordResetConfirmForm,
+) -> crate::Result<Option<<PasswordResetConfirmForm as Form>::Context>> {
+    let errors = set_password_from_form(
+        request,
+        user,
+        form.new_password.as_str(),
+        form.new_password_confirmation.as_str(),
+    )?;
+    if !errors.is_empty() {
+        let mut context = PasswordResetConfirmForm::build_context(request).await?;
+        for (field, error) in errors {
+            context.add_error(FormErrorTarget::Field(field), error);
+        }
+        return Ok(Some(context));
+    }
+    user.save(request.db()).await?;
+
+    Ok(None)
+}
+
+/// Returns the user the password reset link was generated for, or [`None`]
+/// if the link is invalid.
+async fn user_for_token(
+    request: &Request,
+    app: &PasswordResetApp,
+) -> crate::Result<Option<DatabaseUser>> {
+    let path_params = request.path_params();
+    let (Some(user_id), Some(token)) = (path_params.get("user_id"), path_params.get("token"))
+    else {
+        return Ok(None);
+    };
+    let Ok(user_id) = user_id.parse() else {
+        return Ok(None);
+    };
+    let Some(user) = DatabaseUser::get_by_id(request.db(), user_id)
+        .await?
+        .filter(DatabaseUser::is_active)
+    else {
+        return Ok(None);
+    };
+
+    if app
+        .token_generator
+        .check_token(&user, token, request.project_config())
+    {
+        Ok(Some(user))
+    } else {
+        Ok(None)
+    }
+}
+
+async fn complete_view(_request: Request) -> crate::Result<Response> {
+    let template = CompleteTemplate;
+    Ok(Response::new_html(
+        StatusCode::OK,
+        Body::fixed(template.render()?),
+    ))
+}
+
+/// The password reset app.
+///
+/// This app provides the views for resetting the password of a
+/// [`DatabaseUser`]:
+///
+/// * `/` (named `password_reset`) -- a form where the user enters their
+///   username. If the user exists, a link to the confirmation view is delivered
+///   to them with the [`PasswordResetSender`] set in the app.
+/// * `/done` (named `password_reset_done`) -- shown after the form above is
+///   submitted.
+/// * `/reset/{user_id}/{token}` (named `password_reset_confirm`) -- a form
+///   where the user sets their new password.
+/// * `/complete` (named `password_reset_complete`) -- shown after the new
+///   password has been set.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::password_reset::{LogPasswordResetSender, PasswordResetApp};
+/// use cot::project::WithConfig;
+/// use cot::{AppBuilder, Project, ProjectContext};
+///
+/// struct MyProject;
+/// impl Project for MyProject {
+///     fn register_apps(
+///         &self,
+///         modules: &mut AppBuilder,
+///         _app_context: &ProjectContext<WithConfig>,
+///     ) {
+///         modules.register_with_views(
+///             PasswordResetApp::new().sender(LogPasswordResetSender),
+///             "/password-reset",
+///         );
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PasswordResetApp {
+    token_generator: PasswordResetTokenGenerator,
+    #[debug("sender(...)")]
+    sender: Arc<dyn PasswordResetSender>,
+}
+
+impl Default for PasswordResetApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PasswordResetApp {
+    /// Creates a password reset app instance.
+    ///
+    /// The app uses [`LogPasswordResetSender`] to deliver the password reset
+    /// links by default; use [`sender`](Self::sender) to change it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::password_reset::PasswordResetApp;
+    ///
+    /// let app = PasswordResetApp::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            token_generator: PasswordResetTokenGenerator::new(),
+            sender: Arc::new(LogPasswordResetSender),
+        }
+    }
+
+    /// Sets the sender used to deliver the password reset links to the users.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_trait::async_trait;
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::auth::password_reset::{PasswordResetApp, PasswordResetSender};
+    /// use cot::request::Request;
+    ///
+    /// struct EmailSender;
+    ///
+    /// #[async_trait]
+    /// impl PasswordResetSender for EmailSender {
+    ///     async fn send(
+    ///         &self,
+    ///         request: &Request,
+    ///         user: &DatabaseUser,
+    ///         reset_url: &str,
+    ///     ) -> cot::Result<()> {
+    ///         // send an email to the user
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let app = PasswordResetApp::new().sender(EmailSender);
+    /// ```
+    #[must_use]
+    pub fn sender<T: PasswordResetSender + 'static>(mut self, sender: T) -> Self {
+        self.sender = Arc::new(sender);
+        self
+    }
+
+    /// Sets the generator used to create and check the password reset tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::TimeDelta;
+    /// use cot::auth::password_reset::{PasswordResetApp, PasswordResetTokenGenerator};
+    ///
+    /// let app = PasswordResetApp::new()
+    ///     .token_generator(PasswordResetTokenGenerator::new().with_timeout(TimeDelta::hours(1)));
+    /// ```
+    #[must_use]
+    pub fn token_generator(mut self, token_generator: PasswordResetTokenGenerator) -> Self {
+        self.token_generator = token_generator;
+        self
+    }
+}
+
+impl App for PasswordResetApp {
+    fn name(&self) -> &'static str {
+        "cot_password_reset"
+    }
+
+    fn router(&self) -> Router {
+        let request_app = self.clone();
+        let confirm_app = self.clone();
+
+        Router::with_urls([
+            Route::with_handler_and_name(
+                "/",
+                move |request| {
+                    let app = request_app.clone();
+                    async move { request_view(request, &app).await }
+                },
+                "password_reset",
+            ),
+            Route::with_handler_and_name("/done", request_done_view, "password_reset_done"),
+            Route::with_handler_and_name(
+                "/reset/{user_id}/{token}",
+                move |request| {
+                    let app = confirm_app.clone();
+                    async move { confirm_view(request, &app).await }
+                },
+                "password_reset_confirm",
+            ),
+            Route::with_handler_and_name("/complete", complete_view, "password_reset_complete"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset};
+
+    use super::*;
+    use crate::auth::SessionAuthHash;
+
+    struct TestUser {
+        id: i64,
+        password: &'static str,
+        last_login: Option<DateTime<FixedOffset>>,
+    }
+
+    impl TestUser {
+        fn new() -> Self {
+            Self {
+                id: 1,
+                password: "password123",
+                last_login: None,
+            }
+        }
+    }
+
+    impl User for TestUser {
+        fn id(&self) -> Option<UserId> {
+            Some(UserId::Int(self.id))
+        }
+
+        fn last_login(&self) -> Option<DateTime<FixedOffset>> {
+            self.last_login
+        }
+
+        fn session_auth_hash(&self, secret_key: &SecretKey) -> Option<SessionAuthHash> {
+            let mut data = secret_key.as_bytes().to_vec();
+            data.extend_from_slice(self.password.as_bytes());
+            Some(SessionAuthHash::new(&data))
+        }
+    }
+
+    fn config_with_keys(secret_key: &str, fallback_secret_keys: &[&str]) -> ProjectConfig {
+        ProjectConfig::builder()
+            .secret_key(SecretKey::from(secret_key))
+            .fallback_secret_keys(
+                fallback_secret_keys
+                    .iter()
+                    .map(|&key| SecretKey::from(key))
+                    .collect::<Vec<_>>(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn token_valid() {
+        let config = config_with_keys("key", &[]);
+        let generator = PasswordResetTokenGenerator::new();
+        let user = TestUser::new();
+
+        let token = generator.make_token(&user, &config);
+
+        assert!(generator.check_token(&user, &token, &config));
+    }
+
+    #[test]
+    fn token_invalid_for_other_user() {
+        let config = config_with_keys("key", &[]);
+        let generator = PasswordResetTokenGenerator::new();
+
+        let token = generator.make_token(&TestUser::new(), &config);
+        let other_user = TestUser {
+            id: 2,
+            ..TestUser::new()
+        };
+
+        assert!(!generator.check_token(&other_user, &token, &config));
+    }
+
+    #[test]
+    fn token_invalid_after_password_change() {
+        let config = config_with_keys("key", &[]);
+        let generator = PasswordResetTokenGenerator::new();
+
+        let token = generator.make_token(&TestUser::new(), &config);
+        let user = TestUser {
+            password: "newpassword",
+            ..TestUser::new()
+        };
+
+        assert!(!generator.check_token(&user, &token, &config));
+    }
+
+    #[test]
+    fn token_invalid_after_login() {
+        let config = config_with_keys("key", &[]);
+        let generator = PasswordResetTokenGenerator::new();
+
+        let token = generator.make_token(&TestUser::new(), &config);
+        let user = TestUser {
+            last_login: Some(Utc::now().fixed_offset()),
+            ..TestUser::new()
+        };
+
+        assert!(!generator.check_token(&user, &token, &config));
+    }
+
+    #[test]
+    fn token_expired() {
+        let config = config_with_keys("key", &[]);
+        let generator = PasswordResetTokenGenerator::new().with_timeout(TimeDelta::hours(1));
+        let user = TestUser::new();
+
+        let token =
+            PasswordResetTokenGenerator::make_token_with_timestamp(&user, &config.secret_key, 1000);
+
+        assert!(generator.check_token_at(&user, &token, &config, 1000 + 3600));
+        assert!(!generator.check_token_at(&user, &token, &config, 1000 + 3601));
+        assert!(!generator.check_token_at(&user, &token, &config, 999));
+    }
+
+    #[test]
+    fn token_fallback_secret_key() {
+        let old_config = config_with_keys("old_key", &[]);
+        let new_config = config_with_keys("new_key", &["old_key"]);
+        let other_config = config_with_keys("other_key", &[]);
+        let generator = PasswordResetTokenGenerator::new();
+        let user = TestUser::new();
+
+        let token = generator.make_token(&user, &old_config);
+
+        assert!(generator.check_token(&user, &token, &new_config));
+        assert!(!generator.check_token(&user, &token, &other_config));
+    }
+
+    #[test]
+    fn token_malformed() {
+        let config = config_with_keys("key", &[]);
+        let generator = PasswordResetTokenGenerator::new();
+        let user = TestUser::new();
+
+        assert!(!generator.check_token(&user, "", &config));
+        assert!(!generator.check_token(&user, "abc", &config));
+        assert!(!generator.check_token(&user, "zzz-00", &config));
+        assert!(!generator.check_token(&user, "1-xyz", &config));
+        assert!(!generator.check_token(&user, "1-00", &config));
+    }
+}
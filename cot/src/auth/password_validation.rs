@@ -0,0 +1,412 @@
+//! Password validation.
+//!
+//! This module provides the validators that check whether a password is strong
+//! enough to be set for a user. The validators to use are configured in
+//! [`ProjectConfig::password_validators`](crate::config::ProjectConfig::password_validators)
+//! and are checked by [`DatabaseUser::create_user`],
+//! [`DatabaseUser::set_password`] and the password reset form. Custom
+//! validators can be created by implementing the [`PasswordValidator`] trait.
+//!
+//! [`DatabaseUser::create_user`]: crate::auth::db::DatabaseUser::create_user
+//! [`DatabaseUser::set_password`]: crate::auth::db::DatabaseUser::set_password
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use thiserror::Error;
+
+use crate::auth::{Password, User};
+use crate::config::{PasswordValidatorConfig, PasswordValidators};
+
+/// The maximum similarity between the password and the username accepted by
+/// [`UserAttributeSimilarityValidator`].
+const MAX_SIMILARITY: f64 = 0.7;
+/// The maximum length, in characters, of the passwords and usernames compared
+/// by [`UserAttributeSimilarityValidator`]; the comparison takes time
+/// proportional to the product of their lengths.
+const MAX_SIMILARITY_LENGTH: usize = 1000;
+
+static COMMON_PASSWORDS: LazyLock<HashSet<&'static str>> =
+    LazyLock::new(|| include_str!("common_passwords.txt").lines().collect());
+
+/// An error that occurs when setting the password of a user.
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum PasswordError {
+    /// The password is shorter than the minimum length.
+    #[error("password is too short (min {0} characters)")]
+    TooShort(u32),
+    /// The password is one of the commonly used passwords.
+    #[error("password is too common")]
+    TooCommon,
+    /// The password is too similar to the username.
+    #[error("password is too similar to the username")]
+    TooSimilar,
+    /// The password consists of digits only.
+    #[error("password is entirely numeric")]
+    EntirelyNumeric,
+    /// The password was rejected by a custom [`PasswordValidator`].
+    #[error("{0}")]
+    Invalid(String),
+    /// The password could not be hashed with the configured
+    /// [`PasswordHasherConfig`](crate::config::PasswordHasherConfig).
+    #[error("could not hash the password: {0}")]
+    HasherInvalid(String),
+}
+
+/// A validator that checks whether a password can be set for a user.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::password_validation::{PasswordError, PasswordValidator};
+/// use cot::auth::{Password, User};
+///
+/// struct NoSpacesValidator;
+///
+/// impl PasswordValidator for NoSpacesValidator {
+///     fn validate(
+///         &self,
+///         password: &Password,
+///         _user: Option<&dyn User>,
+///     ) -> Result<(), PasswordError> {
+///         if password.as_str().contains(' ') {
+///             Err(PasswordError::Invalid(
+///                 "password can't contain spaces".to_string(),
+///             ))
+///         } else {
+///             Ok(())
+///         }
+///     }
+/// }
+///
+/// assert!(NoSpacesValidator
+///     .validate(&Password::new("correct horse"), None)
+///     .is_err());
+/// ```
+pub trait PasswordValidator: Send + Sync {
+    /// Checks whether the password can be set for the user.
+    ///
+    /// The `user` is the user the password is being set for, or [`None`] if
+    /// it's not known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing why the password was rejected.
+    fn validate(&self, password: &Password, user: Option<&dyn User>) -> Result<(), PasswordError>;
+}
+
+/// Checks that the password has at least a given number of characters.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::password_validation::{MinimumLengthValidator, PasswordValidator};
+/// use cot::auth::Password;
+///
+/// let validator = MinimumLengthValidator::new(8);
+/// assert!(validator.validate(&Password::new("short"), None).is_err());
+/// assert!(validator
+///     .validate(&Password::new("long enough"), None)
+///     .is_ok());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MinimumLengthValidator {
+    min_length: u32,
+}
+
+impl MinimumLengthValidator {
+    /// Creates a new validator accepting the passwords with at least
+    /// `min_length` characters.
+    #[must_use]
+    pub fn new(min_length: u32) -> Self {
+        Self { min_length }
+    }
+}
+
+impl PasswordValidator for MinimumLengthValidator {
+    fn validate(&self, password: &Password, _user: Option<&dyn User>) -> Result<(), PasswordError> {
+        if password.as_str().chars().count() < self.min_length as usize {
+            Err(PasswordError::TooShort(self.min_length))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Checks that the password is not one of the commonly used passwords.
+///
+/// The list of the common passwords is bundled with Cot; the comparison is
+/// case-insensitive.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::password_validation::{CommonPasswordValidator, PasswordValidator};
+/// use cot::auth::Password;
+///
+/// assert!(CommonPasswordValidator
+///     .validate(&Password::new("Password123"), None)
+///     .is_err());
+/// ```
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CommonPasswordValidator;
+
+impl PasswordValidator for CommonPasswordValidator {
+    fn validate(&self, password: &Password, _user: Option<&dyn User>) -> Result<(), PasswordError> {
+        let password = password.as_str().trim().to_lowercase();
+        if COMMON_PASSWORDS.contains(password.as_str()) {
+            Err(PasswordError::TooCommon)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Checks that the password is not too similar to the username.
+///
+/// The password is compared, case-insensitively, with the whole username, as
+/// well as with each of its parts separated by non-alphanumeric characters.
+/// Passwords and usernames longer than 1000 characters are not compared.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use cot::auth::password_validation::{PasswordValidator, UserAttributeSimilarityValidator};
+/// use cot::auth::{Password, User};
+///
+/// struct MyUser;
+///
+/// impl User for MyUser {
+///     fn username(&self) -> Option<Cow<'_, str>> {
+///         Some(Cow::from("john.smith"))
+///     }
+/// }
+///
+/// assert!(UserAttributeSimilarityValidator
+///     .validate(&Password::new("JohnSmith1"), Some(&MyUser))
+///     .is_err());
+/// ```
+#[derive(Debug, Copy, Clone, Default)]
+pub struct UserAttributeSimilarityValidator;
+
+impl PasswordValidator for UserAttributeSimilarityValidator {
+    fn validate(&self, password: &Password, user: Option<&dyn User>) -> Result<(), PasswordError> {
+        let Some(username) = user.and_then(User::username) else {
+            return Ok(());
+        };
+        if password.as_str().chars().count() > MAX_SIMILARITY_LENGTH
+            || username.chars().count() > MAX_SIMILARITY_LENGTH
+        {
+            return Ok(());
+        }
+        let password = password.as_str().to_lowercase();
+        let username = username.to_lowercase();
+
+        let too_similar = std::iter::once(username.as_str())
+            .chain(username.split(|c: char| !c.is_alphanumeric()))
+            .filter(|part| !part.is_empty())
+            .any(|part| similarity(&password, part) >= MAX_SIMILARITY);
+        if too_similar {
+            Err(PasswordError::TooSimilar)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Returns the similarity ratio of two strings, between 0 (nothing in common)
+/// and 1 (equal strings), based on the length of their longest common
+/// subsequence.
+#[allow(clippy::cast_precision_loss)] // the strings are at most `MAX_SIMILARITY_LENGTH` long
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut previous = vec![0_usize; b.len() + 1];
+    let mut current = vec![0_usize; b.len() + 1];
+    for a_char in &a {
+        for (j, b_char) in b.iter().enumerate() {
+            current[j + 1] = if a_char == b_char {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    let common = previous[b.len()];
+
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+/// Checks that the password is not entirely numeric.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::password_validation::{NumericPasswordValidator, PasswordValidator};
+/// use cot::auth::Password;
+///
+/// assert!(NumericPasswordValidator
+///     .validate(&Password::new("20240101"), None)
+///     .is_err());
+/// ```
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NumericPasswordValidator;
+
+impl PasswordValidator for NumericPasswordValidator {
+    fn validate(&self, password: &Password, _user: Option<&dyn User>) -> Result<(), PasswordError> {
+        let password = password.as_str();
+        if !password.is_empty() && password.chars().all(|c| c.is_ascii_digit()) {
+            Err(PasswordError::EntirelyNumeric)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl PasswordValidator for PasswordValidatorConfig {
+    fn validate(&self, password: &Password, user: Option<&dyn User>) -> Result<(), PasswordError> {
+        match self {
+            Self::MinimumLength { min_length } => {
+                MinimumLengthValidator::new(*min_length).validate(password, user)
+            }
+            Self::CommonPassword => CommonPasswordValidator.validate(password, user),
+            Self::UserAttributeSimilarity => {
+                UserAttributeSimilarityValidator.validate(password, user)
+            }
+            Self::Numeric => NumericPasswordValidator.validate(password, user),
+        }
+    }
+}
+
+/// Checks all the validators in order and returns the first error.
+impl PasswordValidator for PasswordValidators {
+    fn validate(&self, password: &Password, user: Option<&dyn User>) -> Result<(), PasswordError> {
+        self.as_slice()
+            .iter()
+            .try_for_each(|validator| validator.validate(password, user))
+    }
+}
+
+impl PasswordValidators {
+    /// Checks all the validators and returns all the errors, so that they can
+    /// be displayed to the user at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::Password;
+    /// use cot::config::PasswordValidators;
+    ///
+    /// let errors = PasswordValidators::recommended().validate_all(&Password::new("1234"), None);
+    /// assert_eq!(errors.len(), 3);
+    /// ```
+    #[must_use]
+    pub fn validate_all(&self, password: &Password, user: Option<&dyn User>) -> Vec<PasswordError> {
+        self.as_slice()
+            .iter()
+            .filter_map(|validator| validator.validate(password, user).err())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    struct TestUser;
+
+    impl User for TestUser {
+        fn username(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::from("john.smith"))
+        }
+    }
+
+    fn validate<T: PasswordValidator>(validator: &T, password: &str) -> Result<(), PasswordError> {
+        validator.validate(&Password::new(password), Some(&TestUser))
+    }
+
+    #[test]
+    fn minimum_length() {
+        let validator = MinimumLengthValidator::new(8);
+
+        assert!(matches!(
+            validate(&validator, "short"),
+            Err(PasswordError::TooShort(8))
+        ));
+        // characters are counted, not bytes
+        assert!(validate(&validator, "ąęółżźćń").is_ok());
+        assert!(validate(&validator, "").is_err());
+    }
+
+    #[test]
+    fn common_password() {
+        assert!(matches!(
+            validate(&CommonPasswordValidator, "password123"),
+            Err(PasswordError::TooCommon)
+        ));
+        assert!(validate(&CommonPasswordValidator, " QWERTY ").is_err());
+        assert!(validate(&CommonPasswordValidator, "correct horse battery staple").is_ok());
+    }
+
+    #[test]
+    fn user_attribute_similarity() {
+        assert!(matches!(
+            validate(&UserAttributeSimilarityValidator, "john.smith"),
+            Err(PasswordError::TooSimilar)
+        ));
+        assert!(validate(&UserAttributeSimilarityValidator, "Smith1").is_err());
+        assert!(validate(&UserAttributeSimilarityValidator, "johnsmith").is_err());
+        assert!(validate(&UserAttributeSimilarityValidator, "correct horse battery").is_ok());
+        // long passwords are not compared
+        assert!(validate(&UserAttributeSimilarityValidator, &"johnsmith".repeat(112)).is_ok());
+        // no user to compare with
+        assert!(UserAttributeSimilarityValidator
+            .validate(&Password::new("john.smith"), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn similarity_ratio() {
+        assert!((similarity("abc", "abc") - 1.0).abs() < f64::EPSILON);
+        assert!(similarity("abc", "xyz").abs() < f64::EPSILON);
+        assert!((similarity("abcd", "abxx") - 0.5).abs() < f64::EPSILON);
+        assert!((similarity("", "") - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn numeric() {
+        assert!(matches!(
+            validate(&NumericPasswordValidator, "1234567890"),
+            Err(PasswordError::EntirelyNumeric)
+        ));
+        assert!(validate(&NumericPasswordValidator, "1234567890a").is_ok());
+    }
+
+    #[test]
+    fn password_validators() {
+        let validators = PasswordValidators::recommended();
+
+        assert!(validate(&validators, "correct horse battery staple").is_ok());
+        assert!(matches!(
+            validate(&validators, "1234"),
+            Err(PasswordError::TooShort(8))
+        ));
+        assert_eq!(
+            validators
+                .validate_all(&Password::new("1234"), Some(&TestUser))
+                .len(),
+            3
+        );
+        assert!(validate(&PasswordValidators::none(), "").is_ok());
+    }
+}
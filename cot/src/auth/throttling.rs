@@ -0,0 +1,672 @@
+//! Login throttling.
+//!
+//! This module limits the number of failed login attempts, as a protection
+//! against guessing the passwords of the users. [`LoginThrottle`] counts the
+//! failed attempts made with each username and from each client IP address,
+//! and locks them out for a while when there are too many of them. It is used
+//! automatically by [`AuthRequestExt::authenticate`], and configured with
+//! [`ProjectConfig::login_throttling`]. The current lockouts can be reviewed
+//! and lifted in the admin panel by the superusers and the users with the
+//! `manage_lockouts` permission.
+//!
+//! [`AuthRequestExt::authenticate`]: crate::auth::AuthRequestExt::authenticate
+//! [`ProjectConfig::login_throttling`]: crate::config::ProjectConfig::login_throttling
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_trait::async_trait;
+use axum::extract::ConnectInfo;
+use chrono::{DateTime, TimeDelta, Utc};
+use derive_more::Debug;
+
+use crate::config::LoginThrottlingConfig;
+use crate::request::Request;
+
+/// The username or the client IP address the failed login attempts are
+/// counted for.
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::throttling::LockoutTarget;
+///
+/// let target = LockoutTarget::Username("alice".to_owned());
+/// assert_eq!(target.to_string(), "username `alice`");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LockoutTarget {
+    /// The username the login attempts were made with.
+    Username(String),
+    /// The IP address of the client that made the login attempts.
+    IpAddress(IpAddr),
+}
+
+impl LockoutTarget {
+    /// Returns the type of the target, as used in the admin panel forms.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::Username(_) => "username",
+            Self::IpAddress(_) => "ip_address",
+        }
+    }
+
+    /// Returns the username or the IP address, as used in the admin panel
+    /// forms.
+    pub(crate) fn value(&self) -> String {
+        match self {
+            Self::Username(username) => username.clone(),
+            Self::IpAddress(ip_address) => ip_address.to_string(),
+        }
+    }
+
+    /// Creates a target from the values returned by [`Self::kind`] and
+    /// [`Self::value`].
+    pub(crate) fn from_kind_and_value(kind: &str, value: &str) -> Option<Self> {
+        match kind {
+            "username" => Some(Self::Username(value.to_owned())),
+            "ip_address" => value.parse().ok().map(Self::IpAddress),
+            _ => None,
+        }
+    }
+}
+
+impl Display for LockoutTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Username(username) => write!(f, "username `{username}`"),
+            Self::IpAddress(ip_address) => write!(f, "IP address {ip_address}"),
+        }
+    }
+}
+
+/// A username or a client IP address that has been locked out after too many
+/// failed login attempts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lockout {
+    target: LockoutTarget,
+    locked_until: DateTime<Utc>,
+    count: u32,
+}
+
+impl Lockout {
+    /// Returns the username or the IP address that has been locked out.
+    #[must_use]
+    pub fn target(&self) -> &LockoutTarget {
+        &self.target
+    }
+
+    /// Returns the time the lockout ends at.
+    #[must_use]
+    pub fn locked_until(&self) -> DateTime<Utc> {
+        self.locked_until
+    }
+
+    /// Returns the number of times the target has been locked out in a row,
+    /// including this lockout. The duration of the lockout grows with each of
+    /// them.
+    #[must_use]
+    pub fn lockout_count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Gets notified when a username or a client IP address is locked out.
+///
+/// This is used by [`LoginThrottle`] when there are too many failed login
+/// attempts. Typically, this would alert the administrators, or send an email
+/// to the user whose account is being attacked.
+#[async_trait]
+pub trait LockoutNotifier: Send + Sync {
+    /// Notifies about a new lockout.
+    ///
+    /// The `request` is the one with the failed login attempt that caused the
+    /// lockout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the notification could not be delivered. The error
+    /// is logged, but it doesn't affect the login attempt.
+    async fn notify(&self, request: &Request, lockout: &Lockout) -> crate::Result<()>;
+}
+
+/// A [`LockoutNotifier`] that writes the lockouts to the log.
+///
+/// This is the default notifier of [`LoginThrottle`].
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LogLockoutNotifier;
+
+#[async_trait]
+impl LockoutNotifier for LogLockoutNotifier {
+    async fn notify(&self, _request: &Request, lockout: &Lockout) -> crate::Result<()> {
+        tracing::warn!(
+            "Too many failed login attempts for {}; locked out until {}",
+            lockout.target(),
+            lockout.locked_until()
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FailureRecord {
+    failures: u32,
+    first_failure: Option<DateTime<Utc>>,
+    last_failure: Option<DateTime<Utc>>,
+    lockout_count: u32,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl FailureRecord {
+    fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until
+            .is_some_and(|locked_until| locked_until > now)
+    }
+}
+
+/// Tracks the failed login attempts and locks out the usernames and the
+/// client IP addresses that have too many of them.
+///
+/// The failed attempts are kept in memory, so they are not shared between
+/// several instances of the project, and are lost when it's restarted. The
+/// limits are configured with [`LoginThrottlingConfig`]; see its
+/// documentation for the details.
+///
+/// The throttle used by the project is created by
+/// [`Project::login_throttle`](crate::project::Project::login_throttle), and
+/// can be accessed with
+/// [`ProjectContext::login_throttle`](crate::ProjectContext::login_throttle).
+///
+/// # Examples
+///
+/// ```
+/// use cot::auth::throttling::{LogLockoutNotifier, LoginThrottle};
+/// use cot::config::LoginThrottlingConfig;
+///
+/// let throttle =
+///     LoginThrottle::new(&LoginThrottlingConfig::default()).notifier(LogLockoutNotifier);
+/// assert!(throttle.lockouts().is_empty());
+/// ```
+#[derive(Debug)]
+pub struct LoginThrottle {
+    config: LoginThrottlingConfig,
+    #[debug("..")]
+    notifier: Arc<dyn LockoutNotifier>,
+    records: Mutex<HashMap<LockoutTarget, FailureRecord>>,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self::new(&LoginThrottlingConfig::default())
+    }
+}
+
+impl LoginThrottle {
+    /// Creates a new login throttle with given configuration.
+    ///
+    /// The throttle uses [`LogLockoutNotifier`] to notify about the lockouts;
+    /// use [`Self::notifier`] to change it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::throttling::LoginThrottle;
+    /// use cot::config::LoginThrottlingConfig;
+    ///
+    /// let throttle = LoginThrottle::new(&LoginThrottlingConfig::default());
+    /// ```
+    #[must_use]
+    pub fn new(config: &LoginThrottlingConfig) -> Self {
+        Self {
+            config: config.clone(),
+            notifier: Arc::new(LogLockoutNotifier),
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the notifier that gets notified about the lockouts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_trait::async_trait;
+    /// use cot::auth::throttling::{Lockout, LockoutNotifier, LoginThrottle};
+    /// use cot::config::LoginThrottlingConfig;
+    /// use cot::request::Request;
+    ///
+    /// struct AlertNotifier;
+    ///
+    /// #[async_trait]
+    /// impl LockoutNotifier for AlertNotifier {
+    ///     async fn notify(&self, request: &Request, lockout: &Lockout) -> cot::Result<()> {
+    ///         // alert the administrators here
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let throttle = LoginThrottle::new(&LoginThrottlingConfig::default()).notifier(AlertNotifier);
+    /// ```
+    #[must_use]
+    pub fn notifier<T: LockoutNotifier + 'static>(mut self, notifier: T) -> Self {
+        self.notifier = Arc::new(notifier);
+        self
+    }
+
+    /// Returns all the usernames and client IP addresses that are currently
+    /// locked out, sorted by the target.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::throttling::LoginThrottle;
+    ///
+    /// let throttle = LoginThrottle::default();
+    /// assert!(throttle.lockouts().is_empty());
+    /// ```
+    #[must_use]
+    pub fn lockouts(&self) -> Vec<Lockout> {
+        self.lockouts_at(Utc::now())
+    }
+
+    /// Removes the lockout and the failed login attempts of the username or
+    /// the client IP address. Returns `true` if the target was locked out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::throttling::{LockoutTarget, LoginThrottle};
+    ///
+    /// let throttle = LoginThrottle::default();
+    /// assert!(!throttle.unlock(&LockoutTarget::Username("alice".to_owned())));
+    /// ```
+    pub fn unlock(&self, target: &LockoutTarget) -> bool {
+        self.unlock_at(target, Utc::now())
+    }
+
+    /// Returns the targets the login attempt with the given username is
+    /// counted for, or an empty list if the throttling is disabled.
+    pub(crate) fn targets(&self, request: &Request, username: &str) -> Vec<LockoutTarget> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let mut targets = vec![LockoutTarget::Username(username.to_owned())];
+        if let Some(ip_address) = client_ip(request, self.config.client_ip_header.as_deref()) {
+            targets.push(LockoutTarget::IpAddress(ip_address));
+        }
+        targets
+    }
+
+    /// Returns the lockout of any of the targets, if there is one.
+    pub(crate) fn lockout(&self, targets: &[LockoutTarget]) -> Option<Lockout> {
+        self.lockout_at(targets, Utc::now())
+    }
+
+    /// Records a failed login attempt, locking out the targets that have too
+    /// many of them, and notifying the notifier about it.
+    pub(crate) async fn record_failure(&self, request: &Request, targets: &[LockoutTarget]) {
+        let lockouts = self.record_failure_at(targets, Utc::now());
+
+        for lockout in lockouts {
+            if let Err(error) = self.notifier.notify(request, &lockout).await {
+                tracing::error!(
+                    "Could not notify about the lockout of {}: {error}",
+                    lockout.target
+                );
+            }
+        }
+    }
+
+    /// Records a successful login attempt, which resets the failed attempts
+    /// of the username (but not of the client IP address).
+    pub(crate) fn record_success(&self, targets: &[LockoutTarget]) {
+        let mut records = self.records();
+        for target in targets {
+            if let LockoutTarget::Username(_) = target {
+                records.remove(target);
+            }
+        }
+    }
+
+    fn lockouts_at(&self, now: DateTime<Utc>) -> Vec<Lockout> {
+        let mut lockouts: Vec<_> = self
+            .records()
+            .iter()
+            .filter(|(_, record)| record.is_locked(now))
+            .map(|(target, record)| Self::lockout_for(target, record))
+            .collect();
+        lockouts.sort_by(|a, b| a.target.cmp(&b.target));
+        lockouts
+    }
+
+    fn unlock_at(&self, target: &LockoutTarget, now: DateTime<Utc>) -> bool {
+        self.records()
+            .remove(target)
+            .is_some_and(|record| record.is_locked(now))
+    }
+
+    fn lockout_at(&self, targets: &[LockoutTarget], now: DateTime<Utc>) -> Option<Lockout> {
+        let records = self.records();
+        targets.iter().find_map(|target| {
+            records
+                .get(target)
+                .filter(|record| record.is_locked(now))
+                .map(|record| Self::lockout_for(target, record))
+        })
+    }
+
+    fn record_failure_at(&self, targets: &[LockoutTarget], now: DateTime<Utc>) -> Vec<Lockout> {
+        let mut records = self.records();
+        let window = seconds(self.config.failure_window_secs);
+        let forget_after = seconds(
+            self.config
+                .failure_window_secs
+                .max(self.config.max_lockout_secs),
+        );
+        records.retain(|_, record| {
+            record.is_locked(now)
+                || record
+                    .last_failure
+                    .is_some_and(|last_failure| now - last_failure < forget_after)
+        });
+
+        let mut lockouts = Vec::new();
+        for target in targets {
+            let max_failures = match target {
+                LockoutTarget::Username(_) => self.config.max_failures_per_username,
+                LockoutTarget::IpAddress(_) => self.config.max_failures_per_ip,
+            };
+            if max_failures == 0 {
+                continue;
+            }
+
+            let record = records.entry(target.clone()).or_default();
+            if record
+                .first_failure
+                .is_none_or(|first_failure| now - first_failure >= window)
+            {
+                record.failures = 0;
+                record.first_failure = Some(now);
+            }
+            record.failures += 1;
+            record.last_failure = Some(now);
+
+            if record.failures >= max_failures {
+                record.failures = 0;
+                record.first_failure = None;
+                record.lockout_count = record.lockout_count.saturating_add(1);
+                let locked_until = now
+                    .checked_add_signed(self.lockout_duration(record.lockout_count))
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC);
+                record.locked_until = Some(locked_until);
+
+                lockouts.push(Self::lockout_for(target, record));
+            }
+        }
+        lockouts
+    }
+
+    fn lockout_duration(&self, lockout_count: u32) -> TimeDelta {
+        let multiplier =
+            u64::from(self.config.backoff_multiplier).saturating_pow(lockout_count - 1);
+        seconds(
+            self.config
+                .lockout_secs
+                .saturating_mul(multiplier)
+                .min(self.config.max_lockout_secs),
+        )
+    }
+
+    fn lockout_for(target: &LockoutTarget, record: &FailureRecord) -> Lockout {
+        Lockout {
+            target: target.clone(),
+            locked_until: record.locked_until.unwrap_or(DateTime::<Utc>::MIN_UTC),
+            count: record.lockout_count,
+        }
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, HashMap<LockoutTarget, FailureRecord>> {
+        // the records are always left in a consistent state, so it's fine to
+        // keep using them after a panic
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn seconds(seconds: u64) -> TimeDelta {
+    i64::try_from(seconds)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .unwrap_or(TimeDelta::MAX)
+}
+
+/// Returns the IP address of the client that sent the request.
+///
+/// If `header` is set, the last address in that header is used; otherwise,
+/// the address of the connection is used.
+pub(crate) fn client_ip(request: &Request, header: Option<&str>) -> Option<IpAddr> {
+    if let Some(header) = header {
+        let value = request.headers().get_all(header).iter().next_back()?;
+        return value.to_str().ok()?.rsplit(',').next()?.trim().parse().ok();
+    }
+
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::test::TestRequestBuilder;
+
+    const CLIENT_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(
+            &LoginThrottlingConfig::builder()
+                .max_failures_per_username(3)
+                .max_failures_per_ip(5)
+                .failure_window_secs(60)
+                .lockout_secs(10)
+                .max_lockout_secs(30)
+                .build(),
+        )
+    }
+
+    fn targets(username: &str) -> Vec<LockoutTarget> {
+        vec![
+            LockoutTarget::Username(username.to_owned()),
+            LockoutTarget::IpAddress(CLIENT_IP),
+        ]
+    }
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn lockout_after_max_failures_per_username() {
+        let throttle = throttle();
+        let targets = targets("alice");
+
+        assert!(throttle.record_failure_at(&targets, time(0)).is_empty());
+        assert!(throttle.record_failure_at(&targets, time(1)).is_empty());
+        assert!(throttle.lockout_at(&targets, time(2)).is_none());
+
+        let lockouts = throttle.record_failure_at(&targets, time(2));
+        assert_eq!(
+            lockouts,
+            [Lockout {
+                target: LockoutTarget::Username("alice".to_owned()),
+                locked_until: time(12),
+                count: 1,
+            }]
+        );
+        assert_eq!(
+            throttle.lockout_at(&targets, time(11)),
+            Some(lockouts[0].clone())
+        );
+        assert!(throttle.lockout_at(&targets, time(12)).is_none());
+        assert!(throttle
+            .lockout_at(&[LockoutTarget::Username("bob".to_owned())], time(2))
+            .is_none());
+    }
+
+    #[test]
+    fn lockout_after_max_failures_per_ip() {
+        let throttle = throttle();
+
+        for (second, username) in (0..).zip(["alice", "bob", "carol", "dave"]) {
+            let lockouts = throttle.record_failure_at(&targets(username), time(second));
+            assert!(lockouts.is_empty());
+        }
+
+        let lockouts = throttle.record_failure_at(&targets("eve"), time(4));
+        assert_eq!(lockouts.len(), 1);
+        assert_eq!(lockouts[0].target(), &LockoutTarget::IpAddress(CLIENT_IP));
+        assert!(throttle.lockout_at(&targets("frank"), time(5)).is_some());
+    }
+
+    #[test]
+    fn failures_outside_window_are_forgotten() {
+        let throttle = throttle();
+        let targets = targets("alice");
+
+        throttle.record_failure_at(&targets, time(0));
+        throttle.record_failure_at(&targets, time(1));
+        assert!(throttle.record_failure_at(&targets, time(61)).is_empty());
+        assert!(throttle.record_failure_at(&targets, time(62)).is_empty());
+        assert_eq!(throttle.record_failure_at(&targets, time(63)).len(), 1);
+    }
+
+    #[test]
+    fn lockout_backoff() {
+        let throttle = throttle();
+        let targets = [LockoutTarget::Username("alice".to_owned())];
+
+        let mut lockout_ends = Vec::new();
+        let mut now = time(0);
+        for _ in 0..4 {
+            let mut lockouts = Vec::new();
+            while lockouts.is_empty() {
+                lockouts = throttle.record_failure_at(&targets, now);
+            }
+            now = lockouts[0].locked_until();
+            lockout_ends.push(now);
+        }
+
+        // 10 seconds, then doubled, capped at 30 seconds
+        assert_eq!(lockout_ends, [time(10), time(30), time(60), time(90)]);
+    }
+
+    #[test]
+    fn success_resets_username() {
+        let throttle = throttle();
+        let alice = targets("alice");
+
+        throttle.record_failure_at(&alice, time(0));
+        throttle.record_failure_at(&alice, time(1));
+        throttle.record_success(&alice);
+        assert!(throttle.record_failure_at(&alice, time(2)).is_empty());
+        assert!(throttle.record_failure_at(&alice, time(3)).is_empty());
+
+        // the failures from the IP address are still counted
+        let lockouts = throttle.record_failure_at(&targets("bob"), time(4));
+        assert_eq!(lockouts.len(), 1);
+        assert_eq!(lockouts[0].target(), &LockoutTarget::IpAddress(CLIENT_IP));
+    }
+
+    #[test]
+    fn lockouts_and_unlock() {
+        let throttle = throttle();
+        let targets = targets("alice");
+        for second in 0..5 {
+            throttle.record_failure_at(&targets, time(second));
+        }
+
+        let lockouts = throttle.lockouts_at(time(5));
+        assert_eq!(
+            lockouts
+                .iter()
+                .map(|lockout| lockout.target().clone())
+                .collect::<Vec<_>>(),
+            targets
+        );
+
+        assert!(throttle.unlock_at(&targets[0], time(5)));
+        assert!(!throttle.unlock_at(&targets[0], time(5)));
+        assert_eq!(throttle.lockouts_at(time(5)).len(), 1);
+        assert!(throttle.lockouts_at(time(20)).is_empty());
+    }
+
+    #[test]
+    fn zero_max_failures_disables_limit() {
+        let throttle = LoginThrottle::new(
+            &LoginThrottlingConfig::builder()
+                .max_failures_per_ip(0)
+                .build(),
+        );
+        let targets = [LockoutTarget::IpAddress(CLIENT_IP)];
+
+        for second in 0..100 {
+            assert!(throttle
+                .record_failure_at(&targets, time(second))
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn targets_disabled() {
+        let throttle = LoginThrottle::new(&LoginThrottlingConfig::builder().enabled(false).build());
+        let request = TestRequestBuilder::get("/").build();
+
+        assert!(throttle.targets(&request, "alice").is_empty());
+    }
+
+    #[test]
+    fn client_ip_from_connection() {
+        let request = TestRequestBuilder::get("/")
+            .remote_addr(SocketAddr::new(CLIENT_IP, 12345))
+            .build();
+
+        assert_eq!(client_ip(&request, None), Some(CLIENT_IP));
+        assert_eq!(client_ip(&request, Some("X-Forwarded-For")), None);
+    }
+
+    #[test]
+    fn client_ip_from_header() {
+        let mut request = TestRequestBuilder::get("/")
+            .remote_addr(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 12345))
+            .build();
+        request.headers_mut().insert(
+            "X-Forwarded-For",
+            http::HeaderValue::from_static("203.0.113.7, 192.0.2.1"),
+        );
+
+        assert_eq!(
+            client_ip(&request, Some("X-Forwarded-For")),
+            Some(CLIENT_IP)
+        );
+    }
+
+    #[test]
+    fn lockout_target_kind_and_value() {
+        for target in targets("alice") {
+            assert_eq!(
+                LockoutTarget::from_kind_and_value(target.kind(), &target.value()),
+                Some(target)
+            );
+        }
+        assert_eq!(
+            LockoutTarget::from_kind_and_value("ip_address", "alice"),
+            None
+        );
+        assert_eq!(LockoutTarget::from_kind_and_value("email", "alice"), None);
+    }
+}
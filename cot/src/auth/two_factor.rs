@@ -0,0 +1,695 @@
+//! Two-factor authentication.
+//!
+//! This module provides the time-based one-time passwords (TOTP, as specified
+//! in [RFC 6238](https://datatracker.ietf.org/doc/html/rfc6238)) that
+//! [`DatabaseUser`]s can use as the second authentication factor, along with
+//! single-use recovery codes for when they lose access to their authenticator
+//! app.
+//!
+//! To enable two-factor authentication, a user creates a [`TotpDevice`], adds
+//! it to their authenticator app using the URI returned by
+//! [`TotpDevice::provisioning_uri`] (typically shown as a QR code), and then
+//! confirms it with a code generated by the app. From then on,
+//! [`AuthRequestExt::login`] doesn't log the user in right away; instead, the
+//! login is completed by [`AuthRequestExt::verify_second_factor`] when the
+//! user enters a code from their app, or one of their [`RecoveryCode`]s.
+//!
+//! [`AuthRequestExt::login`]: crate::auth::AuthRequestExt::login
+//! [`AuthRequestExt::verify_second_factor`]: crate::auth::AuthRequestExt::verify_second_factor
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use totp_rs::{Algorithm, TOTP};
+
+use crate::admin::{get_model_list, AdminList, AdminListParams, AdminModel};
+use crate::auth::db::DatabaseUser;
+use crate::auth::{AuthError, Result};
+use crate::db::{model, query, Auto, DatabaseBackend, ForeignKey, LimitedString, Model};
+use crate::request::{Request, RequestExt};
+
+pub(crate) const MAX_TOTP_DEVICE_NAME_LENGTH: u32 = 255;
+pub(crate) const TOTP_SECRET_LENGTH: u32 = 40;
+pub(crate) const RECOVERY_CODE_HASH_LENGTH: u32 = 64;
+
+const TOTP_SECRET_BYTES: usize = 20;
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECS: u64 = 30;
+/// The number of time steps before and after the current one the codes are
+/// accepted for, to account for clock skew and slow typing.
+const TOTP_SKEW_STEPS: u64 = 1;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_BYTES: usize = 5;
+
+/// An error that occurs when creating a TOTP device.
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum CreateTotpDeviceError {
+    /// The device name is too long.
+    #[error("device name is too long (max {MAX_TOTP_DEVICE_NAME_LENGTH} characters, got {0})")]
+    NameTooLong(usize),
+}
+
+/// An authenticator app of a [`DatabaseUser`] that generates time-based
+/// one-time passwords.
+///
+/// The codes are 6 digits long, change every 30 seconds, and are generated
+/// with HMAC-SHA1, which are the settings supported by all the popular
+/// authenticator apps. Every code can only be used once.
+///
+/// # Security
+///
+/// Unlike the passwords, the shared secret has to be stored as is, since it's
+/// needed to generate the codes; anyone who can read the database can
+/// generate valid codes for the users.
+#[derive(Debug, Clone)]
+#[model]
+pub struct TotpDevice {
+    id: Auto<i64>,
+    user: ForeignKey<DatabaseUser>,
+    name: LimitedString<MAX_TOTP_DEVICE_NAME_LENGTH>,
+    secret: LimitedString<TOTP_SECRET_LENGTH>,
+    confirmed: bool,
+    last_used_step: i64,
+    created_at: DateTime<FixedOffset>,
+}
+
+impl TotpDevice {
+    /// Creates a new, unconfirmed device with a random secret for the user
+    /// and saves it to the database.
+    ///
+    /// The device is not used for authentication until it's confirmed with
+    /// [`TotpDevice::confirm`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is too long, in which case the error
+    /// source is a [`CreateTotpDeviceError`].
+    ///
+    /// Returns an error if the device could not be saved to the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::auth::two_factor::TotpDevice;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     "password123",
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    ///
+    /// let device = TotpDevice::create(&*db, &user, "Phone").await?;
+    /// let uri = device.provisioning_uri("Example", user.username());
+    /// assert!(uri.starts_with("otpauth://totp/Example:testuser?secret="));
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn create<DB: DatabaseBackend, T: Into<String>>(
+        db: &DB,
+        user: &DatabaseUser,
+        name: T,
+    ) -> Result<Self> {
+        let name = name.into();
+        let name_length = name.len();
+        let name = LimitedString::new(name).map_err(|_| {
+            AuthError::backend_error(CreateTotpDeviceError::NameTooLong(name_length))
+        })?;
+
+        let mut secret = [0; TOTP_SECRET_BYTES];
+        OsRng.fill_bytes(&mut secret);
+        let Ok(secret) = LimitedString::new(hex::encode(secret)) else {
+            unreachable!("hex-encoded secret has the correct length");
+        };
+
+        let mut device = Self {
+            id: Auto::auto(),
+            user: ForeignKey::from(user),
+            name,
+            secret,
+            confirmed: false,
+            last_used_step: 0,
+            created_at: Utc::now().fixed_offset(),
+        };
+        device.insert(db).await.map_err(AuthError::backend_error)?;
+
+        Ok(device)
+    }
+
+    /// Returns all the devices of the user, including the unconfirmed ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn for_user<DB: DatabaseBackend>(db: &DB, user: &DatabaseUser) -> Result<Vec<Self>> {
+        let devices = query!(TotpDevice, $user == user)
+            .all(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(devices)
+    }
+
+    /// Confirms the device with a code generated by the authenticator app,
+    /// which proves that the user has added the device correctly. Returns
+    /// `false` if the code is invalid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device could not be saved to the database.
+    pub async fn confirm<DB: DatabaseBackend>(&mut self, db: &DB, code: &str) -> Result<bool> {
+        if !self.verify(db, code).await? {
+            return Ok(false);
+        }
+
+        self.confirmed = true;
+        self.save(db).await.map_err(AuthError::backend_error)?;
+        Ok(true)
+    }
+
+    /// Checks whether the code is valid for this device at the current time,
+    /// and hasn't been used already. Returns `false` if it's not.
+    ///
+    /// A valid code is marked as used, so that it can't be used again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device could not be saved to the database.
+    pub async fn verify<DB: DatabaseBackend>(&mut self, db: &DB, code: &str) -> Result<bool> {
+        let Some(step) = self.matching_step(code, Utc::now()) else {
+            return Ok(false);
+        };
+
+        self.last_used_step = step;
+        self.save(db).await.map_err(AuthError::backend_error)?;
+        Ok(true)
+    }
+
+    /// Deletes the device from the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn delete<DB: DatabaseBackend>(self, db: &DB) -> Result<()> {
+        let id = self.id();
+        query!(TotpDevice, $id == id)
+            .delete(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+
+    /// Returns the `otpauth://` URI that adds the device to an authenticator
+    /// app. It's usually shown to the user as a QR code.
+    ///
+    /// The `issuer` is the name of the site, and the `account_name` is
+    /// typically the username; both are displayed in the app.
+    #[must_use]
+    pub fn provisioning_uri(&self, issuer: &str, account_name: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_STEP_SECS}",
+            percent_encode(issuer),
+            percent_encode(account_name),
+            self.secret(),
+            percent_encode(issuer),
+        )
+    }
+
+    /// Returns the shared secret encoded in base32, which the user can enter
+    /// into the authenticator app manually if they can't scan the QR code.
+    #[must_use]
+    pub fn secret(&self) -> String {
+        self.totp().get_secret_base32()
+    }
+
+    /// Get the ID of the device.
+    #[must_use]
+    pub fn id(&self) -> i64 {
+        match self.id {
+            Auto::Fixed(id) => id,
+            Auto::Auto => unreachable!("TotpDevice constructed with an unknown ID"),
+        }
+    }
+
+    /// Get the ID of the user the device belongs to.
+    #[must_use]
+    pub fn user_id(&self) -> i64 {
+        match self.user.primary_key() {
+            Auto::Fixed(id) => *id,
+            Auto::Auto => unreachable!("TotpDevice constructed with an unknown user ID"),
+        }
+    }
+
+    /// Get the human-readable name of the device.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether the device has been confirmed, and so is used for
+    /// authentication.
+    #[must_use]
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    /// Returns the time the device was created.
+    #[must_use]
+    pub fn created_at(&self) -> DateTime<FixedOffset> {
+        self.created_at
+    }
+
+    fn totp(&self) -> TOTP {
+        let Ok(secret) = hex::decode(self.secret.as_str()) else {
+            unreachable!("TotpDevice constructed with an invalid secret");
+        };
+
+        TOTP::new_unchecked(Algorithm::SHA1, TOTP_DIGITS, 0, TOTP_STEP_SECS, secret)
+    }
+
+    /// Returns the time step the code is valid for, if it's valid at given
+    /// time and hasn't been used already.
+    fn matching_step(&self, code: &str, now: DateTime<Utc>) -> Option<i64> {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        let totp = self.totp();
+        let current_step = u64::try_from(now.timestamp()).ok()? / TOTP_STEP_SECS;
+
+        (current_step.saturating_sub(TOTP_SKEW_STEPS)..=current_step + TOTP_SKEW_STEPS)
+            .filter_map(|step| Some((step, i64::try_from(step).ok()?)))
+            .filter(|&(_, step)| step > self.last_used_step)
+            .find(|&(step, _)| {
+                bool::from(
+                    totp.generate(step * TOTP_STEP_SECS)
+                        .as_bytes()
+                        .ct_eq(code.as_bytes()),
+                )
+            })
+            .map(|(_, step)| step)
+    }
+}
+
+#[async_trait]
+impl AdminModel for TotpDevice {
+    async fn get_objects(request: &Request) -> crate::Result<Vec<Self>> {
+        Ok(TotpDevice::objects()
+            .all(request.db())
+            .await
+            .map_err(AuthError::backend_error)?)
+    }
+
+    async fn get_list(
+        request: &Request,
+        params: &AdminListParams,
+    ) -> crate::Result<AdminList<Self>> {
+        get_model_list(request, params, Vec::new()).await
+    }
+
+    async fn get_object_by_id(request: &Request, id: &str) -> crate::Result<Option<Self>> {
+        let Ok(id) = id.parse::<i64>() else {
+            return Ok(None);
+        };
+
+        Ok(query!(TotpDevice, $id == id)
+            .get(request.db())
+            .await
+            .map_err(AuthError::backend_error)?)
+    }
+
+    fn name() -> &'static str {
+        "TotpDevice"
+    }
+
+    fn url_name() -> &'static str {
+        "totp_device"
+    }
+
+    fn object_id(&self) -> String {
+        self.id().to_string()
+    }
+
+    fn display(&self) -> String {
+        format!(
+            "{} of user #{}{}",
+            self.name.as_str(),
+            self.user_id(),
+            if self.confirmed { "" } else { " (unconfirmed)" }
+        )
+    }
+
+    async fn delete(&self, request: &Request) -> crate::Result<()> {
+        let id = self.id();
+        query!(TotpDevice, $id == id)
+            .delete(request.db())
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(())
+    }
+}
+
+/// A single-use code that a [`DatabaseUser`] can use instead of a code from
+/// their [`TotpDevice`], e.g. when they have lost their phone.
+///
+/// # Security
+///
+/// Only a SHA-256 hash of each code is stored in the database; the codes
+/// themselves are returned only once, when they are generated with
+/// [`RecoveryCode::generate`].
+#[derive(Debug, Clone)]
+#[model]
+pub struct RecoveryCode {
+    id: Auto<i64>,
+    user: ForeignKey<DatabaseUser>,
+    code_hash: LimitedString<RECOVERY_CODE_HASH_LENGTH>,
+    used_at: Option<DateTime<FixedOffset>>,
+}
+
+impl RecoveryCode {
+    /// Generates a new set of recovery codes for the user, replacing any
+    /// existing ones, and returns the codes, which should be shown to the
+    /// user. They can't be retrieved later, as only their hashes are stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the codes could not be saved to the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::auth::db::DatabaseUser;
+    /// use cot::auth::two_factor::RecoveryCode;
+    /// use cot::config::{PasswordHasherConfig, PasswordValidators};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// #     use cot::test::TestDatabase;
+    /// #     let mut test_database = TestDatabase::new_sqlite().await?;
+    /// #     test_database.with_auth().run_migrations().await;
+    /// #     let db = test_database.database();
+    /// let user = DatabaseUser::create_user(
+    ///     &*db,
+    ///     "testuser",
+    ///     "password123",
+    ///     &PasswordValidators::none(),
+    ///     &PasswordHasherConfig::default(),
+    /// )
+    /// .await?;
+    ///
+    /// let codes = RecoveryCode::generate(&*db, &user).await?;
+    /// assert!(RecoveryCode::verify(&*db, &user, &codes[0]).await?);
+    /// // every code can only be used once
+    /// assert!(!RecoveryCode::verify(&*db, &user, &codes[0]).await?);
+    /// #     test_database.cleanup().await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn generate<DB: DatabaseBackend>(
+        db: &DB,
+        user: &DatabaseUser,
+    ) -> Result<Vec<String>> {
+        query!(RecoveryCode, $user == user)
+            .delete(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+        for _ in 0..RECOVERY_CODE_COUNT {
+            let code = generate_recovery_code();
+            let mut recovery_code = Self {
+                id: Auto::auto(),
+                user: ForeignKey::from(user),
+                code_hash: hash_recovery_code(&code),
+                used_at: None,
+            };
+            recovery_code
+                .insert(db)
+                .await
+                .map_err(AuthError::backend_error)?;
+            codes.push(code);
+        }
+
+        Ok(codes)
+    }
+
+    /// Checks whether the code is one of the user's unused recovery codes.
+    /// Returns `false` if it's not.
+    ///
+    /// A valid code is marked as used, so that it can't be used again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn verify<DB: DatabaseBackend>(
+        db: &DB,
+        user: &DatabaseUser,
+        code: &str,
+    ) -> Result<bool> {
+        Self::verify_for(db, ForeignKey::from(user), code).await
+    }
+
+    /// Returns the number of the user's recovery codes that haven't been used
+    /// yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there was an error querying the database.
+    pub async fn remaining<DB: DatabaseBackend>(db: &DB, user: &DatabaseUser) -> Result<usize> {
+        let codes = query!(RecoveryCode, $user == user)
+            .all(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        Ok(codes.iter().filter(|code| code.used_at.is_none()).count())
+    }
+
+    async fn verify_for<DB: DatabaseBackend>(
+        db: &DB,
+        user: ForeignKey<DatabaseUser>,
+        code: &str,
+    ) -> Result<bool> {
+        let code_hash = hash_recovery_code(code);
+        let recovery_code = query!(RecoveryCode, $user == user && $code_hash == code_hash)
+            .get(db)
+            .await
+            .map_err(AuthError::backend_error)?;
+
+        match recovery_code {
+            Some(mut recovery_code) if recovery_code.used_at.is_none() => {
+                recovery_code.used_at = Some(Utc::now().fixed_offset());
+                recovery_code
+                    .save(db)
+                    .await
+                    .map_err(AuthError::backend_error)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Returns whether the user has at least one confirmed [`TotpDevice`], and so
+/// needs to verify the second factor when logging in.
+///
+/// # Errors
+///
+/// Returns an error if there was an error querying the database.
+pub async fn is_enabled<DB: DatabaseBackend>(db: &DB, user: &DatabaseUser) -> Result<bool> {
+    is_enabled_for(db, ForeignKey::from(user)).await
+}
+
+/// Checks the code against the user's confirmed [`TotpDevice`]s and unused
+/// [`RecoveryCode`]s. Returns `false` if it doesn't match any of them.
+///
+/// A valid code is marked as used, so that it can't be used again.
+///
+/// # Errors
+///
+/// Returns an error if there was an error querying the database.
+pub async fn verify<DB: DatabaseBackend>(db: &DB, user: &DatabaseUser, code: &str) -> Result<bool> {
+    verify_for(db, ForeignKey::from(user), code).await
+}
+
+pub(crate) async fn is_enabled_for<DB: DatabaseBackend>(
+    db: &DB,
+    user: ForeignKey<DatabaseUser>,
+) -> Result<bool> {
+    query!(TotpDevice, $user == user && $confirmed == true)
+        .exists(db)
+        .await
+        .map_err(AuthError::backend_error)
+}
+
+pub(crate) async fn verify_for<DB: DatabaseBackend>(
+    db: &DB,
+    user: ForeignKey<DatabaseUser>,
+    code: &str,
+) -> Result<bool> {
+    let devices = query!(TotpDevice, $user == user.clone() && $confirmed == true)
+        .all(db)
+        .await
+        .map_err(AuthError::backend_error)?;
+    for mut device in devices {
+        if device.verify(db, code).await? {
+            return Ok(true);
+        }
+    }
+
+    RecoveryCode::verify_for(db, user, code).await
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0; RECOVERY_CODE_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+
+    let code = hex::encode(bytes);
+    let (first, second) = code.split_at(code.len() / 2);
+    format!("{first}-{second}")
+}
+
+fn hash_recovery_code(code: &str) -> LimitedString<RECOVERY_CODE_HASH_LENGTH> {
+    let normalized: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    LimitedString::new(hex::encode(Sha256::digest(normalized.as_bytes())))
+        .expect("SHA-256 hex digest has the correct length")
+}
+
+/// Percent-encodes everything except the unreserved characters, as required
+/// in the labels and parameters of the `otpauth://` URIs.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 20-byte secret from the test vectors in RFC 6238.
+    const RFC_SECRET: &str = "3132333435363738393031323334353637383930";
+
+    fn device(last_used_step: i64) -> TotpDevice {
+        TotpDevice {
+            id: Auto::fixed(1),
+            user: ForeignKey::PrimaryKey(Auto::fixed(1)),
+            name: LimitedString::new("Phone").unwrap(),
+            secret: LimitedString::new(RFC_SECRET).unwrap(),
+            confirmed: true,
+            last_used_step,
+            created_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    fn time(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap()
+    }
+
+    #[test]
+    fn rfc_6238_test_vectors() {
+        // the SHA-1 test vectors, truncated to 6 digits
+        let device = device(0);
+        assert_eq!(device.matching_step("287082", time(59)), Some(1));
+        assert_eq!(
+            device.matching_step("081804", time(1_111_111_109)),
+            Some(37_037_036)
+        );
+        assert_eq!(
+            device.matching_step("005924", time(1_234_567_890)),
+            Some(41_152_263)
+        );
+        assert_eq!(
+            device.matching_step("279037", time(2_000_000_000)),
+            Some(66_666_666)
+        );
+    }
+
+    #[test]
+    fn code_with_whitespace() {
+        assert_eq!(device(0).matching_step("287 082", time(59)), Some(1));
+    }
+
+    #[test]
+    fn code_skew() {
+        let device = device(0);
+        // the code for step 1 is accepted one step before and after
+        assert_eq!(device.matching_step("287082", time(89)), Some(1));
+        assert_eq!(device.matching_step("287082", time(0)), Some(1));
+        assert_eq!(device.matching_step("287082", time(90)), None);
+    }
+
+    #[test]
+    fn code_cannot_be_reused() {
+        assert_eq!(device(1).matching_step("287082", time(59)), None);
+    }
+
+    #[test]
+    fn invalid_code() {
+        let device = device(0);
+        assert_eq!(device.matching_step("000000", time(59)), None);
+        assert_eq!(device.matching_step("", time(59)), None);
+        assert_eq!(device.matching_step("2870821", time(59)), None);
+    }
+
+    #[test]
+    fn provisioning_uri() {
+        let device = device(0);
+
+        assert_eq!(device.secret(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(
+            device.provisioning_uri("My Site", "alice@example.com"),
+            "otpauth://totp/My%20Site:alice%40example.com\
+             ?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=My%20Site\
+             &algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn recovery_code_format() {
+        let code = generate_recovery_code();
+
+        assert_eq!(code.len(), 11);
+        assert_eq!(code.as_bytes()[5], b'-');
+        assert_ne!(code, generate_recovery_code());
+    }
+
+    #[test]
+    fn recovery_code_hash_normalized() {
+        assert_eq!(
+            hash_recovery_code("abcde-01234"),
+            hash_recovery_code(" ABCDE01234 ")
+        );
+        assert_ne!(
+            hash_recovery_code("abcde-01234"),
+            hash_recovery_code("abcde-01235")
+        );
+    }
+}
@@ -6,9 +6,15 @@ use std::str::FromStr;
 
 use async_trait::async_trait;
 pub use clap;
-use clap::{value_parser, Arg, ArgMatches, Command};
+#[cfg(feature = "db")]
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use derive_more::Debug;
 
+#[cfg(feature = "db")]
+use crate::db::migrations::{DynMigration, MigrationEngine, SyncDynMigration};
+#[cfg(feature = "db")]
+use crate::db::DatabaseEngine;
 use crate::error::ErrorRepr;
 use crate::{Bootstrapper, Error, Result};
 
@@ -17,6 +23,18 @@ const COLLECT_STATIC_SUBCOMMAND: &str = "collect-static";
 const CHECK_SUBCOMMAND: &str = "check";
 const LISTEN_PARAM: &str = "listen";
 const COLLECT_STATIC_DIR_PARAM: &str = "dir";
+#[cfg(feature = "db")]
+const SQL_MIGRATE_SUBCOMMAND: &str = "sql-migrate";
+#[cfg(feature = "db")]
+const SQL_MIGRATE_APP_PARAM: &str = "app";
+#[cfg(feature = "db")]
+const SQL_MIGRATE_MIGRATION_PARAM: &str = "migration";
+#[cfg(feature = "db")]
+const SQL_MIGRATE_BACKWARDS_PARAM: &str = "backwards";
+#[cfg(feature = "db")]
+const SQL_MIGRATE_ENGINE_PARAM: &str = "engine";
+#[cfg(feature = "db")]
+const CHECK_MIGRATIONS_SUBCOMMAND: &str = "check-migrations";
 
 #[derive(Debug)]
 pub struct Cli {
@@ -46,6 +64,10 @@ impl Cli {
         let mut cli = Self { command, tasks };
         cli.add_task(Check);
         cli.add_task(CollectStatic);
+        #[cfg(feature = "db")]
+        cli.add_task(SqlMigrate);
+        #[cfg(feature = "db")]
+        cli.add_task(CheckMigrations);
 
         cli
     }
@@ -261,6 +283,160 @@ impl CliTask for Check {
     }
 }
 
+#[cfg(feature = "db")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct SqlMigrate;
+
+#[cfg(feature = "db")]
+#[async_trait(?Send)]
+impl CliTask for SqlMigrate {
+    fn subcommand(&self) -> Command {
+        Command::new(SQL_MIGRATE_SUBCOMMAND)
+            .about("Prints the SQL statements for a migration without applying it")
+            .arg(
+                Arg::new(SQL_MIGRATE_APP_PARAM)
+                    .help("The name of the app the migration belongs to")
+                    .required(true),
+            )
+            .arg(
+                Arg::new(SQL_MIGRATE_MIGRATION_PARAM)
+                    .help("The name of the migration")
+                    .required(true),
+            )
+            .arg(
+                Arg::new(SQL_MIGRATE_BACKWARDS_PARAM)
+                    .help("Print the SQL for unapplying the migration instead")
+                    .long("backwards")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new(SQL_MIGRATE_ENGINE_PARAM)
+                    .help(
+                        "The database engine to generate the SQL for \
+                        (default: the engine of the configured database)",
+                    )
+                    .long("engine")
+                    .value_name("ENGINE")
+                    .value_parser(PossibleValuesParser::new(
+                        DatabaseEngine::all().iter().map(DatabaseEngine::name),
+                    )),
+            )
+    }
+
+    async fn execute(
+        &mut self,
+        matches: &ArgMatches,
+        bootstrapper: Bootstrapper<WithConfig>,
+    ) -> Result<()> {
+        let app_name = matches
+            .get_one::<String>(SQL_MIGRATE_APP_PARAM)
+            .expect("required argument");
+        let migration_name = matches
+            .get_one::<String>(SQL_MIGRATE_MIGRATION_PARAM)
+            .expect("required argument");
+        let backwards = matches.get_flag(SQL_MIGRATE_BACKWARDS_PARAM);
+
+        let engine = match matches.get_one::<String>(SQL_MIGRATE_ENGINE_PARAM) {
+            Some(name) => DatabaseEngine::from_name(name).expect("value validated by clap"),
+            None => bootstrapper
+                .context()
+                .config()
+                .database
+                .url
+                .as_ref()
+                .and_then(|url| DatabaseEngine::from_url(url.as_str()))
+                .ok_or_else(|| Error::new(ErrorRepr::UnknownDatabaseEngine))?,
+        };
+
+        let bootstrapper = bootstrapper.with_apps();
+        let migration = bootstrapper
+            .context()
+            .apps()
+            .iter()
+            .flat_map(|app| app.migrations())
+            .find(|migration| {
+                migration.app_name() == app_name && migration.name() == migration_name
+            })
+            .ok_or_else(|| {
+                Error::new(ErrorRepr::MigrationNotFound {
+                    app: app_name.clone(),
+                    migration: migration_name.clone(),
+                })
+            })?;
+
+        for statement in migration_sql(&migration, engine, backwards) {
+            println!("{statement};");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "db")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct CheckMigrations;
+
+#[cfg(feature = "db")]
+#[async_trait(?Send)]
+impl CliTask for CheckMigrations {
+    fn subcommand(&self) -> Command {
+        Command::new(CHECK_MIGRATIONS_SUBCOMMAND).about(
+            "Verifies the configuration and that the database schema matches the applied \
+            migrations",
+        )
+    }
+
+    async fn execute(
+        &mut self,
+        _matches: &ArgMatches,
+        bootstrapper: Bootstrapper<WithConfig>,
+    ) -> Result<()> {
+        let (context, _handler) = bootstrapper.boot().await?.into_context_and_handler();
+        let database = context
+            .try_database()
+            .ok_or_else(|| Error::new(ErrorRepr::DatabaseNotConfigured))?;
+
+        let migrations: Vec<Box<SyncDynMigration>> = context
+            .apps()
+            .iter()
+            .flat_map(|app| app.migrations())
+            .collect();
+        let differences = MigrationEngine::new(migrations)?
+            .schema_differences(database)
+            .await?;
+        if !differences.is_empty() {
+            return Err(Error::new(ErrorRepr::SchemaDrift { differences }));
+        }
+
+        println!("Database schema matches the applied migrations");
+        Ok(())
+    }
+}
+
+/// Returns the SQL statements that applying (or unapplying, if `backwards` is
+/// `true`) given migration would execute on given database engine.
+#[cfg(feature = "db")]
+fn migration_sql<T: DynMigration>(
+    migration: &T,
+    engine: DatabaseEngine,
+    backwards: bool,
+) -> Vec<String> {
+    if backwards {
+        migration
+            .operations()
+            .iter()
+            .rev()
+            .map(|operation| operation.backwards_sql(engine))
+            .collect()
+    } else {
+        migration
+            .operations()
+            .iter()
+            .map(|operation| operation.forwards_sql(engine))
+            .collect()
+    }
+}
+
 /// A macro to generate a [`CliMetadata`] struct from the Cargo manifest.
 #[macro_export]
 macro_rules! metadata {
@@ -413,6 +589,310 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[cfg(feature = "db")]
+    mod sql_migrate {
+        use super::*;
+        use crate::db::migrations::{
+            Field, Migration, MigrationDependency, Operation, SyncDynMigration,
+        };
+        use crate::db::{DatabaseField, Identifier};
+
+        struct TestMigration;
+
+        impl Migration for TestMigration {
+            const APP_NAME: &'static str = "test_app";
+            const MIGRATION_NAME: &'static str = "m_0001_initial";
+            const DEPENDENCIES: &'static [MigrationDependency] = &[];
+            const OPERATIONS: &'static [Operation] = &[
+                Operation::create_model()
+                    .table_name(Identifier::new("test_app__test_model"))
+                    .fields(&[
+                        Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+                            .primary_key()
+                            .auto(),
+                    ])
+                    .build(),
+                Operation::add_field()
+                    .table_name(Identifier::new("test_app__test_model"))
+                    .field(Field::new(
+                        Identifier::new("name"),
+                        <String as DatabaseField>::TYPE,
+                    ))
+                    .build(),
+            ];
+        }
+
+        struct TestApp;
+
+        impl App for TestApp {
+            fn name(&self) -> &'static str {
+                "test_app"
+            }
+
+            fn migrations(&self) -> Vec<Box<SyncDynMigration>> {
+                vec![Box::new(TestMigration)]
+            }
+        }
+
+        struct TestProject;
+
+        impl cot::Project for TestProject {
+            fn register_apps(&self, apps: &mut AppBuilder, _context: &ProjectContext<WithConfig>) {
+                apps.register(TestApp);
+            }
+        }
+
+        #[test]
+        fn sql_migrate_subcommand() {
+            let matches = SqlMigrate.subcommand().try_get_matches_from(vec![
+                "test",
+                "test_app",
+                "m_0001_initial",
+                "--backwards",
+                "--engine",
+                "postgres",
+            ]);
+
+            let matches = matches.unwrap();
+            assert_eq!(
+                matches.get_one::<String>(SQL_MIGRATE_APP_PARAM).unwrap(),
+                "test_app"
+            );
+            assert!(matches.get_flag(SQL_MIGRATE_BACKWARDS_PARAM));
+        }
+
+        #[test]
+        fn sql_migrate_subcommand_invalid_engine() {
+            let matches = SqlMigrate.subcommand().try_get_matches_from(vec![
+                "test",
+                "test_app",
+                "m_0001_initial",
+                "--engine",
+                "oracle",
+            ]);
+
+            assert!(matches.is_err());
+        }
+
+        #[test]
+        fn migration_sql_forwards() {
+            let sql = migration_sql(&TestMigration, DatabaseEngine::Sqlite, false);
+
+            assert_eq!(
+                sql,
+                vec![
+                    r#"CREATE TABLE "test_app__test_model" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT )"#,
+                    r#"ALTER TABLE "test_app__test_model" ADD COLUMN "name" text NOT NULL"#,
+                ]
+            );
+        }
+
+        #[test]
+        fn migration_sql_backwards() {
+            let sql = migration_sql(&TestMigration, DatabaseEngine::Sqlite, true);
+
+            assert_eq!(
+                sql,
+                vec![
+                    r#"ALTER TABLE "test_app__test_model" DROP COLUMN "name""#,
+                    r#"DROP TABLE "test_app__test_model""#,
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn sql_migrate_execute() {
+            let matches = SqlMigrate.subcommand().get_matches_from(vec![
+                "test",
+                "test_app",
+                "m_0001_initial",
+                "--engine",
+                "mysql",
+            ]);
+
+            let bootstrapper = Bootstrapper::new(TestProject).with_config(ProjectConfig::default());
+            let result = SqlMigrate.execute(&matches, bootstrapper).await;
+
+            assert!(result.is_ok(), "{result:?}");
+        }
+
+        #[tokio::test]
+        async fn sql_migrate_execute_engine_from_config() {
+            let matches = SqlMigrate.subcommand().get_matches_from(vec![
+                "test",
+                "test_app",
+                "m_0001_initial",
+            ]);
+
+            let config = ProjectConfig::from_toml(
+                r#"
+                [database]
+                url = "sqlite::memory:"
+                "#,
+            )
+            .unwrap();
+            let bootstrapper = Bootstrapper::new(TestProject).with_config(config);
+            let result = SqlMigrate.execute(&matches, bootstrapper).await;
+
+            assert!(result.is_ok(), "{result:?}");
+        }
+
+        #[tokio::test]
+        async fn sql_migrate_execute_no_engine() {
+            let matches = SqlMigrate.subcommand().get_matches_from(vec![
+                "test",
+                "test_app",
+                "m_0001_initial",
+            ]);
+
+            let bootstrapper = Bootstrapper::new(TestProject).with_config(ProjectConfig::default());
+            let result = SqlMigrate.execute(&matches, bootstrapper).await;
+
+            assert!(result.is_err());
+        }
+
+        #[tokio::test]
+        async fn sql_migrate_execute_migration_not_found() {
+            let matches = SqlMigrate.subcommand().get_matches_from(vec![
+                "test",
+                "test_app",
+                "m_0002_missing",
+                "--engine",
+                "sqlite",
+            ]);
+
+            let bootstrapper = Bootstrapper::new(TestProject).with_config(ProjectConfig::default());
+            let result = SqlMigrate.execute(&matches, bootstrapper).await;
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Migration `m_0002_missing` not found in app `test_app`"
+            );
+        }
+    }
+
+    #[cfg(feature = "db")]
+    mod check_migrations {
+        use super::*;
+        use crate::db::migrations::{
+            Field, Migration, MigrationDependency, MigrationEngine, Operation, SyncDynMigration,
+        };
+        use crate::db::{Database, DatabaseField, Identifier};
+
+        struct TestMigration;
+
+        impl Migration for TestMigration {
+            const APP_NAME: &'static str = "test_app";
+            const MIGRATION_NAME: &'static str = "m_0001_initial";
+            const DEPENDENCIES: &'static [MigrationDependency] = &[];
+            const OPERATIONS: &'static [Operation] = &[Operation::create_model()
+                .table_name(Identifier::new("test_app__test_model"))
+                .fields(&[
+                    Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+                        .primary_key()
+                        .auto(),
+                    Field::new(Identifier::new("name"), <String as DatabaseField>::TYPE),
+                ])
+                .build()];
+        }
+
+        struct TestApp;
+
+        impl App for TestApp {
+            fn name(&self) -> &'static str {
+                "test_app"
+            }
+
+            fn migrations(&self) -> Vec<Box<SyncDynMigration>> {
+                vec![Box::new(TestMigration)]
+            }
+        }
+
+        struct TestProject;
+
+        impl cot::Project for TestProject {
+            fn register_apps(&self, apps: &mut AppBuilder, _context: &ProjectContext<WithConfig>) {
+                apps.register(TestApp);
+            }
+        }
+
+        fn config_for(database_url: &str) -> ProjectConfig {
+            ProjectConfig::from_toml(&format!(
+                r#"
+                [database]
+                url = "{database_url}"
+                "#
+            ))
+            .unwrap()
+        }
+
+        #[tokio::test]
+        #[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+        async fn check_migrations_execute() {
+            let temp_dir = tempdir().unwrap();
+            let database_url = format!(
+                "sqlite://{}?mode=rwc",
+                temp_dir.path().join("db.sqlite3").display()
+            );
+            let database = Database::new(&database_url).await.unwrap();
+            MigrationEngine::new([TestMigration])
+                .unwrap()
+                .run(&database)
+                .await
+                .unwrap();
+
+            let matches = CheckMigrations.subcommand().get_matches_from(vec!["test"]);
+            let bootstrapper =
+                Bootstrapper::new(TestProject).with_config(config_for(&database_url));
+            let result = CheckMigrations.execute(&matches, bootstrapper).await;
+
+            assert!(result.is_ok(), "{result:?}");
+        }
+
+        #[tokio::test]
+        #[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+        async fn check_migrations_execute_drift() {
+            let temp_dir = tempdir().unwrap();
+            let database_url = format!(
+                "sqlite://{}?mode=rwc",
+                temp_dir.path().join("db.sqlite3").display()
+            );
+            let database = Database::new(&database_url).await.unwrap();
+            MigrationEngine::new([TestMigration])
+                .unwrap()
+                .run(&database)
+                .await
+                .unwrap();
+            database
+                .raw("ALTER TABLE test_app__test_model DROP COLUMN name")
+                .await
+                .unwrap();
+
+            let matches = CheckMigrations.subcommand().get_matches_from(vec!["test"]);
+            let bootstrapper =
+                Bootstrapper::new(TestProject).with_config(config_for(&database_url));
+            let result = CheckMigrations.execute(&matches, bootstrapper).await;
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Database schema does not match the applied migrations:\n  \
+                - column `test_app__test_model.name` does not exist"
+            );
+        }
+
+        #[tokio::test]
+        async fn check_migrations_execute_no_database() {
+            let matches = CheckMigrations.subcommand().get_matches_from(vec!["test"]);
+            let bootstrapper = Bootstrapper::new(TestProject).with_config(ProjectConfig::default());
+            let result = CheckMigrations.execute(&matches, bootstrapper).await;
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "Database is not configured; set the database URL in the config"
+            );
+        }
+    }
+
     async fn test_check(config: &str) -> Result<()> {
         let temp_dir = tempdir().unwrap();
         let config_path = temp_dir.path().join("config").clone();
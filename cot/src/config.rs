@@ -139,6 +139,129 @@ pub struct ProjectConfig {
     /// # Ok::<(), cot::Error>(())
     /// ```
     pub auth_backend: AuthBackendConfig,
+    /// The URL of the login page.
+    ///
+    /// Users that are not logged in are redirected here when they try to
+    /// access a route guarded with
+    /// [`Route::require_login`](crate::router::Route::require_login) or
+    /// [`Route::require_permissions`](crate::router::Route::require_permissions).
+    /// The URL of the page they tried to access is passed in the `next` query
+    /// parameter. The default is `/login/`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::ProjectConfig;
+    ///
+    /// let config = ProjectConfig::from_toml(
+    ///     r#"
+    /// login_url = "/accounts/login/"
+    /// "#,
+    /// )?;
+    ///
+    /// assert_eq!(config.login_url, "/accounts/login/");
+    /// # Ok::<(), cot::Error>(())
+    /// ```
+    #[builder(setter(into))]
+    pub login_url: String,
+    /// The validators that the passwords of the users have to pass.
+    ///
+    /// These are checked when creating a user or changing their password with
+    /// [`DatabaseUser`](crate::auth::db::DatabaseUser), as well as in the
+    /// password reset form. The default is
+    /// [`PasswordValidators::recommended`]; use [`PasswordValidators::none`]
+    /// to accept any password.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{PasswordValidatorConfig, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::from_toml(
+    ///     r#"
+    /// [[password_validators]]
+    /// type = "minimum_length"
+    /// min_length = 10
+    ///
+    /// [[password_validators]]
+    /// type = "common_password"
+    /// "#,
+    /// )?;
+    ///
+    /// assert_eq!(
+    ///     config.password_validators.as_slice(),
+    ///     &[
+    ///         PasswordValidatorConfig::MinimumLength { min_length: 10 },
+    ///         PasswordValidatorConfig::CommonPassword,
+    ///     ]
+    /// );
+    /// # Ok::<(), cot::Error>(())
+    /// ```
+    pub password_validators: PasswordValidators,
+    /// The algorithm and parameters used to hash the passwords of the users.
+    ///
+    /// New password hashes are always created with this hasher. Existing
+    /// hashes created with any of the supported algorithms (Argon2, scrypt,
+    /// and PBKDF2-SHA256) can still be verified, which makes it possible to
+    /// import users from other systems. When a user logs in and their stored
+    /// hash uses a different algorithm or different parameters than the ones
+    /// configured here, the password is transparently rehashed and saved.
+    ///
+    /// The default is Argon2id with the parameters recommended by OWASP.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{PasswordHasherConfig, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::from_toml(
+    ///     r#"
+    /// [password_hasher]
+    /// type = "argon2"
+    /// memory_cost = 65536
+    /// time_cost = 3
+    /// "#,
+    /// )?;
+    ///
+    /// assert_eq!(
+    ///     config.password_hasher,
+    ///     PasswordHasherConfig::Argon2 {
+    ///         memory_cost: 65536,
+    ///         time_cost: 3,
+    ///         parallelism: 1,
+    ///     }
+    /// );
+    /// # Ok::<(), cot::Error>(())
+    /// ```
+    pub password_hasher: PasswordHasherConfig,
+    /// The limits on failed login attempts.
+    ///
+    /// Failed login attempts are counted per username and per client IP
+    /// address, and when there are too many of them within a time window, the
+    /// username or the IP address is locked out for a while. See
+    /// [`LoginThrottlingConfig`] for the details and the default limits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::ProjectConfig;
+    ///
+    /// let config = ProjectConfig::from_toml(
+    ///     r#"
+    /// [login_throttling]
+    /// max_failures_per_username = 3
+    /// client_ip_header = "X-Forwarded-For"
+    /// "#,
+    /// )?;
+    ///
+    /// assert_eq!(config.login_throttling.max_failures_per_username, 3);
+    /// assert_eq!(
+    ///     config.login_throttling.client_ip_header.as_deref(),
+    ///     Some("X-Forwarded-For")
+    /// );
+    /// # Ok::<(), cot::Error>(())
+    /// ```
+    pub login_throttling: LoginThrottlingConfig,
     /// Configuration related to the database.
     ///
     /// # Examples
@@ -185,6 +308,10 @@ const fn default_debug() -> bool {
     cfg!(debug_assertions)
 }
 
+fn default_login_url() -> String {
+    String::from("/login/")
+}
+
 impl Default for ProjectConfig {
     fn default() -> Self {
         ProjectConfig::builder().build()
@@ -275,7 +402,11 @@ impl ProjectConfigBuilder {
             register_panic_hook: self.register_panic_hook.unwrap_or(true),
             secret_key: self.secret_key.clone().unwrap_or_default(),
             fallback_secret_keys: self.fallback_secret_keys.clone().unwrap_or_default(),
-            auth_backend: self.auth_backend.unwrap_or_default(),
+            auth_backend: self.auth_backend.clone().unwrap_or_default(),
+            login_url: self.login_url.clone().unwrap_or_else(default_login_url),
+            password_validators: self.password_validators.clone().unwrap_or_default(),
+            password_hasher: self.password_hasher.clone().unwrap_or_default(),
+            login_throttling: self.login_throttling.clone().unwrap_or_default(),
             #[cfg(feature = "db")]
             database: self.database.clone().unwrap_or_default(),
             middlewares: self.middlewares.clone().unwrap_or_default(),
@@ -292,7 +423,7 @@ impl ProjectConfigBuilder {
 ///
 /// let config = AuthBackendConfig::Database;
 /// ```
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AuthBackendConfig {
     /// No authentication backend.
@@ -307,6 +438,77 @@ pub enum AuthBackendConfig {
     /// to be used as the authentication backend.
     #[cfg(feature = "db")]
     Database,
+    /// API token authentication backend.
+    ///
+    /// This enables [`ApiTokenBackend`](cot::auth::api_token::ApiTokenBackend)
+    /// to be used as the authentication backend, which authenticates the
+    /// users with both API tokens and their usernames and passwords.
+    #[cfg(feature = "db")]
+    ApiToken,
+    /// JSON Web Token (JWT) authentication backend.
+    ///
+    /// This enables [`JwtBackend`](cot::auth::jwt::JwtBackend) to be used as
+    /// the authentication backend, which authenticates the users with tokens
+    /// issued by an external identity provider.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{AuthBackendConfig, JwtKeyConfig, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::from_toml(
+    ///     r#"
+    /// [auth_backend]
+    /// type = "jwt"
+    /// issuer = "https://id.example.com"
+    ///
+    /// [[auth_backend.keys]]
+    /// algorithm = "HS256"
+    /// secret = "supersecret"
+    /// "#,
+    /// )?;
+    ///
+    /// let AuthBackendConfig::Jwt(jwt_config) = &config.auth_backend else {
+    ///     panic!("expected the JWT backend");
+    /// };
+    /// assert_eq!(jwt_config.keys, [JwtKeyConfig::hs256("supersecret")]);
+    /// # Ok::<(), cot::Error>(())
+    /// ```
+    #[cfg(feature = "jwt")]
+    Jwt(JwtConfig),
+    /// A chain of authentication backends.
+    ///
+    /// This enables [`ChainAuthBackend`](cot::auth::chain::ChainAuthBackend)
+    /// to be used as the authentication backend, which tries the listed
+    /// backends in order. Each backend is identified in the session by its
+    /// type, followed by its position in the list if there are more backends
+    /// of the same type (e.g. `jwt` and `jwt_2`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{AuthBackendConfig, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::from_toml(
+    ///     r#"
+    /// [auth_backend]
+    /// type = "chain"
+    /// backends = [{ type = "none" }, { type = "none" }]
+    /// "#,
+    /// )?;
+    ///
+    /// assert_eq!(
+    ///     config.auth_backend,
+    ///     AuthBackendConfig::Chain {
+    ///         backends: vec![AuthBackendConfig::None, AuthBackendConfig::None],
+    ///     }
+    /// );
+    /// # Ok::<(), cot::Error>(())
+    /// ```
+    Chain {
+        /// The backends to try, in order.
+        backends: Vec<AuthBackendConfig>,
+    },
 }
 
 impl Default for AuthBackendConfig {
@@ -323,6 +525,652 @@ impl Default for AuthBackendConfig {
     }
 }
 
+/// The list of password validators.
+///
+/// It is used as part of the [`ProjectConfig`] struct. The validators are
+/// checked in the order they are listed. The default is
+/// [`PasswordValidators::recommended`].
+///
+/// # Examples
+///
+/// ```
+/// use cot::config::{PasswordValidatorConfig, PasswordValidators};
+///
+/// let validators = PasswordValidators::new(vec![
+///     PasswordValidatorConfig::MinimumLength { min_length: 12 },
+///     PasswordValidatorConfig::Numeric,
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PasswordValidators(Vec<PasswordValidatorConfig>);
+
+impl PasswordValidators {
+    /// Creates a new list of password validators.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{PasswordValidatorConfig, PasswordValidators};
+    ///
+    /// let validators = PasswordValidators::new(vec![PasswordValidatorConfig::CommonPassword]);
+    /// ```
+    #[must_use]
+    pub fn new(validators: Vec<PasswordValidatorConfig>) -> Self {
+        Self(validators)
+    }
+
+    /// Returns an empty list of password validators, i.e. one that accepts
+    /// any password.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::PasswordValidators;
+    ///
+    /// let validators = PasswordValidators::none();
+    /// assert!(validators.as_slice().is_empty());
+    /// ```
+    #[must_use]
+    pub fn none() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Returns the recommended set of password validators.
+    ///
+    /// This checks that the password is at least 8 characters long, is not
+    /// too similar to the username, is not one of the commonly used
+    /// passwords, and is not entirely numeric.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{PasswordValidators, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::builder()
+    ///     .password_validators(PasswordValidators::recommended())
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn recommended() -> Self {
+        Self(vec![
+            PasswordValidatorConfig::UserAttributeSimilarity,
+            PasswordValidatorConfig::MinimumLength { min_length: 8 },
+            PasswordValidatorConfig::CommonPassword,
+            PasswordValidatorConfig::Numeric,
+        ])
+    }
+
+    /// Returns the validators as a slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::PasswordValidators;
+    ///
+    /// let validators = PasswordValidators::recommended();
+    /// assert_eq!(validators.as_slice().len(), 4);
+    /// ```
+    #[must_use]
+    pub fn as_slice(&self) -> &[PasswordValidatorConfig] {
+        &self.0
+    }
+}
+
+impl Default for PasswordValidators {
+    fn default() -> Self {
+        Self::recommended()
+    }
+}
+
+impl From<Vec<PasswordValidatorConfig>> for PasswordValidators {
+    fn from(validators: Vec<PasswordValidatorConfig>) -> Self {
+        Self::new(validators)
+    }
+}
+
+/// The configuration for a single password validator.
+///
+/// # Examples
+///
+/// ```
+/// use cot::config::PasswordValidatorConfig;
+///
+/// let config = PasswordValidatorConfig::MinimumLength { min_length: 8 };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PasswordValidatorConfig {
+    /// Checks that the password has at least `min_length` characters.
+    MinimumLength {
+        /// The minimum number of characters in the password.
+        min_length: u32,
+    },
+    /// Checks that the password is not one of the commonly used passwords
+    /// bundled with Cot.
+    CommonPassword,
+    /// Checks that the password is not too similar to the username.
+    UserAttributeSimilarity,
+    /// Checks that the password is not entirely numeric.
+    Numeric,
+}
+
+/// The configuration of the algorithm used to hash the user passwords.
+///
+/// It is used as part of the [`ProjectConfig`] struct. The parameters that
+/// are not specified in the TOML configuration file default to the values
+/// returned by [`PasswordHasherConfig::argon2`],
+/// [`PasswordHasherConfig::scrypt`], and [`PasswordHasherConfig::pbkdf2`].
+///
+/// # Examples
+///
+/// ```
+/// use cot::config::PasswordHasherConfig;
+///
+/// let config = PasswordHasherConfig::Scrypt {
+///     log_n: 15,
+///     r: 8,
+///     p: 1,
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PasswordHasherConfig {
+    /// The Argon2id algorithm.
+    Argon2 {
+        /// The memory size, in kibibytes, at most 1,048,576 (1 gibibyte).
+        #[serde(default = "default_argon2_memory_cost")]
+        memory_cost: u32,
+        /// The number of iterations, at most 100.
+        #[serde(default = "default_argon2_time_cost")]
+        time_cost: u32,
+        /// The degree of parallelism, at most 16.
+        #[serde(default = "default_argon2_parallelism")]
+        parallelism: u32,
+    },
+    /// The scrypt algorithm.
+    ///
+    /// The memory used, `128 * r * 2^log_n` bytes, is limited to 1 gibibyte.
+    Scrypt {
+        /// The base 2 logarithm of the CPU/memory cost parameter `N`, at most
+        /// 20.
+        #[serde(default = "default_scrypt_log_n")]
+        log_n: u8,
+        /// The block size, at most 32.
+        #[serde(default = "default_scrypt_r")]
+        r: u32,
+        /// The parallelization parameter, at most 16.
+        #[serde(default = "default_scrypt_p")]
+        p: u32,
+    },
+    /// The PBKDF2 algorithm with HMAC-SHA256.
+    Pbkdf2 {
+        /// The number of iterations, at most 10,000,000.
+        #[serde(default = "default_pbkdf2_iterations")]
+        iterations: u32,
+    },
+}
+
+impl PasswordHasherConfig {
+    /// Returns the Argon2id hasher with the default parameters (19 mebibytes
+    /// of memory, 2 iterations, and the degree of parallelism of 1).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::PasswordHasherConfig;
+    ///
+    /// assert_eq!(
+    ///     PasswordHasherConfig::argon2(),
+    ///     PasswordHasherConfig::default()
+    /// );
+    /// ```
+    #[must_use]
+    pub fn argon2() -> Self {
+        Self::Argon2 {
+            memory_cost: default_argon2_memory_cost(),
+            time_cost: default_argon2_time_cost(),
+            parallelism: default_argon2_parallelism(),
+        }
+    }
+
+    /// Returns the scrypt hasher with the default parameters (`log_n = 17`,
+    /// `r = 8`, `p = 1`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{PasswordHasherConfig, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::builder()
+    ///     .password_hasher(PasswordHasherConfig::scrypt())
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn scrypt() -> Self {
+        Self::Scrypt {
+            log_n: default_scrypt_log_n(),
+            r: default_scrypt_r(),
+            p: default_scrypt_p(),
+        }
+    }
+
+    /// Returns the PBKDF2-SHA256 hasher with the default number of
+    /// iterations (600 000).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{PasswordHasherConfig, ProjectConfig};
+    ///
+    /// let config = ProjectConfig::builder()
+    ///     .password_hasher(PasswordHasherConfig::pbkdf2())
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn pbkdf2() -> Self {
+        Self::Pbkdf2 {
+            iterations: default_pbkdf2_iterations(),
+        }
+    }
+}
+
+impl Default for PasswordHasherConfig {
+    fn default() -> Self {
+        Self::argon2()
+    }
+}
+
+const fn default_argon2_memory_cost() -> u32 {
+    19 * 1024
+}
+
+const fn default_argon2_time_cost() -> u32 {
+    2
+}
+
+const fn default_argon2_parallelism() -> u32 {
+    1
+}
+
+const fn default_scrypt_log_n() -> u8 {
+    17
+}
+
+const fn default_scrypt_r() -> u32 {
+    8
+}
+
+const fn default_scrypt_p() -> u32 {
+    1
+}
+
+const fn default_pbkdf2_iterations() -> u32 {
+    600_000
+}
+
+/// The configuration for the JWT authentication backend.
+///
+/// It is used as part of the [`AuthBackendConfig::Jwt`] variant. The tokens
+/// are validated against the keys listed in [`JwtConfig::keys`] and the keys
+/// loaded from [`JwtConfig::jwks_file`].
+///
+/// # Examples
+///
+/// ```
+/// use cot::config::{JwtConfig, JwtKeyConfig};
+///
+/// let config = JwtConfig::builder()
+///     .keys(vec![JwtKeyConfig::hs256("supersecret")])
+///     .issuer("https://id.example.com")
+///     .build();
+/// ```
+#[cfg(feature = "jwt")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Builder, Serialize, Deserialize)]
+#[builder(build_fn(skip, error = std::convert::Infallible))]
+#[serde(default)]
+pub struct JwtConfig {
+    /// The keys the tokens can be signed with.
+    pub keys: Vec<JwtKeyConfig>,
+    /// The path to a JSON Web Key Set (JWKS) file with additional keys the
+    /// tokens can be signed with, as published by most identity providers.
+    ///
+    /// The keys in the file are matched with the tokens using their key IDs.
+    /// HMAC, RSA and Ed25519 keys are supported; other keys are ignored.
+    #[builder(setter(into, strip_option), default)]
+    pub jwks_file: Option<std::path::PathBuf>,
+    /// The expected issuer of the tokens (the `iss` claim). If it's not set,
+    /// the issuer is not checked.
+    #[builder(setter(into, strip_option), default)]
+    pub issuer: Option<String>,
+    /// The expected audience of the tokens (the `aud` claim). If it's not
+    /// set, the audience is not checked.
+    #[builder(setter(into, strip_option), default)]
+    pub audience: Option<String>,
+    /// The number of seconds the token expiration time can be off by, to
+    /// account for clock skew between the servers. The default is 60.
+    pub leeway_secs: u64,
+    /// The claim the username of the user is read from. The default is
+    /// `preferred_username`, the standard claim for it.
+    #[builder(setter(into))]
+    pub username_claim: String,
+    /// The claim the permissions of the user are read from. It can be either
+    /// an array of strings, or a space-separated string, such as the `scope`
+    /// claim. The default is `permissions`.
+    #[builder(setter(into))]
+    pub permissions_claim: String,
+}
+
+#[cfg(feature = "jwt")]
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl JwtConfig {
+    /// Create a new [`JwtConfigBuilder`] to build a [`JwtConfig`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::JwtConfig;
+    ///
+    /// let config = JwtConfig::builder().build();
+    /// ```
+    #[must_use]
+    pub fn builder() -> JwtConfigBuilder {
+        JwtConfigBuilder::default()
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl JwtConfigBuilder {
+    /// Builds the JWT backend configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::{JwtConfig, JwtKeyConfig};
+    ///
+    /// let config = JwtConfig::builder()
+    ///     .keys(vec![JwtKeyConfig::hs256("supersecret")])
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn build(&self) -> JwtConfig {
+        JwtConfig {
+            keys: self.keys.clone().unwrap_or_default(),
+            jwks_file: self.jwks_file.clone().unwrap_or_default(),
+            issuer: self.issuer.clone().unwrap_or_default(),
+            audience: self.audience.clone().unwrap_or_default(),
+            leeway_secs: self.leeway_secs.unwrap_or(60),
+            username_claim: self
+                .username_claim
+                .clone()
+                .unwrap_or_else(|| String::from("preferred_username")),
+            permissions_claim: self
+                .permissions_claim
+                .clone()
+                .unwrap_or_else(|| String::from("permissions")),
+        }
+    }
+}
+
+/// A key used to validate JSON Web Tokens.
+///
+/// It is used as part of the [`JwtConfig`] struct. If the key has an ID, it
+/// is only used for the tokens that either have the same key ID in their
+/// header, or don't have a key ID at all.
+///
+/// # Examples
+///
+/// ```
+/// use cot::config::JwtKeyConfig;
+///
+/// let key = JwtKeyConfig::hs256("supersecret").with_key_id("2026-10");
+/// ```
+#[cfg(feature = "jwt")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "algorithm")]
+#[non_exhaustive]
+pub enum JwtKeyConfig {
+    /// HMAC with SHA-256.
+    #[serde(rename = "HS256")]
+    Hs256 {
+        /// The ID of the key.
+        key_id: Option<String>,
+        /// The shared secret.
+        secret: SecretKey,
+    },
+    /// RSASSA-PKCS1-v1_5 with SHA-256.
+    #[serde(rename = "RS256")]
+    Rs256 {
+        /// The ID of the key.
+        key_id: Option<String>,
+        /// The public key in the PEM format.
+        public_key: String,
+    },
+    /// Edwards-curve digital signature algorithm with Ed25519.
+    #[serde(rename = "EdDSA")]
+    EdDsa {
+        /// The ID of the key.
+        key_id: Option<String>,
+        /// The public key in the PEM format.
+        public_key: String,
+    },
+}
+
+#[cfg(feature = "jwt")]
+impl JwtKeyConfig {
+    /// Create a new HS256 key with given shared secret.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::JwtKeyConfig;
+    ///
+    /// let key = JwtKeyConfig::hs256("supersecret");
+    /// ```
+    #[must_use]
+    pub fn hs256<T: Into<SecretKey>>(secret: T) -> Self {
+        Self::Hs256 {
+            key_id: None,
+            secret: secret.into(),
+        }
+    }
+
+    /// Create a new RS256 key with given public key in the PEM format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::JwtKeyConfig;
+    ///
+    /// let key = JwtKeyConfig::rs256("-----BEGIN PUBLIC KEY-----\n...");
+    /// ```
+    #[must_use]
+    pub fn rs256<T: Into<String>>(public_key: T) -> Self {
+        Self::Rs256 {
+            key_id: None,
+            public_key: public_key.into(),
+        }
+    }
+
+    /// Create a new Ed25519 key with given public key in the PEM format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::JwtKeyConfig;
+    ///
+    /// let key = JwtKeyConfig::ed_dsa("-----BEGIN PUBLIC KEY-----\n...");
+    /// ```
+    #[must_use]
+    pub fn ed_dsa<T: Into<String>>(public_key: T) -> Self {
+        Self::EdDsa {
+            key_id: None,
+            public_key: public_key.into(),
+        }
+    }
+
+    /// Sets the ID of the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::JwtKeyConfig;
+    ///
+    /// let key = JwtKeyConfig::hs256("supersecret").with_key_id("2026-10");
+    /// assert_eq!(key.key_id(), Some("2026-10"));
+    /// ```
+    #[must_use]
+    pub fn with_key_id<T: Into<String>>(mut self, key_id: T) -> Self {
+        match &mut self {
+            Self::Hs256 { key_id: id, .. }
+            | Self::Rs256 { key_id: id, .. }
+            | Self::EdDsa { key_id: id, .. } => *id = Some(key_id.into()),
+        }
+        self
+    }
+
+    /// Returns the ID of the key, if it has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::JwtKeyConfig;
+    ///
+    /// let key = JwtKeyConfig::hs256("supersecret");
+    /// assert_eq!(key.key_id(), None);
+    /// ```
+    #[must_use]
+    pub fn key_id(&self) -> Option<&str> {
+        match self {
+            Self::Hs256 { key_id, .. }
+            | Self::Rs256 { key_id, .. }
+            | Self::EdDsa { key_id, .. } => key_id.as_deref(),
+        }
+    }
+}
+
+/// The configuration for the login throttling.
+///
+/// It is used as part of the [`ProjectConfig`] struct. The failed login
+/// attempts are counted separately for each username and each client IP
+/// address. When there are [`max_failures_per_username`] or
+/// [`max_failures_per_ip`] failures within [`failure_window_secs`] seconds,
+/// the username or the IP address is locked out for [`lockout_secs`] seconds.
+/// Each subsequent lockout is [`backoff_multiplier`] times longer than the
+/// previous one, up to [`max_lockout_secs`] seconds. A successful login
+/// resets the counters for the username.
+///
+/// [`max_failures_per_username`]: Self::max_failures_per_username
+/// [`max_failures_per_ip`]: Self::max_failures_per_ip
+/// [`failure_window_secs`]: Self::failure_window_secs
+/// [`lockout_secs`]: Self::lockout_secs
+/// [`backoff_multiplier`]: Self::backoff_multiplier
+/// [`max_lockout_secs`]: Self::max_lockout_secs
+///
+/// # Examples
+///
+/// ```
+/// use cot::config::LoginThrottlingConfig;
+///
+/// let config = LoginThrottlingConfig::builder()
+///     .max_failures_per_username(3)
+///     .lockout_secs(600)
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Builder, Serialize, Deserialize)]
+#[builder(build_fn(skip, error = std::convert::Infallible))]
+#[serde(default)]
+pub struct LoginThrottlingConfig {
+    /// Whether the failed login attempts are tracked at all. The default is
+    /// `true`.
+    pub enabled: bool,
+    /// The number of failed login attempts for a single username that causes
+    /// it to be locked out. The default is 5.
+    pub max_failures_per_username: u32,
+    /// The number of failed login attempts from a single IP address that
+    /// causes it to be locked out. This is higher than the limit per username
+    /// by default (20), as many users can share the same address.
+    pub max_failures_per_ip: u32,
+    /// The number of seconds the failed login attempts are counted for. The
+    /// default is 900 (15 minutes).
+    pub failure_window_secs: u64,
+    /// The number of seconds the first lockout lasts. The default is 300 (5
+    /// minutes).
+    pub lockout_secs: u64,
+    /// The number the duration of the lockout is multiplied by for each
+    /// subsequent lockout. The default is 2.
+    pub backoff_multiplier: u32,
+    /// The maximum number of seconds a lockout can last. The default is 86400
+    /// (1 day).
+    pub max_lockout_secs: u64,
+    /// The HTTP header the client IP address is read from, such as
+    /// `X-Forwarded-For`. The last address in the header is used, which is
+    /// the one added by the reverse proxy closest to the server.
+    ///
+    /// This should only be set when the project is deployed behind a reverse
+    /// proxy that sets the header, as otherwise the clients can send any
+    /// address they like. If it's not set, the address of the connection
+    /// is used.
+    #[builder(setter(into, strip_option), default)]
+    pub client_ip_header: Option<String>,
+}
+
+impl Default for LoginThrottlingConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl LoginThrottlingConfig {
+    /// Create a new [`LoginThrottlingConfigBuilder`] to build a
+    /// [`LoginThrottlingConfig`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::LoginThrottlingConfig;
+    ///
+    /// let config = LoginThrottlingConfig::builder().build();
+    /// ```
+    #[must_use]
+    pub fn builder() -> LoginThrottlingConfigBuilder {
+        LoginThrottlingConfigBuilder::default()
+    }
+}
+
+impl LoginThrottlingConfigBuilder {
+    /// Builds the login throttling configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::config::LoginThrottlingConfig;
+    ///
+    /// let config = LoginThrottlingConfig::builder().enabled(false).build();
+    /// ```
+    #[must_use]
+    pub fn build(&self) -> LoginThrottlingConfig {
+        LoginThrottlingConfig {
+            enabled: self.enabled.unwrap_or(true),
+            max_failures_per_username: self.max_failures_per_username.unwrap_or(5),
+            max_failures_per_ip: self.max_failures_per_ip.unwrap_or(20),
+            failure_window_secs: self.failure_window_secs.unwrap_or(900),
+            lockout_secs: self.lockout_secs.unwrap_or(300),
+            backoff_multiplier: self.backoff_multiplier.unwrap_or(2),
+            max_lockout_secs: self.max_lockout_secs.unwrap_or(86400),
+            client_ip_header: self.client_ip_header.clone().unwrap_or_default(),
+        }
+    }
+}
+
 /// The configuration for the database.
 ///
 /// It is used as part of the [`ProjectConfig`] struct.
@@ -615,6 +1463,12 @@ impl PartialEq for SecretKey {
 
 impl Eq for SecretKey {}
 
+impl std::hash::Hash for SecretKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
 impl Debug for SecretKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // write in single line, regardless whether alternate mode was used or not
@@ -731,6 +1585,18 @@ mod tests {
         assert_eq!(config.auth_backend, AuthBackendConfig::None);
     }
 
+    #[test]
+    #[cfg(feature = "db")]
+    fn from_toml_api_token_auth_backend() {
+        let toml_content = r#"
+            auth_backend = { type = "api_token" }
+        "#;
+
+        let config = ProjectConfig::from_toml(toml_content).unwrap();
+
+        assert_eq!(config.auth_backend, AuthBackendConfig::ApiToken);
+    }
+
     #[test]
     fn from_toml_invalid() {
         let toml_content = r"
@@ -751,5 +1617,97 @@ mod tests {
         let config = ProjectConfig::from_toml(toml_content).unwrap();
         assert_eq!(config.debug, cfg!(debug_assertions));
         assert_eq!(config.secret_key.as_bytes(), b"123abc");
+        assert_eq!(config.login_url, "/login/");
+        assert_eq!(
+            config.password_validators,
+            PasswordValidators::recommended()
+        );
+        assert_eq!(config.password_hasher, PasswordHasherConfig::argon2());
+    }
+
+    #[test]
+    fn from_toml_password_validators() {
+        let toml_content = r#"
+            [[password_validators]]
+            type = "user_attribute_similarity"
+
+            [[password_validators]]
+            type = "minimum_length"
+            min_length = 8
+
+            [[password_validators]]
+            type = "common_password"
+
+            [[password_validators]]
+            type = "numeric"
+        "#;
+
+        let config = ProjectConfig::from_toml(toml_content).unwrap();
+        assert_eq!(
+            config.password_validators,
+            PasswordValidators::recommended()
+        );
+    }
+
+    #[test]
+    fn from_toml_no_password_validators() {
+        let toml_content = r"
+            password_validators = []
+        ";
+
+        let config = ProjectConfig::from_toml(toml_content).unwrap();
+        assert_eq!(config.password_validators, PasswordValidators::none());
+    }
+
+    #[test]
+    fn from_toml_password_hasher() {
+        let toml_content = r#"
+            [password_hasher]
+            type = "pbkdf2"
+        "#;
+
+        let config = ProjectConfig::from_toml(toml_content).unwrap();
+        assert_eq!(config.password_hasher, PasswordHasherConfig::pbkdf2());
+
+        let toml_content = r#"
+            [password_hasher]
+            type = "scrypt"
+            log_n = 15
+        "#;
+
+        let config = ProjectConfig::from_toml(toml_content).unwrap();
+        assert_eq!(
+            config.password_hasher,
+            PasswordHasherConfig::Scrypt {
+                log_n: 15,
+                r: 8,
+                p: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn from_toml_login_throttling() {
+        let config = ProjectConfig::from_toml("").unwrap();
+        assert!(config.login_throttling.enabled);
+        assert_eq!(config.login_throttling.max_failures_per_username, 5);
+        assert_eq!(config.login_throttling.client_ip_header, None);
+
+        let toml_content = r#"
+            [login_throttling]
+            max_failures_per_ip = 50
+            lockout_secs = 60
+            client_ip_header = "X-Real-IP"
+        "#;
+
+        let config = ProjectConfig::from_toml(toml_content).unwrap();
+        assert_eq!(
+            config.login_throttling,
+            LoginThrottlingConfig::builder()
+                .max_failures_per_ip(50)
+                .lockout_secs(60)
+                .client_ip_header("X-Real-IP")
+                .build()
+        );
     }
 }
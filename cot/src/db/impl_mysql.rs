@@ -20,11 +20,7 @@ impl DatabaseMySql {
         Some(result.last_insert_id())
     }
 
-    #[allow(clippy::unused_self)] // to have a unified interface between database impls
-    pub(super) fn sea_query_column_type_for(
-        &self,
-        column_type: ColumnType,
-    ) -> sea_query::ColumnType {
+    pub(super) fn sea_query_column_type_for(column_type: ColumnType) -> sea_query::ColumnType {
         match column_type {
             ColumnType::DateTime | ColumnType::DateTimeWithTimeZone => {
                 return sea_query::ColumnType::custom("DATETIME(6)");
@@ -34,4 +30,49 @@ impl DatabaseMySql {
 
         sea_query::ColumnType::from(column_type)
     }
+
+    /// Query returning the table name, column name, column type, whether the
+    /// column is nullable, a part of the primary key and unique (`YES`/`NO`),
+    /// the name of the referenced table and the maximum length of the column
+    /// for all the columns in the database, as read by
+    /// [`tables_from_rows`](crate::db::introspection::tables_from_rows).
+    pub(super) const INTROSPECTION_QUERY: &'static str = "\
+        SELECT CAST(c.TABLE_NAME AS CHAR), CAST(c.COLUMN_NAME AS CHAR), \
+        CAST(c.COLUMN_TYPE AS CHAR), CAST(c.IS_NULLABLE AS CHAR), \
+        CASE WHEN c.COLUMN_KEY = 'PRI' THEN 'YES' ELSE 'NO' END, \
+        CASE WHEN c.COLUMN_KEY = 'UNI' THEN 'YES' ELSE 'NO' END, \
+        (\
+            SELECT CAST(k.REFERENCED_TABLE_NAME AS CHAR) \
+            FROM information_schema.KEY_COLUMN_USAGE k \
+            WHERE k.TABLE_SCHEMA = c.TABLE_SCHEMA AND k.TABLE_NAME = c.TABLE_NAME \
+            AND k.COLUMN_NAME = c.COLUMN_NAME AND k.REFERENCED_TABLE_NAME IS NOT NULL \
+            LIMIT 1\
+        ), \
+        CAST(c.CHARACTER_MAXIMUM_LENGTH AS CHAR) \
+        FROM information_schema.COLUMNS c \
+        WHERE c.TABLE_SCHEMA = DATABASE() \
+        ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION";
+
+    /// Returns the (normalized) name of the column type as returned by the
+    /// [`Self::INTROSPECTION_QUERY`] for given column type.
+    pub(super) fn introspected_type_name(column_type: ColumnType) -> &'static str {
+        match column_type {
+            ColumnType::Boolean | ColumnType::TinyInteger => "tinyint",
+            ColumnType::SmallInteger => "smallint",
+            ColumnType::Integer => "int",
+            ColumnType::BigInteger => "bigint",
+            ColumnType::TinyUnsignedInteger => "tinyint unsigned",
+            ColumnType::SmallUnsignedInteger => "smallint unsigned",
+            ColumnType::UnsignedInteger => "int unsigned",
+            ColumnType::BigUnsignedInteger => "bigint unsigned",
+            ColumnType::Float => "float",
+            ColumnType::Double => "double",
+            ColumnType::Time => "time",
+            ColumnType::Date => "date",
+            ColumnType::DateTime | ColumnType::DateTimeWithTimeZone => "datetime",
+            ColumnType::Text => "text",
+            ColumnType::Blob => "blob",
+            ColumnType::String(_) => "varchar",
+        }
+    }
 }
@@ -45,11 +45,75 @@ impl DatabasePostgres {
         None
     }
 
-    #[allow(clippy::unused_self)] // to have a unified interface between database impls
     pub(super) fn sea_query_column_type_for(
-        &self,
         column_type: crate::db::ColumnType,
     ) -> sea_query::ColumnType {
         sea_query::ColumnType::from(column_type)
     }
+
+    /// Query returning the table name, column name, column type, whether the
+    /// column is nullable, a part of the primary key and unique (`YES`/`NO`),
+    /// the name of the referenced table and the maximum length of the column
+    /// for all the columns in the database, as read by
+    /// [`tables_from_rows`](crate::db::introspection::tables_from_rows).
+    pub(super) const INTROSPECTION_QUERY: &'static str = "\
+        SELECT c.table_name::text, c.column_name::text, c.data_type::text, c.is_nullable::text, \
+        CASE WHEN EXISTS (\
+            SELECT 1 FROM information_schema.table_constraints tc \
+            JOIN information_schema.key_column_usage k \
+            ON k.constraint_name = tc.constraint_name AND k.table_schema = tc.table_schema \
+            WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = c.table_schema \
+            AND tc.table_name = c.table_name AND k.column_name = c.column_name\
+        ) THEN 'YES' ELSE 'NO' END, \
+        CASE WHEN EXISTS (\
+            SELECT 1 FROM information_schema.table_constraints tc \
+            JOIN information_schema.key_column_usage k \
+            ON k.constraint_name = tc.constraint_name AND k.table_schema = tc.table_schema \
+            WHERE tc.constraint_type = 'UNIQUE' AND tc.table_schema = c.table_schema \
+            AND tc.table_name = c.table_name AND k.column_name = c.column_name \
+            AND (\
+                SELECT COUNT(*) FROM information_schema.key_column_usage k2 \
+                WHERE k2.constraint_name = tc.constraint_name \
+                AND k2.table_schema = tc.table_schema\
+            ) = 1\
+        ) THEN 'YES' ELSE 'NO' END, \
+        (\
+            SELECT ccu.table_name::text FROM information_schema.table_constraints tc \
+            JOIN information_schema.key_column_usage k \
+            ON k.constraint_name = tc.constraint_name AND k.table_schema = tc.table_schema \
+            JOIN information_schema.constraint_column_usage ccu \
+            ON ccu.constraint_name = tc.constraint_name AND ccu.table_schema = tc.table_schema \
+            WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_schema = c.table_schema \
+            AND tc.table_name = c.table_name AND k.column_name = c.column_name \
+            LIMIT 1\
+        ), \
+        c.character_maximum_length::text \
+        FROM information_schema.columns c \
+        WHERE c.table_schema = current_schema() \
+        ORDER BY c.table_name, c.ordinal_position";
+
+    /// Returns the (normalized) name of the column type as returned by the
+    /// [`Self::INTROSPECTION_QUERY`] for given column type.
+    pub(super) fn introspected_type_name(column_type: crate::db::ColumnType) -> &'static str {
+        use crate::db::ColumnType;
+
+        match column_type {
+            ColumnType::Boolean => "boolean",
+            ColumnType::TinyInteger
+            | ColumnType::TinyUnsignedInteger
+            | ColumnType::SmallInteger
+            | ColumnType::SmallUnsignedInteger => "smallint",
+            ColumnType::Integer | ColumnType::UnsignedInteger => "integer",
+            ColumnType::BigInteger | ColumnType::BigUnsignedInteger => "bigint",
+            ColumnType::Float => "real",
+            ColumnType::Double => "double precision",
+            ColumnType::Time => "time without time zone",
+            ColumnType::Date => "date",
+            ColumnType::DateTime => "timestamp without time zone",
+            ColumnType::DateTimeWithTimeZone => "timestamp with time zone",
+            ColumnType::Text => "text",
+            ColumnType::Blob => "bytea",
+            ColumnType::String(_) => "character varying",
+        }
+    }
 }
@@ -27,11 +27,60 @@ impl DatabaseSqlite {
         Some(result.last_insert_rowid() as u64)
     }
 
-    #[allow(clippy::unused_self)] // to have a unified interface between database impls
     pub(super) fn sea_query_column_type_for(
-        &self,
         column_type: crate::db::ColumnType,
     ) -> sea_query::ColumnType {
         sea_query::ColumnType::from(column_type)
     }
+
+    /// Query returning the table name, column name, column type, whether the
+    /// column is nullable, a part of the primary key and unique (`YES`/`NO`),
+    /// the name of the referenced table and the maximum length of the column
+    /// for all the columns in the database, as read by
+    /// [`tables_from_rows`](crate::db::introspection::tables_from_rows).
+    pub(super) const INTROSPECTION_QUERY: &'static str = "\
+        SELECT m.name, p.name, p.type, CASE WHEN p.\"notnull\" = 0 THEN 'YES' ELSE 'NO' END, \
+        CASE WHEN p.pk > 0 THEN 'YES' ELSE 'NO' END, \
+        CASE WHEN EXISTS (\
+            SELECT 1 FROM pragma_index_list(m.name) il JOIN pragma_index_info(il.name) ii \
+            WHERE il.\"unique\" = 1 AND il.origin != 'pk' AND ii.name = p.name \
+            AND (SELECT COUNT(*) FROM pragma_index_info(il.name)) = 1\
+        ) THEN 'YES' ELSE 'NO' END, \
+        (SELECT f.\"table\" FROM pragma_foreign_key_list(m.name) f WHERE f.\"from\" = p.name), \
+        NULL \
+        FROM sqlite_master m JOIN pragma_table_info(m.name) p \
+        WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' \
+        ORDER BY m.name, p.cid";
+
+    /// Returns the (normalized) name of the column type as returned by the
+    /// [`Self::INTROSPECTION_QUERY`] for given column type.
+    ///
+    /// `auto_value` is whether the column is an auto-incrementing primary key;
+    /// such columns are always declared as `integer`, as that's the only type
+    /// SQLite allows for `AUTOINCREMENT` columns.
+    pub(super) fn introspected_type_name(
+        column_type: crate::db::ColumnType,
+        auto_value: bool,
+    ) -> &'static str {
+        use crate::db::ColumnType;
+
+        // SQLite returns the type the column has been declared with
+        match column_type {
+            ColumnType::BigInteger | ColumnType::BigUnsignedInteger if auto_value => "integer",
+            ColumnType::Boolean => "boolean",
+            ColumnType::TinyInteger | ColumnType::TinyUnsignedInteger => "tinyint",
+            ColumnType::SmallInteger | ColumnType::SmallUnsignedInteger => "smallint",
+            ColumnType::Integer | ColumnType::UnsignedInteger => "integer",
+            ColumnType::BigInteger | ColumnType::BigUnsignedInteger => "bigint",
+            ColumnType::Float => "float",
+            ColumnType::Double => "double",
+            ColumnType::Time => "time_text",
+            ColumnType::Date => "date_text",
+            ColumnType::DateTime => "datetime_text",
+            ColumnType::DateTimeWithTimeZone => "timestamp_with_timezone_text",
+            ColumnType::Text => "text",
+            ColumnType::Blob => "blob",
+            ColumnType::String(_) => "varchar",
+        }
+    }
 }
@@ -0,0 +1,314 @@
+//! Database schema introspection.
+//!
+//! This module contains the types describing the schema of an existing
+//! database, as returned by
+//! [`Database::introspect`](crate::db::Database::introspect).
+
+use crate::db::{Result, Row};
+
+/// A table that exists in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSchema {
+    name: String,
+    columns: Vec<ColumnSchema>,
+}
+
+impl TableSchema {
+    /// Returns the name of the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE test (id INTEGER PRIMARY KEY)").await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert_eq!(tables[0].name(), "test");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the columns of the table, in the order they are defined in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT)")
+    ///     .await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert_eq!(tables[0].columns().len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn columns(&self) -> &[ColumnSchema] {
+        &self.columns
+    }
+
+    /// Returns the column with given name, or [`None`] if the table doesn't
+    /// have such column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT)")
+    ///     .await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert!(tables[0].column("name").is_some());
+    /// assert!(tables[0].column("age").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<&ColumnSchema> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+}
+
+/// A column of a table that exists in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSchema {
+    name: String,
+    type_name: String,
+    nullable: bool,
+    primary_key: bool,
+    unique: bool,
+    foreign_key_table: Option<String>,
+    max_length: Option<u32>,
+}
+
+impl ColumnSchema {
+    /// Returns the name of the column.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the column type, as reported by the database.
+    ///
+    /// The name is normalized: it's lowercase and doesn't contain any
+    /// parameters, such as the length of a `varchar` column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE test (id INTEGER PRIMARY KEY, name VARCHAR(100))")
+    ///     .await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert_eq!(tables[0].columns()[1].type_name(), "varchar");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns whether the column can contain `NULL` values.
+    #[must_use]
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    /// Returns whether the column is (a part of) the primary key of the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT)")
+    ///     .await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert!(tables[0].columns()[0].is_primary_key());
+    /// assert!(!tables[0].columns()[1].is_primary_key());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    /// Returns whether the column has a unique constraint on its own.
+    ///
+    /// Primary keys and columns that are only a part of a multi-column unique
+    /// constraint are not considered unique.
+    #[must_use]
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Returns the name of the table this column references, or [`None`] if
+    /// the column is not a foreign key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE parent (id INTEGER PRIMARY KEY)")
+    ///     .await?;
+    /// db.raw("CREATE TABLE child (id INTEGER PRIMARY KEY, parent INTEGER REFERENCES parent(id))")
+    ///     .await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert_eq!(tables[0].columns()[1].foreign_key_table(), Some("parent"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn foreign_key_table(&self) -> Option<&str> {
+        self.foreign_key_table.as_deref()
+    }
+
+    /// Returns the maximum length of a character column, or [`None`] if the
+    /// column is not limited in length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::Database;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let db = Database::new("sqlite::memory:").await?;
+    /// db.raw("CREATE TABLE test (id INTEGER PRIMARY KEY, name VARCHAR(100))")
+    ///     .await?;
+    ///
+    /// let tables = db.introspect().await?;
+    /// assert_eq!(tables[0].columns()[1].max_length(), Some(100));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn max_length(&self) -> Option<u32> {
+        self.max_length
+    }
+}
+
+/// Groups the rows returned by the backend's introspection query into tables.
+///
+/// The rows must be ordered by the table name and contain the table name,
+/// column name, column type, `YES`/`NO` depending on whether the column is
+/// nullable, a part of the primary key and unique, the name of the referenced
+/// table (if any) and the maximum length of the column (if known).
+pub(super) fn tables_from_rows(rows: &[Row]) -> Result<Vec<TableSchema>> {
+    let mut tables: Vec<TableSchema> = Vec::new();
+
+    for row in rows {
+        let table_name: String = row.get(0)?;
+        let raw_type_name: String = row.get(2)?;
+        let max_length = row
+            .get::<Option<String>>(7)?
+            .and_then(|max_length| max_length.parse().ok())
+            .or_else(|| type_length(&raw_type_name));
+        let column = ColumnSchema {
+            name: row.get(1)?,
+            type_name: normalize_type_name(&raw_type_name),
+            nullable: row.get::<String>(3)? == "YES",
+            primary_key: row.get::<String>(4)? == "YES",
+            unique: row.get::<String>(5)? == "YES",
+            foreign_key_table: row.get(6)?,
+            max_length,
+        };
+
+        match tables.last_mut() {
+            Some(table) if table.name == table_name => table.columns.push(column),
+            _ => tables.push(TableSchema {
+                name: table_name,
+                columns: vec![column],
+            }),
+        }
+    }
+
+    Ok(tables)
+}
+
+/// Returns the length parameter of a column type (e.g. `255` for
+/// `VARCHAR(255)`), if it has exactly one numeric parameter.
+#[must_use]
+fn type_length(type_name: &str) -> Option<u32> {
+    let (_, params) = type_name.split_once('(')?;
+    let (params, _) = params.split_once(')')?;
+    params.trim().parse().ok()
+}
+
+/// Normalizes the name of a column type by converting it to lowercase and
+/// removing any parameters (e.g. `VARCHAR(255)` becomes `varchar`).
+#[must_use]
+fn normalize_type_name(type_name: &str) -> String {
+    let mut without_params = String::with_capacity(type_name.len());
+    let mut depth = 0_usize;
+    for c in type_name.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => without_params.push(c),
+            _ => {}
+        }
+    }
+
+    without_params
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_type_name_params() {
+        assert_eq!(normalize_type_name("VARCHAR(255)"), "varchar");
+        assert_eq!(normalize_type_name("int(10) unsigned"), "int unsigned");
+        assert_eq!(normalize_type_name("DATETIME(6)"), "datetime");
+        assert_eq!(
+            normalize_type_name("timestamp with time zone"),
+            "timestamp with time zone"
+        );
+    }
+
+    #[test]
+    fn type_length_params() {
+        assert_eq!(type_length("VARCHAR(255)"), Some(255));
+        assert_eq!(type_length("varchar( 10 )"), Some(10));
+        assert_eq!(type_length("decimal(10, 2)"), None);
+        assert_eq!(type_length("text"), None);
+    }
+}
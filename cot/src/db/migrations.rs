@@ -1,17 +1,24 @@
 //! Database migrations.
 
+mod schema;
 mod sorter;
 
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 
-use sea_query::{ColumnDef, StringLen};
+use sea_query::{ColumnDef, SchemaBuilder, SchemaStatementBuilder, StringLen, TableStatement};
 use thiserror::Error;
 use tracing::info;
 
+use crate::db::migrations::schema::ExpectedSchema;
+pub use crate::db::migrations::schema::SchemaDifference;
 use crate::db::migrations::sorter::{MigrationSorter, MigrationSorterError};
 use crate::db::relations::{ForeignKeyOnDeletePolicy, ForeignKeyOnUpdatePolicy};
-use crate::db::{model, query, ColumnType, Database, DatabaseField, Identifier, Result};
+use crate::db::{
+    model, query, Auto, ColumnType, Database, DatabaseEngine, DatabaseField, DbValue, Identifier,
+    Model, Result,
+};
 
 /// An error that occurred while running migrations.
 #[derive(Debug, Clone, Error)]
@@ -20,6 +27,21 @@ pub enum MigrationEngineError {
     /// An error occurred while determining the correct order of migrations.
     #[error("Error while determining the correct order of migrations")]
     MigrationSortError(#[from] MigrationSorterError),
+    /// A squashed migration can't be applied because some of the migrations
+    /// it replaces have already been applied, and the rest of them are not
+    /// available anymore.
+    #[error(
+        "Migration {app_name}::{migration_name} replaces partially applied migrations, \
+        but the replaced migration {app_name}::{replaced} is not available"
+    )]
+    ReplacedMigrationNotFound {
+        /// The name of the app the squashed migration belongs to.
+        app_name: String,
+        /// The name of the squashed migration.
+        migration_name: String,
+        /// The name of the replaced migration that is missing.
+        replaced: String,
+    },
 }
 
 /// A migration engine that can run migrations.
@@ -58,6 +80,9 @@ pub enum MigrationEngineError {
 #[derive(Debug)]
 pub struct MigrationEngine {
     migrations: Vec<MigrationWrapper>,
+    /// Migrations that have been replaced by a squashed migration. These are
+    /// only applied when the database is in the middle of the replaced range.
+    replaced: Vec<MigrationWrapper>,
 }
 
 impl MigrationEngine {
@@ -107,9 +132,34 @@ impl MigrationEngine {
         Self::from_wrapper(migrations)
     }
 
-    fn from_wrapper(mut migrations: Vec<MigrationWrapper>) -> Result<Self> {
+    /// Returns the migrations applied by this engine, excluding the ones
+    /// replaced by squashed migrations.
+    #[must_use]
+    pub(crate) fn migrations(&self) -> &[MigrationWrapper] {
+        &self.migrations
+    }
+
+    fn from_wrapper(migrations: Vec<MigrationWrapper>) -> Result<Self> {
+        let replaced_names: HashSet<(String, String)> = migrations
+            .iter()
+            .flat_map(|migration| {
+                migration
+                    .replaces()
+                    .iter()
+                    .map(|name| (migration.app_name().to_owned(), (*name).to_owned()))
+            })
+            .collect();
+        let (replaced, mut migrations): (Vec<_>, Vec<_>) =
+            migrations.into_iter().partition(|migration| {
+                replaced_names
+                    .contains(&(migration.app_name().to_owned(), migration.name().to_owned()))
+            });
+
         Self::sort_migrations(&mut migrations)?;
-        Ok(Self { migrations })
+        Ok(Self {
+            migrations,
+            replaced,
+        })
     }
 
     /// Sorts the migrations by app name and migration name to ensure that the
@@ -176,49 +226,206 @@ impl MigrationEngine {
             .await?;
 
         for migration in &self.migrations {
-            for operation in migration.operations() {
-                if Self::is_migration_applied(database, migration).await? {
-                    info!(
-                        "Migration {} for app {} is already applied",
-                        migration.name(),
-                        migration.app_name()
-                    );
-                    continue;
-                }
-
+            if Self::is_migration_applied(database, migration.app_name(), migration.name()).await? {
                 info!(
-                    "Applying migration {} for app {}",
+                    "Migration {} for app {} is already applied",
                     migration.name(),
                     migration.app_name()
                 );
-                operation.forwards(database).await?;
-                Self::mark_migration_applied(database, migration).await?;
+                continue;
+            }
+
+            if migration.replaces().is_empty() {
+                Self::apply_migration(database, migration).await?;
+            } else {
+                self.apply_squashed_migration(database, migration).await?;
             }
         }
 
         Ok(())
     }
 
-    async fn is_migration_applied(
+    async fn apply_migration<T: DynMigration + Sync>(
         database: &Database,
-        migration: &MigrationWrapper,
-    ) -> Result<bool> {
-        query!(
-            AppliedMigration,
-            $app == migration.app_name() && $name == migration.name()
-        )
-        .exists(database)
-        .await
+        migration: &T,
+    ) -> Result<()> {
+        info!(
+            "Applying migration {} for app {}",
+            migration.name(),
+            migration.app_name()
+        );
+        for operation in migration.operations() {
+            operation.forwards(database).await?;
+        }
+        Self::mark_migration_applied(database, migration.app_name(), migration.name()).await
     }
 
-    async fn mark_migration_applied(
+    /// Applies a squashed migration, taking into account that the database
+    /// might already contain some (or all) of the migrations it replaces.
+    async fn apply_squashed_migration(
+        &self,
         database: &Database,
         migration: &MigrationWrapper,
     ) -> Result<()> {
+        let mut not_applied = Vec::new();
+        for &replaced in migration.replaces() {
+            if !Self::is_migration_applied(database, migration.app_name(), replaced).await? {
+                not_applied.push(replaced);
+            }
+        }
+
+        if not_applied.len() == migration.replaces().len() {
+            Self::apply_migration(database, migration).await?;
+            for replaced in not_applied {
+                Self::mark_migration_applied(database, migration.app_name(), replaced).await?;
+            }
+            return Ok(());
+        }
+
+        for replaced_name in not_applied {
+            let replaced = self
+                .replaced
+                .iter()
+                .find(|replaced| {
+                    replaced.app_name() == migration.app_name() && replaced.name() == replaced_name
+                })
+                .ok_or_else(|| MigrationEngineError::ReplacedMigrationNotFound {
+                    app_name: migration.app_name().to_owned(),
+                    migration_name: migration.name().to_owned(),
+                    replaced: replaced_name.to_owned(),
+                })?;
+            Self::apply_migration(database, replaced).await?;
+        }
+
+        info!(
+            "Migration {} for app {} replaces already applied migrations; marking it as applied",
+            migration.name(),
+            migration.app_name()
+        );
+        Self::mark_migration_applied(database, migration.app_name(), migration.name()).await
+    }
+
+    /// Compares the schema of the database with the schema that the applied
+    /// migrations should have produced, and returns the list of differences
+    /// found.
+    ///
+    /// Only the tables that are created by the migrations are checked; other
+    /// tables that exist in the database are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Throws an error if there is an error while interacting with the
+    /// database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::migrations::{Field, Migration, MigrationDependency, MigrationEngine, Operation};
+    /// use cot::db::{Database, DatabaseField, Identifier};
+    ///
+    /// struct MyMigration;
+    ///
+    /// impl Migration for MyMigration {
+    ///     const APP_NAME: &'static str = "todoapp";
+    ///     const MIGRATION_NAME: &'static str = "m_0001_initial";
+    ///     const DEPENDENCIES: &'static [MigrationDependency] = &[];
+    ///     const OPERATIONS: &'static [Operation] = &[Operation::create_model()
+    ///         .table_name(Identifier::new("todoapp__my_model"))
+    ///         .fields(&[
+    ///             Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+    ///                 .primary_key()
+    ///                 .auto(),
+    ///             Field::new(Identifier::new("app"), <String as DatabaseField>::TYPE),
+    ///         ])
+    ///         .build()];
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let engine = MigrationEngine::new([MyMigration])?;
+    /// let database = Database::new("sqlite::memory:").await?;
+    /// engine.run(&database).await?;
+    ///
+    /// assert!(engine.schema_differences(&database).await?.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn schema_differences(&self, database: &Database) -> Result<Vec<SchemaDifference>> {
+        let tables = database.introspect().await?;
+        // If the table doesn't exist, no migrations have been applied yet
+        let any_applied = tables
+            .iter()
+            .any(|table| table.name() == AppliedMigration::TABLE_NAME.as_str());
+
+        let mut schema = ExpectedSchema::new();
+        schema.apply(&CREATE_APPLIED_MIGRATIONS_MIGRATION);
+        if any_applied {
+            for migration in &self.migrations {
+                if Self::is_migration_applied(database, migration.app_name(), migration.name())
+                    .await?
+                {
+                    migration
+                        .operations()
+                        .iter()
+                        .for_each(|operation| schema.apply(operation));
+                    continue;
+                }
+
+                if migration.replaces().is_empty() {
+                    continue;
+                }
+                let mut all_replaced_applied = true;
+                for &replaced_name in migration.replaces() {
+                    if !Self::is_migration_applied(database, migration.app_name(), replaced_name)
+                        .await?
+                    {
+                        all_replaced_applied = false;
+                        break;
+                    }
+                }
+                if all_replaced_applied {
+                    // the squashed migration is equivalent to the migrations it replaces,
+                    // which might not exist in the code anymore
+                    migration
+                        .operations()
+                        .iter()
+                        .for_each(|operation| schema.apply(operation));
+                    continue;
+                }
+
+                for &replaced_name in migration.replaces() {
+                    let replaced = self.replaced.iter().find(|replaced| {
+                        replaced.app_name() == migration.app_name()
+                            && replaced.name() == replaced_name
+                    });
+                    if let Some(replaced) = replaced {
+                        if Self::is_migration_applied(database, replaced.app_name(), replaced_name)
+                            .await?
+                        {
+                            replaced
+                                .operations()
+                                .iter()
+                                .for_each(|operation| schema.apply(operation));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(schema.differences(database.engine(), &tables))
+    }
+
+    async fn is_migration_applied(database: &Database, app: &str, name: &str) -> Result<bool> {
+        query!(AppliedMigration, $app == app && $name == name)
+            .exists(database)
+            .await
+    }
+
+    async fn mark_migration_applied(database: &Database, app: &str, name: &str) -> Result<()> {
         let mut applied_migration = AppliedMigration {
-            id: 0,
-            app: migration.app_name().to_string(),
-            name: migration.name().to_string(),
+            id: Auto::auto(),
+            app: app.to_string(),
+            name: name.to_string(),
             applied: chrono::Utc::now().into(),
         };
 
@@ -263,6 +470,16 @@ impl Operation {
         Self { inner }
     }
 
+    /// Returns the name of the table created by this operation, or [`None`] if
+    /// the operation doesn't create a table.
+    #[must_use]
+    pub(crate) fn created_table_name(&self) -> Option<Identifier> {
+        match self.inner {
+            OperationInner::CreateModel { table_name, .. } => Some(table_name),
+            OperationInner::AddField { .. } => None,
+        }
+    }
+
     /// Returns a builder for an operation that creates a model.
     ///
     /// Typically, you shouldn't need to use this directly. Instead, in most
@@ -366,6 +583,41 @@ impl Operation {
     /// # }
     /// ```
     pub async fn forwards(&self, database: &Database) -> Result<()> {
+        database
+            .execute_schema(self.forwards_statement(database))
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the SQL that would be executed by [`Self::forwards`] on the
+    /// given database engine, without actually executing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::migrations::{Field, Operation};
+    /// use cot::db::{DatabaseEngine, DatabaseField, Identifier};
+    ///
+    /// const OPERATION: Operation = Operation::add_field()
+    ///     .table_name(Identifier::new("todoapp__my_model"))
+    ///     .field(Field::new(
+    ///         Identifier::new("name"),
+    ///         <String as DatabaseField>::TYPE,
+    ///     ))
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     OPERATION.forwards_sql(DatabaseEngine::Sqlite),
+    ///     r#"ALTER TABLE "todoapp__my_model" ADD COLUMN "name" text NOT NULL"#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn forwards_sql(&self, engine: DatabaseEngine) -> String {
+        self.forwards_statement(&engine)
+            .build_any(engine.schema_builder().as_ref())
+    }
+
+    fn forwards_statement<T: ColumnTypeMapper>(&self, mapper: &T) -> SchemaStatement {
         match &self.inner {
             OperationInner::CreateModel {
                 table_name,
@@ -374,7 +626,7 @@ impl Operation {
             } => {
                 let mut query = sea_query::Table::create().table(*table_name).to_owned();
                 for field in *fields {
-                    query.col(field.as_column_def(database));
+                    query.col(field.as_column_def(mapper));
                     if let Some(foreign_key) = field.foreign_key {
                         query.foreign_key(
                             sea_query::ForeignKeyCreateStatement::new()
@@ -390,17 +642,16 @@ impl Operation {
                 if *if_not_exists {
                     query.if_not_exists();
                 }
-                database.execute_schema(query).await?;
+                SchemaStatement(TableStatement::Create(query))
             }
             OperationInner::AddField { table_name, field } => {
                 let query = sea_query::Table::alter()
                     .table(*table_name)
-                    .add_column(field.as_column_def(database))
+                    .add_column(field.as_column_def(mapper))
                     .to_owned();
-                database.execute_schema(query).await?;
+                SchemaStatement(TableStatement::Alter(query))
             }
         }
-        Ok(())
     }
 
     /// Runs the operation backwards, undoing the changes made by the forwards
@@ -435,6 +686,40 @@ impl Operation {
     /// # }
     /// ```
     pub async fn backwards(&self, database: &Database) -> Result<()> {
+        database.execute_schema(self.backwards_statement()).await?;
+        Ok(())
+    }
+
+    /// Returns the SQL that would be executed by [`Self::backwards`] on the
+    /// given database engine, without actually executing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::migrations::{Field, Operation};
+    /// use cot::db::{DatabaseEngine, DatabaseField, Identifier};
+    ///
+    /// const OPERATION: Operation = Operation::create_model()
+    ///     .table_name(Identifier::new("todoapp__my_model"))
+    ///     .fields(&[
+    ///         Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+    ///             .primary_key()
+    ///             .auto(),
+    ///     ])
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     OPERATION.backwards_sql(DatabaseEngine::Postgres),
+    ///     r#"DROP TABLE "todoapp__my_model""#
+    /// );
+    /// ```
+    #[must_use]
+    pub fn backwards_sql(&self, engine: DatabaseEngine) -> String {
+        self.backwards_statement()
+            .build_any(engine.schema_builder().as_ref())
+    }
+
+    fn backwards_statement(&self) -> SchemaStatement {
         match &self.inner {
             OperationInner::CreateModel {
                 table_name,
@@ -442,17 +727,32 @@ impl Operation {
                 if_not_exists: _,
             } => {
                 let query = sea_query::Table::drop().table(*table_name).to_owned();
-                database.execute_schema(query).await?;
+                SchemaStatement(TableStatement::Drop(query))
             }
             OperationInner::AddField { table_name, field } => {
                 let query = sea_query::Table::alter()
                     .table(*table_name)
                     .drop_column(field.name)
                     .to_owned();
-                database.execute_schema(query).await?;
+                SchemaStatement(TableStatement::Alter(query))
             }
         }
-        Ok(())
+    }
+}
+
+/// A wrapper over [`TableStatement`] that allows it to be passed to
+/// [`Database::execute_schema`], as well as be rendered to SQL for any
+/// database engine.
+#[derive(Debug, Clone)]
+struct SchemaStatement(TableStatement);
+
+impl SchemaStatementBuilder for SchemaStatement {
+    fn build<T: SchemaBuilder>(&self, schema_builder: T) -> String {
+        self.0.build(schema_builder)
+    }
+
+    fn build_any(&self, schema_builder: &dyn SchemaBuilder) -> String {
+        self.0.build_any(schema_builder)
     }
 }
 
@@ -488,6 +788,8 @@ pub struct Field {
     pub null: bool,
     /// Whether the column has a unique constraint
     pub unique: bool,
+    /// The default value of the column
+    pub default_value: Option<FieldDefault>,
     foreign_key: Option<ForeignKeyReference>,
 }
 
@@ -516,6 +818,7 @@ impl Field {
             auto_value: false,
             null: false,
             unique: false,
+            default_value: None,
             foreign_key: None,
         }
     }
@@ -674,6 +977,27 @@ impl Field {
         self
     }
 
+    /// Sets the default value of the field.
+    ///
+    /// This is mostly useful when adding a non-nullable field to a table that
+    /// might already contain some rows, as the database needs to know what
+    /// value to put in the new column for these rows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::db::migrations::{Field, FieldDefault};
+    /// use cot::db::{DatabaseField, Identifier};
+    ///
+    /// let field = Field::new(Identifier::new("is_admin"), <bool as DatabaseField>::TYPE)
+    ///     .default_value(FieldDefault::Bool(false));
+    /// ```
+    #[must_use]
+    pub const fn default_value(mut self, value: FieldDefault) -> Self {
+        self.default_value = Some(value);
+        self
+    }
+
     fn as_column_def<T: ColumnTypeMapper>(&self, mapper: &T) -> ColumnDef {
         let mut def =
             ColumnDef::new_with_type(self.name, mapper.sea_query_column_type_for(self.ty));
@@ -691,10 +1015,37 @@ impl Field {
         if self.unique {
             def.unique_key();
         }
+        if let Some(default_value) = self.default_value {
+            def.default(default_value.as_db_value());
+        }
         def
     }
 }
 
+/// A default value of a field in a migration.
+///
+/// See [`Field::default_value`] for more details.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum FieldDefault {
+    /// A boolean value.
+    Bool(bool),
+    /// An integer value.
+    Int(i64),
+    /// A string value.
+    String(&'static str),
+}
+
+impl FieldDefault {
+    fn as_db_value(self) -> DbValue {
+        match self {
+            Self::Bool(value) => value.into(),
+            Self::Int(value) => value.into(),
+            Self::String(value) => value.into(),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 struct ForeignKeyReference {
     model: Identifier,
@@ -1109,6 +1460,15 @@ pub trait Migration {
 
     /// The list of operations to apply in the migration.
     const OPERATIONS: &'static [Operation];
+
+    /// The names of the migrations (in the same app) that this migration
+    /// replaces.
+    ///
+    /// This is used by squashed migrations. A squashed migration is
+    /// considered applied if all the migrations it replaces have already been
+    /// applied, and other migrations can still depend on any of the replaced
+    /// migrations.
+    const REPLACES: &'static [&'static str] = &[];
 }
 
 /// A trait for defining a migration that can be dynamically applied.
@@ -1132,6 +1492,12 @@ pub trait DynMigration {
 
     /// The list of operations to apply in the migration.
     fn operations(&self) -> &[Operation];
+
+    /// The names of the migrations (in the same app) that this migration
+    /// replaces.
+    fn replaces(&self) -> &[&str] {
+        &[]
+    }
 }
 
 /// A type alias for a dynamic migration that is both [`Send`] and [`Sync`].
@@ -1153,6 +1519,10 @@ impl<T: Migration + Send + Sync + 'static> DynMigration for T {
     fn operations(&self) -> &[Operation] {
         Self::OPERATIONS
     }
+
+    fn replaces(&self) -> &[&str] {
+        Self::REPLACES
+    }
 }
 
 impl DynMigration for &dyn DynMigration {
@@ -1171,6 +1541,10 @@ impl DynMigration for &dyn DynMigration {
     fn operations(&self) -> &[Operation] {
         DynMigration::operations(*self)
     }
+
+    fn replaces(&self) -> &[&str] {
+        DynMigration::replaces(*self)
+    }
 }
 
 impl DynMigration for &SyncDynMigration {
@@ -1189,6 +1563,10 @@ impl DynMigration for &SyncDynMigration {
     fn operations(&self) -> &[Operation] {
         DynMigration::operations(*self)
     }
+
+    fn replaces(&self) -> &[&str] {
+        DynMigration::replaces(*self)
+    }
 }
 
 impl DynMigration for Box<dyn DynMigration> {
@@ -1207,6 +1585,10 @@ impl DynMigration for Box<dyn DynMigration> {
     fn operations(&self) -> &[Operation] {
         DynMigration::operations(&**self)
     }
+
+    fn replaces(&self) -> &[&str] {
+        DynMigration::replaces(&**self)
+    }
 }
 
 impl DynMigration for Box<SyncDynMigration> {
@@ -1225,6 +1607,10 @@ impl DynMigration for Box<SyncDynMigration> {
     fn operations(&self) -> &[Operation] {
         DynMigration::operations(&**self)
     }
+
+    fn replaces(&self) -> &[&str] {
+        DynMigration::replaces(&**self)
+    }
 }
 
 pub(crate) struct MigrationWrapper(Box<SyncDynMigration>);
@@ -1252,6 +1638,10 @@ impl DynMigration for MigrationWrapper {
     fn operations(&self) -> &[Operation] {
         self.0.operations()
     }
+
+    fn replaces(&self) -> &[&str] {
+        self.0.replaces()
+    }
 }
 
 impl Debug for MigrationWrapper {
@@ -1260,6 +1650,7 @@ impl Debug for MigrationWrapper {
             .field("app_name", &self.app_name())
             .field("migration_name", &self.name())
             .field("operations", &self.operations())
+            .field("replaces", &self.replaces())
             .finish()
     }
 }
@@ -1338,7 +1729,7 @@ impl MigrationDependency {
 #[derive(Debug)]
 #[model(table_name = "cot__migrations", model_type = "internal")]
 struct AppliedMigration {
-    id: i32,
+    id: Auto<i32>,
     app: String,
     name: String,
     applied: chrono::DateTime<chrono::FixedOffset>,
@@ -1394,6 +1785,69 @@ mod tests {
         const OPERATIONS: &'static [Operation] = &[];
     }
 
+    struct AddFieldMigration;
+
+    impl Migration for AddFieldMigration {
+        const APP_NAME: &'static str = "testapp";
+        const MIGRATION_NAME: &'static str = "m_0002_add_field";
+        const DEPENDENCIES: &'static [MigrationDependency] =
+            &[MigrationDependency::migration("testapp", "m_0001_initial")];
+        const OPERATIONS: &'static [Operation] = &[Operation::add_field()
+            .table_name(Identifier::new("testapp__test_model"))
+            .field(Field::new(
+                Identifier::new("age"),
+                <i32 as DatabaseField>::TYPE,
+            ))
+            .build()];
+    }
+
+    struct SquashedMigration;
+
+    impl Migration for SquashedMigration {
+        const APP_NAME: &'static str = "testapp";
+        const MIGRATION_NAME: &'static str = "m_0001_squashed_0002";
+        const DEPENDENCIES: &'static [MigrationDependency] = &[];
+        const OPERATIONS: &'static [Operation] = &[Operation::create_model()
+            .table_name(Identifier::new("testapp__test_model"))
+            .fields(&[
+                Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+                    .primary_key()
+                    .auto(),
+                Field::new(Identifier::new("name"), <String as DatabaseField>::TYPE),
+                Field::new(Identifier::new("age"), <i32 as DatabaseField>::TYPE),
+            ])
+            .build()];
+        const REPLACES: &'static [&'static str] = &["m_0001_initial", "m_0002_add_field"];
+    }
+
+    struct AfterSquashedMigration;
+
+    impl Migration for AfterSquashedMigration {
+        const APP_NAME: &'static str = "testapp";
+        const MIGRATION_NAME: &'static str = "m_0003_custom";
+        const DEPENDENCIES: &'static [MigrationDependency] = &[MigrationDependency::migration(
+            "testapp",
+            "m_0002_add_field",
+        )];
+        const OPERATIONS: &'static [Operation] = &[];
+    }
+
+    fn squashed_test_engine(migrations: &[&'static SyncDynMigration]) -> MigrationEngine {
+        MigrationEngine::new(migrations.iter().copied()).unwrap()
+    }
+
+    async fn applied_migrations(database: &Database) -> Vec<String> {
+        let mut applied: Vec<_> = AppliedMigration::objects()
+            .all(database)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|migration| migration.name)
+            .collect();
+        applied.sort();
+        applied
+    }
+
     #[cot_macros::dbtest]
     async fn test_migration_engine_run(test_db: &mut TestDatabase) {
         let engine = MigrationEngine::new([TestMigration]).unwrap();
@@ -1416,6 +1870,253 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_squashed_fresh_database(test_db: &mut TestDatabase) {
+        let engine = squashed_test_engine(&[
+            &TestMigration,
+            &AddFieldMigration,
+            &SquashedMigration,
+            &AfterSquashedMigration,
+        ]);
+
+        engine.run(&test_db.database()).await.unwrap();
+
+        assert_eq!(
+            applied_migrations(&test_db.database()).await,
+            [
+                "m_0001_initial",
+                "m_0001_squashed_0002",
+                "m_0002_add_field",
+                "m_0003_custom"
+            ]
+        );
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_squashed_replaced_applied(test_db: &mut TestDatabase) {
+        squashed_test_engine(&[&TestMigration, &AddFieldMigration])
+            .run(&test_db.database())
+            .await
+            .unwrap();
+
+        // the squashed migration would fail if applied, as the table exists
+        squashed_test_engine(&[&SquashedMigration, &AfterSquashedMigration])
+            .run(&test_db.database())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            applied_migrations(&test_db.database()).await,
+            [
+                "m_0001_initial",
+                "m_0001_squashed_0002",
+                "m_0002_add_field",
+                "m_0003_custom"
+            ]
+        );
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_squashed_partially_applied(test_db: &mut TestDatabase) {
+        squashed_test_engine(&[&TestMigration])
+            .run(&test_db.database())
+            .await
+            .unwrap();
+
+        squashed_test_engine(&[&TestMigration, &AddFieldMigration, &SquashedMigration])
+            .run(&test_db.database())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            applied_migrations(&test_db.database()).await,
+            ["m_0001_initial", "m_0001_squashed_0002", "m_0002_add_field"]
+        );
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_squashed_replaced_missing(test_db: &mut TestDatabase) {
+        squashed_test_engine(&[&TestMigration])
+            .run(&test_db.database())
+            .await
+            .unwrap();
+
+        let result = squashed_test_engine(&[&SquashedMigration])
+            .run(&test_db.database())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(crate::db::DatabaseError::MigrationError(
+                MigrationEngineError::ReplacedMigrationNotFound { .. }
+            ))
+        ));
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_schema_differences_none(test_db: &mut TestDatabase) {
+        let engine = squashed_test_engine(&[&TestMigration, &AddFieldMigration]);
+        engine.run(&test_db.database()).await.unwrap();
+
+        let differences = engine
+            .schema_differences(&test_db.database())
+            .await
+            .unwrap();
+
+        assert_eq!(differences, []);
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_schema_differences_not_migrated(test_db: &mut TestDatabase) {
+        let engine = squashed_test_engine(&[&TestMigration]);
+
+        let differences = engine
+            .schema_differences(&test_db.database())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            differences,
+            [SchemaDifference::MissingTable {
+                table: "cot__migrations".to_owned()
+            }]
+        );
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_schema_differences_columns(test_db: &mut TestDatabase) {
+        let database = test_db.database();
+        squashed_test_engine(&[&TestMigration])
+            .run(&database)
+            .await
+            .unwrap();
+        MigrationEngine::mark_migration_applied(&database, "testapp", "m_0002_add_field")
+            .await
+            .unwrap();
+        database
+            .raw("ALTER TABLE testapp__test_model ADD COLUMN extra integer NULL")
+            .await
+            .unwrap();
+
+        let differences = squashed_test_engine(&[&TestMigration, &AddFieldMigration])
+            .schema_differences(&database)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            differences,
+            [
+                SchemaDifference::MissingColumn {
+                    table: "testapp__test_model".to_owned(),
+                    column: "age".to_owned(),
+                },
+                SchemaDifference::UnexpectedColumn {
+                    table: "testapp__test_model".to_owned(),
+                    column: "extra".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_schema_differences_squashed(test_db: &mut TestDatabase) {
+        squashed_test_engine(&[&TestMigration, &AddFieldMigration])
+            .run(&test_db.database())
+            .await
+            .unwrap();
+
+        let differences = squashed_test_engine(&[&SquashedMigration])
+            .schema_differences(&test_db.database())
+            .await
+            .unwrap();
+
+        assert_eq!(differences, []);
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_schema_differences_squashed_checks_table(
+        test_db: &mut TestDatabase,
+    ) {
+        let database = test_db.database();
+        squashed_test_engine(&[&TestMigration])
+            .run(&database)
+            .await
+            .unwrap();
+        // the column added by the second migration is missing from the database
+        MigrationEngine::mark_migration_applied(&database, "testapp", "m_0002_add_field")
+            .await
+            .unwrap();
+
+        let differences = squashed_test_engine(&[&SquashedMigration])
+            .schema_differences(&database)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            differences,
+            [SchemaDifference::MissingColumn {
+                table: "testapp__test_model".to_owned(),
+                column: "age".to_owned(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "sqlite")]
+    async fn test_migration_engine_schema_differences_type_and_null() {
+        let database = Database::new("sqlite::memory:").await.unwrap();
+        let engine = squashed_test_engine(&[&TestMigration]);
+        engine.run(&database).await.unwrap();
+        database
+            .raw("DROP TABLE testapp__test_model")
+            .await
+            .unwrap();
+        database
+            .raw("CREATE TABLE testapp__test_model (id integer NOT NULL PRIMARY KEY, name blob NULL)")
+            .await
+            .unwrap();
+
+        let differences = engine.schema_differences(&database).await.unwrap();
+
+        assert_eq!(
+            differences,
+            [
+                SchemaDifference::TypeMismatch {
+                    table: "testapp__test_model".to_owned(),
+                    column: "name".to_owned(),
+                    expected: "text".to_owned(),
+                    found: "blob".to_owned(),
+                },
+                SchemaDifference::NullabilityMismatch {
+                    table: "testapp__test_model".to_owned(),
+                    column: "name".to_owned(),
+                    expected_nullable: false,
+                },
+            ]
+        );
+        assert_eq!(
+            differences[1].to_string(),
+            "column `testapp__test_model.name` is NULL, expected NOT NULL"
+        );
+    }
+
+    #[cot_macros::dbtest]
+    async fn test_migration_engine_schema_differences_builtin_migrations(
+        test_db: &mut TestDatabase,
+    ) {
+        let database = test_db.database();
+        let migrations = crate::auth::db::migrations::MIGRATIONS
+            .iter()
+            .chain(crate::admin::migrations::MIGRATIONS)
+            .copied();
+        let engine = MigrationEngine::new(migrations).unwrap();
+        engine.run(&database).await.unwrap();
+
+        let differences = engine.schema_differences(&database).await.unwrap();
+
+        assert_eq!(differences, []);
+    }
+
     #[test]
     fn test_operation_create_model() {
         const OPERATION_CREATE_MODEL_FIELDS: &[Field; 2] = &[
@@ -1462,6 +2163,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn operation_forwards_sql() {
+        const OPERATION: Operation = Operation::create_model()
+            .table_name(Identifier::new("testapp__test_model"))
+            .fields(&[
+                Field::new(Identifier::new("id"), <i32 as DatabaseField>::TYPE)
+                    .primary_key()
+                    .auto(),
+                Field::new(
+                    Identifier::new("created"),
+                    <chrono::DateTime<chrono::FixedOffset> as DatabaseField>::TYPE,
+                ),
+            ])
+            .build();
+
+        assert_eq!(
+            OPERATION.forwards_sql(DatabaseEngine::Postgres),
+            r#"CREATE TABLE "testapp__test_model" ( "id" serial PRIMARY KEY NOT NULL, "created" timestamp with time zone NOT NULL )"#
+        );
+        assert_eq!(
+            OPERATION.forwards_sql(DatabaseEngine::MySql),
+            "CREATE TABLE `testapp__test_model` ( `id` int PRIMARY KEY AUTO_INCREMENT NOT NULL, `created` DATETIME(6) NOT NULL )"
+        );
+    }
+
+    #[test]
+    fn operation_backwards_sql() {
+        let operation = Operation::add_field()
+            .table_name(Identifier::new("testapp__test_model"))
+            .field(Field::new(
+                Identifier::new("age"),
+                <i32 as DatabaseField>::TYPE,
+            ))
+            .build();
+
+        assert_eq!(
+            operation.backwards_sql(DatabaseEngine::Sqlite),
+            r#"ALTER TABLE "testapp__test_model" DROP COLUMN "age""#
+        );
+    }
+
+    #[test]
+    fn operation_forwards_sql_default_value() {
+        let operation = Operation::add_field()
+            .table_name(Identifier::new("testapp__test_model"))
+            .field(
+                Field::new(Identifier::new("active"), <bool as DatabaseField>::TYPE)
+                    .default_value(FieldDefault::Bool(false)),
+            )
+            .build();
+
+        assert_eq!(
+            operation.forwards_sql(DatabaseEngine::Postgres),
+            r#"ALTER TABLE "testapp__test_model" ADD COLUMN "active" bool NOT NULL DEFAULT FALSE"#
+        );
+    }
+
     #[test]
     fn field_new() {
         let field = Field::new(Identifier::new("id"), ColumnType::Integer)
@@ -0,0 +1,200 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::db::introspection::TableSchema;
+use crate::db::migrations::{Field, Operation, OperationInner};
+use crate::db::{DatabaseEngine, Identifier};
+
+/// A difference between the schema of the database and the schema that the
+/// applied migrations should have produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaDifference {
+    /// A table created by the migrations doesn't exist in the database.
+    MissingTable {
+        /// The name of the table.
+        table: String,
+    },
+    /// A column created by the migrations doesn't exist in the database.
+    MissingColumn {
+        /// The name of the table.
+        table: String,
+        /// The name of the column.
+        column: String,
+    },
+    /// A column exists in the database, but it's not created by any of the
+    /// migrations.
+    UnexpectedColumn {
+        /// The name of the table.
+        table: String,
+        /// The name of the column.
+        column: String,
+    },
+    /// A column has a different type than the one defined in the migrations.
+    TypeMismatch {
+        /// The name of the table.
+        table: String,
+        /// The name of the column.
+        column: String,
+        /// The (normalized) type name defined in the migrations.
+        expected: String,
+        /// The (normalized) type name found in the database.
+        found: String,
+    },
+    /// A column is nullable while it shouldn't be, or the other way round.
+    NullabilityMismatch {
+        /// The name of the table.
+        table: String,
+        /// The name of the column.
+        column: String,
+        /// Whether the migrations define the column as nullable.
+        expected_nullable: bool,
+    },
+}
+
+impl Display for SchemaDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn nullability(nullable: bool) -> &'static str {
+            if nullable {
+                "NULL"
+            } else {
+                "NOT NULL"
+            }
+        }
+
+        match self {
+            Self::MissingTable { table } => write!(f, "table `{table}` does not exist"),
+            Self::MissingColumn { table, column } => {
+                write!(f, "column `{table}.{column}` does not exist")
+            }
+            Self::UnexpectedColumn { table, column } => {
+                write!(
+                    f,
+                    "column `{table}.{column}` is not defined in the migrations"
+                )
+            }
+            Self::TypeMismatch {
+                table,
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "column `{table}.{column}` has type `{found}`, expected `{expected}`"
+            ),
+            Self::NullabilityMismatch {
+                table,
+                column,
+                expected_nullable,
+            } => write!(
+                f,
+                "column `{table}.{column}` is {}, expected {}",
+                nullability(!expected_nullable),
+                nullability(*expected_nullable)
+            ),
+        }
+    }
+}
+
+/// The schema of the database, as reconstructed from the migration operations.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ExpectedSchema {
+    tables: Vec<(Identifier, Vec<Field>)>,
+}
+
+impl ExpectedSchema {
+    #[must_use]
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the schema with the changes made by given operation.
+    pub(super) fn apply(&mut self, operation: &Operation) {
+        match operation.inner {
+            OperationInner::CreateModel {
+                table_name, fields, ..
+            } => {
+                if self.table_mut(table_name).is_none() {
+                    self.tables.push((table_name, fields.to_vec()));
+                }
+            }
+            OperationInner::AddField { table_name, field } => {
+                if let Some(fields) = self.table_mut(table_name) {
+                    fields.push(field);
+                }
+            }
+        }
+    }
+
+    fn table_mut(&mut self, table_name: Identifier) -> Option<&mut Vec<Field>> {
+        self.tables
+            .iter_mut()
+            .find(|(name, _)| *name == table_name)
+            .map(|(_, fields)| fields)
+    }
+
+    /// Compares the schema with the tables that exist in the database.
+    ///
+    /// Tables that exist in the database but are not created by the
+    /// migrations are ignored, as they might not be managed by Cot.
+    #[must_use]
+    pub(super) fn differences(
+        &self,
+        engine: DatabaseEngine,
+        tables: &[TableSchema],
+    ) -> Vec<SchemaDifference> {
+        let mut differences = Vec::new();
+
+        for (table_name, fields) in &self.tables {
+            let table = table_name.as_str();
+            let Some(table_schema) = tables.iter().find(|schema| schema.name() == table) else {
+                differences.push(SchemaDifference::MissingTable {
+                    table: table.to_owned(),
+                });
+                continue;
+            };
+
+            for field in fields {
+                let column = field.name.as_str();
+                let Some(column_schema) = table_schema.column(column) else {
+                    differences.push(SchemaDifference::MissingColumn {
+                        table: table.to_owned(),
+                        column: column.to_owned(),
+                    });
+                    continue;
+                };
+
+                let expected_type = engine.introspected_type_name(field);
+                if column_schema.type_name() != expected_type {
+                    differences.push(SchemaDifference::TypeMismatch {
+                        table: table.to_owned(),
+                        column: column.to_owned(),
+                        expected: expected_type.to_owned(),
+                        found: column_schema.type_name().to_owned(),
+                    });
+                }
+                if column_schema.is_nullable() != field.null {
+                    differences.push(SchemaDifference::NullabilityMismatch {
+                        table: table.to_owned(),
+                        column: column.to_owned(),
+                        expected_nullable: field.null,
+                    });
+                }
+            }
+
+            for column_schema in table_schema.columns() {
+                if !fields
+                    .iter()
+                    .any(|field| field.name.as_str() == column_schema.name())
+                {
+                    differences.push(SchemaDifference::UnexpectedColumn {
+                        table: table.to_owned(),
+                        column: column_schema.name().to_owned(),
+                    });
+                }
+            }
+        }
+
+        differences
+    }
+}
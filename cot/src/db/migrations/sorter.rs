@@ -94,6 +94,21 @@ impl<'a, T: DynMigration> MigrationSorter<'a, T> {
                 });
             };
 
+            // Dependencies on the migrations replaced by a squashed migration
+            // are satisfied by the squashed migration itself
+            for &replaced in migration.replaces() {
+                let app_and_name = MigrationLookup::ByAppAndName {
+                    app: migration.app_name(),
+                    name: replaced,
+                };
+                if map.insert(app_and_name, index).is_some() {
+                    return Err(MigrationSorterError::DuplicateMigration {
+                        app_name: migration.app_name().to_owned(),
+                        migration_name: replaced.to_owned(),
+                    });
+                }
+            }
+
             for operation in migration.operations() {
                 if let OperationInner::CreateModel { table_name, .. } = operation.inner {
                     let app_and_model = MigrationLookup::ByAppAndModel {
@@ -139,7 +154,7 @@ impl From<&MigrationDependency> for MigrationLookup<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::migrations::{MigrationDependency, Operation};
+    use crate::db::migrations::{Migration, MigrationDependency, Operation, SyncDynMigration};
     use crate::db::Identifier;
     use crate::test::TestMigration;
 
@@ -249,6 +264,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn toposort_replaced_dependency() {
+        struct SquashedMigration;
+
+        impl Migration for SquashedMigration {
+            const APP_NAME: &'static str = "app1";
+            const MIGRATION_NAME: &'static str = "m_0001_squashed_0002";
+            const DEPENDENCIES: &'static [MigrationDependency] = &[];
+            const OPERATIONS: &'static [Operation] = &[];
+            const REPLACES: &'static [&'static str] = &["m_0001_initial", "m_0002_second"];
+        }
+
+        let mut migrations: Vec<Box<SyncDynMigration>> = vec![
+            Box::new(TestMigration::new(
+                "app1",
+                "m_0003_third",
+                [MigrationDependency::migration("app1", "m_0002_second")],
+                [],
+            )),
+            Box::new(SquashedMigration),
+        ];
+
+        let mut sorter = MigrationSorter::new(&mut migrations);
+        sorter.sort().unwrap();
+
+        assert_eq!(migrations[0].name(), "m_0001_squashed_0002");
+        assert_eq!(migrations[1].name(), "m_0003_third");
+    }
+
     // migration names must be &'static str
     const MIGRATION_NAMES: [&str; 100] = [
         "m0", "m1", "m2", "m3", "m4", "m5", "m6", "m7", "m8", "m9", "m10", "m11", "m12", "m13",
@@ -31,6 +31,9 @@ use crate::db::{
 /// ```
 pub struct Query<T> {
     filter: Option<Expr>,
+    order_by: Vec<(Identifier, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
     phantom_data: PhantomData<fn() -> T>,
 }
 
@@ -39,6 +42,9 @@ impl<T> Debug for Query<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Query")
             .field("filter", &self.filter)
+            .field("order_by", &self.order_by)
+            .field("limit", &self.limit)
+            .field("offset", &self.offset)
             .field("phantom_data", &self.phantom_data)
             .finish()
     }
@@ -49,6 +55,9 @@ impl<T> Clone for Query<T> {
     fn clone(&self) -> Self {
         Self {
             filter: self.filter.clone(),
+            order_by: self.order_by.clone(),
+            limit: self.limit,
+            offset: self.offset,
             phantom_data: PhantomData,
         }
     }
@@ -58,6 +67,9 @@ impl<T> Clone for Query<T> {
 impl<T> PartialEq for Query<T> {
     fn eq(&self, other: &Self) -> bool {
         self.filter == other.filter
+            && self.order_by == other.order_by
+            && self.limit == other.limit
+            && self.offset == other.offset
     }
 }
 
@@ -89,6 +101,9 @@ impl<T: Model> Query<T> {
     pub fn new() -> Self {
         Self {
             filter: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
             phantom_data: PhantomData,
         }
     }
@@ -115,6 +130,75 @@ impl<T: Model> Query<T> {
         self
     }
 
+    /// Add an ordering to the query. Calling this multiple times orders the
+    /// results by each of the fields in turn.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::{Order, Query};
+    ///
+    /// #[model]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    ///     age: i32,
+    /// }
+    ///
+    /// let query = Query::<User>::new()
+    ///     .order_by("age", Order::Desc)
+    ///     .order_by("name", Order::Asc);
+    /// ```
+    pub fn order_by<I: Into<Identifier>>(&mut self, field: I, order: Order) -> &mut Self {
+        self.order_by.push((field.into(), order));
+        self
+    }
+
+    /// Set the maximum number of rows returned by the query.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::Query;
+    ///
+    /// #[model]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    ///     age: i32,
+    /// }
+    ///
+    /// let query = Query::<User>::new().limit(10);
+    /// ```
+    pub fn limit(&mut self, limit: u64) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set the number of rows to skip before returning the results.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::Query;
+    ///
+    /// #[model]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    ///     age: i32,
+    /// }
+    ///
+    /// let query = Query::<User>::new().limit(10).offset(20);
+    /// ```
+    pub fn offset(&mut self, offset: u64) -> &mut Self {
+        self.offset = Some(offset);
+        self
+    }
+
     /// Execute the query and return all results.
     ///
     /// # Errors
@@ -143,6 +227,16 @@ impl<T: Model> Query<T> {
         db.exists(self).await
     }
 
+    /// Count the rows that match the query. The ordering, limit and offset
+    /// of the query are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub async fn count<DB: DatabaseBackend>(&self, db: &DB) -> db::Result<u64> {
+        db.count(self).await
+    }
+
     /// Delete all rows that match the query.
     ///
     /// # Errors
@@ -160,8 +254,44 @@ impl<T: Model> Query<T> {
             statement.and_where(filter.as_sea_query_expr());
         }
     }
+
+    pub(super) fn add_order_to_statement(&self, statement: &mut sea_query::SelectStatement) {
+        for (field, order) in &self.order_by {
+            statement.order_by(*field, order.as_sea_query_order());
+        }
+    }
+
+    pub(super) fn add_limit_to_statement(&self, statement: &mut sea_query::SelectStatement) {
+        if let Some(limit) = self.limit {
+            statement.limit(limit);
+        }
+        if let Some(offset) = self.offset {
+            statement.offset(offset);
+        }
+    }
+}
+
+/// The direction in which the results of a [`Query`] are ordered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// Ascending order (smallest values first).
+    Asc,
+    /// Descending order (largest values first).
+    Desc,
 }
 
+impl Order {
+    fn as_sea_query_order(self) -> sea_query::Order {
+        match self {
+            Self::Asc => sea_query::Order::Asc,
+            Self::Desc => sea_query::Order::Desc,
+        }
+    }
+}
+
+/// The character used to escape the wildcards in [`Expr::like`] patterns.
+const LIKE_ESCAPE_CHAR: char = '\\';
+
 /// An expression that can be used to filter, update, or delete rows.
 ///
 /// This is used to create complex queries with multiple conditions. Typically,
@@ -505,6 +635,95 @@ pub enum Expr {
     /// );
     /// ```
     Div(Box<Expr>, Box<Expr>),
+    /// A `LIKE` expression. The `%` and `_` characters in the pattern match
+    /// any sequence of characters and any single character, respectively; a
+    /// backslash can be used to match them literally.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::{Expr, Query};
+    ///
+    /// #[model]
+    /// struct MyModel {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     name: String,
+    /// };
+    ///
+    /// let expr = Expr::like(Expr::field("name"), "John%");
+    /// let query = <Query<MyModel>>::new().filter(expr);
+    /// ```
+    Like(Box<Expr>, String),
+    /// A `LOWER` function call, converting a text expression to lowercase.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::{Expr, Query};
+    ///
+    /// #[model]
+    /// struct MyModel {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     name: String,
+    /// };
+    ///
+    /// let expr = Expr::like(Expr::lower(Expr::field("name")), "john%");
+    /// let query = <Query<MyModel>>::new().filter(expr);
+    /// ```
+    Lower(Box<Expr>),
+    /// An `IN` expression checking whether the value is one of the values of
+    /// a field in the rows matching a query of a (usually another) model.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::query::{Expr, Query};
+    /// use cot::db::{model, query, ForeignKey};
+    ///
+    /// #[model]
+    /// struct Author {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     name: String,
+    /// };
+    ///
+    /// #[model]
+    /// struct Book {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     author: ForeignKey<Author>,
+    ///     published: bool,
+    /// };
+    ///
+    /// // the authors having published books
+    /// let expr = Expr::in_subquery(Expr::field("id"), "author", &query!(Book, $published == true));
+    /// let query = <Query<Author>>::new().filter(expr);
+    /// ```
+    InSubquery(Box<Expr>, Box<Subquery>),
+}
+
+/// A query returning the values of a single field of a model, used in
+/// [`Expr::InSubquery`] expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subquery {
+    table: Identifier,
+    field: Identifier,
+    filter: Option<Expr>,
+}
+
+impl Subquery {
+    fn as_sea_query_select(&self) -> sea_query::SelectStatement {
+        let mut statement = sea_query::Query::select();
+        statement.column(self.field).from(self.table);
+        if let Some(filter) = &self.filter {
+            statement.and_where(filter.as_sea_query_expr());
+        }
+        statement
+    }
 }
 
 impl Expr {
@@ -894,6 +1113,126 @@ impl Expr {
         Self::Div(Box::new(lhs), Box::new(rhs))
     }
 
+    /// Create a new `LIKE` expression. The `%` and `_` characters in the
+    /// pattern match any sequence of characters and any single character,
+    /// respectively; a backslash can be used to match them literally (see
+    /// [`Expr::escape_like`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::{Expr, Query};
+    ///
+    /// #[model]
+    /// struct MyModel {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     name: String,
+    /// };
+    ///
+    /// let expr = Expr::like(Expr::field("name"), "John%");
+    /// let query = <Query<MyModel>>::new().filter(expr);
+    /// ```
+    #[must_use]
+    pub fn like<T: Into<String>>(lhs: Self, pattern: T) -> Self {
+        Self::Like(Box::new(lhs), pattern.into())
+    }
+
+    /// Create a new `LOWER` expression.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::model;
+    /// use cot::db::query::{Expr, Query};
+    ///
+    /// #[model]
+    /// struct MyModel {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     name: String,
+    /// };
+    ///
+    /// let expr = Expr::like(Expr::lower(Expr::field("name")), "john%");
+    /// let query = <Query<MyModel>>::new().filter(expr);
+    /// ```
+    #[must_use]
+    pub fn lower(expr: Self) -> Self {
+        Self::Lower(Box::new(expr))
+    }
+
+    /// Create a new `IN` expression checking whether the value of `lhs` is
+    /// one of the values of given field in the rows matching given query.
+    ///
+    /// Only the filter of the query is used; its ordering, limit and offset
+    /// are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::query::{Expr, Query};
+    /// use cot::db::{model, query, ForeignKey};
+    ///
+    /// #[model]
+    /// struct Author {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     name: String,
+    /// };
+    ///
+    /// #[model]
+    /// struct Book {
+    ///     #[model(primary_key)]
+    ///     id: i32,
+    ///     author: ForeignKey<Author>,
+    ///     published: bool,
+    /// };
+    ///
+    /// // the authors having published books
+    /// let expr = Expr::in_subquery(Expr::field("id"), "author", &query!(Book, $published == true));
+    /// let query = <Query<Author>>::new().filter(expr);
+    /// ```
+    #[must_use]
+    pub fn in_subquery<T: Model, I: Into<Identifier>>(
+        lhs: Self,
+        field: I,
+        query: &Query<T>,
+    ) -> Self {
+        Self::InSubquery(
+            Box::new(lhs),
+            Box::new(Subquery {
+                table: T::TABLE_NAME,
+                field: field.into(),
+                filter: query.filter.clone(),
+            }),
+        )
+    }
+
+    /// Escapes the `%`, `_` and `\` characters in a string so that it can be
+    /// used literally inside a [`Expr::like`] pattern.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cot::db::query::Expr;
+    ///
+    /// assert_eq!(Expr::escape_like("100%_done"), "100\\%\\_done");
+    /// let pattern = format!("%{}%", Expr::escape_like("50%"));
+    /// let expr = Expr::like(Expr::field("name"), pattern);
+    /// ```
+    #[must_use]
+    pub fn escape_like(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '%' | '_' | LIKE_ESCAPE_CHAR) {
+                escaped.push(LIKE_ESCAPE_CHAR);
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
     /// Returns the expression as a [`sea_query::SimpleExpr`].
     ///
     /// # Example
@@ -930,6 +1269,13 @@ impl Expr {
             Self::Sub(lhs, rhs) => lhs.as_sea_query_expr().sub(rhs.as_sea_query_expr()),
             Self::Mul(lhs, rhs) => lhs.as_sea_query_expr().mul(rhs.as_sea_query_expr()),
             Self::Div(lhs, rhs) => lhs.as_sea_query_expr().div(rhs.as_sea_query_expr()),
+            Self::Like(lhs, pattern) => lhs
+                .as_sea_query_expr()
+                .like(sea_query::LikeExpr::new(pattern).escape(LIKE_ESCAPE_CHAR)),
+            Self::Lower(expr) => sea_query::Func::lower(expr.as_sea_query_expr()).into(),
+            Self::InSubquery(lhs, subquery) => lhs
+                .as_sea_query_expr()
+                .in_subquery(subquery.as_sea_query_select()),
         }
     }
 }
@@ -1367,6 +1713,46 @@ mod tests {
         assert!(query.filter.is_some());
     }
 
+    #[test]
+    fn query_order_limit_offset() {
+        let mut query: Query<MockModel> = Query::new();
+
+        query
+            .order_by("name", Order::Desc)
+            .order_by("id", Order::Asc)
+            .limit(10)
+            .offset(20);
+
+        assert_eq!(
+            query.order_by,
+            [
+                (Identifier::new("name"), Order::Desc),
+                (Identifier::new("id"), Order::Asc)
+            ]
+        );
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(query.offset, Some(20));
+        assert_ne!(query, Query::new());
+    }
+
+    #[test]
+    fn query_order_limit_in_statement() {
+        let mut query: Query<MockModel> = Query::new();
+        query.order_by("name", Order::Desc).limit(10).offset(20);
+
+        let mut select = sea_query::Query::select();
+        select
+            .column(Identifier::new("id"))
+            .from(Identifier::new("t"));
+        query.add_order_to_statement(&mut select);
+        query.add_limit_to_statement(&mut select);
+
+        assert_eq!(
+            select.to_string(sea_query::SqliteQueryBuilder),
+            r#"SELECT "id" FROM "t" ORDER BY "name" DESC LIMIT 10 OFFSET 20"#
+        );
+    }
+
     #[tokio::test]
     async fn query_all() {
         let mut db = MockDatabaseBackend::new();
@@ -1401,6 +1787,17 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn query_count() {
+        let mut db = MockDatabaseBackend::new();
+        db.expect_count().returning(|_: &Query<MockModel>| Ok(5));
+        let query: Query<MockModel> = Query::new();
+
+        let result = query.count(&db).await;
+
+        assert_eq!(result.unwrap(), 5);
+    }
+
     #[tokio::test]
     async fn query_delete() {
         let mut db = MockDatabaseBackend::new();
@@ -1460,4 +1857,42 @@ mod tests {
     test_expr_constructor!(expr_sub, Sub, sub);
     test_expr_constructor!(expr_mul, Mul, mul);
     test_expr_constructor!(expr_div, Div, div);
+
+    #[test]
+    fn expr_like_lower() {
+        let expr = Expr::like(Expr::lower(Expr::field("name")), "%john%");
+
+        let mut select = sea_query::Query::select();
+        select
+            .column(Identifier::new("id"))
+            .from(Identifier::new("t"))
+            .and_where(expr.as_sea_query_expr());
+        assert_eq!(
+            select.to_string(sea_query::SqliteQueryBuilder),
+            r#"SELECT "id" FROM "t" WHERE LOWER("name") LIKE '%john%' ESCAPE '\'"#
+        );
+    }
+
+    #[test]
+    fn expr_in_subquery() {
+        let mut query: Query<MockModel> = Query::new();
+        query.filter(Expr::eq(Expr::field("owner"), Expr::value(5)));
+        let expr = Expr::in_subquery(Expr::field("id"), "group", &query);
+
+        let mut select = sea_query::Query::select();
+        select
+            .column(Identifier::new("id"))
+            .from(Identifier::new("t"))
+            .and_where(expr.as_sea_query_expr());
+        assert_eq!(
+            select.to_string(sea_query::SqliteQueryBuilder),
+            r#"SELECT "id" FROM "t" WHERE "id" IN (SELECT "group" FROM "mock_model" WHERE "owner" = 5)"#
+        );
+    }
+
+    #[test]
+    fn expr_escape_like() {
+        assert_eq!(Expr::escape_like("john"), "john");
+        assert_eq!(Expr::escape_like("50%_off\\"), "50\\%\\_off\\\\");
+    }
 }
@@ -4,24 +4,56 @@
 /// they need to be implemented in a separate `impl` block. These methods are:
 /// * `prepare_values`
 /// * `sea_query_column_type_for`
+/// * `introspected_type_name`
+/// * `INTROSPECTION_QUERY`
 macro_rules! impl_sea_query_db_backend {
     ($db_name:ident : $sqlx_db_ty:ty, $pool_ty:ty, $row_name:ident, $value_ref_name:ident, $query_builder:expr) => {
         /// A wrapper over [`$sqlx_db_ty`] that serves an in internal implementation of
         /// `Database` using `SeaQuery`.
-        #[derive(Debug)]
+        #[derive(derive_more::Debug)]
         pub(super) struct $db_name {
             db_connection: $pool_ty,
+            /// The transaction all the queries are run in, if any; otherwise, the
+            /// queries are run on the connection pool.
+            #[debug("...")]
+            transaction: Option<tokio::sync::Mutex<sqlx::Transaction<'static, $sqlx_db_ty>>>,
         }
 
         impl $db_name {
             pub(super) async fn new(url: &str) -> crate::db::Result<Self> {
                 let db_connection = <$pool_ty>::connect(url).await?;
 
-                let db = Self { db_connection };
+                let db = Self {
+                    db_connection,
+                    transaction: None,
+                };
                 db.init().await?;
                 Ok(db)
             }
 
+            pub(super) async fn begin_transaction(&self) -> crate::db::Result<Self> {
+                let transaction = self.db_connection.begin().await?;
+
+                Ok(Self {
+                    db_connection: self.db_connection.clone(),
+                    transaction: Some(tokio::sync::Mutex::new(transaction)),
+                })
+            }
+
+            pub(super) async fn commit(self) -> crate::db::Result<()> {
+                if let Some(transaction) = self.transaction {
+                    transaction.into_inner().commit().await?;
+                }
+                Ok(())
+            }
+
+            pub(super) async fn rollback(self) -> crate::db::Result<()> {
+                if let Some(transaction) = self.transaction {
+                    transaction.into_inner().rollback().await?;
+                }
+                Ok(())
+            }
+
             pub(super) async fn close(&self) -> crate::db::Result<()> {
                 self.db_connection.close().await;
                 Ok(())
@@ -33,9 +65,15 @@ macro_rules! impl_sea_query_db_backend {
             ) -> crate::db::Result<Option<$row_name>> {
                 let (sql, values) = Self::build_sql(statement);
 
-                let row = Self::sqlx_query_with(&sql, values)
-                    .fetch_optional(&self.db_connection)
-                    .await?;
+                let query = Self::sqlx_query_with(&sql, values);
+                let row = match &self.transaction {
+                    Some(transaction) => {
+                        query
+                            .fetch_optional(&mut **transaction.lock().await)
+                            .await?
+                    }
+                    None => query.fetch_optional(&self.db_connection).await?,
+                };
                 Ok(row.map($row_name::new))
             }
 
@@ -45,12 +83,27 @@ macro_rules! impl_sea_query_db_backend {
             ) -> crate::db::Result<Vec<$row_name>> {
                 let (sql, values) = Self::build_sql(statement);
 
-                let result = Self::sqlx_query_with(&sql, values)
-                    .fetch_all(&self.db_connection)
-                    .await?
-                    .into_iter()
-                    .map($row_name::new)
-                    .collect();
+                let query = Self::sqlx_query_with(&sql, values);
+                let rows = match &self.transaction {
+                    Some(transaction) => query.fetch_all(&mut **transaction.lock().await).await?,
+                    None => query.fetch_all(&self.db_connection).await?,
+                };
+                let result = rows.into_iter().map($row_name::new).collect();
+                Ok(result)
+            }
+
+            pub(super) async fn fetch_all_raw(
+                &self,
+                sql: &str,
+            ) -> crate::db::Result<Vec<$row_name>> {
+                tracing::debug!("Query: `{}`", sql);
+
+                let query = sqlx::query(sql);
+                let rows = match &self.transaction {
+                    Some(transaction) => query.fetch_all(&mut **transaction.lock().await).await?,
+                    None => query.fetch_all(&self.db_connection).await?,
+                };
+                let result = rows.into_iter().map($row_name::new).collect();
                 Ok(result)
             }
 
@@ -89,7 +142,14 @@ macro_rules! impl_sea_query_db_backend {
             where
                 A: 'a + sqlx::IntoArguments<'a, $sqlx_db_ty>,
             {
-                let result = sqlx_statement.execute(&self.db_connection).await?;
+                let result = match &self.transaction {
+                    Some(transaction) => {
+                        sqlx_statement
+                            .execute(&mut **transaction.lock().await)
+                            .await?
+                    }
+                    None => sqlx_statement.execute(&self.db_connection).await?,
+                };
                 let result = crate::db::StatementResult {
                     rows_affected: crate::db::RowsNum(result.rows_affected()),
                     last_inserted_row_id: Self::last_inserted_row_id_for(&result),
@@ -90,6 +90,9 @@ impl_error_from_repr!(crate::router::path::ReverseError);
 impl_error_from_repr!(crate::db::DatabaseError);
 impl_error_from_repr!(crate::form::FormError);
 impl_error_from_repr!(crate::auth::AuthError);
+#[cfg(feature = "jwt")]
+impl_error_from_repr!(crate::auth::jwt::JwtConfigError);
+impl_error_from_repr!(tower_sessions::session::Error);
 #[cfg(feature = "json")]
 impl_error_from_repr!(serde_json::Error);
 impl_error_from_repr!(crate::request::PathParamsDeserializerError);
@@ -112,12 +115,40 @@ pub(crate) enum ErrorRepr {
         #[from]
         source: toml::de::Error,
     },
+    /// The configuration of the JWT authentication backend is invalid.
+    #[cfg(feature = "jwt")]
+    #[error("Invalid JWT backend configuration: {0}")]
+    JwtConfig(#[from] crate::auth::jwt::JwtConfigError),
     /// An error occurred while trying to start the server.
     #[error("Could not start server: {source}")]
     StartServer { source: std::io::Error },
     /// An error occurred while trying to collect static files into a directory.
     #[error("Could not collect static files: {source}")]
     CollectStatic { source: std::io::Error },
+    /// The migration that was requested could not be found in any of the
+    /// apps.
+    #[error("Migration `{migration}` not found in app `{app}`")]
+    #[cfg(feature = "db")]
+    MigrationNotFound { app: String, migration: String },
+    /// The database engine to generate the SQL for could not be determined.
+    #[error(
+        "Could not determine the database engine; pass `--engine` or set the database URL in the config"
+    )]
+    #[cfg(feature = "db")]
+    UnknownDatabaseEngine,
+    /// The operation requires a database, but none is configured.
+    #[error("Database is not configured; set the database URL in the config")]
+    #[cfg(feature = "db")]
+    DatabaseNotConfigured,
+    /// The schema of the database doesn't match the applied migrations.
+    #[error(
+        "Database schema does not match the applied migrations:{}",
+        format_schema_differences(.differences)
+    )]
+    #[cfg(feature = "db")]
+    SchemaDrift {
+        differences: Vec<crate::db::migrations::SchemaDifference>,
+    },
     /// An error occurred while trying to read the request body.
     #[error("Could not retrieve request body: {source}")]
     ReadRequestBody {
@@ -153,6 +184,9 @@ pub(crate) enum ErrorRepr {
     /// An error occurred while trying to authenticate a user.
     #[error("Failed to authenticate user: {0}")]
     Authentication(#[from] crate::auth::AuthError),
+    /// An error occurred while accessing the session object.
+    #[error("Could not access the session: {0}")]
+    SessionAccess(#[from] tower_sessions::session::Error),
     /// An error occurred while trying to serialize or deserialize JSON.
     #[error("JSON error: {0}")]
     #[cfg(feature = "json")]
@@ -168,6 +202,18 @@ pub(crate) enum ErrorRepr {
     PathParametersParse(#[from] crate::request::PathParamsDeserializerError),
 }
 
+#[cfg(feature = "db")]
+fn format_schema_differences(differences: &[crate::db::migrations::SchemaDifference]) -> String {
+    use std::fmt::Write;
+
+    differences
+        .iter()
+        .fold(String::new(), |mut output, difference| {
+            let _ = write!(output, "\n  - {difference}");
+            output
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use std::io;
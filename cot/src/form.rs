@@ -337,6 +337,9 @@ pub trait DynFormField: Display {
     /// Returns the HTML ID of the form field.
     fn dyn_id(&self) -> &str;
 
+    /// Returns the string value of the form field.
+    fn dyn_value(&self) -> Option<&str>;
+
     /// Sets the string value of the form field.
     fn dyn_set_value(&mut self, value: Cow<'_, str>);
 }
@@ -350,6 +353,10 @@ impl<T: FormField> DynFormField for T {
         FormField::id(self)
     }
 
+    fn dyn_value(&self) -> Option<&str> {
+        FormField::value(self)
+    }
+
     fn dyn_set_value(&mut self, value: Cow<'_, str>) {
         FormField::set_value(self, value);
     }
@@ -390,4 +397,11 @@ pub trait AsFormField {
     fn clean_value(field: &Self::Type) -> Result<Self, FormFieldValidationError>
     where
         Self: Sized;
+
+    /// Converts the value to the string that is set as the form field's value
+    /// with [`FormField::set_value`].
+    ///
+    /// This is the inverse of [`AsFormField::clean_value`]; it's used to fill
+    /// in a form with existing data, e.g. when editing an object.
+    fn to_field_value(&self) -> String;
 }
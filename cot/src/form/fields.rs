@@ -8,6 +8,8 @@ use std::num::{
 use derive_more::Deref;
 use rinja::filters::HtmlSafe;
 
+#[cfg(feature = "db")]
+use crate::db::{Auto, ForeignKey, LimitedString, Model};
 use crate::form::{AsFormField, FormField, FormFieldOptions, FormFieldValidationError};
 use crate::html::HtmlTag;
 
@@ -71,6 +73,9 @@ impl Display for StringField {
         if let Some(max_length) = self.custom_options.max_length {
             tag.attr("maxlength", &max_length.to_string());
         }
+        if let Some(value) = &self.value {
+            tag.attr("value", value);
+        }
 
         write!(f, "{}", tag.render())
     }
@@ -93,6 +98,10 @@ impl AsFormField for String {
         }
         Ok(value.to_owned())
     }
+
+    fn to_field_value(&self) -> String {
+        self.clone()
+    }
 }
 
 /// A newtype for String holding a password.
@@ -187,6 +196,10 @@ impl AsFormField for Password {
 
         Ok(Password::new(value))
     }
+
+    fn to_field_value(&self) -> String {
+        self.as_str().to_owned()
+    }
 }
 
 impl_form_field!(IntegerField, IntegerFieldOptions, "an integer", T: Integer);
@@ -225,6 +238,9 @@ impl<T: Integer> Display for IntegerField<T> {
         if let Some(max) = &self.custom_options.max {
             tag.attr("max", &max.to_string());
         }
+        if let Some(value) = &self.value {
+            tag.attr("value", value);
+        }
 
         write!(f, "{}", tag.render())
     }
@@ -311,6 +327,10 @@ macro_rules! impl_integer_as_form_field {
                     .parse()
                     .map_err(|_| FormFieldValidationError::invalid_value(value))
             }
+
+            fn to_field_value(&self) -> String {
+                self.to_string()
+            }
         }
     };
 }
@@ -355,6 +375,13 @@ impl Display for BoolField {
         bool_input.attr("name", self.id());
         bool_input.attr("id", self.id());
         bool_input.attr("value", "1");
+        if self
+            .value
+            .as_deref()
+            .is_some_and(|value| ["true", "on", "1"].contains(&value))
+        {
+            bool_input.bool_attr("checked");
+        }
 
         if self.custom_options.must_be_true.unwrap_or(false) {
             bool_input.bool_attr("required");
@@ -407,6 +434,108 @@ impl AsFormField for bool {
         }
         Ok(value.to_owned())
     }
+
+    fn to_field_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Implementation of `AsFormField` for [`LimitedString`].
+///
+/// The field is rendered as a [`StringField`] with its maximum length set to
+/// the length limit of the string, unless a lower one is specified in the
+/// options.
+#[cfg(feature = "db")]
+impl<const LIMIT: u32> AsFormField for LimitedString<LIMIT> {
+    type Type = StringField;
+
+    fn new_field(
+        options: FormFieldOptions,
+        mut custom_options: <Self::Type as FormField>::CustomOptions,
+    ) -> Self::Type {
+        custom_options.max_length = Some(
+            custom_options
+                .max_length
+                .map_or(LIMIT, |max_length| max_length.min(LIMIT)),
+        );
+        Self::Type::with_options(options, custom_options)
+    }
+
+    fn clean_value(field: &Self::Type) -> Result<Self, FormFieldValidationError> {
+        let value = String::clean_value(field)?;
+
+        LimitedString::new(value)
+            .map_err(|_| FormFieldValidationError::maximum_length_exceeded(LIMIT))
+    }
+
+    fn to_field_value(&self) -> String {
+        self.as_str().to_owned()
+    }
+}
+
+/// Implementation of `AsFormField` for [`Auto`].
+///
+/// The field is rendered as the field of the inner type; an empty value means
+/// that the value should be generated automatically by the database.
+#[cfg(feature = "db")]
+impl<T: AsFormField> AsFormField for Auto<T> {
+    type Type = T::Type;
+
+    fn new_field(
+        mut options: FormFieldOptions,
+        custom_options: <Self::Type as FormField>::CustomOptions,
+    ) -> Self::Type {
+        options.required = false;
+        T::new_field(options, custom_options)
+    }
+
+    fn clean_value(field: &Self::Type) -> Result<Self, FormFieldValidationError> {
+        match T::clean_value(field) {
+            Ok(value) => Ok(Auto::fixed(value)),
+            Err(FormFieldValidationError::Required) => Ok(Auto::auto()),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn to_field_value(&self) -> String {
+        match self {
+            Auto::Fixed(value) => value.to_field_value(),
+            Auto::Auto => String::new(),
+        }
+    }
+}
+
+/// Implementation of `AsFormField` for [`ForeignKey`].
+///
+/// The field is rendered as the field of the primary key of the referenced
+/// model; the cleaned value is a [`ForeignKey::PrimaryKey`], so it's not
+/// checked whether the referenced object exists until it's saved.
+#[cfg(feature = "db")]
+impl<T> AsFormField for ForeignKey<T>
+where
+    T: Model,
+    T::PrimaryKey: AsFormField,
+{
+    type Type = <T::PrimaryKey as AsFormField>::Type;
+
+    fn new_field(
+        options: FormFieldOptions,
+        custom_options: <Self::Type as FormField>::CustomOptions,
+    ) -> Self::Type {
+        T::PrimaryKey::new_field(options, custom_options)
+    }
+
+    fn clean_value(field: &Self::Type) -> Result<Self, FormFieldValidationError> {
+        // an empty value is not a reference, even if the primary key can be empty
+        // (e.g. when it's an `Auto`)
+        check_required(field)?;
+
+        T::PrimaryKey::clean_value(field).map(ForeignKey::PrimaryKey)
+    }
+
+    fn to_field_value(&self) -> String {
+        self.primary_key().to_field_value()
+    }
 }
 
 impl<T: AsFormField> AsFormField for Option<T> {
@@ -420,6 +549,10 @@ impl<T: AsFormField> AsFormField for Option<T> {
             Err(error) => Err(error),
         }
     }
+
+    fn to_field_value(&self) -> String {
+        self.as_ref().map_or_else(String::new, T::to_field_value)
+    }
 }
 
 fn check_required<T: FormField>(field: &T) -> Result<&str, FormFieldValidationError> {
@@ -527,6 +660,119 @@ mod tests {
         assert!(html.contains("required"));
     }
 
+    #[test]
+    fn field_render_value() {
+        let mut field = StringField::with_options(
+            FormFieldOptions {
+                id: "test".to_owned(),
+                required: true,
+            },
+            StringFieldOptions::default(),
+        );
+        field.set_value(Cow::Borrowed("\"quoted\""));
+        assert!(field.to_string().contains("value=\"&#34;quoted&#34;\""));
+
+        let mut field = IntegerField::<i32>::with_options(
+            FormFieldOptions {
+                id: "test".to_owned(),
+                required: true,
+            },
+            IntegerFieldOptions::default(),
+        );
+        field.set_value(Cow::Borrowed("5"));
+        assert!(field.to_string().contains("value=\"5\""));
+
+        let mut field = PasswordField::with_options(
+            FormFieldOptions {
+                id: "test".to_owned(),
+                required: true,
+            },
+            PasswordFieldOptions::default(),
+        );
+        field.set_value(Cow::Borrowed("password"));
+        assert!(!field.to_string().contains("value="));
+    }
+
+    #[test]
+    fn bool_field_render_checked() {
+        let mut field = BoolField::with_options(
+            FormFieldOptions {
+                id: "test".to_owned(),
+                required: false,
+            },
+            BoolFieldOptions::default(),
+        );
+        assert!(!field.to_string().contains("checked"));
+
+        field.set_value(Cow::Borrowed("1"));
+        assert!(field.to_string().contains("checked"));
+
+        field.set_value(Cow::Borrowed("0"));
+        assert!(!field.to_string().contains("checked"));
+    }
+
+    #[test]
+    #[cfg(feature = "db")]
+    fn limited_string_field() {
+        let mut field = LimitedString::<5>::new_field(
+            FormFieldOptions {
+                id: "test".to_owned(),
+                required: true,
+            },
+            StringFieldOptions::default(),
+        );
+        assert!(field.to_string().contains("maxlength=\"5\""));
+
+        field.set_value(Cow::Borrowed("test"));
+        assert_eq!(LimitedString::<5>::clean_value(&field).unwrap(), "test");
+
+        field.set_value(Cow::Borrowed("too long"));
+        assert_eq!(
+            LimitedString::<5>::clean_value(&field),
+            Err(FormFieldValidationError::maximum_length_exceeded(5))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "db")]
+    fn auto_field() {
+        let mut field = Auto::<i32>::new_field(
+            FormFieldOptions {
+                id: "test".to_owned(),
+                required: true,
+            },
+            IntegerFieldOptions::default(),
+        );
+        assert!(!field.to_string().contains("required"));
+
+        field.set_value(Cow::Borrowed(""));
+        assert_eq!(Auto::<i32>::clean_value(&field).unwrap(), Auto::auto());
+
+        field.set_value(Cow::Borrowed("42"));
+        assert_eq!(Auto::<i32>::clean_value(&field).unwrap(), Auto::fixed(42));
+
+        field.set_value(Cow::Borrowed("abc"));
+        assert!(Auto::<i32>::clean_value(&field).is_err());
+    }
+
+    #[test]
+    fn to_field_value() {
+        assert_eq!(String::from("test").to_field_value(), "test");
+        assert_eq!(42_i32.to_field_value(), "42");
+        assert_eq!(true.to_field_value(), "true");
+        assert_eq!(Some(5_u8).to_field_value(), "5");
+        assert_eq!(None::<u8>.to_field_value(), "");
+        #[cfg(feature = "db")]
+        {
+            assert_eq!(Auto::fixed(7_i64).to_field_value(), "7");
+            assert_eq!(Auto::<i64>::auto().to_field_value(), "");
+            assert_eq!(
+                LimitedString::<5>::new("test").unwrap().to_field_value(),
+                "test"
+            );
+        }
+    }
+
     #[test]
     fn string_field_clean_value() {
         let mut field = StringField::with_options(
@@ -592,6 +838,34 @@ mod tests {
         assert_eq!(value, 5);
     }
 
+    #[cfg(feature = "db")]
+    #[test]
+    fn foreign_key_field_clean_value() {
+        use crate::auth::db::DatabaseUser;
+        use crate::db::{Auto, ForeignKey};
+
+        let mut field = ForeignKey::<DatabaseUser>::new_field(
+            FormFieldOptions {
+                id: "user".to_owned(),
+                required: true,
+            },
+            IntegerFieldOptions::default(),
+        );
+        field.set_value(Cow::Borrowed("5"));
+        let value = ForeignKey::<DatabaseUser>::clean_value(&field).unwrap();
+        assert_eq!(value.primary_key(), &Auto::fixed(5));
+        assert_eq!(value.to_field_value(), "5");
+
+        field.set_value(Cow::Borrowed(""));
+        let value = ForeignKey::<DatabaseUser>::clean_value(&field);
+        assert!(matches!(value, Err(FormFieldValidationError::Required)));
+        let value = Option::<ForeignKey<DatabaseUser>>::clean_value(&field);
+        assert!(matches!(value, Ok(None)));
+
+        field.set_value(Cow::Borrowed("invalid"));
+        assert!(ForeignKey::<DatabaseUser>::clean_value(&field).is_err());
+    }
+
     #[test]
     fn bool_field_clean_value() {
         let mut field = BoolField::with_options(
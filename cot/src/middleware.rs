@@ -4,6 +4,12 @@
 //! are used to add functionality to the request/response cycle, such as
 //! session management, adding security headers, and more.
 
+#[cfg(any(feature = "db", feature = "jwt"))]
+use std::future::Future;
+#[cfg(any(feature = "db", feature = "jwt"))]
+use std::pin::Pin;
+#[cfg(feature = "db")]
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
@@ -13,9 +19,21 @@ use http_body_util::BodyExt;
 use tower::Service;
 use tower_sessions::{MemoryStore, SessionManagerLayer};
 
+#[cfg(feature = "db")]
+use crate::auth::api_token::{ApiTokenBackend, TOKEN_PREFIX};
+#[cfg(feature = "jwt")]
+use crate::auth::jwt::JwtCredentials;
+#[cfg(feature = "jwt")]
+use crate::auth::AuthRequestExt;
+#[cfg(any(feature = "db", feature = "jwt"))]
+use crate::auth::UserExtension;
 use crate::error::ErrorRepr;
 use crate::request::Request;
 use crate::response::Response;
+#[cfg(any(feature = "db", feature = "jwt"))]
+use crate::response::ResponseExt;
+#[cfg(any(feature = "db", feature = "jwt"))]
+use crate::StatusCode;
 use crate::{Body, Error};
 
 /// Middleware that converts a any [`http::Response`] generic type to a
@@ -325,4 +343,352 @@ impl<S> tower::Layer<S> for LiveReloadMiddleware {
     }
 }
 
+/// Middleware that authenticates the requests with API tokens.
+///
+/// The token is read from the `Authorization: Bearer <token>` header and
+/// authenticated with [`ApiTokenBackend`]. If it is valid, the token owner
+/// becomes the current user, as returned by
+/// [`AuthRequestExt::user`](crate::auth::AuthRequestExt::user), and the
+/// [`ApiToken`] itself is added to the request extensions. The session is not
+/// touched at all, so this middleware can be used in projects without the
+/// [`SessionMiddleware`].
+///
+/// Requests without the `Authorization` header, with a header using a scheme
+/// other than `Bearer` (such as `Basic`), or with a bearer token that is not
+/// an API token (which start with `cot_`) are passed to the inner service
+/// unchanged, so the middleware can be combined with other token-based
+/// authentication. Requests with an invalid or expired API token are rejected
+/// with a `401 Unauthorized` response.
+///
+/// [`ApiTokenBackend`]: crate::auth::api_token::ApiTokenBackend
+/// [`ApiToken`]: crate::auth::api_token::ApiToken
+///
+/// # Examples
+///
+/// ```
+/// use cot::middleware::ApiTokenMiddleware;
+/// use cot::project::{RootHandlerBuilder, WithApps};
+/// use cot::{BoxedHandler, Project, ProjectContext};
+///
+/// struct MyProject;
+/// impl Project for MyProject {
+///     fn middlewares(
+///         &self,
+///         handler: RootHandlerBuilder,
+///         context: &ProjectContext<WithApps>,
+///     ) -> BoxedHandler {
+///         handler.middleware(ApiTokenMiddleware::new()).build()
+///     }
+/// }
+/// ```
+#[cfg(feature = "db")]
+#[derive(Debug, Copy, Clone)]
+pub struct ApiTokenMiddleware;
+
+#[cfg(feature = "db")]
+impl ApiTokenMiddleware {
+    /// Create a new [`ApiTokenMiddleware`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::middleware::ApiTokenMiddleware;
+    ///
+    /// let middleware = ApiTokenMiddleware::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(feature = "db")]
+impl Default for ApiTokenMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "db")]
+impl<S> tower::Layer<S> for ApiTokenMiddleware {
+    type Service = ApiTokenService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiTokenService { inner }
+    }
+}
+
+/// Service struct that authenticates the requests with API tokens.
+///
+/// Used by [`ApiTokenMiddleware`].
+///
+/// # Examples
+///
+/// ```
+/// use std::any::TypeId;
+///
+/// use cot::middleware::{ApiTokenMiddleware, ApiTokenService};
+///
+/// assert_eq!(
+///     TypeId::of::<<ApiTokenMiddleware as tower::Layer<()>>::Service>(),
+///     TypeId::of::<ApiTokenService::<()>>()
+/// );
+/// ```
+#[cfg(feature = "db")]
+#[derive(Debug, Clone)]
+pub struct ApiTokenService<S> {
+    inner: S,
+}
+
+#[cfg(feature = "db")]
+impl<S> Service<Request> for ApiTokenService<S>
+where
+    S: Service<Request, Response = Response, Error = Error> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        call_with_bearer_token::<ApiTokenMiddleware, S>(&mut self.inner, request)
+    }
+}
+
+#[cfg(feature = "db")]
+impl BearerTokenAuth for ApiTokenMiddleware {
+    fn recognizes(token: &str) -> bool {
+        token.starts_with(TOKEN_PREFIX)
+    }
+
+    async fn authenticate(request: &mut Request, token: String) -> crate::Result<bool> {
+        let user = ApiTokenBackend::new()
+            .authenticate_token(request, &token)
+            .await?;
+        let Some(user) = user else {
+            return Ok(false);
+        };
+
+        request.extensions_mut().insert(user.token().clone());
+        request
+            .extensions_mut()
+            .insert::<UserExtension>(Arc::new(user));
+        Ok(true)
+    }
+}
+
+/// Middleware that authenticates the requests with JSON Web Tokens.
+///
+/// The token is read from the `Authorization: Bearer <token>` header and
+/// authenticated with the project's auth backend, which needs to support
+/// [`JwtCredentials`] (such as [`JwtBackend`], enabled with
+/// [`AuthBackendConfig::Jwt`]). If it is valid, the user becomes the current
+/// user, as returned by
+/// [`AuthRequestExt::user`]. The session is not touched at all, so this
+/// middleware can be used in projects without the [`SessionMiddleware`].
+///
+/// Requests without the `Authorization` header, with a header using a scheme
+/// other than `Bearer`, or with a bearer token that is not a JWT are passed
+/// to the inner service unchanged, so the middleware can be combined with
+/// other token-based authentication, such as API tokens. Requests with an
+/// invalid or expired JWT are rejected with a `401 Unauthorized` response.
+///
+/// [`JwtBackend`]: crate::auth::jwt::JwtBackend
+/// [`AuthBackendConfig::Jwt`]: crate::config::AuthBackendConfig::Jwt
+///
+/// # Examples
+///
+/// ```
+/// use cot::middleware::JwtMiddleware;
+/// use cot::project::{RootHandlerBuilder, WithApps};
+/// use cot::{BoxedHandler, Project, ProjectContext};
+///
+/// struct MyProject;
+/// impl Project for MyProject {
+///     fn middlewares(
+///         &self,
+///         handler: RootHandlerBuilder,
+///         context: &ProjectContext<WithApps>,
+///     ) -> BoxedHandler {
+///         handler.middleware(JwtMiddleware::new()).build()
+///     }
+/// }
+/// ```
+#[cfg(feature = "jwt")]
+#[derive(Debug, Copy, Clone)]
+pub struct JwtMiddleware;
+
+#[cfg(feature = "jwt")]
+impl JwtMiddleware {
+    /// Create a new [`JwtMiddleware`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::middleware::JwtMiddleware;
+    ///
+    /// let middleware = JwtMiddleware::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl Default for JwtMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl<S> tower::Layer<S> for JwtMiddleware {
+    type Service = JwtService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JwtService { inner }
+    }
+}
+
+/// Service struct that authenticates the requests with JSON Web Tokens.
+///
+/// Used by [`JwtMiddleware`].
+///
+/// # Examples
+///
+/// ```
+/// use std::any::TypeId;
+///
+/// use cot::middleware::{JwtMiddleware, JwtService};
+///
+/// assert_eq!(
+///     TypeId::of::<<JwtMiddleware as tower::Layer<()>>::Service>(),
+///     TypeId::of::<JwtService::<()>>()
+/// );
+/// ```
+#[cfg(feature = "jwt")]
+#[derive(Debug, Clone)]
+pub struct JwtService<S> {
+    inner: S,
+}
+
+#[cfg(feature = "jwt")]
+impl<S> Service<Request> for JwtService<S>
+where
+    S: Service<Request, Response = Response, Error = Error> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        call_with_bearer_token::<JwtMiddleware, S>(&mut self.inner, request)
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl BearerTokenAuth for JwtMiddleware {
+    fn recognizes(token: &str) -> bool {
+        jsonwebtoken::decode_header(token).is_ok()
+    }
+
+    async fn authenticate(request: &mut Request, token: String) -> crate::Result<bool> {
+        let Some(user) = request.authenticate(&JwtCredentials::new(token)).await? else {
+            return Ok(false);
+        };
+
+        request.extensions_mut().insert(UserExtension::from(user));
+        Ok(true)
+    }
+}
+
+/// A way of authenticating the requests with the bearer token from the
+/// `Authorization` header, implemented by the middlewares using
+/// [`call_with_bearer_token`].
+#[cfg(any(feature = "db", feature = "jwt"))]
+trait BearerTokenAuth: 'static {
+    /// Returns whether the token has the format handled by the middleware.
+    /// Requests with other tokens are passed to the inner service unchanged,
+    /// so that they can be authenticated by another middleware.
+    fn recognizes(token: &str) -> bool;
+
+    /// Authenticates the request with the token, making the user available
+    /// in the request extensions. Returns `false` if the token is invalid.
+    fn authenticate(
+        request: &mut Request,
+        token: String,
+    ) -> impl Future<Output = crate::Result<bool>> + Send;
+}
+
+/// Authenticates the request with the bearer token recognized by `A` and
+/// calls the inner service, or returns a `401 Unauthorized` response if the
+/// token is invalid.
+#[cfg(any(feature = "db", feature = "jwt"))]
+fn call_with_bearer_token<A, S>(
+    inner: &mut S,
+    mut request: Request,
+) -> Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>
+where
+    A: BearerTokenAuth,
+    S: Service<Request, Response = Response, Error = Error> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    // the service that was polled for readiness has to be the one that is called
+    let clone = inner.clone();
+    let mut inner = std::mem::replace(inner, clone);
+
+    Box::pin(async move {
+        let token = request
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .and_then(bearer_token)
+            .filter(|token| A::recognizes(token))
+            .map(ToOwned::to_owned);
+        let Some(token) = token else {
+            return inner.call(request).await;
+        };
+
+        if A::authenticate(&mut request, token).await? {
+            inner.call(request).await
+        } else {
+            Ok(invalid_token())
+        }
+    })
+}
+
+/// Returns the token from the `Authorization` header value, or [`None`] if
+/// the header doesn't contain a bearer token.
+#[cfg(any(feature = "db", feature = "jwt"))]
+fn bearer_token(header: &http::HeaderValue) -> Option<&str> {
+    let (scheme, token) = header.to_str().ok()?.split_once(' ')?;
+    let token = token.trim();
+
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+#[cfg(any(feature = "db", feature = "jwt"))]
+fn invalid_token() -> Response {
+    let mut response = Response::new_html(
+        StatusCode::UNAUTHORIZED,
+        Body::fixed(Bytes::from("401 Unauthorized")),
+    );
+    response.headers_mut().insert(
+        http::header::WWW_AUTHENTICATE,
+        http::HeaderValue::from_static("Bearer error=\"invalid_token\""),
+    );
+    response
+}
+
 // TODO: add Cot ORM-based session store
@@ -20,7 +20,9 @@
 ///     MyProject
 /// }
 /// ```
+use std::collections::HashSet;
 use std::future::poll_fn;
+use std::net::SocketAddr;
 use std::panic::AssertUnwindSafe;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -34,9 +36,16 @@ use http::request::Parts;
 use tower::{Layer, Service};
 use tracing::{error, info, trace};
 
+use crate::admin::dashboard::AdminWidget;
 use crate::admin::AdminModelManager;
 #[cfg(feature = "db")]
+use crate::auth::api_token::ApiTokenBackend;
+use crate::auth::chain::ChainAuthBackend;
+#[cfg(feature = "db")]
 use crate::auth::db::DatabaseUserBackend;
+#[cfg(feature = "jwt")]
+use crate::auth::jwt::JwtBackend;
+use crate::auth::throttling::LoginThrottle;
 use crate::auth::{AuthBackend, NoAuthBackend};
 use crate::cli::Cli;
 #[cfg(feature = "db")]
@@ -148,6 +157,15 @@ pub trait App: Send + Sync {
         vec![]
     }
 
+    /// Returns the widgets the app shows on the admin dashboard. By default, it
+    /// returns an empty list.
+    ///
+    /// See the [`admin::dashboard`](crate::admin::dashboard) module for an
+    /// example.
+    fn admin_dashboard_widgets(&self) -> Vec<Box<dyn AdminWidget>> {
+        vec![]
+    }
+
     /// Returns a list of static files that the app serves. By default, it
     /// returns an empty list.
     fn static_files(&self) -> Vec<(String, Bytes)> {
@@ -316,6 +334,14 @@ pub trait Project {
     /// already provides a default implementation that uses the auth backend
     /// specified in the project's configuration.
     ///
+    /// # Panics
+    ///
+    /// The default implementation panics if the JWT backend is configured
+    /// with invalid keys; see
+    /// [`JwtBackend::new`](crate::auth::jwt::JwtBackend::new). This can't
+    /// happen when the project is started with a [`Bootstrapper`], as it
+    /// checks the configuration and returns an error instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -331,12 +357,44 @@ pub trait Project {
     /// }
     /// ```
     fn auth_backend(&self, app_context: &ProjectContext<WithApps>) -> Box<dyn AuthBackend> {
-        #[allow(trivial_casts)] // cast to Box<dyn AuthBackend>
-        match &app_context.config().auth_backend {
-            AuthBackendConfig::None => Box::new(NoAuthBackend) as Box<dyn AuthBackend>,
-            #[cfg(feature = "db")]
-            AuthBackendConfig::Database => Box::new(DatabaseUserBackend) as Box<dyn AuthBackend>,
-        }
+        auth_backend_from_config(&app_context.config().auth_backend)
+    }
+
+    /// Sets the login throttle to use.
+    ///
+    /// The default implementation creates a throttle with the limits
+    /// specified in the project's configuration, which writes the lockouts to
+    /// the log. Override this method to be notified about the lockouts in a
+    /// different way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_trait::async_trait;
+    /// use cot::auth::throttling::{Lockout, LockoutNotifier, LoginThrottle};
+    /// use cot::project::WithApps;
+    /// use cot::request::Request;
+    /// use cot::{Project, ProjectContext};
+    ///
+    /// struct AlertNotifier;
+    ///
+    /// #[async_trait]
+    /// impl LockoutNotifier for AlertNotifier {
+    ///     async fn notify(&self, request: &Request, lockout: &Lockout) -> cot::Result<()> {
+    ///         // alert the administrators here
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// struct MyProject;
+    /// impl Project for MyProject {
+    ///     fn login_throttle(&self, app_context: &ProjectContext<WithApps>) -> LoginThrottle {
+    ///         LoginThrottle::new(&app_context.config().login_throttling).notifier(AlertNotifier)
+    ///     }
+    /// }
+    /// ```
+    fn login_throttle(&self, app_context: &ProjectContext<WithApps>) -> LoginThrottle {
+        LoginThrottle::new(&app_context.config().login_throttling)
     }
 
     /// Returns the middlewares for the project.
@@ -464,6 +522,70 @@ pub trait Project {
     }
 }
 
+fn auth_backend_from_config(config: &AuthBackendConfig) -> Box<dyn AuthBackend> {
+    #[allow(trivial_casts)] // cast to Box<dyn AuthBackend>
+    match config {
+        AuthBackendConfig::None => Box::new(NoAuthBackend) as Box<dyn AuthBackend>,
+        #[cfg(feature = "db")]
+        AuthBackendConfig::Database => Box::new(DatabaseUserBackend) as Box<dyn AuthBackend>,
+        #[cfg(feature = "db")]
+        AuthBackendConfig::ApiToken => Box::new(ApiTokenBackend) as Box<dyn AuthBackend>,
+        #[cfg(feature = "jwt")]
+        AuthBackendConfig::Jwt(config) => Box::new(
+            JwtBackend::new(config)
+                .expect("the JWT backend configuration should be checked when bootstrapping"),
+        ) as Box<dyn AuthBackend>,
+        AuthBackendConfig::Chain { backends } => {
+            let mut chain = ChainAuthBackend::new();
+            let mut names = HashSet::new();
+            for (index, config) in backends.iter().enumerate() {
+                let type_name = auth_backend_type_name(config);
+                let name = if names.contains(type_name) {
+                    format!("{type_name}_{}", index + 1)
+                } else {
+                    type_name.to_owned()
+                };
+                names.insert(type_name);
+
+                chain = chain.backend(name, auth_backend_from_config(config));
+            }
+            Box::new(chain)
+        }
+    }
+}
+
+/// Checks that the auth backend can be created from given config, so that
+/// an invalid config is reported as an error instead of a panic in
+/// [`Project::auth_backend`].
+fn check_auth_backend_config(config: &AuthBackendConfig) -> cot::Result<()> {
+    match config {
+        #[cfg(feature = "jwt")]
+        AuthBackendConfig::Jwt(config) => {
+            JwtBackend::new(config)?;
+        }
+        AuthBackendConfig::Chain { backends } => {
+            for config in backends {
+                check_auth_backend_config(config)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn auth_backend_type_name(config: &AuthBackendConfig) -> &'static str {
+    match config {
+        AuthBackendConfig::None => "none",
+        #[cfg(feature = "db")]
+        AuthBackendConfig::Database => "database",
+        #[cfg(feature = "db")]
+        AuthBackendConfig::ApiToken => "api_token",
+        #[cfg(feature = "jwt")]
+        AuthBackendConfig::Jwt(_) => "jwt",
+        AuthBackendConfig::Chain { .. } => "chain",
+    }
+}
+
 /// A helper struct to build the root handler for the project.
 ///
 /// This is mainly useful for attaching middlewares to the project.
@@ -1025,7 +1147,8 @@ impl Bootstrapper<WithConfig> {
     /// # Errors
     ///
     /// This method may return an error if it cannot initialize any of the
-    /// project's components, such as the database.
+    /// project's components, such as the database, or if the configured auth
+    /// backend is invalid (e.g. the JWT keys can't be read).
     ///
     /// # Examples
     ///
@@ -1109,7 +1232,8 @@ impl Bootstrapper<WithApps> {
     /// # Errors
     ///
     /// This method may return an error if it cannot initialize any of the
-    /// project's components, such as the database.
+    /// project's components, such as the database, or if the configured auth
+    /// backend is invalid (e.g. the JWT keys can't be read).
     ///
     /// # Examples
     ///
@@ -1139,11 +1263,14 @@ impl Bootstrapper<WithApps> {
         };
         let handler = self.project.middlewares(handler, &self.context);
 
+        check_auth_backend_config(&self.context.config.auth_backend)?;
         let auth_backend = self.project.auth_backend(&self.context);
+        let login_throttle = self.project.login_throttle(&self.context);
         #[cfg(feature = "db")]
         let database = Self::init_database(&self.context.config.database).await?;
         let app_context = self.context.with_auth_and_db(
             auth_backend,
+            login_throttle,
             #[cfg(feature = "db")]
             database,
         );
@@ -1249,6 +1376,8 @@ pub trait BootstrapPhase: sealed::Sealed {
     type Router: Debug;
     /// The type of the auth backend.
     type AuthBackend;
+    /// The type of the login throttle.
+    type LoginThrottle: Debug;
     /// The type of the database.
     #[cfg(feature = "db")]
     type Database: Debug;
@@ -1270,6 +1399,7 @@ impl BootstrapPhase for Uninitialized {
     type Apps = ();
     type Router = ();
     type AuthBackend = ();
+    type LoginThrottle = ();
     #[cfg(feature = "db")]
     type Database = ();
 }
@@ -1290,6 +1420,7 @@ impl BootstrapPhase for WithConfig {
     type Apps = ();
     type Router = ();
     type AuthBackend = ();
+    type LoginThrottle = ();
     #[cfg(feature = "db")]
     type Database = ();
 }
@@ -1310,6 +1441,7 @@ impl BootstrapPhase for WithApps {
     type Apps = Vec<Box<dyn App>>;
     type Router = Arc<Router>;
     type AuthBackend = ();
+    type LoginThrottle = ();
     #[cfg(feature = "db")]
     type Database = ();
 }
@@ -1330,6 +1462,7 @@ impl BootstrapPhase for Initialized {
     type Apps = <WithApps as BootstrapPhase>::Apps;
     type Router = <WithApps as BootstrapPhase>::Router;
     type AuthBackend = Box<dyn AuthBackend>;
+    type LoginThrottle = Arc<LoginThrottle>;
     #[cfg(feature = "db")]
     type Database = Option<Arc<Database>>;
 }
@@ -1344,6 +1477,7 @@ pub struct ProjectContext<S: BootstrapPhase = Initialized> {
     router: S::Router,
     #[debug("..")]
     auth_backend: S::AuthBackend,
+    login_throttle: S::LoginThrottle,
     #[cfg(feature = "db")]
     database: S::Database,
 }
@@ -1356,6 +1490,7 @@ impl ProjectContext<Uninitialized> {
             apps: (),
             router: (),
             auth_backend: (),
+            login_throttle: (),
             #[cfg(feature = "db")]
             database: (),
         }
@@ -1367,6 +1502,7 @@ impl ProjectContext<Uninitialized> {
             apps: self.apps,
             router: self.router,
             auth_backend: self.auth_backend,
+            login_throttle: self.login_throttle,
             #[cfg(feature = "db")]
             database: self.database,
         }
@@ -1405,6 +1541,7 @@ impl ProjectContext<WithConfig> {
             apps,
             router,
             auth_backend: self.auth_backend,
+            login_throttle: self.login_throttle,
             #[cfg(feature = "db")]
             database: self.database,
         }
@@ -1460,6 +1597,7 @@ impl ProjectContext<WithApps> {
     fn with_auth_and_db(
         self,
         auth_backend: Box<dyn AuthBackend>,
+        login_throttle: LoginThrottle,
         #[cfg(feature = "db")] database: Option<Arc<Database>>,
     ) -> ProjectContext<Initialized> {
         ProjectContext {
@@ -1467,6 +1605,7 @@ impl ProjectContext<WithApps> {
             apps: self.apps,
             router: self.router,
             auth_backend,
+            login_throttle: Arc::new(login_throttle),
             #[cfg(feature = "db")]
             database,
         }
@@ -1479,6 +1618,7 @@ impl ProjectContext<Initialized> {
         apps: <Initialized as BootstrapPhase>::Apps,
         router: <Initialized as BootstrapPhase>::Router,
         auth_backend: <Initialized as BootstrapPhase>::AuthBackend,
+        login_throttle: <Initialized as BootstrapPhase>::LoginThrottle,
         #[cfg(feature = "db")] database: <Initialized as BootstrapPhase>::Database,
     ) -> Self {
         Self {
@@ -1486,6 +1626,7 @@ impl ProjectContext<Initialized> {
             apps,
             router,
             auth_backend,
+            login_throttle,
             #[cfg(feature = "db")]
             database,
         }
@@ -1578,6 +1719,25 @@ impl ProjectContext<Initialized> {
         self.auth_backend.as_ref()
     }
 
+    /// Returns the login throttle for the project.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::request::{Request, RequestExt};
+    /// use cot::response::Response;
+    ///
+    /// async fn index(request: Request) -> cot::Result<Response> {
+    ///     let lockouts = request.context().login_throttle().lockouts();
+    ///     // ...
+    /// #    todo!()
+    /// }
+    /// ```
+    #[must_use]
+    pub fn login_throttle(&self) -> &LoginThrottle {
+        &self.login_throttle
+    }
+
     /// Returns the database for the project, if it is enabled.
     ///
     /// # Examples
@@ -1683,6 +1843,8 @@ pub async fn run_at(
         }
         let migration_engine = MigrationEngine::new(migrations)?;
         migration_engine.run(database).await?;
+        crate::auth::db::register_model_permissions(&**database, migration_engine.migrations())
+            .await?;
     }
 
     let mut apps = std::mem::take(&mut context.apps);
@@ -1756,9 +1918,12 @@ pub async fn run_at(
         };
         std::panic::set_hook(Box::new(new_hook));
     }
-    axum::serve(listener, handler.into_make_service())
-        .await
-        .map_err(|e| ErrorRepr::StartServer { source: e })?;
+    axum::serve(
+        listener,
+        handler.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .map_err(|e| ErrorRepr::StartServer { source: e })?;
     if register_panic_hook {
         let _ = std::panic::take_hook();
     }
@@ -2015,6 +2180,26 @@ mod tests {
             .is_none());
     }
 
+    #[tokio::test]
+    async fn test_chain_auth_backend() {
+        let context = ProjectContext::new()
+            .with_config(
+                ProjectConfig::builder()
+                    .auth_backend(AuthBackendConfig::Chain {
+                        backends: vec![AuthBackendConfig::None, AuthBackendConfig::None],
+                    })
+                    .build(),
+            )
+            .with_apps(vec![], Arc::new(Router::empty()));
+
+        let auth_backend = TestProject.auth_backend(&context);
+        assert!(auth_backend
+            .get_by_id(&TestRequestBuilder::get("/").build(), UserId::Int(0))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
     async fn bootstrapper() {
@@ -2034,4 +2219,33 @@ mod tests {
         assert_eq!(bootstrapper.context().apps.len(), 1);
         assert_eq!(bootstrapper.context().router.routes().len(), 1);
     }
+
+    #[cfg(feature = "jwt")]
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // unsupported operation: can't call foreign function `sqlite3_open_v2`
+    async fn bootstrapper_invalid_jwt_config() {
+        struct TestProject;
+        impl Project for TestProject {}
+
+        let jwt_config = crate::config::JwtConfig::builder()
+            .jwks_file("nonexistent_jwks.json")
+            .build();
+        let result = Bootstrapper::new(TestProject)
+            .with_config(
+                ProjectConfig::builder()
+                    .auth_backend(AuthBackendConfig::Chain {
+                        backends: vec![AuthBackendConfig::None, AuthBackendConfig::Jwt(jwt_config)],
+                    })
+                    .build(),
+            )
+            .boot()
+            .await;
+
+        let Err(error) = result else {
+            panic!("bootstrapping with invalid JWT config should fail");
+        };
+        assert!(error
+            .to_string()
+            .starts_with("Invalid JWT backend configuration: could not read the JWKS file"));
+    }
 }
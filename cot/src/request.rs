@@ -35,6 +35,12 @@ mod path_params_deserializer;
 /// HTTP request type.
 pub type Request = http::Request<Body>;
 
+/// The database [`RequestExt::db`] returns instead of the project's one, added
+/// to the request extensions to handle it in a transaction.
+#[cfg(feature = "db")]
+#[derive(Debug, Clone)]
+pub(crate) struct RequestDatabase(pub(crate) Arc<Database>);
+
 mod private {
     pub trait Sealed {}
 }
@@ -156,6 +162,10 @@ pub trait RequestExt: private::Sealed {
 
     /// Get the database.
     ///
+    /// This is the database of the project, unless the request is handled in
+    /// a [transaction](Database::begin_transaction), such as the rows of an
+    /// import in the admin panel.
+    ///
     /// # Examples
     ///
     /// ```
@@ -361,7 +371,10 @@ impl RequestExt for Request {
 
     #[cfg(feature = "db")]
     fn db(&self) -> &Database {
-        self.context().database()
+        match self.extensions().get::<RequestDatabase>() {
+            Some(database) => &database.0,
+            None => self.context().database(),
+        }
     }
 
     fn session(&self) -> &Session {
@@ -37,9 +37,11 @@ use crate::error_page::ErrorPageTrigger;
 use crate::handler::RequestHandler;
 use crate::request::{PathParams, Request, RouteName};
 use crate::response::{Response, ResponseExt};
+use crate::router::guard::{Guard, GuardedHandler};
 use crate::router::path::{CaptureResult, PathMatcher, ReverseParamMap};
 use crate::{Body, Error, Result};
 
+mod guard;
 pub mod path;
 
 /// A router that can be used to route requests to their respective views.
@@ -281,6 +283,69 @@ impl Router {
     pub fn is_empty(&self) -> bool {
         self.urls.is_empty()
     }
+
+    /// Require the user to be logged in to access any of the routes in this
+    /// router.
+    ///
+    /// See [`Route::require_login`] for more details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::request::Request;
+    /// use cot::response::Response;
+    /// use cot::router::{Route, Router};
+    ///
+    /// async fn profile(request: Request) -> cot::Result<Response> {
+    ///     todo!()
+    /// }
+    ///
+    /// let router = Router::with_urls([Route::with_handler("/profile", profile)]).require_login();
+    /// ```
+    #[must_use]
+    pub fn require_login(self) -> Self {
+        self.with_guard(&Guard::Login)
+    }
+
+    /// Require the user to be logged in and have all the given permissions to
+    /// access any of the routes in this router.
+    ///
+    /// See [`Route::require_permissions`] for more details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::request::Request;
+    /// use cot::response::Response;
+    /// use cot::router::{Route, Router};
+    ///
+    /// async fn articles(request: Request) -> cot::Result<Response> {
+    ///     todo!()
+    /// }
+    ///
+    /// let router = Router::with_urls([Route::with_handler("/articles", articles)])
+    ///     .require_permissions(["view_article"]);
+    /// ```
+    #[must_use]
+    pub fn require_permissions<T: Into<String>, I: IntoIterator<Item = T>>(
+        self,
+        permissions: I,
+    ) -> Self {
+        self.with_guard(&Guard::Permissions(
+            permissions.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    fn with_guard(self, guard: &Guard) -> Self {
+        Self {
+            urls: self
+                .urls
+                .into_iter()
+                .map(|route| route.with_guard(guard))
+                .collect(),
+            names: self.names,
+        }
+    }
 }
 
 impl Default for Router {
@@ -490,6 +555,79 @@ impl Route {
         self.name.as_ref().map(|name| name.0.as_str())
     }
 
+    /// Require the user to be logged in to access this route.
+    ///
+    /// Users that are not logged in are redirected to the login page
+    /// configured in
+    /// [`ProjectConfig::login_url`](crate::config::ProjectConfig::login_url),
+    /// with the URL they tried to access passed in the `next` query
+    /// parameter. If this route contains a nested router, all of its routes
+    /// are guarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::request::Request;
+    /// use cot::response::Response;
+    /// use cot::router::{Route, Router};
+    ///
+    /// async fn profile(request: Request) -> cot::Result<Response> {
+    ///     todo!()
+    /// }
+    ///
+    /// let router = Router::with_urls([Route::with_handler("/profile", profile).require_login()]);
+    /// ```
+    #[must_use]
+    pub fn require_login(self) -> Self {
+        self.with_guard(&Guard::Login)
+    }
+
+    /// Require the user to be logged in and have all the given permissions to
+    /// access this route.
+    ///
+    /// Users that are not logged in are redirected to the login page, just
+    /// like with [`Self::require_login`]. Users that are logged in, but lack
+    /// any of the permissions (as checked by
+    /// [`User::has_perms`](crate::auth::User::has_perms)), get a
+    /// `403 Forbidden` response. If this route contains a nested router, all
+    /// of its routes are guarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::request::Request;
+    /// use cot::response::Response;
+    /// use cot::router::{Route, Router};
+    ///
+    /// async fn add_article(request: Request) -> cot::Result<Response> {
+    ///     todo!()
+    /// }
+    ///
+    /// let router = Router::with_urls([
+    ///     Route::with_handler("/articles/add", add_article).require_permissions(["add_article"])
+    /// ]);
+    /// ```
+    #[must_use]
+    pub fn require_permissions<T: Into<String>, I: IntoIterator<Item = T>>(
+        self,
+        permissions: I,
+    ) -> Self {
+        self.with_guard(&Guard::Permissions(
+            permissions.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    fn with_guard(self, guard: &Guard) -> Self {
+        let view = match self.view {
+            RouteInner::Handler(handler) => {
+                RouteInner::Handler(Arc::new(GuardedHandler::new(handler, guard.clone())))
+            }
+            RouteInner::Router(router) => RouteInner::Router(router.with_guard(guard)),
+        };
+
+        Self { view, ..self }
+    }
+
     #[must_use]
     pub(crate) fn kind(&self) -> RouteKind {
         match &self.view {
@@ -508,7 +646,7 @@ impl Route {
     }
 }
 
-fn handle_not_found() -> Response {
+pub(crate) fn handle_not_found() -> Response {
     let mut response = Response::new_html(
         StatusCode::NOT_FOUND,
         Body::fixed(Bytes::from("404 Not Found")),
@@ -517,6 +655,27 @@ fn handle_not_found() -> Response {
     response
 }
 
+pub(crate) fn handle_forbidden() -> Response {
+    Response::new_html(
+        StatusCode::FORBIDDEN,
+        Body::fixed(Bytes::from("403 Forbidden")),
+    )
+}
+
+/// Returns the response for a request to a view handling a form, which only
+/// accepts the `GET` and `POST` methods, made with another method.
+pub(crate) fn handle_method_not_allowed() -> Response {
+    let mut response = Response::new_html(
+        StatusCode::METHOD_NOT_ALLOWED,
+        Body::fixed(Bytes::from("405 Method Not Allowed")),
+    );
+    response.headers_mut().insert(
+        http::header::ALLOW,
+        http::HeaderValue::from_static("GET, POST"),
+    );
+    response
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) enum RouteKind {
     Handler,
@@ -763,6 +922,51 @@ mod tests {
         assert_eq!(response.headers().get("location").unwrap(), "/test/123");
     }
 
+    #[tokio::test]
+    async fn route_require_login() {
+        let router = Router::with_urls(vec![
+            Route::with_handler("/public", MockHandler),
+            Route::with_handler("/private", MockHandler).require_login(),
+        ]);
+
+        let response = router
+            .handle(TestRequestBuilder::get("/public").with_session().build())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = router
+            .handle(TestRequestBuilder::get("/private").with_session().build())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/login/?next=%2Fprivate"
+        );
+    }
+
+    #[tokio::test]
+    async fn sub_router_require_permissions() {
+        let sub_router = Router::with_urls(vec![Route::with_handler_and_name(
+            "/test",
+            MockHandler,
+            "test",
+        )])
+        .require_permissions(["view_test"]);
+        let router = Router::with_urls(vec![Route::with_router("/sub", sub_router)]);
+
+        let response = router
+            .handle(TestRequestBuilder::get("/sub/test").with_session().build())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            router.reverse("test", &ReverseParamMap::new()).unwrap(),
+            "/sub/test"
+        );
+    }
+
     fn test_request() -> Request {
         TestRequestBuilder::get("/test").build()
     }
@@ -0,0 +1,202 @@
+//! Guards restricting access to routes.
+//!
+//! This module provides request handler wrappers that only pass the request to
+//! the wrapped handler when the current user is logged in, and optionally has
+//! given permissions. They are created with [`Route::require_login`],
+//! [`Route::require_permissions`] and their [`Router`] counterparts.
+//!
+//! [`Route::require_login`]: crate::router::Route::require_login
+//! [`Route::require_permissions`]: crate::router::Route::require_permissions
+//! [`Router`]: crate::router::Router
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_more::Debug;
+
+use crate::auth::AuthRequestExt;
+use crate::handler::RequestHandler;
+use crate::request::{Request, RequestExt};
+use crate::response::{Response, ResponseExt};
+use crate::router::handle_forbidden;
+use crate::Result;
+
+/// The requirement a user has to meet to access a guarded route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Guard {
+    /// The user has to be logged in.
+    Login,
+    /// The user has to be logged in and have all the given permissions.
+    Permissions(Vec<String>),
+}
+
+/// A request handler that checks a [`Guard`] before passing the request to
+/// the wrapped handler.
+///
+/// Users that are not logged in are redirected to
+/// [`ProjectConfig::login_url`](crate::config::ProjectConfig::login_url), with
+/// the URL they tried to access in the `next` query parameter. Users that are
+/// logged in, but lack the required permissions, get a `403 Forbidden`
+/// response.
+#[derive(Debug, Clone)]
+pub(crate) struct GuardedHandler {
+    #[debug("handler(...)")]
+    handler: Arc<dyn RequestHandler + Send + Sync>,
+    guard: Guard,
+}
+
+impl GuardedHandler {
+    #[must_use]
+    pub(crate) fn new(handler: Arc<dyn RequestHandler + Send + Sync>, guard: Guard) -> Self {
+        Self { handler, guard }
+    }
+}
+
+#[async_trait]
+impl RequestHandler for GuardedHandler {
+    async fn handle(&self, mut request: Request) -> Result<Response> {
+        let user = request.user().await?;
+        if !user.is_authenticated() {
+            return Ok(redirect_to_login(&request));
+        }
+        if let Guard::Permissions(permissions) = &self.guard {
+            let permissions: Vec<&str> = permissions.iter().map(String::as_str).collect();
+            if !user.has_perms(&permissions) {
+                return Ok(handle_forbidden());
+            }
+        }
+
+        self.handler.handle(request).await
+    }
+}
+
+fn redirect_to_login(request: &Request) -> Response {
+    let next = request.uri().path_and_query().map_or_else(
+        || request.uri().path(),
+        |path_and_query| path_and_query.as_str(),
+    );
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("next", next)
+        .finish();
+
+    let login_url = &request.project_config().login_url;
+    let separator = if login_url.contains('?') { '&' } else { '?' };
+    Response::new_redirect(format!("{login_url}{separator}{query}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::auth::{User, UserId};
+    use crate::config::ProjectConfig;
+    use crate::test::TestRequestBuilder;
+    use crate::{Body, StatusCode};
+
+    struct TestUser;
+
+    impl User for TestUser {
+        fn id(&self) -> Option<UserId> {
+            Some(UserId::Int(1))
+        }
+
+        fn username(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::from("testuser"))
+        }
+
+        fn is_active(&self) -> bool {
+            true
+        }
+
+        fn is_authenticated(&self) -> bool {
+            true
+        }
+
+        fn has_perm(&self, perm: &str) -> bool {
+            perm == "view_article"
+        }
+    }
+
+    async fn ok(_request: Request) -> Result<Response> {
+        Ok(Response::new_html(StatusCode::OK, Body::fixed("OK")))
+    }
+
+    async fn logged_in_request(path: &str) -> Request {
+        let mut request = TestRequestBuilder::get(path).with_session().build();
+        request.login(Box::new(TestUser)).await.unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn login_required_anonymous() {
+        let handler = GuardedHandler::new(Arc::new(ok), Guard::Login);
+        let request = TestRequestBuilder::get("/articles/?page=2")
+            .with_session()
+            .build();
+
+        let response = handler.handle(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/login/?next=%2Farticles%2F%3Fpage%3D2"
+        );
+    }
+
+    #[tokio::test]
+    async fn login_required_custom_login_url() {
+        let handler = GuardedHandler::new(Arc::new(ok), Guard::Login);
+        let request = TestRequestBuilder::get("/articles/")
+            .config(
+                ProjectConfig::builder()
+                    .login_url("/accounts/login/?lang=en")
+                    .build(),
+            )
+            .with_session()
+            .build();
+
+        let response = handler.handle(request).await.unwrap();
+
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/accounts/login/?lang=en&next=%2Farticles%2F"
+        );
+    }
+
+    #[tokio::test]
+    async fn login_required_logged_in() {
+        let handler = GuardedHandler::new(Arc::new(ok), Guard::Login);
+
+        let response = handler
+            .handle(logged_in_request("/articles/").await)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn permissions_required() {
+        let allowed = GuardedHandler::new(
+            Arc::new(ok),
+            Guard::Permissions(vec!["view_article".to_owned()]),
+        );
+        let denied = GuardedHandler::new(
+            Arc::new(ok),
+            Guard::Permissions(vec!["view_article".to_owned(), "add_article".to_owned()]),
+        );
+
+        let response = allowed
+            .handle(logged_in_request("/articles/").await)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = denied
+            .handle(logged_in_request("/articles/").await)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
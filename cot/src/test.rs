@@ -2,15 +2,18 @@
 
 use std::any::Any;
 use std::future::poll_fn;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 
 use async_trait::async_trait;
+use axum::extract::ConnectInfo;
 use derive_more::Debug;
 use tower::Service;
 use tower_sessions::{MemoryStore, Session};
 
 #[cfg(feature = "db")]
 use crate::auth::db::DatabaseUserBackend;
+use crate::auth::throttling::LoginThrottle;
 use crate::auth::{AuthBackend, NoAuthBackend, User, UserId};
 use crate::config::ProjectConfig;
 #[cfg(feature = "db")]
@@ -191,6 +194,8 @@ pub struct TestRequestBuilder {
     session: Option<Session>,
     config: Option<Arc<ProjectConfig>>,
     auth_backend: Option<AuthBackendWrapper>,
+    login_throttle: Option<Arc<LoginThrottle>>,
+    remote_addr: Option<SocketAddr>,
     #[cfg(feature = "db")]
     database: Option<Arc<Database>>,
     form_data: Option<Vec<(String, String)>>,
@@ -233,6 +238,31 @@ impl AuthBackend for AuthBackendWrapper {
     ) -> cot::auth::Result<Option<Box<dyn User + Send + Sync>>> {
         self.inner.get_by_id(request, id).await
     }
+
+    async fn login(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+    ) -> cot::auth::Result<()> {
+        self.inner.login(request, user).await
+    }
+
+    async fn requires_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+    ) -> cot::auth::Result<bool> {
+        self.inner.requires_second_factor(request, user).await
+    }
+
+    async fn verify_second_factor(
+        &self,
+        request: &Request,
+        user: &(dyn User + Send + Sync),
+        code: &str,
+    ) -> cot::auth::Result<bool> {
+        self.inner.verify_second_factor(request, user, code).await
+    }
 }
 
 impl Default for TestRequestBuilder {
@@ -244,6 +274,8 @@ impl Default for TestRequestBuilder {
             session: None,
             config: None,
             auth_backend: None,
+            login_throttle: None,
+            remote_addr: None,
             #[cfg(feature = "db")]
             database: None,
             form_data: None,
@@ -386,6 +418,48 @@ impl TestRequestBuilder {
         self
     }
 
+    /// Add a login throttle to the request builder.
+    ///
+    /// The throttle can be shared between several requests to test the
+    /// lockouts after failed login attempts. If it's not set, a new throttle
+    /// is created from the configuration for each request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use cot::auth::throttling::LoginThrottle;
+    /// use cot::test::TestRequestBuilder;
+    ///
+    /// let throttle = Arc::new(LoginThrottle::default());
+    /// let request = TestRequestBuilder::get("/")
+    ///     .login_throttle(Arc::clone(&throttle))
+    ///     .build();
+    /// ```
+    pub fn login_throttle(&mut self, login_throttle: Arc<LoginThrottle>) -> &mut Self {
+        self.login_throttle = Some(login_throttle);
+        self
+    }
+
+    /// Set the address of the client the request comes from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::SocketAddr;
+    ///
+    /// use cot::test::TestRequestBuilder;
+    ///
+    /// let request = TestRequestBuilder::get("/")
+    ///     .remote_addr(SocketAddr::from(([192, 0, 2, 1], 12345)))
+    ///     .build();
+    /// ```
+    pub fn remote_addr(&mut self, remote_addr: SocketAddr) -> &mut Self {
+        self.remote_addr = Some(remote_addr);
+        self
+    }
+
     /// Add a router to the request builder.
     ///
     /// # Examples
@@ -557,11 +631,18 @@ impl TestRequestBuilder {
             None => Box::new(NoAuthBackend),
         };
 
+        let config = self.config.clone().unwrap_or_default();
+        let login_throttle = self
+            .login_throttle
+            .clone()
+            .unwrap_or_else(|| Arc::new(LoginThrottle::new(&config.login_throttling)));
+
         let app_context = ProjectContext::initialized(
-            self.config.clone().unwrap_or_default(),
+            config,
             Vec::new(),
             Arc::new(self.router.clone().unwrap_or_else(Router::empty)),
             auth_backend,
+            login_throttle,
             #[cfg(feature = "db")]
             self.database.clone(),
         );
@@ -571,6 +652,10 @@ impl TestRequestBuilder {
             request.extensions_mut().insert(session.clone());
         }
 
+        if let Some(remote_addr) = self.remote_addr {
+            request.extensions_mut().insert(ConnectInfo(remote_addr));
+        }
+
         if let Some(form_data) = &self.form_data {
             if self.method != http::Method::POST {
                 todo!("Form data can currently only be used with POST requests");
@@ -838,6 +923,38 @@ impl TestDatabase {
         self
     }
 
+    /// Add the migrations of the [admin panel](cot::admin::AdminApp) to the
+    /// test database.
+    ///
+    /// This is useful if you want to test something that records
+    /// [admin log entries](cot::admin::log::LogEntry).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cot::test::TestDatabase;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> cot::Result<()> {
+    /// let mut test_database = TestDatabase::new_sqlite().await?;
+    /// test_database
+    ///     .with_auth()
+    ///     .with_admin()
+    ///     .run_migrations()
+    ///     .await;
+    ///
+    /// // do something with the database
+    ///
+    /// test_database.cleanup().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "db")]
+    pub fn with_admin(&mut self) -> &mut Self {
+        self.add_migrations(cot::admin::migrations::MIGRATIONS.to_vec());
+        self
+    }
+
     /// Add migrations to the test database.
     ///
     /// # Examples
@@ -901,6 +1018,9 @@ impl TestDatabase {
                 .run(&self.database())
                 .await
                 .expect("Failed to run migrations");
+            crate::auth::db::register_model_permissions(&*self.database(), engine.migrations())
+                .await
+                .expect("Failed to register model permissions");
         }
         self
     }
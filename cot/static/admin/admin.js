@@ -0,0 +1,51 @@
+"use strict";
+
+// Filters the options of the select following a search input.
+document.addEventListener("input", (event) => {
+    const search = event.target.closest(".select-search");
+    if (!search) {
+        return;
+    }
+
+    const select = document.getElementById(search.dataset.select);
+    const query = search.value.trim().toLowerCase();
+    for (const option of select.options) {
+        option.hidden = option.value !== "" && !option.text.toLowerCase().includes(query);
+    }
+});
+
+document.addEventListener("click", (event) => {
+    // Adds a row to an inline, numbered after the existing ones.
+    const addButton = event.target.closest(".inline-add");
+    if (addButton) {
+        const inline = addButton.closest(".inline");
+        const total = inline.querySelector(".inline-total");
+        const template = inline.querySelector(".inline-empty-row");
+        const html = template.innerHTML.replaceAll("__prefix__", total.value);
+        inline.querySelector(".inline-rows").insertAdjacentHTML("beforeend", html);
+        total.value = Number(total.value) + 1;
+        return;
+    }
+
+    // Removes a row that hasn't been saved yet; the missing rows are skipped.
+    const removeButton = event.target.closest(".inline-remove");
+    if (removeButton) {
+        removeButton.closest(".inline-row").remove();
+    }
+});
+
+// Loads the file chosen for an import into its text area.
+document.addEventListener("change", async (event) => {
+    const input = event.target.closest(".import-file");
+    if (!input || input.files.length === 0) {
+        return;
+    }
+
+    const file = input.files[0];
+    document.getElementById(input.dataset.target).value = await file.text();
+    const format = file.name.split(".").pop().toLowerCase();
+    const select = input.form.elements.format;
+    if ([...select.options].some((option) => option.value === format)) {
+        select.value = format;
+    }
+});
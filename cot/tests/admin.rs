@@ -0,0 +1,572 @@
+use chrono::NaiveDate;
+use cot::admin::actions::{AdminAction, AdminActionResult};
+use cot::admin::filters::{AdminFilterChoice, AdminFilterField, AdminFilterKind};
+use cot::admin::log::{FieldChange, LogAction, LogEntry};
+use cot::admin::{AdminFormField, AdminFormResult, AdminListParams, AdminModel, AdminPermission};
+use cot::auth::db::DatabaseUser;
+use cot::auth::{AuthRequestExt, Password};
+use cot::config::{PasswordHasherConfig, PasswordValidators};
+use cot::db::migrations::{Field, Operation};
+use cot::db::{model, Auto, Database, DatabaseField, ForeignKey, Identifier, LimitedString, Model};
+use cot::request::{Request, RequestExt};
+use cot::test::{TestDatabase, TestRequestBuilder};
+
+#[derive(Debug, Clone, AdminModel)]
+#[admin(
+    name = "Blog post",
+    display = title,
+    list_columns(id, title, published),
+    search_fields(title),
+    read_only(views),
+    filters(published),
+    actions(mark_published),
+    inline(model = Tag, field = post)
+)]
+#[model]
+struct Post {
+    id: Auto<i32>,
+    title: LimitedString<100>,
+    published: bool,
+    views: i32,
+}
+
+async fn mark_published(request: Request, ids: Vec<String>) -> cot::Result<AdminActionResult> {
+    for id in &ids {
+        if let Some(mut post) = Post::get_object_by_id(&request, id).await? {
+            post.published = true;
+            post.save(request.db()).await?;
+        }
+    }
+
+    Ok(AdminActionResult::Message(format!(
+        "{} posts were published.",
+        ids.len()
+    )))
+}
+
+#[derive(Debug, Clone)]
+#[model]
+struct Comment {
+    id: Auto<i32>,
+    post: ForeignKey<Post>,
+    created: NaiveDate,
+}
+
+#[derive(Debug, Clone, AdminModel)]
+#[admin(display = name)]
+#[model]
+struct Tag {
+    id: Auto<i32>,
+    post: ForeignKey<Post>,
+    name: LimitedString<50>,
+}
+
+#[derive(Debug, Clone, AdminModel)]
+#[model]
+struct Invoice {
+    #[model(primary_key)]
+    number: Auto<i64>,
+    customer: LimitedString<50>,
+}
+
+async fn migrate_post(db: &Database) {
+    CREATE_POST.forwards(db).await.unwrap();
+}
+
+async fn migrate_comment(db: &Database) {
+    CREATE_COMMENT.forwards(db).await.unwrap();
+}
+
+async fn migrate_tag(db: &Database) {
+    CREATE_TAG.forwards(db).await.unwrap();
+}
+
+const CREATE_POST: Operation = Operation::create_model()
+    .table_name(Identifier::new("post"))
+    .fields(&[
+        Field::new(Identifier::new("id"), <Auto<i32> as DatabaseField>::TYPE)
+            .primary_key()
+            .auto(),
+        Field::new(
+            Identifier::new("title"),
+            <LimitedString<100> as DatabaseField>::TYPE,
+        ),
+        Field::new(Identifier::new("published"), <bool as DatabaseField>::TYPE),
+        Field::new(Identifier::new("views"), <i32 as DatabaseField>::TYPE),
+    ])
+    .build();
+
+const CREATE_COMMENT: Operation = Operation::create_model()
+    .table_name(Identifier::new("comment"))
+    .fields(&[
+        Field::new(Identifier::new("id"), <Auto<i32> as DatabaseField>::TYPE)
+            .primary_key()
+            .auto(),
+        Field::new(
+            Identifier::new("post"),
+            <ForeignKey<Post> as DatabaseField>::TYPE,
+        ),
+        Field::new(
+            Identifier::new("created"),
+            <NaiveDate as DatabaseField>::TYPE,
+        ),
+    ])
+    .build();
+
+const CREATE_TAG: Operation = Operation::create_model()
+    .table_name(Identifier::new("tag"))
+    .fields(&[
+        Field::new(Identifier::new("id"), <Auto<i32> as DatabaseField>::TYPE)
+            .primary_key()
+            .auto(),
+        Field::new(
+            Identifier::new("post"),
+            <ForeignKey<Post> as DatabaseField>::TYPE,
+        ),
+        Field::new(
+            Identifier::new("name"),
+            <LimitedString<50> as DatabaseField>::TYPE,
+        ),
+    ])
+    .build();
+
+async fn create_post(db: &Database, title: &str, published: bool) -> Post {
+    let mut post = Post {
+        id: Auto::auto(),
+        title: LimitedString::new(title).unwrap(),
+        published,
+        views: 0,
+    };
+    post.insert(db).await.unwrap();
+    post
+}
+
+#[test]
+fn derive_admin_model_metadata() {
+    assert_eq!(Post::name(), "Blog post");
+    assert_eq!(Post::url_name(), "post");
+    assert_eq!(Post::list_columns(), ["id", "title", "published"]);
+    assert_eq!(Post::search_fields(), ["title"]);
+    assert_eq!(Post::read_only_fields(), ["id", "views"]);
+    assert_eq!(
+        Post::permission_codename(AdminPermission::Change),
+        "change_post"
+    );
+
+    let form = Post::add_form().unwrap();
+    let field_ids: Vec<_> = form.fields().iter().map(AdminFormField::id).collect();
+    assert_eq!(field_ids, ["title", "published"]);
+
+    let post = Post {
+        id: Auto::fixed(5),
+        title: LimitedString::new("Hello").unwrap(),
+        published: true,
+        views: 42,
+    };
+    assert_eq!(post.object_id(), "5");
+    assert_eq!(post.display(), "Hello");
+    assert_eq!(post.field_value("published").as_deref(), Some("true"));
+    assert_eq!(post.field_value("views").as_deref(), Some("42"));
+    assert_eq!(post.field_value("unknown"), None);
+}
+
+#[test]
+fn derive_admin_model_custom_primary_key() {
+    assert_eq!(Invoice::read_only_fields(), ["number"]);
+
+    let form = Invoice::add_form().unwrap();
+    let field_ids: Vec<_> = form.fields().iter().map(AdminFormField::id).collect();
+    assert_eq!(field_ids, ["customer"]);
+}
+
+#[cot_macros::dbtest]
+async fn derive_admin_model_crud(test_db: &mut TestDatabase) {
+    migrate_post(test_db).await;
+    let post = |form_data: &[(&str, &str)]| {
+        TestRequestBuilder::post("/")
+            .database(test_db.database())
+            .form_data(form_data)
+            .build()
+    };
+
+    // Add
+    let mut request = post(&[("title", &"a".repeat(101)), ("published", "0")]);
+    let AdminFormResult::ValidationError(form) =
+        Post::create_from_request(&mut request).await.unwrap()
+    else {
+        panic!("expected a validation error");
+    };
+    assert!(!form.fields()[0].errors().is_empty());
+
+    let mut request = post(&[("title", "Hello"), ("published", "0")]);
+    let AdminFormResult::Ok(created) = Post::create_from_request(&mut request).await.unwrap()
+    else {
+        panic!("expected the post to be created");
+    };
+    assert_eq!(created.views, 0);
+
+    // Change
+    let object_id = created.object_id();
+    let mut object = Post::get_object_by_id(&request, &object_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(object.title.as_str(), "Hello");
+    assert!(object.change_form().unwrap().fields()[0]
+        .html()
+        .contains("value=\"Hello\""));
+
+    let mut request = post(&[("title", "Hello, world"), ("published", "1")]);
+    assert!(matches!(
+        object.update_from_request(&mut request).await.unwrap(),
+        AdminFormResult::Ok(())
+    ));
+    let objects = Post::get_objects(&request).await.unwrap();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].title.as_str(), "Hello, world");
+    assert!(objects[0].published);
+
+    // Delete
+    AdminModel::delete(&objects[0], &request).await.unwrap();
+    assert!(Post::get_object_by_id(&request, &object_id)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(Post::get_object_by_id(&request, "invalid")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[cot_macros::dbtest]
+async fn derive_admin_model_list(test_db: &mut TestDatabase) {
+    migrate_post(test_db).await;
+    for (title, views) in [
+        ("Rust 100%", 3),
+        ("Cot news", 1),
+        ("rust tips", 2),
+        ("Other", 4),
+    ] {
+        let mut post = Post {
+            id: Auto::auto(),
+            title: LimitedString::new(title).unwrap(),
+            published: false,
+            views,
+        };
+        post.insert(&**test_db).await.unwrap();
+    }
+    let request = TestRequestBuilder::get("/")
+        .database(test_db.database())
+        .build();
+    let titles = |list: cot::admin::AdminList<Post>| -> Vec<String> {
+        list.into_objects()
+            .into_iter()
+            .map(|post| post.title.as_str().to_owned())
+            .collect()
+    };
+
+    let params = AdminListParams::new().with_per_page(3);
+    let list = Post::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.total(), 4);
+    assert_eq!(titles(list), ["Rust 100%", "Cot news", "rust tips"]);
+
+    let params = params.with_page(2);
+    let list = Post::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.total(), 4);
+    assert_eq!(titles(list), ["Other"]);
+
+    let params = AdminListParams::new().with_sort("title", true);
+    let list = Post::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.objects()[0].title.as_str(), "rust tips");
+
+    // `views` is not a list column, so sorting by it is ignored
+    let params = AdminListParams::new().with_sort("views", false);
+    let list = Post::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.objects()[0].title.as_str(), "Rust 100%");
+
+    let params = AdminListParams::new()
+        .with_search("RUST")
+        .with_sort("id", true);
+    let list = Post::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.total(), 2);
+    assert_eq!(titles(list), ["rust tips", "Rust 100%"]);
+
+    let params = AdminListParams::new().with_search("0%");
+    let list = Post::get_list(&request, &params).await.unwrap();
+    assert_eq!(titles(list), ["Rust 100%"]);
+}
+
+#[cot_macros::dbtest]
+async fn derive_admin_model_filters_and_actions(test_db: &mut TestDatabase) {
+    // the deletions are recorded in the admin log
+    test_db.with_admin().run_migrations().await;
+    migrate_post(test_db).await;
+    let first = create_post(test_db, "First", false).await;
+    let second = create_post(test_db, "Second", true).await;
+    let third = create_post(test_db, "Third", false).await;
+    let request = || {
+        TestRequestBuilder::get("/")
+            .database(test_db.database())
+            .build()
+    };
+
+    let filters = Post::filters(&request()).await.unwrap();
+    assert_eq!(filters.len(), 1);
+    assert_eq!(filters[0].field(), "published");
+    assert_eq!(filters[0].kind(), &AdminFilterKind::Boolean);
+
+    let params = AdminListParams::new().with_filter("published", "true");
+    let list = Post::get_list(&request(), &params).await.unwrap();
+    assert_eq!(list.total(), 1);
+    assert_eq!(list.objects()[0].title.as_str(), "Second");
+
+    // Actions
+    let actions = Post::actions();
+    let action_names: Vec<_> = actions.iter().map(AdminAction::name).collect();
+    assert_eq!(
+        action_names,
+        ["delete_selected", "export_csv", "mark_published"]
+    );
+
+    let result = actions[2]
+        .run(request(), vec![first.object_id()])
+        .await
+        .unwrap();
+    assert!(
+        matches!(result, AdminActionResult::Message(message) if message == "1 posts were published.")
+    );
+    let list = Post::get_list(&request(), &params).await.unwrap();
+    assert_eq!(list.total(), 2);
+
+    let AdminActionResult::Response(response) = actions[1]
+        .run(request(), vec![first.object_id(), second.object_id()])
+        .await
+        .unwrap()
+    else {
+        panic!("expected a CSV response");
+    };
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let body = response.into_body().into_bytes().await.unwrap();
+    assert_eq!(
+        body,
+        format!(
+            "Id,Title,Published\r\n{},First,true\r\n{},Second,true\r\n",
+            first.object_id(),
+            second.object_id()
+        )
+    );
+
+    let result = actions[0]
+        .run(request(), vec![first.object_id(), third.object_id()])
+        .await
+        .unwrap();
+    assert!(
+        matches!(result, AdminActionResult::Message(message) if message == "2 Blog post objects were deleted successfully.")
+    );
+    let objects = Post::get_objects(&request()).await.unwrap();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].title.as_str(), "Second");
+}
+
+#[cot_macros::dbtest]
+async fn derive_admin_model_foreign_keys_and_inlines(test_db: &mut TestDatabase) {
+    migrate_post(test_db).await;
+    migrate_tag(test_db).await;
+    let first = create_post(test_db, "First", false).await;
+    let second = create_post(test_db, "Second", false).await;
+    let post = |form_data: &[(&str, &str)]| {
+        TestRequestBuilder::post("/")
+            .database(test_db.database())
+            .form_data(form_data)
+            .build()
+    };
+
+    // the foreign keys are chosen from the referenced objects
+    let mut request = post(&[]);
+    let choices = Tag::form_choices(&request).await.unwrap();
+    assert_eq!(choices.len(), 1);
+    assert_eq!(choices[0].0, "post");
+    let choices: Vec<_> = choices[0]
+        .1
+        .iter()
+        .map(|choice| (choice.value().to_owned(), choice.label().to_owned()))
+        .collect();
+    assert_eq!(
+        choices,
+        [
+            (first.object_id(), String::from("First")),
+            (second.object_id(), String::from("Second"))
+        ]
+    );
+    assert!(Post::form_choices(&request).await.unwrap().is_empty());
+
+    let AdminFormResult::ValidationError(form) =
+        Tag::create_from_request(&mut request).await.unwrap()
+    else {
+        panic!("expected a validation error");
+    };
+    assert!(!form.fields()[0].errors().is_empty());
+
+    let first_id = first.object_id();
+    let mut request = post(&[("post", &first_id), ("name", "rust")]);
+    let AdminFormResult::Ok(tag) = Tag::create_from_request(&mut request).await.unwrap() else {
+        panic!("expected the tag to be created");
+    };
+    assert_eq!(tag.post.primary_key(), first.primary_key());
+    assert_eq!(
+        tag.change_form().unwrap().fields()[0].value(),
+        Some(first_id.as_str())
+    );
+
+    // the tags are edited on the change page of their post
+    let inlines = Post::inlines();
+    assert_eq!(inlines.len(), 1);
+    assert_eq!(inlines[0].field(), "post");
+    assert_eq!(inlines[0].model().url_name(), "tag");
+
+    let tags = inlines[0].get_objects(&request, &first_id).await.unwrap();
+    let tags: Vec<_> = tags.iter().map(|tag| tag.display()).collect();
+    assert_eq!(tags, ["rust"]);
+    let tags = inlines[0]
+        .get_objects(&request, &second.object_id())
+        .await
+        .unwrap();
+    assert!(tags.is_empty());
+    let tags = inlines[0].get_objects(&request, "invalid").await.unwrap();
+    assert!(tags.is_empty());
+}
+
+#[cot_macros::dbtest]
+async fn admin_filter_fields(test_db: &mut TestDatabase) {
+    migrate_post(test_db).await;
+    migrate_comment(test_db).await;
+    let first = create_post(test_db, "First", false).await;
+    let second = create_post(test_db, "Second", false).await;
+    for (post, day) in [(&first, 1), (&first, 15), (&second, 31)] {
+        let mut comment = Comment {
+            id: Auto::auto(),
+            post: ForeignKey::from(post),
+            created: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+        };
+        comment.insert(&**test_db).await.unwrap();
+    }
+    let request = TestRequestBuilder::get("/")
+        .database(test_db.database())
+        .build();
+
+    let filter = <ForeignKey<Post> as AdminFilterField>::admin_filter(&request, "post")
+        .await
+        .unwrap();
+    assert_eq!(
+        filter.kind(),
+        &AdminFilterKind::Choices(vec![
+            AdminFilterChoice::new(first.object_id(), "First"),
+            AdminFilterChoice::new(second.object_id(), "Second"),
+        ])
+    );
+
+    let db: &Database = test_db;
+    let comment_days = |params: AdminListParams| async move {
+        let conditions = [
+            <ForeignKey<Post> as AdminFilterField>::admin_filter_expr("post", &params),
+            <NaiveDate as AdminFilterField>::admin_filter_expr("created", &params),
+        ];
+        let mut query = Comment::objects();
+        if let Some(filter) = conditions
+            .into_iter()
+            .flatten()
+            .reduce(cot::db::query::Expr::and)
+        {
+            query.filter(filter);
+        }
+        let mut days: Vec<_> = query
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|comment| chrono::Datelike::day(&comment.created))
+            .collect();
+        days.sort_unstable();
+        days
+    };
+
+    assert_eq!(comment_days(AdminListParams::new()).await, [1, 15, 31]);
+    let params = AdminListParams::new().with_filter("post", first.object_id());
+    assert_eq!(comment_days(params).await, [1, 15]);
+    let params = AdminListParams::new().with_filter("post", "invalid");
+    assert_eq!(comment_days(params).await, [1, 15, 31]);
+    let params = AdminListParams::new()
+        .with_filter("created__from", "2024-01-15")
+        .with_filter("created__to", "2024-01-31");
+    assert_eq!(comment_days(params).await, [15, 31]);
+    let params = AdminListParams::new()
+        .with_filter("post", first.object_id())
+        .with_filter("created__to", "2024-01-14");
+    assert_eq!(comment_days(params).await, [1]);
+}
+
+#[cot_macros::dbtest]
+async fn admin_log_entries(test_db: &mut TestDatabase) {
+    test_db.with_auth().with_admin().run_migrations().await;
+    migrate_post(test_db).await;
+
+    let user = DatabaseUser::create_superuser(
+        &**test_db,
+        "admin",
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    let mut request = TestRequestBuilder::get("/")
+        .with_db_auth(test_db.database())
+        .with_session()
+        .build();
+    request.login(Box::new(user)).await.unwrap();
+
+    let post = create_post(test_db, "Hello", false).await;
+    LogEntry::log(&mut request, "post", &post, LogAction::Add, &[])
+        .await
+        .unwrap();
+    let changes = [FieldChange::new("title", "Hello", "Hello, world")];
+    LogEntry::log(&mut request, "post", &post, LogAction::Change, &changes)
+        .await
+        .unwrap();
+    let other_post = create_post(test_db, "Other", false).await;
+    LogEntry::log(&mut request, "post", &other_post, LogAction::Delete, &[])
+        .await
+        .unwrap();
+
+    let entries = LogEntry::for_object(&**test_db, "post", &post.object_id())
+        .await
+        .unwrap();
+    let actions: Vec<_> = entries.iter().map(LogEntry::action).collect();
+    assert_eq!(actions, [LogAction::Change, LogAction::Add]);
+    assert_eq!(entries[0].username(), Some("admin"));
+    assert_eq!(entries[0].object_repr(), "Hello");
+    assert_eq!(entries[0].changes(), changes);
+    assert!(entries[1].changes().is_empty());
+
+    let entries = LogEntry::for_object(&**test_db, "post", "invalid")
+        .await
+        .unwrap();
+    assert!(entries.is_empty());
+
+    let entries = LogEntry::recent(&**test_db, &["post", "comment"], 2)
+        .await
+        .unwrap();
+    let actions: Vec<_> = entries.iter().map(LogEntry::action).collect();
+    assert_eq!(actions, [LogAction::Delete, LogAction::Change]);
+    assert!(LogEntry::recent(&**test_db, &["comment"], 10)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(LogEntry::recent(&**test_db, &[], 10)
+        .await
+        .unwrap()
+        .is_empty());
+}
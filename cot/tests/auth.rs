@@ -1,8 +1,25 @@
 use std::borrow::Cow;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 
-use cot::auth::db::{DatabaseUser, DatabaseUserCredentials};
-use cot::auth::{AuthRequestExt, Password};
+use async_trait::async_trait;
+use chrono::{TimeDelta, Utc};
+use cot::admin::{AdminFormField, AdminFormResult, AdminListParams, AdminModel};
+use cot::auth::api_token::{ApiToken, ApiTokenBackend, ApiTokenCredentials};
+use cot::auth::db::{DatabaseUser, DatabaseUserCredentials, Group, ModelPermission, Permission};
+use cot::auth::password_reset::{PasswordResetApp, PasswordResetSender};
+use cot::auth::throttling::{Lockout, LockoutNotifier, LockoutTarget, LoginThrottle};
+use cot::auth::two_factor::{self, RecoveryCode, TotpDevice};
+use cot::auth::{AuthError, AuthRequestExt, Password};
+use cot::config::{LoginThrottlingConfig, PasswordHasherConfig, PasswordValidators, ProjectConfig};
+use cot::db::Model;
+use cot::http::Method;
+use cot::middleware::ApiTokenMiddleware;
+use cot::request::Request;
+use cot::response::{Response, ResponseExt};
 use cot::test::{TestDatabase, TestRequestBuilder};
+use cot::{App, Body, StatusCode};
+use tower::{Layer, ServiceExt};
 
 #[cot_macros::dbtest]
 async fn database_user(test_db: &mut TestDatabase) {
@@ -20,6 +37,8 @@ async fn database_user(test_db: &mut TestDatabase) {
         &**test_db,
         "testuser".to_string(),
         &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
     )
     .await
     .unwrap();
@@ -62,3 +81,786 @@ async fn database_user(test_db: &mut TestDatabase) {
     let user = request.user().await.unwrap();
     assert!(!user.is_authenticated());
 }
+
+#[cot_macros::dbtest]
+async fn database_user_inactive(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder.with_db_auth(test_db.database());
+    let credentials =
+        DatabaseUserCredentials::new("testuser".to_string(), Password::new("password123"));
+
+    let mut user = DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert!(user.is_active());
+
+    let mut request = request_builder.clone().with_session().build();
+    let authenticated = request.authenticate(&credentials).await.unwrap().unwrap();
+    request.login(authenticated).await.unwrap();
+
+    // Deactivated users can't log in and their sessions end
+    user.set_active(false);
+    user.save(&**test_db).await.unwrap();
+    assert!(request.authenticate(&credentials).await.unwrap().is_none());
+    let mut request = request_builder.clone().with_session_from(&request).build();
+    assert!(!request.user().await.unwrap().is_authenticated());
+
+    // Reactivated users can log in again
+    user.set_active(true);
+    user.save(&**test_db).await.unwrap();
+    assert!(request.authenticate(&credentials).await.unwrap().is_some());
+}
+
+#[cot_macros::dbtest]
+async fn database_user_permissions(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder.with_db_auth(test_db.database());
+
+    let user = DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    DatabaseUser::create_superuser(
+        &**test_db,
+        "admin",
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    // Model permissions are registered when running the migrations
+    let view_permission = Permission::get_by_codename(
+        &**test_db,
+        &ModelPermission::View.codename_for::<DatabaseUser>(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(view_permission.name(), "Can view database user");
+    let change_permission = Permission::get_by_codename(&**test_db, "change_database_user")
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Permissions granted directly and through a group
+    user.add_permission(&**test_db, &view_permission)
+        .await
+        .unwrap();
+    let group = Group::create(&**test_db, "editors").await.unwrap();
+    group
+        .add_permission(&**test_db, &change_permission)
+        .await
+        .unwrap();
+    user.add_to_group(&**test_db, &group).await.unwrap();
+
+    let mut request = request_builder.clone().with_session().build();
+    let user = request
+        .authenticate(&DatabaseUserCredentials::new(
+            "testuser".to_string(),
+            Password::new("password123"),
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    request.login(user).await.unwrap();
+
+    let mut request = request_builder.clone().with_session_from(&request).build();
+    let user = request.user().await.unwrap();
+    assert!(user.has_perm("view_database_user"));
+    assert!(user.has_perms(&["view_database_user", "change_database_user"]));
+    assert!(!user.has_perm("delete_database_user"));
+
+    // Superusers have all the permissions
+    let superuser = request
+        .authenticate(&DatabaseUserCredentials::new(
+            "admin".to_string(),
+            Password::new("password123"),
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(superuser.has_perms(&["delete_database_user", "custom_permission"]));
+}
+
+#[derive(Debug, Clone, Default)]
+struct CapturingPasswordResetSender {
+    reset_urls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl PasswordResetSender for CapturingPasswordResetSender {
+    async fn send(
+        &self,
+        _request: &Request,
+        _user: &DatabaseUser,
+        reset_url: &str,
+    ) -> cot::Result<()> {
+        self.reset_urls.lock().unwrap().push(reset_url.to_owned());
+        Ok(())
+    }
+}
+
+#[cot_macros::dbtest]
+async fn database_user_password_reset(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    let mut inactive_user = DatabaseUser::create_user(
+        &**test_db,
+        "inactiveuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    inactive_user.set_active(false);
+    inactive_user.save(&**test_db).await.unwrap();
+
+    let sender = CapturingPasswordResetSender::default();
+    let router = PasswordResetApp::new().sender(sender.clone()).router();
+    let build_request = |request_builder: &mut TestRequestBuilder| {
+        request_builder
+            .config(
+                ProjectConfig::builder()
+                    .password_validators(PasswordValidators::recommended())
+                    .build(),
+            )
+            .with_db_auth(test_db.database())
+            .router(router.clone())
+            .build()
+    };
+
+    // Unknown and deactivated users don't get a link, but the response is the same
+    for username in ["testuser", "nonexistent", "inactiveuser"] {
+        let request =
+            build_request(TestRequestBuilder::post("/").form_data(&[("username", username)]));
+        let response = router.handle(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get("location").unwrap(), "/done");
+    }
+    let reset_urls = sender.reset_urls.lock().unwrap().clone();
+    assert_eq!(reset_urls.len(), 1);
+    let reset_url = &reset_urls[0];
+
+    // Other methods than GET and POST are rejected
+    for url in ["/", reset_url.as_str()] {
+        let mut request = build_request(&mut TestRequestBuilder::get(url));
+        *request.method_mut() = Method::PUT;
+        let response = router.handle(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    // Mismatched passwords
+    let request = build_request(TestRequestBuilder::post(reset_url).form_data(&[
+        ("new_password", "newpassword"),
+        ("new_password_confirmation", "otherpassword"),
+    ]));
+    let response = router.handle(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Password rejected by the validators
+    let request = build_request(TestRequestBuilder::post(reset_url).form_data(&[
+        ("new_password", "12345678"),
+        ("new_password_confirmation", "12345678"),
+    ]));
+    let response = router.handle(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Set the new password
+    let request = build_request(TestRequestBuilder::post(reset_url).form_data(&[
+        ("new_password", "newpassword"),
+        ("new_password_confirmation", "newpassword"),
+    ]));
+    let response = router.handle(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers().get("location").unwrap(), "/complete");
+
+    let credentials =
+        DatabaseUserCredentials::new("testuser".to_string(), Password::new("newpassword"));
+    assert!(
+        DatabaseUser::authenticate(&**test_db, &credentials, &PasswordHasherConfig::default())
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    // The link can't be used again
+    let request = build_request(TestRequestBuilder::post(reset_url).form_data(&[
+        ("new_password", "anotherpassword"),
+        ("new_password_confirmation", "anotherpassword"),
+    ]));
+    let response = router.handle(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let credentials =
+        DatabaseUserCredentials::new("testuser".to_string(), Password::new("anotherpassword"));
+    assert!(
+        DatabaseUser::authenticate(&**test_db, &credentials, &PasswordHasherConfig::default())
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[derive(Debug, Clone, Default)]
+struct TestLockoutNotifier {
+    lockouts: Arc<Mutex<Vec<Lockout>>>,
+}
+
+#[async_trait]
+impl LockoutNotifier for TestLockoutNotifier {
+    async fn notify(&self, _request: &Request, lockout: &Lockout) -> cot::Result<()> {
+        self.lockouts.lock().unwrap().push(lockout.clone());
+        Ok(())
+    }
+}
+
+#[cot_macros::dbtest]
+async fn database_user_login_throttling(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let notifier = TestLockoutNotifier::default();
+    let throttle = Arc::new(
+        LoginThrottle::new(
+            &LoginThrottlingConfig::builder()
+                .max_failures_per_username(3)
+                .build(),
+        )
+        .notifier(notifier.clone()),
+    );
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder
+        .with_db_auth(test_db.database())
+        .login_throttle(Arc::clone(&throttle))
+        .remote_addr(SocketAddr::from(([192, 0, 2, 1], 12345)));
+    let credentials = |password: &str| {
+        DatabaseUserCredentials::new("testuser".to_string(), Password::new(password))
+    };
+
+    for _ in 0..3 {
+        let mut request = request_builder.clone().build();
+        let user = request
+            .authenticate(&credentials("wrongpassword"))
+            .await
+            .unwrap();
+        assert!(user.is_none());
+    }
+    let lockouts = notifier.lockouts.lock().unwrap().clone();
+    assert_eq!(lockouts.len(), 1);
+    assert_eq!(
+        lockouts[0].target(),
+        &LockoutTarget::Username("testuser".to_string())
+    );
+
+    // The correct password is rejected as well while locked out
+    let mut request = request_builder.clone().build();
+    let result = request.authenticate(&credentials("password123")).await;
+    assert!(matches!(
+        result,
+        Err(AuthError::TooManyFailedAttempts { .. })
+    ));
+
+    // Unlocking the account allows logging in again
+    assert!(throttle.unlock(&LockoutTarget::Username("testuser".to_string())));
+    let mut request = request_builder.clone().build();
+    let user = request
+        .authenticate(&credentials("password123"))
+        .await
+        .unwrap();
+    assert!(user.is_some());
+    assert!(throttle.lockouts().is_empty());
+}
+
+#[cot_macros::dbtest]
+async fn api_token(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder
+        .database(test_db.database())
+        .auth_backend(ApiTokenBackend::new());
+
+    let user = DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    let (token, token_string) =
+        ApiToken::create(&**test_db, &user, "CLI", &["articles:read"], None)
+            .await
+            .unwrap();
+    assert!(token_string.starts_with(token.token_prefix()));
+    assert!(token.last_used_at().is_none());
+
+    // Authenticate with the token
+    let mut request = request_builder.clone().build();
+    let authenticated = request
+        .authenticate(&ApiTokenCredentials::new(token_string.clone()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(authenticated.username(), Some(Cow::from("testuser")));
+    let token = ApiToken::get_by_token(&**test_db, &token_string)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(token.last_used_at().is_some());
+
+    // Username and password still work
+    let authenticated = request
+        .authenticate(&DatabaseUserCredentials::new(
+            "testuser".to_string(),
+            Password::new("password123"),
+        ))
+        .await
+        .unwrap();
+    assert!(authenticated.is_some());
+
+    // Revoked tokens are rejected
+    token.revoke(&**test_db).await.unwrap();
+    let authenticated = request
+        .authenticate(&ApiTokenCredentials::new(token_string))
+        .await
+        .unwrap();
+    assert!(authenticated.is_none());
+}
+
+#[cot_macros::dbtest]
+async fn api_token_middleware(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder.database(test_db.database());
+
+    let mut user = DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    let (_, token_string) = ApiToken::create(&**test_db, &user, "CLI", &[], None)
+        .await
+        .unwrap();
+
+    // No session is needed
+    let service =
+        ApiTokenMiddleware::new().layer(tower::service_fn(|mut request: Request| async move {
+            let user = request.user().await?;
+            let body = user.username().unwrap_or_default().into_owned();
+            Ok::<_, cot::Error>(Response::new_html(StatusCode::OK, Body::fixed(body)))
+        }));
+    let authorized_request = |token: &str| {
+        let mut request = request_builder.clone().build();
+        request.headers_mut().insert(
+            http::header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        request
+    };
+
+    let response = service
+        .clone()
+        .oneshot(authorized_request(&token_string))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_body().into_bytes().await.unwrap(), "testuser");
+
+    let response = service
+        .clone()
+        .oneshot(request_builder.clone().build())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_body().into_bytes().await.unwrap(), "");
+
+    // Other authorization schemes are left to the inner service
+    let mut basic_auth_request = request_builder.clone().build();
+    basic_auth_request.headers_mut().insert(
+        http::header::AUTHORIZATION,
+        http::HeaderValue::from_static("Basic dXNlcjpwYXNzd29yZA=="),
+    );
+    let response = service.clone().oneshot(basic_auth_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_body().into_bytes().await.unwrap(), "");
+
+    // Tokens that aren't API tokens are left to other middlewares
+    let response = service
+        .clone()
+        .oneshot(authorized_request("eyJhbGciOiJIUzI1NiJ9.e30.c2lnbmF0dXJl"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_body().into_bytes().await.unwrap(), "");
+
+    let response = service
+        .clone()
+        .oneshot(authorized_request("cot_invalid"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response
+            .headers()
+            .get(http::header::WWW_AUTHENTICATE)
+            .unwrap(),
+        "Bearer error=\"invalid_token\""
+    );
+
+    // Expired tokens are rejected
+    let (_, expired_token_string) = ApiToken::create(
+        &**test_db,
+        &user,
+        "expired",
+        &[],
+        Some(Utc::now().fixed_offset() - TimeDelta::hours(1)),
+    )
+    .await
+    .unwrap();
+    let response = service
+        .clone()
+        .oneshot(authorized_request(&expired_token_string))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        ApiToken::for_user(&**test_db, &user).await.unwrap().len(),
+        2
+    );
+
+    // Tokens of deactivated users are rejected
+    user.set_active(false);
+    user.save(&**test_db).await.unwrap();
+    let response = service
+        .clone()
+        .oneshot(authorized_request(&token_string))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[cfg(feature = "jwt")]
+#[tokio::test]
+async fn jwt_middleware() {
+    use cot::auth::jwt::JwtBackend;
+    use cot::config::{JwtConfig, JwtKeyConfig};
+    use cot::middleware::JwtMiddleware;
+
+    let config = JwtConfig::builder()
+        .keys(vec![JwtKeyConfig::hs256("supersecret")])
+        .build();
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder.auth_backend(JwtBackend::new(&config).unwrap());
+
+    let service =
+        JwtMiddleware::new().layer(tower::service_fn(|mut request: Request| async move {
+            let user = request.user().await?;
+            let body = user.username().unwrap_or_default().into_owned();
+            Ok::<_, cot::Error>(Response::new_html(StatusCode::OK, Body::fixed(body)))
+        }));
+    let authorized_request = |token: &str| {
+        let mut request = request_builder.clone().build();
+        request.headers_mut().insert(
+            http::header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        request
+    };
+
+    let exp = jsonwebtoken::get_current_timestamp() + 3600;
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &serde_json::json!({"sub": "user-123", "exp": exp, "preferred_username": "testuser"}),
+        &jsonwebtoken::EncodingKey::from_secret(b"supersecret"),
+    )
+    .unwrap();
+
+    let response = service
+        .clone()
+        .oneshot(authorized_request(&token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_body().into_bytes().await.unwrap(), "testuser");
+
+    let forged_token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &serde_json::json!({"sub": "user-123", "exp": exp, "preferred_username": "admin"}),
+        &jsonwebtoken::EncodingKey::from_secret(b"notthesecret"),
+    )
+    .unwrap();
+    let response = service
+        .clone()
+        .oneshot(authorized_request(&forged_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Tokens that aren't JWTs are left to other middlewares
+    let response = service
+        .clone()
+        .oneshot(authorized_request("cot_0123456789abcdef"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.into_body().into_bytes().await.unwrap(), "");
+}
+
+fn current_totp_code(device: &TotpDevice) -> String {
+    let secret = totp_rs::Secret::Encoded(device.secret())
+        .to_bytes()
+        .unwrap();
+    totp_rs::TOTP::new_unchecked(totp_rs::Algorithm::SHA1, 6, 0, 30, secret)
+        .generate_current()
+        .unwrap()
+}
+
+#[cot_macros::dbtest]
+async fn database_user_two_factor(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    let user = DatabaseUser::create_user(
+        &**test_db,
+        "testuser".to_string(),
+        &Password::new("password123"),
+        &PasswordValidators::none(),
+        &PasswordHasherConfig::default(),
+    )
+    .await
+    .unwrap();
+    let mut request_builder = TestRequestBuilder::get("/");
+    request_builder.with_db_auth(test_db.database());
+    let credentials =
+        DatabaseUserCredentials::new("testuser".to_string(), Password::new("password123"));
+
+    // An unconfirmed device doesn't require the second factor
+    let mut device = TotpDevice::create(&**test_db, &user, "Phone")
+        .await
+        .unwrap();
+    assert!(!two_factor::is_enabled(&**test_db, &user).await.unwrap());
+    let mut request = request_builder.clone().with_session().build();
+    let authenticated = request.authenticate(&credentials).await.unwrap().unwrap();
+    request.login(authenticated).await.unwrap();
+    assert!(!request.second_factor_pending().await.unwrap());
+    assert!(request.user().await.unwrap().is_authenticated());
+
+    let code = current_totp_code(&device);
+    assert!(device.confirm(&**test_db, &code).await.unwrap());
+    assert!(device.is_confirmed());
+    assert!(two_factor::is_enabled(&**test_db, &user).await.unwrap());
+    let recovery_codes = RecoveryCode::generate(&**test_db, &user).await.unwrap();
+    assert_eq!(recovery_codes.len(), 10);
+
+    // The login is pending until the second factor is verified
+    let mut request = request_builder.clone().with_session().build();
+    let authenticated = request.authenticate(&credentials).await.unwrap().unwrap();
+    request.login(authenticated).await.unwrap();
+    assert!(request.second_factor_pending().await.unwrap());
+    assert!(!request.user().await.unwrap().is_authenticated());
+
+    // The code used to confirm the device can't be used again
+    assert!(!request.verify_second_factor(&code).await.unwrap());
+    assert!(!request.user().await.unwrap().is_authenticated());
+
+    assert!(request
+        .verify_second_factor(&recovery_codes[0])
+        .await
+        .unwrap());
+    assert!(!request.second_factor_pending().await.unwrap());
+    let logged_in_user = request.user().await.unwrap();
+    assert!(logged_in_user.is_authenticated());
+    assert_eq!(logged_in_user.username(), Some(Cow::from("testuser")));
+    assert_eq!(RecoveryCode::remaining(&**test_db, &user).await.unwrap(), 9);
+
+    // Recovery codes can only be used once
+    assert!(!RecoveryCode::verify(&**test_db, &user, &recovery_codes[0])
+        .await
+        .unwrap());
+}
+
+#[cot_macros::dbtest]
+async fn database_user_admin(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    let post = |form_data: &[(&str, &str)]| {
+        TestRequestBuilder::post("/")
+            .config(
+                ProjectConfig::builder()
+                    .password_validators(PasswordValidators::recommended())
+                    .build(),
+            )
+            .with_db_auth(test_db.database())
+            .form_data(form_data)
+            .build()
+    };
+
+    let form = DatabaseUser::add_form().unwrap();
+    let field_ids: Vec<_> = form.fields().iter().map(AdminFormField::id).collect();
+    assert_eq!(field_ids, ["username", "password"]);
+
+    // Add
+    let mut request = post(&[("username", "newuser"), ("password", "123")]);
+    let AdminFormResult::ValidationError(form) = DatabaseUser::create_from_request(&mut request)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a validation error");
+    };
+    assert!(!form.fields()[1].errors().is_empty());
+    assert!(!form.fields()[1].html().contains("value="));
+
+    let mut request = post(&[
+        ("username", "newuser"),
+        ("password", "correct horse battery"),
+    ]);
+    let AdminFormResult::Ok(user) = DatabaseUser::create_from_request(&mut request)
+        .await
+        .unwrap()
+    else {
+        panic!("expected the user to be created");
+    };
+    assert_eq!(user.username(), "newuser");
+
+    let mut request = post(&[
+        ("username", "newuser"),
+        ("password", "correct horse battery"),
+    ]);
+    let AdminFormResult::ValidationError(form) = DatabaseUser::create_from_request(&mut request)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a validation error");
+    };
+    assert_eq!(
+        form.fields()[0].errors(),
+        ["a user with this username already exists"]
+    );
+
+    // Change
+    let request = TestRequestBuilder::get("/")
+        .with_db_auth(test_db.database())
+        .build();
+    let object_id = user.object_id();
+    let mut user = DatabaseUser::get_object_by_id(&request, &object_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.change_form().unwrap().fields()[0]
+        .html()
+        .contains("value=\"newuser\""));
+
+    let mut request = post(&[
+        ("username", "renamed"),
+        ("is_staff", "0"),
+        ("is_staff", "on"),
+        ("is_superuser", "0"),
+        ("is_active", "0"),
+    ]);
+    assert!(matches!(
+        user.update_from_request(&mut request).await.unwrap(),
+        AdminFormResult::Ok(())
+    ));
+    let user = DatabaseUser::get_object_by_id(&request, &object_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.username(), "renamed");
+    assert!(user.is_staff());
+    assert!(!user.is_superuser());
+    assert!(!user.is_active());
+
+    // Delete
+    AdminModel::delete(&user, &request).await.unwrap();
+    assert!(DatabaseUser::get_object_by_id(&request, &object_id)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(DatabaseUser::get_object_by_id(&request, "invalid")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[cot_macros::dbtest]
+async fn database_user_admin_list(test_db: &mut TestDatabase) {
+    test_db.with_auth().run_migrations().await;
+    for username in ["carol", "alice", "bob", "alicia"] {
+        let mut user = DatabaseUser::create_user(
+            &**test_db,
+            username.to_string(),
+            &Password::new("password123"),
+            &PasswordValidators::none(),
+            &PasswordHasherConfig::default(),
+        )
+        .await
+        .unwrap();
+        user.set_staff(username.starts_with("al"));
+        user.save(&**test_db).await.unwrap();
+    }
+    let request = TestRequestBuilder::get("/")
+        .with_db_auth(test_db.database())
+        .build();
+    let usernames = |list: cot::admin::AdminList<DatabaseUser>| -> Vec<String> {
+        list.objects()
+            .iter()
+            .map(|user| user.username().to_owned())
+            .collect()
+    };
+
+    let params = AdminListParams::new()
+        .with_sort("username", false)
+        .with_per_page(3);
+    let list = DatabaseUser::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.total(), 4);
+    assert_eq!(usernames(list), ["alice", "alicia", "bob"]);
+    let list = DatabaseUser::get_list(&request, &params.clone().with_page(2))
+        .await
+        .unwrap();
+    assert_eq!(usernames(list), ["carol"]);
+
+    let params = AdminListParams::new()
+        .with_sort("username", true)
+        .with_search("ALI");
+    let list = DatabaseUser::get_list(&request, &params).await.unwrap();
+    assert_eq!(list.total(), 2);
+    assert_eq!(usernames(list), ["alicia", "alice"]);
+
+    let params = AdminListParams::new().with_filter("is_staff", "false");
+    let list = DatabaseUser::get_list(&request, &params).await.unwrap();
+    assert_eq!(usernames(list), ["carol", "bob"]);
+}
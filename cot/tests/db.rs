@@ -100,6 +100,38 @@ async fn model_update(test_db: &mut TestDatabase) {
     assert_eq!(objects[0].name, "test2");
 }
 
+#[cot_macros::dbtest]
+async fn transaction(test_db: &mut TestDatabase) {
+    migrate_test_model(&*test_db).await;
+    let mut model = TestModel {
+        id: Auto::auto(),
+        name: "test".to_owned(),
+    };
+
+    // Rollback
+    let transaction = test_db.begin_transaction().await.unwrap();
+    model.save(&transaction).await.unwrap();
+    assert_eq!(TestModel::objects().count(&transaction).await.unwrap(), 1);
+    transaction.rollback().await.unwrap();
+    assert_eq!(TestModel::objects().count(&**test_db).await.unwrap(), 0);
+
+    // Dropped without committing
+    let transaction = test_db.begin_transaction().await.unwrap();
+    model.id = Auto::auto();
+    model.save(&transaction).await.unwrap();
+    drop(transaction);
+    assert_eq!(TestModel::objects().count(&**test_db).await.unwrap(), 0);
+
+    // Commit
+    let transaction = test_db.begin_transaction().await.unwrap();
+    model.id = Auto::auto();
+    model.save(&transaction).await.unwrap();
+    transaction.commit().await.unwrap();
+    let objects = TestModel::objects().all(&**test_db).await.unwrap();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].name, "test");
+}
+
 #[cot_macros::dbtest]
 async fn model_macro_filtering(test_db: &mut TestDatabase) {
     migrate_test_model(&*test_db).await;
@@ -498,3 +530,64 @@ async fn foreign_keys_cascade(db: &mut TestDatabase) {
         .unwrap();
     assert!(Child::objects().all(&**db).await.unwrap().is_empty());
 }
+
+#[cot_macros::dbtest]
+async fn introspect(db: &mut TestDatabase) {
+    const CREATE_AUTHOR: Operation = Operation::create_model()
+        .table_name(Identifier::new("author"))
+        .fields(&[
+            Field::new(Identifier::new("id"), <Auto<i32> as DatabaseField>::TYPE)
+                .primary_key()
+                .auto(),
+            Field::new(
+                Identifier::new("email"),
+                <LimitedString<100> as DatabaseField>::TYPE,
+            )
+            .unique(),
+        ])
+        .build();
+    const CREATE_BOOK: Operation = Operation::create_model()
+        .table_name(Identifier::new("book"))
+        .fields(&[
+            Field::new(Identifier::new("id"), <Auto<i32> as DatabaseField>::TYPE)
+                .primary_key()
+                .auto(),
+            Field::new(Identifier::new("author"), <i32 as DatabaseField>::TYPE).foreign_key(
+                Identifier::new("author"),
+                Identifier::new("id"),
+                ForeignKeyOnDeletePolicy::Restrict,
+                ForeignKeyOnUpdatePolicy::Restrict,
+            ),
+            Field::new(Identifier::new("title"), <String as DatabaseField>::TYPE).null(),
+        ])
+        .build();
+
+    CREATE_AUTHOR.forwards(db).await.unwrap();
+    CREATE_BOOK.forwards(db).await.unwrap();
+
+    let tables = db.introspect().await.unwrap();
+    let author = tables
+        .iter()
+        .find(|table| table.name() == "author")
+        .unwrap();
+    let book = tables.iter().find(|table| table.name() == "book").unwrap();
+
+    let id = author.column("id").unwrap();
+    assert!(id.is_primary_key());
+    assert!(!id.is_nullable());
+    assert!(!id.is_unique());
+    assert_eq!(id.foreign_key_table(), None);
+
+    let email = author.column("email").unwrap();
+    assert!(!email.is_primary_key());
+    assert!(email.is_unique());
+    assert_eq!(email.max_length(), Some(100));
+
+    let author = book.column("author").unwrap();
+    assert_eq!(author.foreign_key_table(), Some("author"));
+    assert!(!author.is_unique());
+
+    let title = book.column("title").unwrap();
+    assert!(title.is_nullable());
+    assert_eq!(title.max_length(), None);
+}
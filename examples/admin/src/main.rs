@@ -2,7 +2,7 @@ use cot::__private::async_trait;
 use cot::admin::AdminApp;
 use cot::auth::db::{DatabaseUser, DatabaseUserApp};
 use cot::cli::CliMetadata;
-use cot::config::{DatabaseConfig, ProjectConfig};
+use cot::config::{DatabaseConfig, PasswordValidators, ProjectConfig};
 use cot::middleware::SessionMiddleware;
 use cot::project::{WithApps, WithConfig};
 use cot::request::Request;
@@ -37,7 +37,15 @@ impl App for HelloApp {
         // TODO use transaction
         let user = DatabaseUser::get_by_username(context.database(), "admin").await?;
         if user.is_none() {
-            DatabaseUser::create_user(context.database(), "admin", "admin").await?;
+            // the demo password is too weak for the default validators
+            DatabaseUser::create_superuser(
+                context.database(),
+                "admin",
+                "admin",
+                &PasswordValidators::none(),
+                &context.config().password_hasher,
+            )
+            .await?;
         }
 
         Ok(())